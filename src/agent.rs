@@ -0,0 +1,436 @@
+// 在后台保存解锁后的密钥的 agent
+//
+// `passman agent` 解锁密码库后转入后台，在本地 Unix 套接字上为之后运行的 passman 加密和
+// 解密密码库，与 ssh-agent 类似：密钥只保存在 agent 锁定的内存中，不交给客户端，也不写入
+// 磁盘。套接字位于只有当前用户可以访问的运行时目录中，两端都检查对方进程的用户 ID。每个
+// 密码库一个 agent，套接字以密码库的 ID 命名，不需要设置环境变量。
+//
+// 协议：客户端每个请求建立一个连接，发送一个字节的操作码和若干字段，agent 返回一个字节的
+// 状态和一个字段；字段为 4 字节大端序的长度加内容。
+
+#[cfg(unix)]
+pub use unix::{connect, run, socket, stop, stop_all};
+#[cfg(not(unix))]
+pub use unsupported::{connect, run, socket, stop, stop_all};
+
+#[cfg(unix)]
+mod unix {
+    use std::env;
+    use std::error::Error;
+    use std::fs;
+    use std::io::{self, Read, Write};
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use aes_gcm::aead::Buffer;
+
+    use passman::AppError;
+    use passman::crypto::{Cipher, CryptoBackend, KeyHeader};
+    use passman::secmem::{self, SecretBytes};
+
+    use crate::autolock::{self, AutoLock};
+    use crate::config;
+    use crate::i18n::t;
+
+    // 操作码
+    const HEADER: u8 = 1;
+    const ENCRYPT: u8 = 2;
+    const DECRYPT: u8 = 3;
+    const STOP: u8 = 4;
+    // 响应状态
+    const OK: u8 = 0;
+    const AEAD_FAILED: u8 = 1;
+    const FAILED: u8 = 2;
+    // 读写一个请求或响应的最长等待时间
+    const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// 密码库 `vault_path` 的 agent 套接字，套接字不存在时为 `None`
+    pub fn socket(vault_path: &Path) -> Option<PathBuf> {
+        let path = socket_path(vault_path);
+        path.exists().then_some(path)
+    }
+
+    /// 连接 `socket` 上的 agent，它保存的是头部为 `header` 的密码库的密钥时返回通过它加密
+    /// 和解密的后端
+    pub fn connect(socket: &Path, header: &KeyHeader) -> Option<Box<dyn CryptoBackend>> {
+        let mut response = Vec::new();
+        request(socket, HEADER, &mut response).ok()?;
+        // agent 以同样的方式序列化头部，内容相同即为同一个密钥
+        if response != serde_json::to_vec(header).ok()? {
+            return None;
+        }
+        Some(Box::new(AgentBackend { socket: socket.to_path_buf(), header: header.clone() }))
+    }
+
+    /// 停止密码库 `vault_path` 的 agent，返回是否有 agent 在运行
+    pub fn stop(vault_path: &Path) -> bool {
+        request(&socket_path(vault_path), STOP, &mut Vec::new()).is_ok()
+    }
+
+    /// 停止当前用户的所有 agent
+    pub fn stop_all() {
+        let Ok(entries) = fs::read_dir(socket_dir()) else { return };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with("agent-") {
+                let _ = request(&entry.path(), STOP, &mut Vec::new());
+            }
+        }
+    }
+
+    /// 为密码库 `vault_path` 运行 agent，直到收到停止请求、超过 `timeout`，或者按
+    /// `autolock` 因空闲或系统休眠而退出
+    ///
+    /// 不在前台运行时先 fork，由子进程调用 `unlock` 解锁（锁定的内存不会被 fork 继承），
+    /// 准备好之后脱离终端；父进程等到这时输出子进程的 PID 后退出。
+    pub fn run(
+        vault_path: &Path,
+        foreground: bool,
+        timeout: Option<Duration>,
+        autolock: autolock::Settings,
+        unlock: impl FnOnce() -> Result<Box<dyn CryptoBackend>, Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let path = socket_path(vault_path);
+        if UnixStream::connect(&path).is_ok() {
+            return Err(AppError::from(t!("这个密码库的 agent 已在运行")).into());
+        }
+        create_socket_dir(&socket_dir())?;
+
+        let daemon = if foreground { None } else { Some(Daemon::fork()?) };
+        let backend = unlock()?;
+        // 上次没有正常退出时留下的套接字
+        let _ = fs::remove_file(&path);
+        let listener = UnixListener::bind(&path)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        match daemon {
+            Some(daemon) => daemon.detach()?,
+            None => println!("{}", t!("agent 正在运行，按 Ctrl-C 停止")),
+        }
+        // 到时间、空闲过久或系统休眠时向自己发送停止请求
+        let autolock = Arc::new(autolock.start());
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        {
+            let autolock = Arc::clone(&autolock);
+            let path = path.clone();
+            thread::spawn(move || {
+                autolock.wait(deadline);
+                let _ = request(&path, STOP, &mut Vec::new());
+            });
+        }
+
+        serve(&listener, &*backend, &autolock);
+        autolock.stop();
+        let _ = fs::remove_file(&path);
+        Ok(())
+    }
+
+    // 通过 agent 加密和解密的后端
+    struct AgentBackend {
+        socket: PathBuf,
+        header: KeyHeader,
+    }
+
+    impl AgentBackend {
+        fn apply(&self, op: u8, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+            let mut stream = connect_stream(&self.socket)?;
+            send(&mut stream, op, &[cipher.name().as_bytes(), nonce, aad, data.as_ref()])?;
+            data.truncate(0);
+            receive(&mut stream, data)
+        }
+    }
+
+    impl CryptoBackend for AgentBackend {
+        fn key_header(&self) -> KeyHeader {
+            self.header.clone()
+        }
+
+        fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+            self.apply(ENCRYPT, cipher, nonce, aad, data)
+        }
+
+        fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+            self.apply(DECRYPT, cipher, nonce, aad, data)
+        }
+    }
+
+    // 转入后台的子进程，准备好之后通知等待中的父进程
+    struct Daemon {
+        ready: fs::File,
+    }
+
+    impl Daemon {
+        // fork 出子进程；父进程等待子进程准备好（或者失败退出）后直接退出，不会返回
+        fn fork() -> Result<Self, AppError> {
+            let mut fds = [0; 2];
+            if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+            let (mut reader, writer) = unsafe { (fs::File::from_raw_fd(fds[0]), fs::File::from_raw_fd(fds[1])) };
+            match unsafe { libc::fork() } {
+                -1 => Err(io::Error::last_os_error().into()),
+                0 => Ok(Daemon { ready: writer }),
+                pid => {
+                    drop(writer);
+                    // 子进程解锁失败时已经输出了错误信息
+                    if reader.read_exact(&mut [0]).is_err() {
+                        std::process::exit(1);
+                    }
+                    println!("{}", t!("agent 已在后台运行（PID {}），用 passman agent --stop 或 passman lock 停止", pid));
+                    std::process::exit(0);
+                }
+            }
+        }
+
+        // 脱离终端：开始新的会话，标准输入输出改为 /dev/null，然后通知父进程
+        fn detach(mut self) -> Result<(), AppError> {
+            let null = fs::OpenOptions::new().read(true).write(true).open("/dev/null")?;
+            unsafe {
+                libc::setsid();
+                for fd in 0..3 {
+                    libc::dup2(null.as_raw_fd(), fd);
+                }
+            }
+            Ok(self.ready.write_all(&[1])?)
+        }
+    }
+
+    // 依次处理连接，直到收到停止请求；不属于当前用户的连接直接关闭
+    fn serve(listener: &UnixListener, backend: &dyn CryptoBackend, autolock: &AutoLock) {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            if !peer_uid(&stream).is_ok_and(|peer| peer == uid()) {
+                continue;
+            }
+            let _ = stream.set_read_timeout(Some(IO_TIMEOUT));
+            let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+            if let Ok(true) = handle(&mut stream, backend) {
+                return;
+            }
+            autolock.touch();
+        }
+    }
+
+    // 处理一个请求，返回是否要求停止
+    fn handle(stream: &mut UnixStream, backend: &dyn CryptoBackend) -> Result<bool, AppError> {
+        let mut op = [0];
+        stream.read_exact(&mut op)?;
+        match op[0] {
+            HEADER => respond(stream, Ok(&serde_json::to_vec(&backend.key_header())?))?,
+            STOP => {
+                respond(stream, Ok(&[]))?;
+                return Ok(true);
+            }
+            op @ (ENCRYPT | DECRYPT) => {
+                let (mut cipher, mut nonce, mut aad) = (Vec::new(), Vec::new(), Vec::new());
+                read_field(stream, &mut cipher)?;
+                read_field(stream, &mut nonce)?;
+                read_field(stream, &mut aad)?;
+                // 加密请求的数据是明文，保存在锁定的内存中
+                let mut data = SecretBytes::from_slice(&[]);
+                read_field(stream, &mut data)?;
+                let result = match std::str::from_utf8(&cipher).ok().and_then(Cipher::from_name) {
+                    Some(cipher) if op == ENCRYPT => backend.encrypt(cipher, &nonce, &aad, &mut data),
+                    Some(cipher) => backend.decrypt(cipher, &nonce, &aad, &mut data),
+                    None => Err(AppError::from("不支持的加密算法")),
+                };
+                respond(stream, result.map(|()| &data[..]))?;
+            }
+            _ => return Err(AppError::from("未知的 agent 请求")),
+        }
+        Ok(false)
+    }
+
+    // 返回处理结果；错误信息不翻译，由客户端按自己的语言显示
+    fn respond(stream: &mut UnixStream, result: Result<&[u8], AppError>) -> io::Result<()> {
+        match result {
+            Ok(data) => {
+                stream.write_all(&[OK])?;
+                write_field(stream, data)
+            }
+            Err(AppError::Aes(message)) => {
+                stream.write_all(&[AEAD_FAILED])?;
+                write_field(stream, message.as_bytes())
+            }
+            Err(AppError::Other(message)) => {
+                stream.write_all(&[FAILED])?;
+                write_field(stream, message.as_bytes())
+            }
+            Err(e) => {
+                stream.write_all(&[FAILED])?;
+                write_field(stream, e.to_string().as_bytes())
+            }
+        }
+    }
+
+    // 发送一个没有字段的请求，成功时把响应追加到 `response`
+    fn request(socket: &Path, op: u8, response: &mut dyn Buffer) -> Result<(), AppError> {
+        let mut stream = connect_stream(socket)?;
+        send(&mut stream, op, &[])?;
+        receive(&mut stream, response)
+    }
+
+    // 连接 agent，确认它属于当前用户
+    fn connect_stream(socket: &Path) -> Result<UnixStream, AppError> {
+        let stream = UnixStream::connect(socket)?;
+        if peer_uid(&stream)? != uid() {
+            return Err(AppError::from(t!("{} 上的 agent 不属于当前用户", socket.display())));
+        }
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    fn send(stream: &mut UnixStream, op: u8, fields: &[&[u8]]) -> io::Result<()> {
+        stream.write_all(&[op])?;
+        for field in fields {
+            write_field(stream, field)?;
+        }
+        Ok(())
+    }
+
+    // 读取响应，成功时把内容追加到 `response`，否则还原 agent 返回的错误
+    fn receive(stream: &mut UnixStream, response: &mut dyn Buffer) -> Result<(), AppError> {
+        let mut status = [0];
+        stream.read_exact(&mut status)?;
+        if status[0] == OK {
+            return read_field(stream, response);
+        }
+        let mut message = Vec::new();
+        read_field(stream, &mut message)?;
+        let message = String::from_utf8_lossy(&message).into_owned();
+        match status[0] {
+            AEAD_FAILED => Err(AppError::Aes(message)),
+            _ => Err(AppError::Other(message)),
+        }
+    }
+
+    fn write_field(stream: &mut UnixStream, data: &[u8]) -> io::Result<()> {
+        let length = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "数据过长"))?;
+        stream.write_all(&length.to_be_bytes())?;
+        stream.write_all(data)
+    }
+
+    // 读取一个字段追加到 `buffer`；中转用的缓冲区用后清零
+    fn read_field(stream: &mut UnixStream, buffer: &mut dyn Buffer) -> Result<(), AppError> {
+        let mut length = [0; 4];
+        stream.read_exact(&mut length)?;
+        let mut remaining = u32::from_be_bytes(length) as usize;
+        let mut chunk = [0; 4096];
+        let result = loop {
+            if remaining == 0 {
+                break Ok(());
+            }
+            let n = remaining.min(chunk.len());
+            if let Err(e) = stream.read_exact(&mut chunk[..n]) {
+                break Err(AppError::from(e));
+            }
+            if let Err(e) = buffer.extend_from_slice(&chunk[..n]) {
+                break Err(AppError::from(e));
+            }
+            remaining -= n;
+        };
+        secmem::zero(&mut chunk);
+        result
+    }
+
+    // 套接字所在目录：XDG_RUNTIME_DIR 下的 passman，没有时为临时目录下以用户 ID 区分的目录
+    fn socket_dir() -> PathBuf {
+        match env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|dir| dir.is_dir()) {
+            Some(dir) => dir.join("passman"),
+            None => env::temp_dir().join(format!("passman-{}", uid())),
+        }
+    }
+
+    fn socket_path(vault_path: &Path) -> PathBuf {
+        socket_dir().join(format!("agent-{}.sock", config::vault_id(vault_path)))
+    }
+
+    // 创建只有当前用户可以访问的目录；目录已经存在时确认它属于当前用户，其他用户无法访问
+    fn create_socket_dir(dir: &Path) -> Result<(), AppError> {
+        match fs::DirBuilder::new().mode(0o700).create(dir) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+        let metadata = fs::symlink_metadata(dir)?;
+        if !metadata.is_dir() || metadata.uid() != uid() || metadata.mode() & 0o077 != 0 {
+            return Err(AppError::from(t!("{} 不安全（应为只有当前用户可以访问的目录）", dir.display())));
+        }
+        Ok(())
+    }
+
+    fn uid() -> u32 {
+        unsafe { libc::getuid() }
+    }
+
+    // 连接另一端进程的用户 ID
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+        let mut credentials: libc::ucred = unsafe { std::mem::zeroed() };
+        let mut length = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+        let result = unsafe {
+            libc::getsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                (&mut credentials as *mut libc::ucred).cast(),
+                &mut length,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(credentials.uid)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+        let (mut uid, mut gid) = (0, 0);
+        if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(uid)
+    }
+}
+
+// 其他系统没有 Unix 套接字，不支持 agent
+#[cfg(not(unix))]
+mod unsupported {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use passman::AppError;
+    use passman::crypto::{CryptoBackend, KeyHeader};
+
+    use crate::autolock;
+    use crate::i18n::t;
+
+    pub fn socket(_vault_path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    pub fn connect(_socket: &Path, _header: &KeyHeader) -> Option<Box<dyn CryptoBackend>> {
+        None
+    }
+
+    pub fn stop(_vault_path: &Path) -> bool {
+        false
+    }
+
+    pub fn stop_all() {}
+
+    pub fn run(
+        _vault_path: &Path,
+        _foreground: bool,
+        _timeout: Option<Duration>,
+        _autolock: autolock::Settings,
+        _unlock: impl FnOnce() -> Result<Box<dyn CryptoBackend>, Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        Err(AppError::from(t!("agent 仅支持类 Unix 系统")).into())
+    }
+}
@@ -0,0 +1,43 @@
+// Ansible Vault 的密码客户端（`passman ansible-vault-client --vault-id <ID>`）
+//
+// Ansible 把文件名以 `-client` 结尾（可以带扩展名）的密码文件当作客户端脚本：运行它时加上
+// `--vault-id <ID>`，从标准输出读取这个 vault ID 的密码。客户端不认识这个 vault ID 时按约定
+// 以 2 退出，Ansible 据此给出错误或尝试其他密码。
+//
+// 密码保存为 ansible-vault 文件夹中的账号，用户名为 vault ID。把 passman 链接为
+// `passman-vault-client` 即可，以这个名字运行时自动加上 `ansible-vault-client` 子命令。
+
+use passman::{Account, AppError, Vault};
+
+use crate::i18n::t;
+
+/// 在 Ansible 配置中使用的程序名称
+pub const PROGRAM: &str = "passman-vault-client";
+/// 保存 Vault 密码的文件夹
+pub const FOLDER: &str = "ansible-vault";
+/// 没有指定 vault ID 时 Ansible 使用的 ID
+pub const DEFAULT_ID: &str = "default";
+/// 不认识 vault ID 时的退出码
+pub const UNKNOWN_ID: i32 = 2;
+
+/// 保存 `vault_id` 的密码的账号
+pub fn find<'a>(vault: &'a Vault, vault_id: &str) -> Option<&'a Account> {
+    vault.list().iter().find(|account| account.in_folder(FOLDER) && account.username == vault_id)
+}
+
+/// 保存 `vault_id` 的密码，已有时替换；返回是否有修改
+pub fn set(vault: &mut Vault, vault_id: &str, password: &str) -> Result<bool, AppError> {
+    if let Some(id) = find(vault, vault_id).map(|account| account.id.clone()) {
+        let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+        if account.password == password {
+            return Ok(false);
+        }
+        account.set_password(password);
+        account.touch();
+        return Ok(true);
+    }
+    let mut account = Account::new(vault_id, password, "");
+    account.set_folder(FOLDER);
+    vault.add(account)?;
+    Ok(true)
+}
@@ -0,0 +1,287 @@
+// 本机的 REST API（`passman serve`），供图形界面、编辑器插件和 Raycast、Alfred 等启动器使用
+//
+// 启动时解锁一次密码库，之后只在内存中保留密钥，与 agent 一样按自动锁定的设置在空闲过久或
+// 系统休眠时退出。每个请求重新读取密码库文件并在处理期间锁定它，与同时运行的其他 passman
+// 命令互不覆盖。只监听本机地址；请求以 `Authorization: Bearer <令牌>` 认证，令牌保存在数据
+// 目录中只有当前用户可以读取的 api-token 文件里。响应不带 CORS 头，网页中的脚本无法读取。
+//
+//   GET  /v1/accounts           账号列表（不含密码），可以用 q、tag、folder、type 筛选
+//   GET  /v1/accounts/<id>      一个账号的全部内容，ID 可以只写开头几位
+//   POST /v1/accounts           添加账号，内容与 `add --from` 的 JSON 条目相同，?generate=N 生成密码
+//   GET  /v1/accounts/<id>/otp  当前的一次性密码
+//
+// 响应是 JSON，账号的结构与 `--output json` 相同；出错时为 `{"error": "说明"}`。
+
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use aes_gcm::aead::Buffer;
+use base64::{Engine as _, engine::general_purpose};
+use serde::Serialize;
+
+use passman::crypto::{Cipher, CryptoBackend, KeyHeader};
+use passman::generator::{self, PasswordOptions};
+use passman::otp::OtpKind;
+use passman::search;
+use passman::{atomic, timestamp, Account, AppError, EntryKind, Selector, Vault};
+
+use crate::autolock::{self, Reason};
+use crate::config;
+use crate::entry_file::EntryFile;
+use crate::http_server::{Connection, Request, Response};
+use crate::i18n::{self, t};
+use crate::output::EntryJson;
+use crate::AccountFilter;
+
+// 添加账号的请求的最大长度
+const MAX_BODY: u64 = 1024 * 1024;
+
+// 服务器的状态：密钥和请求之间不变的设置
+struct Server<'a> {
+    vault_path: &'a Path,
+    backend: Rc<dyn CryptoBackend>,
+    token: String,
+    password: PasswordOptions,
+}
+
+/// 共用服务器保存的密钥，每个请求打开密码库时交给它（`secret-service` 也这样使用）
+pub struct SharedBackend(pub Rc<dyn CryptoBackend>);
+
+// 出错时的状态码和说明
+struct Failure(u16, String);
+
+#[derive(Serialize)]
+struct OtpJson {
+    code: String,
+    // TOTP 验证码剩余的有效秒数
+    remaining: Option<u64>,
+    // 生成这个 HOTP 验证码使用的计数器
+    counter: Option<u64>,
+}
+
+/// 访问令牌文件，`renew` 为真或文件不存在时生成新的令牌
+pub fn token_file(renew: bool) -> Result<PathBuf, AppError> {
+    let path = config::data_dir().ok_or_else(|| AppError::from(t!("无法确定数据目录")))?.join("api-token");
+    if renew || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        atomic::write_private(&path, general_purpose::URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>()))?;
+    }
+    Ok(path)
+}
+
+/// 监听 `listen`，只接受本机地址
+pub fn bind(listen: &str) -> Result<TcpListener, AppError> {
+    let invalid = || AppError::from(t!("无效的监听地址: {}", listen));
+    let addrs: Vec<SocketAddr> = listen.to_socket_addrs().map_err(|_| invalid())?.collect();
+    if addrs.is_empty() {
+        return Err(invalid());
+    }
+    if !addrs.iter().all(|addr| addr.ip().is_loopback()) {
+        return Err(AppError::from(t!("REST API 只能监听本机地址（例如 127.0.0.1:7599）")));
+    }
+    TcpListener::bind(&addrs[..]).map_err(|e| AppError::from(t!("无法监听 {}: {}", listen, e)))
+}
+
+/// 以 `backend` 中的密钥处理请求，直到按自动锁定的设置退出（返回原因）或进程被终止
+pub fn run(
+    listener: TcpListener,
+    vault_path: &Path,
+    backend: Box<dyn CryptoBackend>,
+    token_file: &Path,
+    password: PasswordOptions,
+    autolock: autolock::Settings,
+) -> Result<Option<Reason>, Box<dyn Error>> {
+    let token = fs::read_to_string(token_file)?.trim().to_string();
+    let server = Server { vault_path, backend: Rc::from(backend), token, password };
+    let address = listener.local_addr()?;
+    println!("{}", t!("REST API 正在监听 http://{}，访问令牌保存在 {}，按 Ctrl-C 停止", address, token_file.display()));
+
+    // 应当锁定时连接自己，让等待中的 accept 返回
+    let autolock = Arc::new(autolock.start());
+    let stopped = Arc::new(AtomicBool::new(false));
+    {
+        let (autolock, stopped) = (Arc::clone(&autolock), Arc::clone(&stopped));
+        thread::spawn(move || {
+            autolock.wait(None);
+            stopped.store(true, Ordering::SeqCst);
+            let _ = TcpStream::connect(address);
+        });
+    }
+    for stream in listener.incoming() {
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else { continue };
+        autolock.touch();
+        let _ = server.serve(stream);
+    }
+    let reason = autolock.reason();
+    autolock.stop();
+    Ok(reason)
+}
+
+impl Server<'_> {
+    // 处理一个连接上的一个请求
+    fn serve(&self, stream: TcpStream) -> io::Result<()> {
+        let mut connection = Connection::new(stream)?;
+        let Some(request) = connection.read_request()? else {
+            return connection.respond(Response::status(400));
+        };
+        let response = self.handle(&request, &mut connection);
+        println!("{} {} {} {}", timestamp::format(timestamp::now()), request.method, request.path, response.status);
+        connection.respond(response)
+    }
+
+    fn handle(&self, request: &Request, connection: &mut Connection) -> Response {
+        if !request.credentials("Bearer").is_some_and(|token| same_token(&token, &self.token)) {
+            return error(Failure(401, t!("缺少访问令牌或令牌不正确").to_string())).header("WWW-Authenticate", "Bearer");
+        }
+        let segments: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+        let result = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["v1", "accounts"]) => self.list(request),
+            ("POST", ["v1", "accounts"]) => match connection.read_body(request, MAX_BODY) {
+                Ok(body) => self.add(request, &body),
+                Err(response) => return response,
+            },
+            ("GET", ["v1", "accounts", id]) => self.get(id),
+            ("GET", ["v1", "accounts", id, "otp"]) => self.otp(id),
+            (_, ["v1", "accounts"] | ["v1", "accounts", _] | ["v1", "accounts", _, "otp"]) => {
+                return error(Failure(405, t!("不支持的请求方法").to_string()));
+            }
+            _ => Err(Failure(404, t!("没有这个接口").to_string())),
+        };
+        result.unwrap_or_else(error)
+    }
+
+    fn list(&self, request: &Request) -> Result<Response, Failure> {
+        let query = request.query("q").filter(|q| !q.trim().is_empty()).map(|q| search::Query::new(q, search::Mode::Substring)).transpose()?;
+        let kind = request.query("type").map(parse_kind).transpose()?;
+        let tags: Vec<String> = request.query("tag").map(str::to_string).into_iter().collect();
+        let filter = AccountFilter { tags: &tags, folder: request.query("folder"), kind, query: None };
+        let vault = self.open()?;
+        let entries: Vec<EntryJson> = vault
+            .list()
+            .iter()
+            .filter(|account| filter.matches(account))
+            .filter(|account| query.as_ref().is_none_or(|query| query.matches(account, &search::ALL_FIELDS)))
+            .map(|account| EntryJson::summary(account, false))
+            .collect();
+        json(200, &entries)
+    }
+
+    fn get(&self, id: &str) -> Result<Response, Failure> {
+        let mut vault = self.open()?;
+        let account = select(&vault, id)?;
+        let response = json(200, &EntryJson::detail(account, true, true))?;
+        let id = account.id.clone();
+        crate::record_use(&mut vault, &id);
+        Ok(response)
+    }
+
+    fn add(&self, request: &Request, body: &[u8]) -> Result<Response, Failure> {
+        let text = std::str::from_utf8(body).map_err(|e| Failure(400, e.to_string()))?;
+        let mut file = EntryFile::parse_json(text).map_err(|message| Failure(400, message))?;
+        if let Some(length) = request.query("generate") {
+            let mut options = self.password.clone();
+            if !length.is_empty() {
+                options.length = length.parse().map_err(|_| Failure(400, t!("无效的密码长度: {}", length)))?;
+            }
+            file.password = generator::generate_password(&options)?;
+        }
+        let account = crate::new_account(&file).map_err(bad_request)?;
+        let mut vault = self.open()?;
+        let id = vault.add(account).map_err(bad_request)?;
+        crate::save_vault(&mut vault)?;
+        let account = vault.get(&id).ok_or_else(|| Failure(500, t!("账号不存在").to_string()))?;
+        json(201, &EntryJson::detail(account, true, true))
+    }
+
+    fn otp(&self, id: &str) -> Result<Response, Failure> {
+        let mut vault = self.open()?;
+        let id = select(&vault, id)?.id.clone();
+        let account = vault.get_mut(&id).ok_or_else(|| Failure(404, t!("账号不存在").to_string()))?;
+        let otp = account.otp.as_mut().ok_or_else(|| Failure(404, t!("该账号未设置一次性密码").to_string()))?;
+        let code = otp.generate()?;
+        let response = match otp.kind {
+            OtpKind::Hotp { counter } => OtpJson { code, remaining: None, counter: Some(counter - 1) },
+            OtpKind::Totp { .. } | OtpKind::Steam => {
+                let remaining = otp.kind.period().map(|period| period - timestamp::now() % period);
+                OtpJson { code, remaining, counter: None }
+            }
+        };
+        // HOTP 计数器已递增，需要立即保存
+        if response.counter.is_some() {
+            crate::save_vault(&mut vault)?;
+        }
+        crate::record_use(&mut vault, &id);
+        json(200, &response)
+    }
+
+    // 用保存的密钥打开密码库，直到请求处理完才释放锁
+    fn open(&self) -> Result<Vault, Failure> {
+        let backend = Rc::clone(&self.backend);
+        Ok(Vault::open_with_backend(self.vault_path, |_| Ok(Box::new(SharedBackend(backend))))?)
+    }
+}
+
+impl CryptoBackend for SharedBackend {
+    fn key_header(&self) -> KeyHeader {
+        self.0.key_header()
+    }
+
+    fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        self.0.encrypt(cipher, nonce, aad, data)
+    }
+
+    fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        self.0.decrypt(cipher, nonce, aad, data)
+    }
+}
+
+impl From<AppError> for Failure {
+    fn from(e: AppError) -> Self {
+        Failure(500, i18n::error_message(&e))
+    }
+}
+
+// ID 以 `id` 开头的唯一账号，没有时为 404，有多个时为 409
+fn select<'a>(vault: &'a Vault, id: &str) -> Result<&'a Account, Failure> {
+    vault.select(&Selector { id: Some(id), username: None, site: None, kind: None }).map_err(|e| {
+        let found = vault.list().iter().any(|account| account.id.starts_with(&id.to_lowercase()));
+        Failure(if found { 409 } else { 404 }, i18n::error_message(&e))
+    })
+}
+
+// 条目类型，与命令行一样接受 api-key 和 api_key
+fn parse_kind(name: &str) -> Result<EntryKind, Failure> {
+    serde_json::from_value(serde_json::Value::String(name.trim().to_lowercase().replace('-', "_")))
+        .map_err(|_| Failure(400, t!("未知的条目类型: {}（可选 login、card、identity、api-key、note、ssh-key、wifi）", name)))
+}
+
+fn bad_request(e: AppError) -> Failure {
+    Failure(400, i18n::error_message(&e))
+}
+
+fn json(status: u16, value: &impl Serialize) -> Result<Response, Failure> {
+    let body = serde_json::to_vec(value).map_err(AppError::from)?;
+    Ok(Response::new(status, "application/json", body))
+}
+
+fn error(Failure(status, message): Failure) -> Response {
+    let body = serde_json::json!({ "error": message }).to_string().into_bytes();
+    Response::new(status, "application/json", body)
+}
+
+// 逐字节比较，比较时间与内容无关
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
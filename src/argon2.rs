@@ -0,0 +1,467 @@
+// Argon2id 实现 (RFC 9106, 版本 0x13)
+//
+// 依赖的 BLAKE2b 也在本文件中实现，避免引入额外的依赖。
+
+// Argon2 版本号
+const VERSION: u32 = 0x13;
+// 每个内存块包含的 64 位字数量 (1024 字节)
+const QWORDS_IN_BLOCK: usize = 128;
+// 每条通道划分的分段数量
+const SYNC_POINTS: usize = 4;
+
+// Argon2 变体
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Argon2d = 0,
+    Argon2i = 1,
+    Argon2id = 2,
+}
+
+// 计算 Argon2id 哈希
+pub fn argon2id(password: &[u8], salt: &[u8], m_cost: u32, t_cost: u32, p_cost: u32, out: &mut [u8]) {
+    hash(Variant::Argon2id, password, salt, m_cost, t_cost, p_cost, out);
+}
+
+// 计算指定变体的 Argon2 哈希
+pub fn hash(
+    variant: Variant,
+    password: &[u8],
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    out: &mut [u8],
+) {
+    let lanes = p_cost.max(1) as usize;
+    let passes = t_cost.max(1) as usize;
+
+    // 内存块数量向下取整为 4 * p 的倍数，且不少于 8 * p
+    let memory_blocks = (m_cost as usize).max(8 * lanes);
+    let segment_length = memory_blocks / (lanes * SYNC_POINTS);
+    let lane_length = segment_length * SYNC_POINTS;
+    let memory_blocks = lane_length * lanes;
+
+    // 计算初始哈希 H0
+    let mut h = Blake2b::new(64);
+    h.update(&p_cost.to_le_bytes());
+    h.update(&(out.len() as u32).to_le_bytes());
+    h.update(&m_cost.to_le_bytes());
+    h.update(&t_cost.to_le_bytes());
+    h.update(&VERSION.to_le_bytes());
+    h.update(&(variant as u32).to_le_bytes());
+    h.update(&(password.len() as u32).to_le_bytes());
+    h.update(password);
+    h.update(&(salt.len() as u32).to_le_bytes());
+    h.update(salt);
+    // 不使用密钥 K 与附加数据 X
+    h.update(&0u32.to_le_bytes());
+    h.update(&0u32.to_le_bytes());
+    let mut h0 = [0u8; 72];
+    h.finalize(&mut h0[..64]);
+
+    let mut memory = vec![[0u64; QWORDS_IN_BLOCK]; memory_blocks];
+
+    // 初始化每条通道的前两个块
+    for lane in 0..lanes {
+        h0[68..72].copy_from_slice(&(lane as u32).to_le_bytes());
+        for i in 0..2 {
+            h0[64..68].copy_from_slice(&(i as u32).to_le_bytes());
+            let mut bytes = [0u8; 1024];
+            blake2b_long(&h0, &mut bytes);
+            memory[lane * lane_length + i] = block_from_bytes(&bytes);
+        }
+    }
+
+    let instance = Instance {
+        variant,
+        passes,
+        lanes,
+        lane_length,
+        segment_length,
+        memory_blocks,
+    };
+
+    for pass in 0..passes {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                instance.fill_segment(&mut memory, pass, lane, slice);
+            }
+        }
+    }
+
+    // 将每条通道的最后一个块异或得到最终块
+    let mut final_block = memory[lane_length - 1];
+    for lane in 1..lanes {
+        let last = &memory[lane * lane_length + lane_length - 1];
+        for (f, l) in final_block.iter_mut().zip(last.iter()) {
+            *f ^= l;
+        }
+    }
+
+    blake2b_long(&block_to_bytes(&final_block), out);
+}
+
+type Block = [u64; QWORDS_IN_BLOCK];
+
+// 参与填充过程的参数
+struct Instance {
+    variant: Variant,
+    passes: usize,
+    lanes: usize,
+    lane_length: usize,
+    segment_length: usize,
+    memory_blocks: usize,
+}
+
+impl Instance {
+    // 填充一个分段
+    fn fill_segment(&self, memory: &mut [Block], pass: usize, lane: usize, slice: usize) {
+        let data_independent = match self.variant {
+            Variant::Argon2i => true,
+            Variant::Argon2d => false,
+            Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+
+        let zero_block = [0u64; QWORDS_IN_BLOCK];
+        let mut input_block = [0u64; QWORDS_IN_BLOCK];
+        let mut address_block = [0u64; QWORDS_IN_BLOCK];
+
+        if data_independent {
+            input_block[0] = pass as u64;
+            input_block[1] = lane as u64;
+            input_block[2] = slice as u64;
+            input_block[3] = self.memory_blocks as u64;
+            input_block[4] = self.passes as u64;
+            input_block[5] = self.variant as u64;
+        }
+
+        let mut starting_index = 0;
+        if pass == 0 && slice == 0 {
+            starting_index = 2;
+            if data_independent {
+                next_addresses(&mut address_block, &mut input_block, &zero_block);
+            }
+        }
+
+        let lane_start = lane * self.lane_length;
+        let segment_start = lane_start + slice * self.segment_length;
+
+        for i in starting_index..self.segment_length {
+            let curr_offset = segment_start + i;
+            // 通道的第一个块以通道的最后一个块作为前驱
+            let prev_offset = if curr_offset == lane_start {
+                lane_start + self.lane_length - 1
+            } else {
+                curr_offset - 1
+            };
+
+            let pseudo_rand = if data_independent {
+                if i.is_multiple_of(QWORDS_IN_BLOCK) {
+                    next_addresses(&mut address_block, &mut input_block, &zero_block);
+                }
+                address_block[i % QWORDS_IN_BLOCK]
+            } else {
+                memory[prev_offset][0]
+            };
+
+            let ref_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                ((pseudo_rand >> 32) as usize) % self.lanes
+            };
+
+            let ref_index = self.index_alpha(pass, slice, i, pseudo_rand & 0xFFFF_FFFF, ref_lane == lane);
+            let ref_block = memory[self.lane_length * ref_lane + ref_index];
+            let prev_block = memory[prev_offset];
+            fill_block(&prev_block, &ref_block, &mut memory[curr_offset], pass != 0);
+        }
+    }
+
+    // 计算参考块在通道中的位置
+    fn index_alpha(&self, pass: usize, slice: usize, index: usize, pseudo_rand: u64, same_lane: bool) -> usize {
+        let reference_area_size = if pass == 0 {
+            if slice == 0 {
+                index - 1
+            } else if same_lane {
+                slice * self.segment_length + index - 1
+            } else if index == 0 {
+                slice * self.segment_length - 1
+            } else {
+                slice * self.segment_length
+            }
+        } else if same_lane {
+            self.lane_length - self.segment_length + index - 1
+        } else if index == 0 {
+            self.lane_length - self.segment_length - 1
+        } else {
+            self.lane_length - self.segment_length
+        };
+
+        let mut relative_position = pseudo_rand;
+        relative_position = (relative_position * relative_position) >> 32;
+        let relative_position =
+            reference_area_size - 1 - ((reference_area_size as u64 * relative_position) >> 32) as usize;
+
+        let start_position = if pass != 0 && slice != SYNC_POINTS - 1 {
+            (slice + 1) * self.segment_length
+        } else {
+            0
+        };
+
+        (start_position + relative_position) % self.lane_length
+    }
+}
+
+// 生成下一批数据无关的地址
+fn next_addresses(address_block: &mut Block, input_block: &mut Block, zero_block: &Block) {
+    input_block[6] += 1;
+    fill_block(zero_block, input_block, address_block, false);
+    let tmp = *address_block;
+    fill_block(zero_block, &tmp, address_block, false);
+}
+
+// 压缩函数 G，结果写入 next（with_xor 时与原有内容异或）
+fn fill_block(prev: &Block, reference: &Block, next: &mut Block, with_xor: bool) {
+    let mut r = [0u64; QWORDS_IN_BLOCK];
+    for i in 0..QWORDS_IN_BLOCK {
+        r[i] = prev[i] ^ reference[i];
+    }
+
+    let mut tmp = r;
+    if with_xor {
+        for i in 0..QWORDS_IN_BLOCK {
+            tmp[i] ^= next[i];
+        }
+    }
+
+    // 按行应用置换 P
+    for row in 0..8 {
+        let base = row * 16;
+        let mut v = [0u64; 16];
+        v.copy_from_slice(&r[base..base + 16]);
+        permute(&mut v);
+        r[base..base + 16].copy_from_slice(&v);
+    }
+
+    // 按列应用置换 P
+    for col in 0..8 {
+        let mut v = [0u64; 16];
+        for row in 0..8 {
+            v[2 * row] = r[row * 16 + 2 * col];
+            v[2 * row + 1] = r[row * 16 + 2 * col + 1];
+        }
+        permute(&mut v);
+        for row in 0..8 {
+            r[row * 16 + 2 * col] = v[2 * row];
+            r[row * 16 + 2 * col + 1] = v[2 * row + 1];
+        }
+    }
+
+    for i in 0..QWORDS_IN_BLOCK {
+        next[i] = tmp[i] ^ r[i];
+    }
+}
+
+// 基于 BLAKE2b 轮函数的置换 P
+fn permute(v: &mut [u64; 16]) {
+    gb(v, 0, 4, 8, 12);
+    gb(v, 1, 5, 9, 13);
+    gb(v, 2, 6, 10, 14);
+    gb(v, 3, 7, 11, 15);
+    gb(v, 0, 5, 10, 15);
+    gb(v, 1, 6, 11, 12);
+    gb(v, 2, 7, 8, 13);
+    gb(v, 3, 4, 9, 14);
+}
+
+fn fblamka(x: u64, y: u64) -> u64 {
+    let m = 0xFFFF_FFFFu64;
+    x.wrapping_add(y).wrapping_add(2u64.wrapping_mul((x & m).wrapping_mul(y & m)))
+}
+
+fn gb(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = fblamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = fblamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = fblamka(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = fblamka(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+fn block_from_bytes(bytes: &[u8; 1024]) -> Block {
+    let mut block = [0u64; QWORDS_IN_BLOCK];
+    for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+        block[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    block
+}
+
+fn block_to_bytes(block: &Block) -> [u8; 1024] {
+    let mut bytes = [0u8; 1024];
+    for (i, word) in block.iter().enumerate() {
+        bytes[i * 8..i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
+}
+
+// 变长哈希函数 H'
+fn blake2b_long(input: &[u8], out: &mut [u8]) {
+    let out_len = out.len();
+    let len_prefix = (out_len as u32).to_le_bytes();
+
+    if out_len <= 64 {
+        let mut h = Blake2b::new(out_len);
+        h.update(&len_prefix);
+        h.update(input);
+        h.finalize(out);
+        return;
+    }
+
+    let mut v = [0u8; 64];
+    let mut h = Blake2b::new(64);
+    h.update(&len_prefix);
+    h.update(input);
+    h.finalize(&mut v);
+
+    let mut pos = 0;
+    out[pos..pos + 32].copy_from_slice(&v[..32]);
+    pos += 32;
+
+    while out_len - pos > 64 {
+        let mut h = Blake2b::new(64);
+        h.update(&v);
+        h.finalize(&mut v);
+        out[pos..pos + 32].copy_from_slice(&v[..32]);
+        pos += 32;
+    }
+
+    let mut h = Blake2b::new(out_len - pos);
+    h.update(&v);
+    h.finalize(&mut out[pos..]);
+}
+
+// BLAKE2b 初始向量
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+// BLAKE2b 消息字调度表
+const BLAKE2B_SIGMA: [[usize; 16]; 12] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+];
+
+// 无密钥的 BLAKE2b 增量哈希
+struct Blake2b {
+    h: [u64; 8],
+    t: u128,
+    buf: [u8; 128],
+    buf_len: usize,
+    out_len: usize,
+}
+
+impl Blake2b {
+    fn new(out_len: usize) -> Self {
+        let mut h = BLAKE2B_IV;
+        h[0] ^= 0x0101_0000 ^ out_len as u64;
+        Blake2b {
+            h,
+            t: 0,
+            buf: [0u8; 128],
+            buf_len: 0,
+            out_len,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // 仅当后续还有数据时才压缩缓冲区，保证最后一块在 finalize 中处理
+            if self.buf_len == 128 {
+                self.t += 128;
+                let block = self.buf;
+                self.compress(&block, false);
+                self.buf_len = 0;
+            }
+            let take = (128 - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&data[..take]);
+            self.buf_len += take;
+            data = &data[take..];
+        }
+    }
+
+    fn finalize(mut self, out: &mut [u8]) {
+        self.t += self.buf_len as u128;
+        for b in &mut self.buf[self.buf_len..] {
+            *b = 0;
+        }
+        let block = self.buf;
+        self.compress(&block, true);
+
+        let mut bytes = [0u8; 64];
+        for (i, word) in self.h.iter().enumerate() {
+            bytes[i * 8..i * 8 + 8].copy_from_slice(&word.to_le_bytes());
+        }
+        out.copy_from_slice(&bytes[..self.out_len]);
+    }
+
+    fn compress(&mut self, block: &[u8; 128], last: bool) {
+        let mut m = [0u64; 16];
+        for (i, chunk) in block.chunks_exact(8).enumerate() {
+            m[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+
+        let mut v = [0u64; 16];
+        v[..8].copy_from_slice(&self.h);
+        v[8..].copy_from_slice(&BLAKE2B_IV);
+        v[12] ^= self.t as u64;
+        v[13] ^= (self.t >> 64) as u64;
+        if last {
+            v[14] = !v[14];
+        }
+
+        for s in &BLAKE2B_SIGMA {
+            g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+            g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+            g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+            g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+            g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+            g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+            g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+            g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+        }
+
+        for i in 0..8 {
+            self.h[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
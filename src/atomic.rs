@@ -0,0 +1,102 @@
+//! 原子地写入文件
+//!
+//! 先写入同一目录下的临时文件并同步到磁盘，再重命名替换目标文件。进程崩溃或断电时，
+//! 目标文件要么还是原来的内容，要么是完整的新内容，不会留下写了一半的文件。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// 原子地写入文件，已存在的文件保留原来的权限
+pub fn write(path: impl AsRef<Path>, data: impl AsRef<[u8]>) -> io::Result<()> {
+    write_with(path.as_ref(), false, |file| file.write_all(data.as_ref()))
+}
+
+/// 原子地写入只有当前用户可以读写的文件（Unix 上权限为 0600）
+pub fn write_private(path: impl AsRef<Path>, data: impl AsRef<[u8]>) -> io::Result<()> {
+    write_with(path.as_ref(), true, |file| file.write_all(data.as_ref()))
+}
+
+/// 由 `f` 向临时文件写入内容（用于边生成边写入的大文件），成功后替换目标文件
+///
+/// `f` 失败时删除临时文件，目标文件保持不变。
+pub fn write_with<T, E: From<io::Error>>(
+    path: &Path,
+    private: bool,
+    f: impl FnOnce(&mut File) -> Result<T, E>,
+) -> Result<T, E> {
+    // 目标是符号链接时替换链接指向的文件，而不是链接本身
+    let path = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let (temp_path, mut file) = create_temp(&path, private)?;
+
+    let result = f(&mut file).and_then(|value| {
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, &path)?;
+        Ok(value)
+    });
+    match result {
+        Ok(value) => {
+            sync_dir(&path);
+            Ok(value)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+// 在目标文件所在目录中创建临时文件，例如 `.vault.json.1234.tmp`
+fn create_temp(path: &Path, private: bool) -> io::Result<(PathBuf, File)> {
+    let file_name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "无效的文件路径"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if private {
+            options.mode(0o600);
+        }
+    }
+    // 同一进程崩溃后留下的临时文件可以直接删除
+    let file = match options.open(&temp_path) {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            fs::remove_file(&temp_path)?;
+            options.open(&temp_path)?
+        }
+        result => result?,
+    };
+
+    // 替换已存在的文件时沿用它的权限
+    if !private
+        && let Ok(metadata) = fs::metadata(path)
+        && let Err(e) = file.set_permissions(metadata.permissions())
+    {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok((temp_path, file))
+}
+
+// 同步目录，确保重命名本身也已写入磁盘；部分文件系统不支持，失败时忽略
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
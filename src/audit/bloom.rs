@@ -0,0 +1,151 @@
+// 由 HIBP 泄露密码列表生成的布隆过滤器，用于完全离线的泄露检查
+//
+// 文件格式（整数均为小端序）：
+//   8 字节  魔数 "PMBLOOM1"
+//   8 字节  位数 m
+//   4 字节  哈希函数个数 k
+//   8 字节  收录的摘要数量 n
+//   其余    位数组（第 i 位位于第 i / 8 字节的第 i % 8 位）
+//
+// 每个密码先取 SHA-1 摘要，再用摘要的前 16 字节做双重哈希得到 k 个位置。
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::sha1;
+
+// 文件魔数
+const MAGIC: &[u8; 8] = b"PMBLOOM1";
+// 文件头长度
+const HEADER_LENGTH: usize = 28;
+// 最多使用的哈希函数个数
+const MAX_HASHES: u32 = 32;
+
+/// 布隆过滤器
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    m: u64,
+    k: u32,
+    n: u64,
+}
+
+impl BloomFilter {
+    /// 读取布隆过滤器文件
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        let data = fs::read(path)?;
+        if data.len() < HEADER_LENGTH || &data[..8] != MAGIC {
+            return Err(AppError::from(format!("{} 不是 passman 泄露数据库文件", path.display())));
+        }
+
+        let m = u64::from_le_bytes(data[8..16].try_into().unwrap());
+        let k = u32::from_le_bytes(data[16..20].try_into().unwrap());
+        let n = u64::from_le_bytes(data[20..28].try_into().unwrap());
+        let bits = data[HEADER_LENGTH..].to_vec();
+        if m == 0 || k == 0 || k > MAX_HASHES || (bits.len() as u64) < m.div_ceil(8) {
+            return Err(AppError::from(format!("泄露数据库文件已损坏: {}", path.display())));
+        }
+
+        Ok(BloomFilter { bits, m, k, n })
+    }
+
+    /// 密码可能出现在泄露中（存在误报，不会漏报）
+    pub fn contains(&self, password: &str) -> bool {
+        self.contains_digest(&sha1::sha1(password.as_bytes()))
+    }
+
+    /// 收录的摘要数量
+    pub fn count(&self) -> u64 {
+        self.n
+    }
+
+    /// 估计的误报率
+    pub fn false_positive_rate(&self) -> f64 {
+        let (m, k, n) = (self.m as f64, self.k as f64, self.n as f64);
+        (1.0 - (-k * n / m).exp()).powf(k)
+    }
+
+    fn contains_digest(&self, digest: &[u8; 20]) -> bool {
+        positions(digest, self.m, self.k).all(|i| self.bits[(i / 8) as usize] & (1 << (i % 8)) != 0)
+    }
+}
+
+/// 由 HIBP 的 SHA-1 泄露密码列表（每行 `摘要:次数` 或只有摘要）生成布隆过滤器，返回收录的摘要数量
+///
+/// 列表需要读取两遍：第一遍统计行数以确定过滤器大小，第二遍写入。
+pub fn build_bloom<R: Read + Seek>(
+    input: R,
+    mut output: impl Write,
+    false_positive_rate: f64,
+) -> Result<u64, AppError> {
+    if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+        return Err(AppError::from("误报率必须在 0 与 1 之间"));
+    }
+
+    let mut reader = BufReader::new(input);
+    let mut n = 0u64;
+    for_each_digest(&mut reader, |_| n += 1)?;
+    if n == 0 {
+        return Err(AppError::from("列表中没有 SHA-1 摘要"));
+    }
+
+    // m = -n·ln(p) / ln(2)²，k = m/n·ln(2)
+    let ln2 = std::f64::consts::LN_2;
+    let m = ((-(n as f64) * false_positive_rate.ln() / (ln2 * ln2)).ceil() as u64).max(8);
+    let k = ((m as f64 / n as f64 * ln2).round() as u32).clamp(1, MAX_HASHES);
+
+    let mut bits = vec![0u8; m.div_ceil(8) as usize];
+    reader.seek(SeekFrom::Start(0))?;
+    for_each_digest(&mut reader, |digest| {
+        for i in positions(digest, m, k) {
+            bits[(i / 8) as usize] |= 1 << (i % 8);
+        }
+    })?;
+
+    output.write_all(MAGIC)?;
+    output.write_all(&m.to_le_bytes())?;
+    output.write_all(&k.to_le_bytes())?;
+    output.write_all(&n.to_le_bytes())?;
+    output.write_all(&bits)?;
+    output.flush()?;
+    Ok(n)
+}
+
+// 逐行解析摘要
+fn for_each_digest(reader: &mut impl BufRead, mut f: impl FnMut(&[u8; 20])) -> Result<(), AppError> {
+    let mut line = String::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+
+        let hex = line.trim().split(':').next().unwrap_or("");
+        if hex.is_empty() {
+            continue;
+        }
+        let digest = parse_digest(hex).ok_or_else(|| AppError::from(format!("第 {} 行不是 SHA-1 摘要", number)))?;
+        f(&digest);
+    }
+}
+
+fn parse_digest(hex: &str) -> Option<[u8; 20]> {
+    if hex.len() != 40 || !hex.is_ascii() {
+        return None;
+    }
+    let mut digest = [0u8; 20];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(digest)
+}
+
+// 双重哈希：第 i 个位置为 (h1 + i·h2) mod m
+fn positions(digest: &[u8; 20], m: u64, k: u32) -> impl Iterator<Item = u64> {
+    let h1 = u64::from_le_bytes(digest[..8].try_into().unwrap());
+    let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap()) | 1;
+    (0..k as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % m)
+}
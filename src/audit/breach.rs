@@ -0,0 +1,141 @@
+// 通过 Have I Been Pwned 的 k-匿名接口检查密码是否出现在已知泄露中
+//
+// 只发送密码 SHA-1 摘要的前 5 个十六进制字符，服务器返回所有以此开头的摘要后缀，
+// 在本地比对剩余部分，因此密码及其完整摘要都不会离开本机。
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::atomic;
+use crate::error::AppError;
+use crate::sha1;
+
+// 查询接口
+const RANGE_API: &str = "https://api.pwnedpasswords.com/range/";
+// 摘要前缀长度
+const PREFIX_LENGTH: usize = 5;
+// 两次请求之间的最小间隔
+const REQUEST_INTERVAL: Duration = Duration::from_millis(200);
+// 收到 429 后的最大重试次数
+const MAX_RETRIES: u32 = 3;
+
+/// 单个密码的检查结果
+#[derive(Clone, Copy, PartialEq)]
+pub enum Breach {
+    /// 出现在泄露中的次数
+    Found(u64),
+    NotFound,
+    /// 出现在本地泄露数据库中（布隆过滤器不记录次数）
+    Listed,
+    /// 离线模式下没有对应的本地缓存
+    Unchecked,
+}
+
+/// 泄露检查器
+///
+/// 相同前缀只查询一次；每次查询的结果会保存到缓存目录，离线模式只使用缓存。
+/// 在线查询失败时如果有缓存也会退回使用缓存。
+pub struct BreachChecker {
+    cache_dir: Option<PathBuf>,
+    offline: bool,
+    ranges: HashMap<String, Option<HashMap<String, u64>>>,
+    last_request: Option<Instant>,
+}
+
+impl BreachChecker {
+    pub fn new(cache_dir: Option<PathBuf>, offline: bool) -> Self {
+        BreachChecker { cache_dir, offline, ranges: HashMap::new(), last_request: None }
+    }
+
+    /// 检查密码
+    pub fn check(&mut self, password: &str) -> Result<Breach, AppError> {
+        let digest: String = sha1::sha1(password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect();
+        let (prefix, suffix) = digest.split_at(PREFIX_LENGTH);
+
+        if !self.ranges.contains_key(prefix) {
+            let range = self.load_range(prefix)?;
+            self.ranges.insert(prefix.to_string(), range);
+        }
+
+        Ok(match &self.ranges[prefix] {
+            None => Breach::Unchecked,
+            Some(range) => match range.get(suffix) {
+                Some(&count) if count > 0 => Breach::Found(count),
+                _ => Breach::NotFound,
+            },
+        })
+    }
+
+    // 获取一个前缀对应的摘要后缀及次数
+    fn load_range(&mut self, prefix: &str) -> Result<Option<HashMap<String, u64>>, AppError> {
+        let cached = self.cache_dir.as_ref().map(|dir| dir.join(prefix));
+        let read_cache = || cached.as_ref().and_then(|path| fs::read_to_string(path).ok()).map(|s| parse_range(&s));
+
+        if self.offline {
+            return Ok(read_cache());
+        }
+
+        match self.fetch(prefix) {
+            Ok(body) => {
+                if let Some(path) = &cached {
+                    // 缓存只是为了离线使用，写入失败不影响检查
+                    let _ = path.parent().map(fs::create_dir_all);
+                    let _ = atomic::write(path, &body);
+                }
+                Ok(Some(parse_range(&body)))
+            }
+            Err(e) => read_cache().map(Some).ok_or(e),
+        }
+    }
+
+    // 调用 curl 查询接口，遵守请求间隔并在被限流时等待后重试
+    fn fetch(&mut self, prefix: &str) -> Result<String, AppError> {
+        let mut backoff = Duration::from_secs(2);
+        for attempt in 0..=MAX_RETRIES {
+            if let Some(last) = self.last_request {
+                let elapsed = last.elapsed();
+                if elapsed < REQUEST_INTERVAL {
+                    thread::sleep(REQUEST_INTERVAL - elapsed);
+                }
+            }
+            self.last_request = Some(Instant::now());
+
+            let output = Command::new("curl")
+                .args(["--silent", "--show-error", "--max-time", "15", "--user-agent", "passman"])
+                .args(["--header", "Add-Padding: true", "--write-out", "\n%{http_code}"])
+                .arg(format!("{}{}", RANGE_API, prefix))
+                .output()
+                .map_err(|e| AppError::from(format!("无法运行 curl: {}", e)))?;
+            if !output.status.success() {
+                return Err(AppError::from(format!(
+                    "查询泄露数据库失败: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+
+            let text = String::from_utf8_lossy(&output.stdout);
+            let (body, status) = text.rsplit_once('\n').unwrap_or(("", &text));
+            match status.trim() {
+                "200" => return Ok(body.to_string()),
+                "429" if attempt < MAX_RETRIES => {
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                status => return Err(AppError::from(format!("查询泄露数据库失败: HTTP {}", status))),
+            }
+        }
+        Err(AppError::from("查询泄露数据库失败: 请求过于频繁"))
+    }
+}
+
+// 解析 "后缀:次数" 格式的响应
+fn parse_range(body: &str) -> HashMap<String, u64> {
+    body.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .filter_map(|(suffix, count)| Some((suffix.to_ascii_uppercase(), count.trim().parse().ok()?)))
+        .collect()
+}
@@ -0,0 +1,9 @@
+//! 密码审计：查找重复使用的密码，检查密码是否出现在已知泄露中
+
+mod bloom;
+mod breach;
+mod reuse;
+
+pub use bloom::{BloomFilter, build_bloom};
+pub use breach::{Breach, BreachChecker};
+pub use reuse::{ReuseGroup, SIMILARITY_THRESHOLD, find_reused};
@@ -0,0 +1,92 @@
+// 查找多个账号共用的相同或近似密码
+
+use strsim::normalized_levenshtein;
+
+use crate::vault::AccountStore;
+
+/// 视为近似密码的最小相似度（归一化编辑距离）
+pub const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+// 参与近似比较的最短密码长度，更短的密码之间相似度没有意义
+const MIN_SIMILAR_LENGTH: usize = 6;
+
+/// 共用相同或近似密码的一组账号
+pub struct ReuseGroup {
+    /// 组内账号的 ID（按用户名排序）
+    pub ids: Vec<String>,
+    /// 组内所有账号的密码完全相同
+    pub identical: bool,
+}
+
+/// 查找共用密码的账号，`similar` 为真时同时把近似的密码（例如只有大小写或末尾数字不同）归为一组
+///
+/// 结果按组内账号数量从多到少排列。
+pub fn find_reused(accounts: &AccountStore, similar: bool) -> Vec<ReuseGroup> {
+    let mut entries: Vec<(&str, &str, &str)> = accounts
+        .iter()
+        .filter(|account| !account.password.is_empty())
+        .map(|account| (account.username.as_str(), account.password.as_str(), account.id.as_str()))
+        .collect();
+    entries.sort();
+
+    // 并查集：相同或近似的密码合并到同一组
+    let mut parent: Vec<usize> = (0..entries.len()).collect();
+    for i in 0..entries.len() {
+        for j in i + 1..entries.len() {
+            let (a, b) = (entries[i].1, entries[j].1);
+            if a == b || (similar && is_similar(a, b)) {
+                let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                parent[root_j] = root_i;
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: Vec<Option<usize>> = vec![None; entries.len()];
+    for i in 0..entries.len() {
+        let root = find(&mut parent, i);
+        match group_of[root] {
+            Some(index) => groups[index].push(i),
+            None => {
+                group_of[root] = Some(groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+
+    let mut groups: Vec<ReuseGroup> = groups
+        .into_iter()
+        .filter(|members| members.len() > 1)
+        .map(|members| ReuseGroup {
+            identical: members.iter().all(|&i| entries[i].1 == entries[members[0]].1),
+            ids: members.iter().map(|&i| entries[i].2.to_string()).collect(),
+        })
+        .collect();
+    groups.sort_by(|a, b| b.ids.len().cmp(&a.ids.len()).then_with(|| a.ids.cmp(&b.ids)));
+    groups
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[i] = root;
+    root
+}
+
+// 忽略大小写后足够相似，或者去掉末尾的数字和符号后相同
+fn is_similar(a: &str, b: &str) -> bool {
+    if a.chars().count() < MIN_SIMILAR_LENGTH || b.chars().count() < MIN_SIMILAR_LENGTH {
+        return false;
+    }
+
+    let (a, b) = (a.to_lowercase(), b.to_lowercase());
+    let stem = |s: &str| s.trim_end_matches(|c: char| c.is_ascii_digit() || c.is_ascii_punctuation()).to_string();
+    let (stem_a, stem_b) = (stem(&a), stem(&b));
+    if stem_a.chars().count() >= MIN_SIMILAR_LENGTH && stem_a == stem_b {
+        return true;
+    }
+
+    normalized_levenshtein(&a, &b) >= SIMILARITY_THRESHOLD
+}
@@ -0,0 +1,185 @@
+// 空闲和系统休眠时自动锁定
+//
+// agent 和交互式界面在一段时间没有使用之后、以及系统休眠时丢弃内存中的密钥，之后需要重新
+// 解锁。systemd-logind 在休眠前发出 PrepareForSleep 信号（通过 gdbus 监听）；没有 logind
+// 的系统在唤醒后由时钟发现：单调时钟在休眠期间停止，系统时间照常前进。
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::Config;
+
+// 比较两个时钟的间隔，以及系统时间比单调时钟多走多久时认为发生了休眠
+const CLOCK_INTERVAL: Duration = Duration::from_secs(5);
+const CLOCK_JUMP: Duration = Duration::from_secs(30);
+
+/// 自动锁定的原因
+#[derive(Clone, Copy)]
+pub enum Reason {
+    Idle,
+    Suspend,
+}
+
+/// 自动锁定的设置
+#[derive(Clone, Copy)]
+pub struct Settings {
+    /// 空闲多久后锁定，`None` 表示不因空闲锁定
+    pub idle: Option<Duration>,
+    /// 是否在系统休眠时锁定
+    pub suspend: bool,
+}
+
+impl Settings {
+    /// 按配置项 autolock.idle（分钟，0 表示不启用）和 autolock.suspend（默认启用）
+    pub fn from_config(config: &Config) -> Self {
+        Settings {
+            idle: config.get_int("autolock.idle").and_then(|minutes| minutes_to_duration(minutes.max(0) as u64)),
+            suspend: config.get_bool("autolock.suspend").unwrap_or(true),
+        }
+    }
+
+    /// 从现在开始计算空闲时间，并开始监视系统休眠
+    pub fn start(self) -> AutoLock {
+        let state = Arc::new((Mutex::new(State { last_used: Instant::now(), suspended: false }), Condvar::new()));
+        let monitor = match self.suspend {
+            true => {
+                watch_clock(Arc::downgrade(&state));
+                watch_logind(Arc::downgrade(&state))
+            }
+            false => None,
+        };
+        AutoLock { idle: self.idle, state, monitor: Mutex::new(monitor) }
+    }
+}
+
+/// 分钟数转为时长，0 表示不启用
+pub fn minutes_to_duration(minutes: u64) -> Option<Duration> {
+    (minutes > 0).then(|| Duration::from_secs(minutes * 60))
+}
+
+/// 记录最后一次使用的时间和是否发生过休眠
+pub struct AutoLock {
+    idle: Option<Duration>,
+    state: Arc<(Mutex<State>, Condvar)>,
+    // 监听 logind 信号的 gdbus
+    monitor: Mutex<Option<Child>>,
+}
+
+struct State {
+    last_used: Instant,
+    suspended: bool,
+}
+
+impl AutoLock {
+    /// 记录一次使用
+    pub fn touch(&self) {
+        self.lock().last_used = Instant::now();
+    }
+
+    /// 重新解锁之后从头开始
+    pub fn reset(&self) {
+        let mut state = self.lock();
+        state.last_used = Instant::now();
+        state.suspended = false;
+    }
+
+    /// 现在是否应当锁定
+    pub fn reason(&self) -> Option<Reason> {
+        self.check(&self.lock())
+    }
+
+    /// 等到应当锁定时返回原因；先到达 `deadline` 时返回 `None`
+    pub fn wait(&self, deadline: Option<Instant>) -> Option<Reason> {
+        let (_, condvar) = &*self.state;
+        let mut state = self.lock();
+        loop {
+            if let Some(reason) = self.check(&state) {
+                return Some(reason);
+            }
+            let now = Instant::now();
+            if deadline.is_some_and(|deadline| deadline <= now) {
+                return None;
+            }
+            let wake = [self.idle.map(|idle| state.last_used + idle), deadline].into_iter().flatten().min();
+            state = match wake {
+                Some(wake) => condvar.wait_timeout(state, wake.saturating_duration_since(now)).unwrap_or_else(PoisonError::into_inner).0,
+                None => condvar.wait(state).unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// 停止监视系统休眠
+    pub fn stop(&self) {
+        if let Some(mut monitor) = self.monitor.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            let _ = monitor.kill();
+            let _ = monitor.wait();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn check(&self, state: &State) -> Option<Reason> {
+        if state.suspended {
+            Some(Reason::Suspend)
+        } else if self.idle.is_some_and(|idle| state.last_used.elapsed() >= idle) {
+            Some(Reason::Idle)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for AutoLock {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// 记录发生了休眠并唤醒等待的线程；`AutoLock` 已经释放时返回 false
+fn suspended(state: &Weak<(Mutex<State>, Condvar)>) -> bool {
+    let Some(state) = state.upgrade() else { return false };
+    let (lock, condvar) = &*state;
+    lock.lock().unwrap_or_else(PoisonError::into_inner).suspended = true;
+    condvar.notify_all();
+    true
+}
+
+// 定期比较系统时间和单调时钟，在唤醒后发现休眠
+fn watch_clock(state: Weak<(Mutex<State>, Condvar)>) {
+    thread::spawn(move || {
+        loop {
+            let (instant, time) = (Instant::now(), SystemTime::now());
+            thread::sleep(CLOCK_INTERVAL);
+            let wall = time.elapsed().unwrap_or_default();
+            if state.strong_count() == 0 || (wall > instant.elapsed() + CLOCK_JUMP && !suspended(&state)) {
+                return;
+            }
+        }
+    });
+}
+
+// 监听 logind 的 PrepareForSleep 信号；没有 gdbus 时返回 None，只靠时钟发现休眠
+fn watch_logind(state: Weak<(Mutex<State>, Condvar)>) -> Option<Child> {
+    let mut child = Command::new("gdbus")
+        .args(["monitor", "--system", "--dest", "org.freedesktop.login1", "--object-path", "/org/freedesktop/login1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let stdout = child.stdout.take()?;
+    thread::spawn(move || {
+        // 例如 /org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if line.contains("PrepareForSleep (true") && !suspended(&state) {
+                return;
+            }
+        }
+    });
+    Some(child)
+}
@@ -0,0 +1,129 @@
+//! 加密的便携备份文件 (.pmbk)
+//!
+//! 备份文件是一个 JSON 文档：头部记录格式版本、创建时间和 Argon2id 参数，账号数据
+//! 使用 AES-256-GCM 加密，头部作为附加认证数据参与校验，因此任何篡改都会导致恢复失败。
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+use crate::kdf::{self, KdfParams};
+use crate::vault::AccountStore;
+
+/// 备份文件格式标识
+pub const FORMAT: &str = "passman-backup";
+/// 当前备份格式版本（版本 2 起账号数据为带 ID 的列表）
+pub const VERSION: u32 = 2;
+
+// 初始化向量长度
+const NONCE_LENGTH: usize = 12;
+
+// 备份文件头部，序列化结果作为附加认证数据
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    // 创建时间（Unix 时间戳，秒）
+    created: u64,
+    kdf: KdfParams,
+}
+
+// 备份文件结构
+#[derive(Serialize, Deserialize)]
+struct BackupFile {
+    #[serde(flatten)]
+    header: Header,
+    iv: String,
+    encrypted_data: String,
+}
+
+// 加密前的备份内容
+#[derive(Serialize, Deserialize)]
+struct Content {
+    // 账号数量，恢复时用于校验
+    count: usize,
+    #[serde(deserialize_with = "crate::vault::deserialize_accounts")]
+    accounts: AccountStore,
+}
+
+/// 解密后的备份
+pub struct Backup {
+    /// 创建时间（Unix 时间戳，秒）
+    pub created: u64,
+    pub accounts: AccountStore,
+}
+
+/// 判断数据是否为 passman 备份文件
+pub fn is_backup(data: &[u8]) -> bool {
+    #[derive(Deserialize)]
+    struct Probe {
+        format: String,
+    }
+
+    serde_json::from_slice::<Probe>(data).is_ok_and(|probe| probe.format == FORMAT)
+}
+
+/// 使用备份密码加密账号数据，生成备份文件内容
+pub fn create(accounts: &AccountStore, password: &str) -> Result<Vec<u8>, AppError> {
+    let header = Header {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| AppError::from(e.to_string()))?
+            .as_secs(),
+        kdf: KdfParams::generate(),
+    };
+    let key = kdf::derive_key(password, &header.kdf)?;
+
+    let content = serde_json::to_vec(&Content { count: accounts.len(), accounts: accounts.clone() })?;
+    let aad = serde_json::to_vec(&header)?;
+
+    let iv = rand::random::<[u8; NONCE_LENGTH]>();
+    let cipher = Aes256Gcm::new((&key).into());
+    let encrypted_data = cipher.encrypt(Nonce::from_slice(&iv), Payload { msg: &content, aad: &aad })?;
+
+    let file = BackupFile {
+        header,
+        iv: general_purpose::STANDARD.encode(iv),
+        encrypted_data: general_purpose::STANDARD.encode(encrypted_data),
+    };
+    Ok(serde_json::to_vec_pretty(&file)?)
+}
+
+/// 校验并解密备份文件
+pub fn open(data: &[u8], password: &str) -> Result<Backup, AppError> {
+    let file: BackupFile = serde_json::from_slice(data).map_err(|_| AppError::from("不是有效的 passman 备份文件"))?;
+    let header = file.header;
+    if header.format != FORMAT {
+        return Err(AppError::from("不是有效的 passman 备份文件"));
+    }
+    if header.version > VERSION {
+        return Err(AppError::from(format!("备份文件版本 {} 过新，请升级 passman", header.version)));
+    }
+
+    let key = kdf::derive_key(password, &header.kdf)?;
+    let iv = general_purpose::STANDARD.decode(&file.iv)?;
+    if iv.len() != NONCE_LENGTH {
+        return Err(AppError::from("备份文件已损坏"));
+    }
+    let encrypted_data = general_purpose::STANDARD.decode(&file.encrypted_data)?;
+    let aad = serde_json::to_vec(&header)?;
+
+    let cipher = Aes256Gcm::new((&key).into());
+    let content = cipher
+        .decrypt(Nonce::from_slice(&iv), Payload { msg: &encrypted_data, aad: &aad })
+        .map_err(|_| AppError::from("备份密码错误或文件已被篡改"))?;
+
+    let content: Content = serde_json::from_slice(&content)?;
+    if content.count != content.accounts.len() {
+        return Err(AppError::from("备份文件校验失败：账号数量不一致"));
+    }
+
+    Ok(Backup { created: header.created, accounts: content.accounts })
+}
@@ -0,0 +1,85 @@
+// Bech32 编码 (BIP 173)，用于 age 的公钥 (age1...) 和私钥 (AGE-SECRET-KEY-1...)
+//
+// 与 age 一样不限制总长度。
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ u32::from(value);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn expand_hrp(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes().map(|b| b >> 5).chain([0]).chain(hrp.bytes().map(|b| b & 31))
+}
+
+/// 编码为小写的 Bech32 字符串，`hrp` 为小写
+pub fn encode(hrp: &str, data: &[u8]) -> String {
+    // 8 位分组转为 5 位分组，末尾补零
+    let mut values = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &byte in data {
+        acc = (acc << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push(((acc >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        values.push(((acc << (5 - bits)) & 31) as u8);
+    }
+
+    let checksum = polymod(expand_hrp(hrp).chain(values.iter().copied()).chain([0; 6])) ^ 1;
+    values.extend((0..6).map(|i| ((checksum >> (5 * (5 - i))) & 31) as u8));
+
+    let mut text = format!("{}1", hrp);
+    text.extend(values.iter().map(|&v| CHARSET[v as usize] as char));
+    text
+}
+
+/// 解码 Bech32 字符串，返回小写的 hrp 和数据；大小写混用或校验和错误时返回 None
+pub fn decode(text: &str) -> Option<(String, Vec<u8>)> {
+    if text.bytes().any(|b| b.is_ascii_lowercase()) && text.bytes().any(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    let text = text.to_ascii_lowercase();
+    let separator = text.rfind('1')?;
+    let (hrp, rest) = (&text[..separator], &text[separator + 1..]);
+    if hrp.is_empty() || rest.len() < 6 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return None;
+    }
+    let values: Vec<u8> = rest
+        .bytes()
+        .map(|b| CHARSET.iter().position(|&c| c == b).map(|v| v as u8))
+        .collect::<Option<_>>()?;
+    if polymod(expand_hrp(hrp).chain(values.iter().copied())) != 1 {
+        return None;
+    }
+
+    // 5 位分组转回 8 位分组，补上的位必须为零
+    let mut data = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &value in &values[..values.len() - 6] {
+        acc = (acc << 5) | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc & ((1 << bits) - 1)) != 0 {
+        return None;
+    }
+    Some((hrp.to_string(), data))
+}
@@ -0,0 +1,42 @@
+// 在默认浏览器中打开网址
+//
+// 与剪贴板一样调用平台自带的命令，可以通过 BROWSER 环境变量指定浏览器。
+
+use std::env;
+use std::process::{Command, Stdio};
+
+use crate::i18n::t;
+
+// 打开网址的系统命令
+#[cfg(target_os = "macos")]
+const OPEN_COMMAND: &[&str] = &["open"];
+
+#[cfg(windows)]
+const OPEN_COMMAND: &[&str] = &["cmd", "/C", "start", ""];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const OPEN_COMMAND: &[&str] = &["xdg-open"];
+
+// 打开网址，没有协议时按 https 处理
+pub fn open(url: &str) -> Result<(), String> {
+    let url = if url.contains("://") { url.to_string() } else { format!("https://{}", url) };
+
+    let browser = env::var("BROWSER").ok().filter(|b| !b.trim().is_empty());
+    let command: Vec<&str> = match &browser {
+        Some(browser) => vec![browser.as_str()],
+        None => OPEN_COMMAND.to_vec(),
+    };
+
+    let status = Command::new(command[0])
+        .args(&command[1..])
+        .arg(&url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| t!("无法启动浏览器 {}: {}", command[0], e))?;
+    if !status.success() {
+        return Err(t!("{} 打开网址失败", command[0]));
+    }
+    Ok(())
+}
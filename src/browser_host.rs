@@ -0,0 +1,347 @@
+// 浏览器扩展的本地消息主机（native messaging host）
+//
+// 浏览器为扩展启动 `passman browser-host`，两者通过标准输入输出交换消息：每条消息是 4 字节
+// 本机字节序的长度加 UTF-8 编码的 JSON。主机不请求主密钥（标准输入就是消息通道），只用运行中
+// 的 agent、解锁会话或 age 身份文件打开密码库，都不可用时回复 `locked`，由扩展提示用户先运行
+// `passman agent`。标准输出只用于消息，警告等写到标准错误，浏览器会记入自己的日志。
+//
+// 请求是 `{"action": ..., ...}`，回复 `{"ok": true, ...}` 或
+// `{"ok": false, "error": "locked" | "not_found" | "bad_request" | "failed", "message": "说明"}`：
+//
+//   status                 是否已解锁：`{"unlocked": true}`
+//   logins {url}           与页面网站相符的登录：`{"logins": [{"id", "username", "url", "otp"}]}`
+//   get {id, url}          一个登录的用户名和密码：`{"login": {"id", "username", "password", "url", "otp"}}`
+//   otp {id, url}          一次性密码：`{"code", "remaining"}`
+//
+// get 和 otp 也要带上页面的网址，账号与页面的网站不符时回复 not_found，即使扩展的内容脚本
+// 被页面欺骗，也只能拿到这个网站的密码。
+//
+// `passman install-browser-host` 把主机注册到浏览器：在数据目录中写入启动 passman 的脚本，
+// 再在各浏览器的 NativeMessagingHosts 目录中写入指向它的清单，清单中列出允许连接的扩展。
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use passman::otp::OtpKind;
+use passman::{atomic, timestamp, Account, AppError, EntryKind, Vault};
+
+use crate::config;
+use crate::i18n::{self, t};
+
+/// 清单中的主机名称，扩展以它连接主机
+pub const HOST_NAME: &str = "passman";
+// 一条消息的最大长度（浏览器发给主机的消息最长 4 GB，这里只需要很短的请求）
+const MAX_MESSAGE: u32 = 1024 * 1024;
+
+/// 支持的浏览器
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Browser {
+    /// Google Chrome
+    Chrome,
+    /// Chromium
+    Chromium,
+    /// Brave
+    Brave,
+    /// Mozilla Firefox
+    Firefox,
+}
+
+const ALL_BROWSERS: [Browser; 4] = [Browser::Chrome, Browser::Chromium, Browser::Brave, Browser::Firefox];
+
+// 回复中的登录，密码只在 get 中给出
+#[derive(Serialize)]
+struct LoginJson<'a> {
+    id: &'a str,
+    username: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<&'a str>,
+    url: Option<&'a str>,
+    otp: bool,
+}
+
+// 出错时回复的错误代码和说明
+struct Failure(&'static str, String);
+
+/// 处理浏览器发来的消息，直到浏览器关闭标准输入；`open` 不请求主密钥地打开密码库，
+/// 无法解锁时返回 None
+pub fn run(open: impl Fn() -> Result<Option<Vault>, AppError>) -> Result<(), AppError> {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    while let Some(message) = read_message(&mut input)? {
+        let reply = match serde_json::from_slice::<Value>(&message) {
+            Ok(request) => handle(&request, &open),
+            Err(e) => Err(Failure("bad_request", e.to_string())),
+        };
+        let reply = match reply {
+            Ok(mut reply) => {
+                reply["ok"] = Value::Bool(true);
+                reply
+            }
+            Err(Failure(error, message)) => json!({ "ok": false, "error": error, "message": message }),
+        };
+        write_message(&mut output, &reply)?;
+    }
+    Ok(())
+}
+
+fn handle(request: &Value, open: &impl Fn() -> Result<Option<Vault>, AppError>) -> Result<Value, Failure> {
+    let field = |name: &str| request.get(name).and_then(Value::as_str);
+    let required = |name: &'static str| field(name).ok_or_else(|| Failure("bad_request", t!("缺少 {}", name)));
+    let open = || match open() {
+        Ok(Some(vault)) => Ok(vault),
+        Ok(None) => Err(Failure("locked", t!("密码库已锁定，请先运行 passman agent").to_string())),
+        Err(e) => Err(Failure("failed", i18n::error_message(&e))),
+    };
+    match field("action").unwrap_or_default() {
+        "status" => Ok(json!({ "unlocked": open().is_ok() })),
+        "logins" => {
+            let host = page_host(required("url")?)?;
+            let vault = open()?;
+            let logins: Vec<LoginJson> = vault.list().iter().filter(|account| matches_site(account, &host)).map(|account| login(account, false)).collect();
+            Ok(json!({ "logins": logins }))
+        }
+        "get" => {
+            let host = page_host(required("url")?)?;
+            let mut vault = open()?;
+            let account = find(&vault, required("id")?, &host)?;
+            let reply = json!({ "login": login(account, true) });
+            let id = account.id.clone();
+            crate::record_use(&mut vault, &id);
+            Ok(reply)
+        }
+        "otp" => {
+            let host = page_host(required("url")?)?;
+            let mut vault = open()?;
+            let id = find(&vault, required("id")?, &host)?.id.clone();
+            let failed = |e: AppError| Failure("failed", i18n::error_message(&e));
+            let account = vault.get_mut(&id).ok_or_else(|| Failure("not_found", t!("账号不存在").to_string()))?;
+            let otp = account.otp.as_mut().ok_or_else(|| Failure("not_found", t!("该账号未设置一次性密码").to_string()))?;
+            let code = otp.generate().map_err(failed)?;
+            let reply = match otp.kind.period() {
+                Some(period) => json!({ "code": code, "remaining": period - timestamp::now() % period }),
+                None => json!({ "code": code, "remaining": null }),
+            };
+            // HOTP 计数器已递增，需要立即保存
+            if matches!(otp.kind, OtpKind::Hotp { .. }) {
+                crate::save_vault(&mut vault).map_err(failed)?;
+            }
+            crate::record_use(&mut vault, &id);
+            Ok(reply)
+        }
+        action => Err(Failure("bad_request", t!("未知的操作: {}", action))),
+    }
+}
+
+fn login(account: &Account, with_password: bool) -> LoginJson<'_> {
+    LoginJson {
+        id: &account.id,
+        username: &account.username,
+        password: with_password.then_some(account.password.as_str()),
+        url: account.url.as_deref(),
+        otp: account.otp.is_some(),
+    }
+}
+
+// ID 为 `id` 且与页面的网站相符的登录
+fn find<'a>(vault: &'a Vault, id: &str, host: &str) -> Result<&'a Account, Failure> {
+    vault
+        .get(id)
+        .filter(|account| matches_site(account, host))
+        .ok_or_else(|| Failure("not_found", t!("这个网站没有 ID 为 {} 的账号", id)))
+}
+
+// 页面网址中的主机名，只接受 http 和 https
+fn page_host(url: &str) -> Result<String, Failure> {
+    let valid = url.starts_with("https://") || url.starts_with("http://");
+    host(url).filter(|_| valid).ok_or_else(|| Failure("bad_request", t!("无效的网址: {}", url)))
+}
+
+// 网址或域名中的主机名（小写，去掉开头的 www.）
+fn host(text: &str) -> Option<String> {
+    let rest = text.split_once("://").map_or(text, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next()?.rsplit('@').next()?.split(':').next()?.to_lowercase();
+    let host = host.strip_prefix("www.").map(str::to_string).unwrap_or(host);
+    (host.contains('.') && !host.starts_with('.') && !host.ends_with('.')).then_some(host)
+}
+
+// 账号的网址或备注中的域名与页面的主机相同，或者页面在它的子域名上
+fn matches_site(account: &Account, page: &str) -> bool {
+    if account.kind != EntryKind::Login {
+        return false;
+    }
+    account
+        .url
+        .iter()
+        .map(String::as_str)
+        .chain(account.notes.split_whitespace())
+        .filter_map(host)
+        .any(|site| page == site || page.strip_suffix(&site).is_some_and(|prefix| prefix.ends_with('.')))
+}
+
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>, AppError> {
+    let mut length = [0; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let length = u32::from_ne_bytes(length);
+    if length > MAX_MESSAGE {
+        return Err(AppError::from(t!("浏览器发来的消息过长（{} 字节）", length)));
+    }
+    let mut message = vec![0; length as usize];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<(), AppError> {
+    let data = serde_json::to_vec(message)?;
+    output.write_all(&(data.len() as u32).to_ne_bytes())?;
+    output.write_all(&data)?;
+    output.flush()?;
+    Ok(())
+}
+
+impl Browser {
+    pub fn name(self) -> &'static str {
+        match self {
+            Browser::Chrome => "Chrome",
+            Browser::Chromium => "Chromium",
+            Browser::Brave => "Brave",
+            Browser::Firefox => "Firefox",
+        }
+    }
+
+    // 浏览器的配置目录和其中存放清单的目录
+    fn dirs(self) -> Option<(PathBuf, PathBuf)> {
+        let profile = match self {
+            #[cfg(target_os = "macos")]
+            Browser::Firefox => dirs_next::home_dir()?.join("Library/Application Support/Mozilla"),
+            #[cfg(not(target_os = "macos"))]
+            Browser::Firefox => dirs_next::home_dir()?.join(".mozilla"),
+            #[cfg(target_os = "macos")]
+            browser => dirs_next::home_dir()?.join("Library/Application Support").join(browser.profile_name()),
+            #[cfg(not(target_os = "macos"))]
+            browser => dirs_next::config_dir()?.join(browser.profile_name()),
+        };
+        let hosts = match self {
+            #[cfg(not(target_os = "macos"))]
+            Browser::Firefox => profile.join("native-messaging-hosts"),
+            _ => profile.join("NativeMessagingHosts"),
+        };
+        Some((profile, hosts))
+    }
+
+    // Chrome 系浏览器的配置目录名
+    fn profile_name(self) -> &'static str {
+        match self {
+            #[cfg(target_os = "macos")]
+            Browser::Chrome => "Google/Chrome",
+            #[cfg(not(target_os = "macos"))]
+            Browser::Chrome => "google-chrome",
+            #[cfg(target_os = "macos")]
+            Browser::Chromium => "Chromium",
+            #[cfg(not(target_os = "macos"))]
+            Browser::Chromium => "chromium",
+            Browser::Brave => "BraveSoftware/Brave-Browser",
+            Browser::Firefox => "",
+        }
+    }
+
+    fn manifest(self) -> Option<PathBuf> {
+        self.dirs().map(|(_, hosts)| hosts.join(format!("{}.json", HOST_NAME)))
+    }
+}
+
+/// 为 `browsers`（为空时为本机已安装的所有浏览器）注册主机，允许 `extension_ids` 中的扩展
+/// 连接，主机打开密码库 `vault_path`；返回注册了的浏览器和清单的路径
+pub fn install(browsers: &[Browser], extension_ids: &[String], vault_path: &Path) -> Result<Vec<(Browser, PathBuf)>, AppError> {
+    if cfg!(windows) {
+        return Err(AppError::from(t!("Windows 上的本地消息主机需要写入注册表，暂不支持")));
+    }
+    // Chrome 系的扩展 ID 是 32 个 a 到 p 的字母，其余的是 Firefox 扩展的 ID
+    let (chrome_ids, firefox_ids): (Vec<&String>, Vec<&String>) =
+        extension_ids.iter().partition(|id| id.len() == 32 && id.bytes().all(|b| (b'a'..=b'p').contains(&b)));
+    let detected: Vec<Browser> = match browsers.is_empty() {
+        true => ALL_BROWSERS.into_iter().filter(|browser| browser.dirs().is_some_and(|(profile, _)| profile.is_dir())).collect(),
+        false => browsers.to_vec(),
+    };
+    let targets: Vec<Browser> = detected
+        .into_iter()
+        .filter(|browser| match browser {
+            Browser::Firefox => !firefox_ids.is_empty(),
+            _ => !chrome_ids.is_empty(),
+        })
+        .collect();
+    if targets.is_empty() {
+        return Err(AppError::from(t!("没有可以注册的浏览器：请用 --browser 指定浏览器，并用 --extension-id 给出对应的扩展 ID")));
+    }
+
+    let script = write_script(vault_path)?;
+    let mut installed = Vec::new();
+    for browser in targets {
+        let (_, hosts) = browser.dirs().ok_or_else(|| AppError::from(t!("无法确定主目录")))?;
+        let mut manifest = json!({
+            "name": HOST_NAME,
+            "description": "passman",
+            "path": script,
+            "type": "stdio",
+        });
+        match browser {
+            Browser::Firefox => manifest["allowed_extensions"] = json!(firefox_ids),
+            _ => manifest["allowed_origins"] = json!(chrome_ids.iter().map(|id| format!("chrome-extension://{}/", id)).collect::<Vec<_>>()),
+        }
+        fs::create_dir_all(&hosts)?;
+        let path = hosts.join(format!("{}.json", HOST_NAME));
+        atomic::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+        installed.push((browser, path));
+    }
+    Ok(installed)
+}
+
+/// 删除 `browsers`（为空时为所有浏览器）中注册的主机，返回删除了的清单；都删除后也删除启动脚本
+pub fn uninstall(browsers: &[Browser]) -> Result<Vec<(Browser, PathBuf)>, AppError> {
+    let browsers = if browsers.is_empty() { &ALL_BROWSERS[..] } else { browsers };
+    let mut removed = Vec::new();
+    for &browser in browsers {
+        if let Some(path) = browser.manifest().filter(|path| path.exists()) {
+            fs::remove_file(&path)?;
+            removed.push((browser, path));
+        }
+    }
+    if !ALL_BROWSERS.iter().any(|browser| browser.manifest().is_some_and(|path| path.exists()))
+        && let Ok(script) = script_path()
+    {
+        let _ = fs::remove_file(script);
+    }
+    Ok(removed)
+}
+
+fn script_path() -> Result<PathBuf, AppError> {
+    let dir = config::data_dir().ok_or_else(|| AppError::from(t!("无法确定数据目录")))?;
+    Ok(dir.join("browser-host").join("passman-browser-host"))
+}
+
+// 清单只能指定可执行文件，不能带参数，由这个脚本以 browser-host 子命令启动 passman
+fn write_script(vault_path: &Path) -> Result<PathBuf, AppError> {
+    let exe = env::current_exe()?;
+    let path = script_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let quote = |path: &Path| format!("'{}'", path.to_string_lossy().replace('\'', "'\\''"));
+    let script = format!("#!/bin/sh\nexec {} --vault {} browser-host \"$@\"\n", quote(&exe), quote(vault_path));
+    atomic::write_private(&path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(path)
+}
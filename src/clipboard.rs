@@ -0,0 +1,121 @@
+// 系统剪贴板
+//
+// 调用平台自带的剪贴板命令，避免链接图形库。
+
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crate::i18n::t;
+
+// 依次尝试的剪贴板命令
+#[cfg(target_os = "macos")]
+const COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+
+#[cfg(windows)]
+const COMMANDS: &[&[&str]] = &[&["clip"]];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const COMMANDS: &[&[&str]] = &[
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+];
+
+// 读取剪贴板的命令
+#[cfg(target_os = "macos")]
+const PASTE_COMMANDS: &[&[&str]] = &[&["pbpaste"]];
+
+#[cfg(windows)]
+const PASTE_COMMANDS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]];
+
+#[cfg(not(any(target_os = "macos", windows)))]
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-o"],
+    &["xsel", "--clipboard", "--output"],
+];
+
+// 隐藏的子命令，由后台进程执行清除
+pub const CLEAR_COMMAND: &str = "clear-clipboard";
+
+// 将文本复制到剪贴板
+pub fn copy(text: &str) -> Result<(), String> {
+    for command in COMMANDS {
+        let child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+        }
+
+        if child.wait().map_err(|e| e.to_string())?.success() {
+            return Ok(());
+        }
+    }
+
+    Err(t!("未找到可用的剪贴板工具").to_string())
+}
+
+// 读取剪贴板内容
+fn paste() -> Option<String> {
+    PASTE_COMMANDS.iter().find_map(|command| {
+        let output = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+}
+
+// 启动后台进程，在指定秒数后清除剪贴板
+//
+// 只把内容的哈希通过标准输入交给后台进程，剪贴板在此期间被替换为其他内容时不会清除。
+pub fn clear_after(text: &str, seconds: u64) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut command = Command::new(exe);
+    command
+        .args([CLEAR_COMMAND, &seconds.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // 脱离当前进程组，终端关闭时不会被一同结束
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(hex_digest(text).as_bytes()).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// 后台进程：等待后若剪贴板仍是原内容则清空
+pub fn run_clear(seconds: u64) -> Result<(), String> {
+    let mut expected = String::new();
+    std::io::stdin().read_to_string(&mut expected).map_err(|e| e.to_string())?;
+
+    thread::sleep(Duration::from_secs(seconds));
+
+    match paste() {
+        Some(current) if hex_digest(&current) != expected.trim() => Ok(()),
+        _ => copy(""),
+    }
+}
+
+fn hex_digest(text: &str) -> String {
+    Sha256::digest(text.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
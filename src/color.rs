@@ -0,0 +1,178 @@
+// 彩色输出
+//
+// 用 ANSI 转义序列给条目名称、警告（例如弱密码）和过期提醒着色。是否着色由 --color
+// 决定：auto 时只在标准输出是终端且未设置 NO_COLOR 时着色。各类文字的样式可以在配置
+// 文件的 [color] 表中修改，例如 `name = "bold blue"`，`none` 表示不着色。
+//
+// 表格单元格不直接包含转义序列，而是设置 prettytable 的样式，由 prettytable 在输出时
+// 加上转义序列，以免影响列宽和换行的计算。
+
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use prettytable::{Attr, Cell, Table};
+use term::Terminal;
+use term::terminfo::{TermInfo, TerminfoTerminal};
+
+use passman::AppError;
+
+use crate::config::Config;
+use crate::i18n::t;
+
+/// 何时着色
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    /// 标准输出是终端且未设置 NO_COLOR 时
+    Auto,
+    /// 总是着色
+    Always,
+    /// 从不着色
+    Never,
+}
+
+/// 需要着色的文字
+#[derive(Clone, Copy)]
+pub enum Role {
+    /// 条目名称
+    Name,
+    /// 警告，例如弱密码和已泄露的密码
+    Warning,
+    /// 过期提醒
+    Expiring,
+    /// 操作成功
+    Success,
+}
+
+// 各类文字的配置项和默认样式，与 Role 的顺序相同
+const ROLES: [(&str, &str); 4] = [
+    ("color.name", "bold cyan"),
+    ("color.warning", "red"),
+    ("color.expiring", "yellow"),
+    ("color.success", "green"),
+];
+
+const COLORS: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+// 各类文字的 SGR 参数，不着色时为 None
+static THEME: OnceLock<Option<Vec<String>>> = OnceLock::new();
+
+/// 根据 --color、NO_COLOR 和配置文件决定着色方式，在输出任何内容前调用一次
+pub fn init(mode: ColorMode, config: &Config) {
+    let enabled = match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stdout().is_terminal(),
+    };
+    let theme = enabled.then(|| {
+        ROLES
+            .iter()
+            .map(|(key, default)| {
+                // 配置文件中的样式在加载时已经检查过
+                let style = config.get_str(key).unwrap_or(default);
+                parse_style(style).unwrap_or_default()
+            })
+            .collect()
+    });
+    let _ = THEME.set(theme);
+}
+
+// 某类文字的 SGR 参数，不着色时为 None
+fn codes(role: Role) -> Option<&'static str> {
+    let codes = THEME.get()?.as_ref()?[role as usize].as_str();
+    (!codes.is_empty()).then_some(codes)
+}
+
+/// 按文字的类别着色；多行文字逐行着色
+pub fn paint(role: Role, text: &str) -> String {
+    match codes(role) {
+        Some(codes) => text
+            .split('\n')
+            .map(|line| if line.is_empty() { String::new() } else { format!("\x1b[{}m{}\x1b[0m", codes, line) })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        None => text.to_string(),
+    }
+}
+
+/// 按文字的类别设置样式的表格单元格
+pub fn cell(role: Role, text: &str) -> Cell {
+    let mut cell = Cell::new(text);
+    for code in codes(role).unwrap_or("").split(';') {
+        let attr = match code.parse::<u32>() {
+            Ok(1) => Attr::Bold,
+            Ok(2) => Attr::Dim,
+            Ok(3) => Attr::Italic(true),
+            Ok(4) => Attr::Underline(true),
+            Ok(code @ 30..=37) => Attr::ForegroundColor(code - 30),
+            Ok(code @ 90..=97) => Attr::ForegroundColor(code - 90 + 8),
+            _ => continue,
+        };
+        cell.style(attr);
+    }
+    cell
+}
+
+/// 将表格渲染为文字，着色时带有单元格样式的转义序列
+pub fn render(table: &Table) -> String {
+    if THEME.get().is_some_and(Option::is_some) {
+        // 是否着色已经由 init 决定，不再查询终端的 terminfo
+        let mut terminal = TerminfoTerminal::new_with_terminfo(Vec::new(), ansi());
+        if table.print_term(&mut terminal).is_ok() {
+            return String::from_utf8_lossy(&terminal.into_inner()).into_owned();
+        }
+    }
+    table.to_string()
+}
+
+/// 输出表格到标准输出
+pub fn print_table(table: &Table) {
+    print!("{}", render(table));
+}
+
+// 支持 16 种颜色和 parse_style 中各种样式的 ANSI 终端
+fn ansi() -> TermInfo {
+    let strings = HashMap::from([
+        ("sgr0", b"\x1b[0m".to_vec()),
+        ("bold", b"\x1b[1m".to_vec()),
+        ("dim", b"\x1b[2m".to_vec()),
+        ("sitm", b"\x1b[3m".to_vec()),
+        ("smul", b"\x1b[4m".to_vec()),
+        ("setaf", b"\x1b[%?%p1%{8}%<%t3%p1%d%e9%p1%{8}%-%d%;m".to_vec()),
+        ("setab", b"\x1b[%?%p1%{8}%<%t4%p1%d%e10%p1%{8}%-%d%;m".to_vec()),
+    ]);
+    TermInfo {
+        names: vec!["ansi".to_string()],
+        bools: HashMap::new(),
+        numbers: HashMap::from([("colors", 16)]),
+        strings,
+    }
+}
+
+/// 解析样式，例如 `bold red`、`bright-yellow`，返回 SGR 参数（`none` 为空）
+pub fn parse_style(text: &str) -> Result<String, AppError> {
+    let mut codes = Vec::new();
+    for word in text.split_whitespace() {
+        let code = match word {
+            "none" => continue,
+            "bold" => 1,
+            "dim" => 2,
+            "italic" => 3,
+            "underline" => 4,
+            _ => {
+                let (base, name) = match word.strip_prefix("bright-") {
+                    Some(name) => (90, name),
+                    None => (30, word),
+                };
+                let index = COLORS.iter().position(|color| *color == name).ok_or_else(|| {
+                    AppError::from(t!("未知的颜色或样式: {}（可用: {}、bright-<颜色>、bold、dim、italic、underline、none）", word, COLORS.join(t!("、"))))
+                })?;
+                base + index
+            }
+        };
+        codes.push(code.to_string());
+    }
+    Ok(codes.join(";"))
+}
@@ -0,0 +1,310 @@
+// 生成 shell 补全脚本
+//
+// 脚本根据 clap 的命令定义生成：每一级子命令（例如 `passman note add`）列出可用的
+// 子命令和选项，选项有固定取值（例如 `--output`）时补全这些取值，其他需要值的选项
+// 补全文件名。用户名（`-u` 选项和 `show` 等命令的用户名参数）通过
+// `passman _complete usernames` 从用户名索引中补全，命令行中的 `--vault` 会一并传入。
+
+use clap::{Command, ValueEnum};
+
+/// 支持的 shell
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+// 一级命令，`path` 为以空格分隔的命令路径，例如 `passman note add`
+struct Node {
+    path: String,
+    subcommands: Vec<(String, String)>,
+    options: Vec<Opt>,
+    // 位置参数是用户名
+    usernames: bool,
+}
+
+struct Opt {
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+    values: Vec<String>,
+    // 选项的值是用户名
+    username: bool,
+}
+
+// 值为用户名的参数
+const USERNAME_ARGS: &[&str] = &["username", "old"];
+
+impl Opt {
+    // 选项的各种写法，例如 `-u` 和 `--username`
+    fn flags(&self) -> Vec<String> {
+        let short = self.short.map(|short| format!("-{}", short));
+        let long = self.long.as_ref().map(|long| format!("--{}", long));
+        short.into_iter().chain(long).collect()
+    }
+}
+
+/// 生成补全脚本
+pub fn generate(shell: Shell, mut command: Command) -> String {
+    // build 之后才能看到全局选项和自动添加的 --help
+    command.build();
+    let mut nodes = Vec::new();
+    collect(&command, command.get_name(), &mut nodes);
+
+    match shell {
+        Shell::Bash => bash(&nodes),
+        Shell::Zsh => zsh(&nodes),
+        Shell::Fish => fish(&nodes),
+        Shell::Powershell => powershell(&nodes),
+    }
+}
+
+fn collect(command: &Command, path: &str, nodes: &mut Vec<Node>) {
+    let subcommands: Vec<&Command> = command.get_subcommands().filter(|sub| !sub.is_hide_set()).collect();
+    let is_username = |arg: &clap::Arg| USERNAME_ARGS.contains(&arg.get_id().as_str());
+    let options = command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .map(|arg| Opt {
+            long: arg.get_long().map(str::to_string),
+            short: arg.get_short(),
+            help: first_line(arg.get_help().map(ToString::to_string)),
+            takes_value: arg.get_action().takes_values(),
+            values: arg
+                .get_possible_values()
+                .iter()
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name().to_string())
+                .collect(),
+            username: is_username(arg),
+        })
+        .collect();
+    nodes.push(Node {
+        path: path.to_string(),
+        subcommands: subcommands
+            .iter()
+            .map(|sub| (sub.get_name().to_string(), first_line(sub.get_about().map(ToString::to_string))))
+            .collect(),
+        options,
+        usernames: command.get_positionals().any(is_username),
+    });
+
+    // clap 自动添加的 help 子命令只补全名称
+    for sub in subcommands.into_iter().filter(|sub| sub.get_name() != "help") {
+        collect(sub, &format!("{} {}", path, sub.get_name()), nodes);
+    }
+}
+
+fn first_line(text: Option<String>) -> String {
+    text.unwrap_or_default().lines().next().unwrap_or_default().to_string()
+}
+
+// 除顶层命令以外的所有命令路径
+fn subcommand_paths(nodes: &[Node]) -> Vec<&str> {
+    nodes.iter().skip(1).map(|node| node.path.as_str()).collect()
+}
+
+fn bash(nodes: &[Node]) -> String {
+    let mut script = String::from(
+        "_passman_usernames() {\n    local IFS=$'\\n'\n    COMPREPLY=($(compgen -W \"$(passman \"${vault[@]}\" _complete usernames 2>/dev/null)\" -- \"$cur\"))\n}\n\n",
+    );
+    script += "_passman() {\n    local cur prev cmd users i\n    local -a vault\n    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n    prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n    cmd=\"passman\"\n";
+    script += "    for ((i = 1; i < COMP_CWORD; i++)); do\n        if [[ ${COMP_WORDS[i]} == --vault ]]; then\n            vault=(--vault \"${COMP_WORDS[i+1]}\")\n        fi\n        case \"$cmd ${COMP_WORDS[i]}\" in\n";
+    script += &format!("            {})\n", quoted_patterns(&subcommand_paths(nodes)));
+    script += "                cmd=\"$cmd ${COMP_WORDS[i]}\"\n                ;;\n        esac\n    done\n\n";
+
+    // 选项的值：固定取值或者文件名（由 complete -o default 处理）
+    script += "    case \"$cmd $prev\" in\n";
+    for node in nodes {
+        for opt in node.options.iter().filter(|opt| opt.takes_value) {
+            let patterns: Vec<String> = opt.flags().iter().map(|flag| format!("{} {}", node.path, flag)).collect();
+            let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+            script += &format!("        {})\n", quoted_patterns(&patterns));
+            if opt.username {
+                script += "            _passman_usernames\n";
+            } else if opt.values.is_empty() {
+                script += "            COMPREPLY=()\n";
+            } else {
+                script += &format!("            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n", opt.values.join(" "));
+            }
+            script += "            return\n            ;;\n";
+        }
+    }
+    script += "    esac\n\n    local words\n    case \"$cmd\" in\n";
+    for node in nodes {
+        let words: Vec<String> = node
+            .subcommands
+            .iter()
+            .map(|(name, _)| name.clone())
+            .chain(node.options.iter().flat_map(Opt::flags))
+            .collect();
+        script += &format!("        \"{}\")\n            words=\"{}\"\n", node.path, words.join(" "));
+        if node.usernames {
+            script += "            users=1\n";
+        }
+        script += "            ;;\n";
+    }
+    script += "    esac\n    if [[ -n $users && $cur != -* ]]; then\n        _passman_usernames\n        return\n    fi\n";
+    script += "    COMPREPLY=($(compgen -W \"$words\" -- \"$cur\"))\n}\n\ncomplete -o default -F _passman passman\n";
+    script
+}
+
+fn quoted_patterns(patterns: &[&str]) -> String {
+    patterns.iter().map(|pattern| format!("\"{}\"", pattern)).collect::<Vec<_>>().join("|")
+}
+
+// 单引号字符串，' 写作 '\''
+fn sh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn zsh(nodes: &[Node]) -> String {
+    let mut script = String::from("#compdef passman\n\n_passman_usernames() {\n    local -a names\n    names=(${(f)\"$(passman \"${vault[@]}\" _complete usernames 2>/dev/null)\"})\n    compadd -a names\n}\n\n");
+    script += "_passman() {\n    local cmd=passman prev=${words[CURRENT-1]} users i\n    local -a vault\n";
+    script += "    for ((i = 2; i < CURRENT; i++)); do\n        if [[ ${words[i]} == --vault ]]; then\n            vault=(--vault \"${words[i+1]}\")\n        fi\n        case \"$cmd ${words[i]}\" in\n";
+    script += &format!("            ({})\n", quoted_patterns(&subcommand_paths(nodes)));
+    script += "                cmd=\"$cmd ${words[i]}\"\n                ;;\n        esac\n    done\n\n";
+
+    script += "    case \"$cmd $prev\" in\n";
+    for node in nodes {
+        for opt in node.options.iter().filter(|opt| opt.takes_value) {
+            let patterns: Vec<String> = opt.flags().iter().map(|flag| format!("{} {}", node.path, flag)).collect();
+            let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+            script += &format!("        ({})\n", quoted_patterns(&patterns));
+            if opt.username {
+                script += "            _passman_usernames\n";
+            } else if opt.values.is_empty() {
+                script += "            _files\n";
+            } else {
+                script += &format!("            compadd -- {}\n", opt.values.join(" "));
+            }
+            script += "            return\n            ;;\n";
+        }
+    }
+    script += "    esac\n\n    local -a items\n    case \"$cmd\" in\n";
+    for node in nodes {
+        script += &format!("        (\"{}\")\n            items=(\n", node.path);
+        for (name, help) in &node.subcommands {
+            script += &format!("                {}\n", sh_quote(&format!("{}:{}", name, help)));
+        }
+        for opt in &node.options {
+            for flag in opt.flags() {
+                script += &format!("                {}\n", sh_quote(&format!("{}:{}", flag, opt.help)));
+            }
+        }
+        script += "            )\n";
+        if node.usernames {
+            script += "            users=1\n";
+        }
+        script += "            ;;\n";
+    }
+    script += "    esac\n    if [[ -n $users && $PREFIX != -* ]]; then\n        _passman_usernames\n        return\n    fi\n";
+    script += "    _describe -t commands passman items\n}\n\n";
+    script += "if [ \"$funcstack[1]\" = \"_passman\" ]; then\n    _passman \"$@\"\nelse\n    compdef _passman passman\nfi\n";
+    script
+}
+
+// fish 的单引号字符串，' 和 \ 前加 \
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn fish(nodes: &[Node]) -> String {
+    let mut script = String::from("function __passman_cmd\n    set -l cmd passman\n    for token in (commandline -opc)[2..-1]\n        switch \"$cmd $token\"\n");
+    let paths: Vec<String> = subcommand_paths(nodes).iter().map(|path| format!("\"{}\"", path)).collect();
+    script += &format!("            case {}\n", paths.join(" "));
+    script += "                set cmd \"$cmd $token\"\n        end\n    end\n    echo $cmd\nend\n\n";
+    script += "function __passman_using\n    test (__passman_cmd) = \"$argv[1]\"\nend\n\n";
+    script += "function __passman_usernames\n    set -l tokens (commandline -opc)\n    set -l vault\n    if set -l i (contains -i -- --vault $tokens)\n        set vault --vault $tokens[(math $i + 1)]\n    end\n    passman $vault _complete usernames 2>/dev/null\nend\n\ncomplete -c passman -f\n";
+
+    for node in nodes {
+        let condition = format!("-n {}", fish_quote(&format!("__passman_using \"{}\"", node.path)));
+        for (name, help) in &node.subcommands {
+            script += &format!("complete -c passman {} -a {} -d {}\n", condition, name, fish_quote(help));
+        }
+        if node.usernames {
+            script += &format!("complete -c passman {} -a '(__passman_usernames)'\n", condition);
+        }
+        for opt in &node.options {
+            let mut line = format!("complete -c passman {}", condition);
+            if let Some(short) = opt.short {
+                line += &format!(" -s {}", short);
+            }
+            if let Some(long) = &opt.long {
+                line += &format!(" -l {}", long);
+            }
+            if opt.username {
+                line += " -xa '(__passman_usernames)'";
+            } else if !opt.values.is_empty() {
+                line += &format!(" -xa {}", fish_quote(&opt.values.join(" ")));
+            } else if opt.takes_value {
+                line += " -rF";
+            }
+            script += &format!("{} -d {}\n", line, fish_quote(&opt.help));
+        }
+    }
+    script
+}
+
+// PowerShell 的单引号字符串，' 写作 ''
+fn ps_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn powershell(nodes: &[Node]) -> String {
+    let mut script = String::from(
+        "using namespace System.Management.Automation\n\nRegister-ArgumentCompleter -Native -CommandName 'passman' -ScriptBlock {\n    param($wordToComplete, $commandAst, $cursorPosition)\n\n",
+    );
+    let paths: Vec<String> = subcommand_paths(nodes).iter().map(|path| ps_quote(path)).collect();
+    script += &format!("    $paths = @({})\n", paths.join(", "));
+    script += "    $words = @($commandAst.CommandElements | Where-Object { $_.Extent.EndOffset -lt $cursorPosition } | ForEach-Object { $_.ToString() })\n";
+    script += "    $cmd = 'passman'\n    foreach ($word in $words | Select-Object -Skip 1) {\n        if ($paths -contains \"$cmd $word\") {\n            $cmd = \"$cmd $word\"\n        }\n    }\n    $prev = $words[-1]\n\n";
+
+    script += "    $usernames = {\n        $arguments = @()\n        $i = [array]::IndexOf($words, '--vault')\n        if ($i -ge 0 -and $i + 1 -lt $words.Count) {\n            $arguments = @('--vault', $words[$i + 1])\n        }\n";
+    script += "        & passman @arguments _complete usernames 2>$null | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n            [CompletionResult]::new($_, $_, 'ParameterValue', $_)\n        }\n    }\n";
+    let options: Vec<String> = nodes
+        .iter()
+        .flat_map(|node| {
+            let flags: Vec<String> = node.options.iter().filter(|opt| opt.username).flat_map(Opt::flags).collect();
+            flags.into_iter().map(move |flag| ps_quote(&format!("{} {}", node.path, flag)))
+        })
+        .collect();
+    let positionals: Vec<String> = nodes.iter().filter(|node| node.usernames).map(|node| ps_quote(&node.path)).collect();
+    script += &format!("    $usernameOptions = @({})\n    $usernameCommands = @({})\n", options.join(", "), positionals.join(", "));
+    script += "    if ($usernameOptions -contains \"$cmd $prev\") {\n        & $usernames\n        return\n    }\n\n";
+
+    script += "    $values = @{\n";
+    for node in nodes {
+        for opt in node.options.iter().filter(|opt| !opt.values.is_empty()) {
+            let values: Vec<String> = opt.values.iter().map(|value| ps_quote(value)).collect();
+            for flag in opt.flags() {
+                script += &format!("        {} = @({})\n", ps_quote(&format!("{} {}", node.path, flag)), values.join(", "));
+            }
+        }
+    }
+    script += "    }\n    if ($values.ContainsKey(\"$cmd $prev\")) {\n        $values[\"$cmd $prev\"] | Where-Object { $_ -like \"$wordToComplete*\" } | ForEach-Object {\n            [CompletionResult]::new($_, $_, 'ParameterValue', $_)\n        }\n        return\n    }\n\n";
+
+    script += "    $items = switch ($cmd) {\n";
+    for node in nodes {
+        script += &format!("        {} {{\n", ps_quote(&node.path));
+        let result = |name: &str, kind: &str, help: &str| {
+            let help = if help.is_empty() { name } else { help };
+            format!("            [CompletionResult]::new({0}, {0}, '{1}', {2})\n", ps_quote(name), kind, ps_quote(help))
+        };
+        for (name, help) in &node.subcommands {
+            script += &result(name, "ParameterValue", help);
+        }
+        for opt in &node.options {
+            for flag in opt.flags() {
+                script += &result(&flag, "ParameterName", &opt.help);
+            }
+        }
+        script += "        }\n";
+    }
+    script += "    }\n    if ($usernameCommands -contains $cmd -and $wordToComplete -notlike '-*') {\n        & $usernames\n    }\n";
+    script += "    $items | Where-Object { $_.CompletionText -like \"$wordToComplete*\" }\n}\n";
+    script
+}
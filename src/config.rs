@@ -0,0 +1,562 @@
+// 配置文件 (~/.config/passman/config.toml) 与默认数据位置
+//
+// 只支持 TOML 的一个子集：表头、键值对、字符串、整数和布尔值。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{MAIN_SEPARATOR, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use passman::AppError;
+use passman::crypto::Cipher;
+use passman::kdf::KdfParams;
+
+use crate::i18n::t;
+
+// 配置目录名称
+const APP_DIR: &str = "passman";
+// 配置文件名称
+const CONFIG_FILE: &str = "config.toml";
+// 默认密码库文件名称
+const VAULT_FILE: &str = "vault.json";
+
+// list 命令可以显示的列
+pub const LIST_COLUMNS: [&str; 12] =
+    ["id", "type", "username", "password", "url", "notes", "folder", "tags", "created", "updated", "last-used", "expires"];
+// 列的别名
+const COLUMN_ALIASES: [(&str, &str); 2] = [("name", "username"), ("modified", "updated")];
+// list 命令默认显示的列
+pub const DEFAULT_LIST_COLUMNS: [&str; 3] = ["username", "password", "notes"];
+
+// 配置值的类型
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    String,
+    Integer,
+    Boolean,
+}
+
+// 支持的配置项：键、类型和说明
+pub const KEYS: &[(&str, Kind, &str)] = &[
+    ("vault", Kind::String, "默认密码库路径或档案名称"),
+    ("lang", Kind::String, "界面语言 (zh-CN、en-US)，未设置时按 LANG 环境变量"),
+    ("clipboard.timeout", Kind::Integer, "复制到剪贴板后自动清除的秒数，0 表示不清除"),
+    ("generator.length", Kind::Integer, "随机密码长度"),
+    ("generator.upper", Kind::Boolean, "随机密码包含大写字母"),
+    ("generator.lower", Kind::Boolean, "随机密码包含小写字母"),
+    ("generator.digits", Kind::Boolean, "随机密码包含数字"),
+    ("generator.symbols", Kind::Boolean, "随机密码包含符号"),
+    ("generator.words", Kind::Integer, "口令单词数"),
+    ("generator.separator", Kind::String, "口令单词分隔符"),
+    ("list.columns", Kind::String, "list 默认显示的列，以逗号分隔 (username,password,notes,url,tags,updated,...)"),
+    ("cipher", Kind::String, "新建或重新加密密码库时使用的加密算法 (aes-256-gcm、xchacha20-poly1305)"),
+    ("age.identity", Kind::String, "默认的 age 身份文件，用来解锁以 age 接收者加密的密码库"),
+    ("fido2.device", Kind::String, "FIDO2 安全密钥的设备路径，未设置时使用第一个连接的设备"),
+    ("session.timeout", Kind::Integer, "用主密钥解锁后在系统钥匙串中缓存解锁会话的分钟数，0 表示不缓存"),
+    ("agent.timeout", Kind::Integer, "passman agent 运行的分钟数，0 表示一直运行到停止"),
+    ("autolock.idle", Kind::Integer, "agent 和交互式界面空闲多少分钟后丢弃密钥，0 表示不启用"),
+    ("autolock.suspend", Kind::Boolean, "系统休眠时 agent 和交互式界面丢弃密钥"),
+    ("kdf.m_cost", Kind::Integer, "新建密码库时 Argon2id 的内存开销 (KiB)"),
+    ("kdf.t_cost", Kind::Integer, "新建密码库时 Argon2id 的迭代次数"),
+    ("kdf.p_cost", Kind::Integer, "新建密码库时 Argon2id 的并行度"),
+    ("completion.usernames", Kind::Boolean, "在缓存目录中保存用户名索引，供 shell 补全用户名"),
+    ("color.name", Kind::String, "条目名称的样式，例如 bold cyan；none 表示不着色"),
+    ("color.warning", Kind::String, "警告（弱密码、已泄露的密码）的样式"),
+    ("color.expiring", Kind::String, "过期提醒的样式"),
+    ("color.success", Kind::String, "操作成功提示的样式"),
+];
+
+// 配置档案所在的表，其中的键可以任意命名
+const PROFILES: &str = "profiles";
+
+// 配置值
+#[derive(Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Boolean(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl Value {
+    // 按 TOML 语法输出
+    fn to_toml(&self) -> String {
+        match self {
+            Value::String(s) => quote(s),
+            other => other.to_string(),
+        }
+    }
+}
+
+// 已加载的配置，键为带点号的完整路径（例如 `profiles.work`）
+pub struct Config {
+    path: Option<PathBuf>,
+    values: BTreeMap<String, Value>,
+}
+
+impl Config {
+    // 加载配置文件，文件不存在时返回空配置
+    pub fn load() -> Result<Self, AppError> {
+        let path = config_path();
+        let values = match &path {
+            Some(path) if path.exists() => {
+                let text = fs::read_to_string(path)?;
+                parse(&text).map_err(|e| AppError::from(format!("{}: {}", path.display(), e)))?
+            }
+            _ => BTreeMap::new(),
+        };
+
+        let config = Config { path, values };
+        for (key, value) in &config.values {
+            config.check(key, value).map_err(|e| AppError::from(t!("配置文件错误: {}", e)))?;
+        }
+        Ok(config)
+    }
+
+    // 配置文件路径
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    // 所有已设置的配置项
+    pub fn values(&self) -> &BTreeMap<String, Value> {
+        &self.values
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.values.get(key) {
+            Some(Value::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Option<i64> {
+        match self.values.get(key) {
+            Some(Value::Integer(i)) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// 路径类型的配置项，展开开头的 ~
+    pub fn get_path(&self, key: &str) -> Option<PathBuf> {
+        self.get_str(key).map(expand_home)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key) {
+            Some(Value::Boolean(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// 新建或升级密码库时使用的密钥派生参数，未配置的项使用默认值
+    pub fn kdf_params(&self) -> Result<KdfParams, String> {
+        kdf_params(&self.values)
+    }
+
+    // 设置配置项并写回文件，保留文件中的其他内容和注释
+    pub fn set(&mut self, key: &str, raw: &str) -> Result<(), AppError> {
+        let value = match kind_of(key)? {
+            Kind::String => Value::String(raw.to_string()),
+            Kind::Integer => Value::Integer(raw.parse().map_err(|_| AppError::from(t!("{} 必须是整数", key)))?),
+            Kind::Boolean => Value::Boolean(match raw {
+                "true" | "yes" | "on" | "1" => true,
+                "false" | "no" | "off" | "0" => false,
+                _ => return Err(AppError::from(t!("{} 必须是 true 或 false", key))),
+            }),
+        };
+        self.check(key, &value)?;
+        let mut values = self.values.clone();
+        values.insert(key.to_string(), value.clone());
+        check_kdf(key, &values)?;
+
+        let text = self.read_text()?;
+        let text = edit(&text, key, Some(&value));
+        self.write_text(&text)?;
+        self.values.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    // 删除配置项并写回文件，返回该项之前是否存在
+    pub fn unset(&mut self, key: &str) -> Result<bool, AppError> {
+        kind_of(key)?;
+        let mut values = self.values.clone();
+        if values.remove(key).is_none() {
+            return Ok(false);
+        }
+        check_kdf(key, &values)?;
+        self.values = values;
+
+        let text = self.read_text()?;
+        self.write_text(&edit(&text, key, None))?;
+        Ok(true)
+    }
+
+    fn read_text(&self) -> Result<String, AppError> {
+        match &self.path {
+            Some(path) if path.exists() => Ok(fs::read_to_string(path)?),
+            _ => Ok(String::new()),
+        }
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), AppError> {
+        let path = self.path.as_ref().ok_or_else(|| AppError::from(t!("无法确定配置目录")))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        passman::atomic::write(path, text)?;
+        Ok(())
+    }
+
+    // 校验配置项的类型和取值
+    fn check(&self, key: &str, value: &Value) -> Result<(), AppError> {
+        let kind = kind_of(key)?;
+        let actual = match value {
+            Value::String(_) => Kind::String,
+            Value::Integer(_) => Kind::Integer,
+            Value::Boolean(_) => Kind::Boolean,
+        };
+        if actual != kind {
+            return Err(AppError::from(t!("{} 的类型不正确", key)));
+        }
+
+        match (key, value) {
+            ("clipboard.timeout", Value::Integer(i)) if *i < 0 => Err(AppError::from(t!("clipboard.timeout 不能为负数"))),
+            ("generator.length" | "generator.words" | "kdf.m_cost" | "kdf.t_cost" | "kdf.p_cost", Value::Integer(i))
+                if *i <= 0 || *i > u32::MAX as i64 =>
+            {
+                Err(AppError::from(t!("{} 必须是正整数", key)))
+            }
+            ("lang", Value::String(lang)) => crate::i18n::check_lang(lang),
+            ("cipher", Value::String(name)) if Cipher::from_name(name).is_none() => {
+                let names: Vec<&str> = Cipher::ALL.iter().map(|cipher| cipher.name()).collect();
+                Err(AppError::from(t!("不支持的加密算法: {}（可用: {}）", name, names.join(", "))))
+            }
+            ("list.columns", Value::String(columns)) => parse_columns(columns).map(|_| ()),
+            (key, Value::String(style)) if key.starts_with("color.") => crate::color::parse_style(style).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    // 某个表下的所有键（不含表名前缀）
+    pub fn table(&self, name: &str) -> impl Iterator<Item = (&str, &Value)> {
+        let prefix = format!("{}.", name);
+        self.values
+            .iter()
+            .filter_map(move |(key, value)| key.strip_prefix(&prefix).map(|k| (k, value)))
+    }
+
+    // 解析 --vault 参数：包含路径分隔符或以 .json 结尾的视为路径，否则视为 [profiles] 中的名称
+    pub fn resolve_vault(&self, vault: &str) -> Result<PathBuf, AppError> {
+        let is_path = vault.contains(['/', MAIN_SEPARATOR]) || vault.ends_with(".json") || vault.starts_with('.');
+        if is_path {
+            return Ok(expand_home(vault));
+        }
+
+        match self.values.get(&format!("{}.{}", PROFILES, vault)) {
+            Some(Value::String(path)) => Ok(expand_home(path)),
+            Some(_) => Err(AppError::from(t!("配置档案 {} 的路径必须是字符串", vault))),
+            None => {
+                let names: Vec<&str> = self.table(PROFILES).map(|(name, _)| name).collect();
+                if names.is_empty() {
+                    Err(AppError::from(t!("未知的配置档案: {}（配置文件中没有 [profiles]）", vault)))
+                } else {
+                    Err(AppError::from(t!("未知的配置档案: {}（可用: {}）", vault, names.join(", "))))
+                }
+            }
+        }
+    }
+}
+
+// 由 kdf.* 配置项得到密钥派生参数，未配置的项使用默认值
+fn kdf_params(values: &BTreeMap<String, Value>) -> Result<KdfParams, String> {
+    let defaults = KdfParams::generate();
+    let cost = |key: &str, default: u32| match values.get(key) {
+        Some(Value::Integer(i)) => *i as u32,
+        _ => default,
+    };
+    KdfParams::with_costs(cost("kdf.m_cost", defaults.m_cost), cost("kdf.t_cost", defaults.t_cost), cost("kdf.p_cost", defaults.p_cost))
+}
+
+// kdf.* 的各项需要一起校验（例如内存开销至少为并行度的 8 倍），否则保存后无法新建或打开密码库
+fn check_kdf(key: &str, values: &BTreeMap<String, Value>) -> Result<(), AppError> {
+    if key.starts_with("kdf.") {
+        kdf_params(values)?;
+    }
+    Ok(())
+}
+
+// 展开开头的 ~
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs_next::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// 默认密码库路径（平台数据目录，例如 Linux 上的 ~/.local/share/passman/vault.json）
+pub fn default_vault_path() -> Result<PathBuf, AppError> {
+    dirs_next::data_dir()
+        .map(|dir| dir.join(APP_DIR).join(VAULT_FILE))
+        .ok_or_else(|| AppError::from(t!("无法确定数据目录，请使用 --vault 指定密码库")))
+}
+
+// 解析以逗号分隔的列名，别名换成正式名称
+pub fn parse_columns(text: &str) -> Result<Vec<String>, AppError> {
+    text.split(',')
+        .map(str::trim)
+        .map(|column| {
+            let column = COLUMN_ALIASES.iter().find(|(alias, _)| *alias == column).map_or(column, |(_, name)| *name);
+            if LIST_COLUMNS.contains(&column) {
+                Ok(column.to_string())
+            } else {
+                Err(AppError::from(t!("未知的列: {}（可用: {}）", column, LIST_COLUMNS.join(", "))))
+            }
+        })
+        .collect()
+}
+
+// 密码库的标识：绝对路径的哈希，用来命名每个密码库各自的索引、封存的密钥等
+pub fn vault_id(vault_path: &Path) -> String {
+    let vault_path = fs::canonicalize(vault_path).unwrap_or_else(|_| vault_path.to_path_buf());
+    let digest = Sha256::digest(vault_path.to_string_lossy().as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 数据目录（例如 Linux 上的 ~/.local/share/passman）
+pub fn data_dir() -> Option<PathBuf> {
+    dirs_next::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+// 缓存目录（例如 Linux 上的 ~/.cache/passman）
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs_next::cache_dir().map(|dir| dir.join(APP_DIR))
+}
+
+// 配置文件路径
+pub fn config_path() -> Option<PathBuf> {
+    dirs_next::config_dir().map(|dir| dir.join(APP_DIR).join(CONFIG_FILE))
+}
+
+// 配置项的类型，未知的键返回错误
+fn kind_of(key: &str) -> Result<Kind, AppError> {
+    if let Some(name) = key.strip_prefix(PROFILES).and_then(|k| k.strip_prefix('.'))
+        && !name.is_empty()
+        && !name.contains('.')
+    {
+        return Ok(Kind::String);
+    }
+    KEYS.iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, kind, _)| *kind)
+        .ok_or_else(|| AppError::from(t!("未知的配置项: {}", key)))
+}
+
+// 在配置文本中设置（value 为 Some）或删除（None）一个键，保留其余行
+fn edit(text: &str, key: &str, value: Option<&Value>) -> String {
+    let (table, leaf) = match key.rsplit_once('.') {
+        Some((table, leaf)) => (table, leaf),
+        None => ("", key),
+    };
+    let new_line = value.map(|v| format!("{} = {}", format_key(leaf), v.to_toml()));
+
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let mut current = String::new();
+    // 目标表中最后一行的位置（表头或键值对），用于插入新键
+    let mut insert_at = if table.is_empty() { Some(0) } else { None };
+
+    for i in 0..lines.len() {
+        let line = strip_comment(&lines[i]).trim().to_string();
+        if let Some(name) = line.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+            current = parse_key(name).unwrap_or_default();
+            if current == table {
+                insert_at = Some(i + 1);
+            }
+            continue;
+        }
+        if current != table || line.is_empty() {
+            continue;
+        }
+
+        insert_at = Some(i + 1);
+        let existing = line.split_once('=').and_then(|(k, _)| parse_key(k));
+        if existing.as_deref() == Some(leaf) {
+            match new_line {
+                Some(new_line) => lines[i] = new_line,
+                None => {
+                    lines.remove(i);
+                }
+            }
+            return join_lines(lines);
+        }
+    }
+
+    let Some(new_line) = new_line else {
+        return join_lines(lines);
+    };
+    match insert_at {
+        Some(index) => lines.insert(index, new_line),
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", table));
+            lines.push(new_line);
+        }
+    }
+    join_lines(lines)
+}
+
+fn join_lines(lines: Vec<String>) -> String {
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+// 需要时为键加上引号
+pub fn format_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+// 输出为 TOML 基本字符串
+pub fn quote(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// 解析配置文本
+fn parse(text: &str) -> Result<BTreeMap<String, Value>, String> {
+    let mut values = BTreeMap::new();
+    let mut table = String::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: &str| t!("第 {} 行: {}", number + 1, message);
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name.strip_suffix(']').ok_or_else(|| error(t!("表头缺少 ]")))?;
+            table = parse_key(name).ok_or_else(|| error(t!("无效的表名")))?;
+            continue;
+        }
+
+        let (key, value) = line.split_once('=').ok_or_else(|| error(t!("缺少 =")))?;
+        let key = parse_key(key).ok_or_else(|| error(t!("无效的键")))?;
+        let value = parse_value(value.trim()).ok_or_else(|| error(t!("无效的值")))?;
+
+        let full_key = if table.is_empty() { key } else { format!("{}.{}", table, key) };
+        if values.insert(full_key, value).is_some() {
+            return Err(error(t!("重复的键")));
+        }
+    }
+
+    Ok(values)
+}
+
+// 去掉不在字符串中的 # 注释
+pub fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..i],
+            None => {}
+        }
+    }
+    line
+}
+
+// 解析裸键、带引号的键以及点号分隔的键
+fn parse_key(key: &str) -> Option<String> {
+    let parts: Option<Vec<String>> = key
+        .split('.')
+        .map(|part| {
+            let part = part.trim();
+            if part.len() >= 2 && (part.starts_with('"') && part.ends_with('"') || part.starts_with('\'') && part.ends_with('\'')) {
+                return Some(part[1..part.len() - 1].to_string());
+            }
+            let bare = !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            bare.then(|| part.to_string())
+        })
+        .collect();
+    parts.map(|p| p.join("."))
+}
+
+fn parse_value(value: &str) -> Option<Value> {
+    match value {
+        "true" => return Some(Value::Boolean(true)),
+        "false" => return Some(Value::Boolean(false)),
+        _ => {}
+    }
+
+    if let Some(inner) = value.strip_prefix('\'') {
+        return inner.strip_suffix('\'').map(|s| Value::String(s.to_string()));
+    }
+    if let Some(inner) = value.strip_prefix('"') {
+        return unescape(inner.strip_suffix('"')?).map(Value::String);
+    }
+
+    value.replace('_', "").parse().ok().map(Value::Integer)
+}
+
+// 还原基本字符串中的转义
+pub fn unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            '"' => out.push('"'),
+            '\\' => out.push('\\'),
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            }
+            _ => return None,
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,301 @@
+//! 密码库的加密后端
+//!
+//! 密码库文件的读写（头部、格式迁移、修改记录）只通过 [`CryptoBackend`] 使用密钥：后端
+//! 持有密钥，提供写入文件头部的密钥参数，并按头部记录的 [`Cipher`] 加密和解密数据。内置
+//! 的 [`PasswordBackend`] 用 Argon2id 从主密钥派生密钥；其他密钥派生算法或由硬件保管的
+//! 密钥（密钥不离开设备）只需实现这个 trait，再通过 [`Vault::open_with_backend`] 打开
+//! 密码库；[`age`](crate::age) 和 [`gpg`](crate::gpg) 模块的后端分别把密钥封装给 age
+//! 接收者和 GPG 公钥，[`fido2`](crate::fido2) 模块的后端由 FIDO2 安全密钥派生密钥。
+//! [`KeyBackend`] 直接使用在别处保存的密钥，例如由 [`tpm`](crate::tpm) 模块解开的密钥和
+//! [`recovery`](crate::recovery) 模块用恢复代码解开的密钥。新的加密算法加入 [`Cipher`]。
+//!
+//! [`Vault::open_with_backend`]: crate::Vault::open_with_backend
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use aes_gcm::aead::{self, AeadInPlace, Buffer, Key, KeyInit};
+use aes_gcm::Aes256Gcm;
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::age::Stanza;
+use crate::error::AppError;
+use crate::fido2::Fido2Key;
+use crate::gpg::GpgKey;
+use crate::kdf::{self, KdfParams};
+use crate::secmem::{self, SecretBytes};
+use crate::xchacha20poly1305::XChaCha20Poly1305;
+use crate::yubikey;
+
+/// 密码库的加密算法，记录在文件头部
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Cipher {
+    /// AES-256-GCM，12 字节的初始化向量
+    #[default]
+    Aes256Gcm,
+    /// XChaCha20-Poly1305，24 字节的初始化向量，不依赖 AES 硬件指令也能保持常数时间
+    XChaCha20Poly1305,
+}
+
+impl Cipher {
+    /// 所有支持的加密算法
+    pub const ALL: [Cipher; 2] = [Cipher::Aes256Gcm, Cipher::XChaCha20Poly1305];
+
+    /// 文件头部和配置中使用的名称，例如 `aes-256-gcm`
+    pub fn name(self) -> &'static str {
+        match self {
+            Cipher::Aes256Gcm => "aes-256-gcm",
+            Cipher::XChaCha20Poly1305 => "xchacha20-poly1305",
+        }
+    }
+
+    /// 按名称查找加密算法
+    pub fn from_name(name: &str) -> Option<Cipher> {
+        Cipher::ALL.into_iter().find(|cipher| cipher.name() == name)
+    }
+
+    /// 初始化向量的长度（字节）
+    pub fn nonce_length(self) -> usize {
+        match self {
+            Cipher::Aes256Gcm => 12,
+            Cipher::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// 用 32 字节的密钥原地加密，认证标签附加在数据末尾
+    pub fn encrypt(self, key: &[u8], nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> aead::Result<()> {
+        match self {
+            Cipher::Aes256Gcm => seal::<Aes256Gcm>(key, nonce, aad, data),
+            Cipher::XChaCha20Poly1305 => seal::<XChaCha20Poly1305>(key, nonce, aad, data),
+        }
+    }
+
+    /// 用 32 字节的密钥原地校验并解密，去掉末尾的认证标签
+    pub fn decrypt(self, key: &[u8], nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> aead::Result<()> {
+        match self {
+            Cipher::Aes256Gcm => open::<Aes256Gcm>(key, nonce, aad, data),
+            Cipher::XChaCha20Poly1305 => open::<XChaCha20Poly1305>(key, nonce, aad, data),
+        }
+    }
+}
+
+fn seal<C: KeyInit + AeadInPlace>(key: &[u8], nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> aead::Result<()> {
+    C::new(Key::<C>::from_slice(key)).encrypt_in_place(aead::Nonce::<C>::from_slice(nonce), aad, data)
+}
+
+fn open<C: KeyInit + AeadInPlace>(key: &[u8], nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> aead::Result<()> {
+    C::new(Key::<C>::from_slice(key)).decrypt_in_place(aead::Nonce::<C>::from_slice(nonce), aad, data)
+}
+
+/// 加密后端：持有解锁后的密钥，加密和解密密码库数据
+///
+/// 加密和解密失败（密钥错误、数据被篡改）应返回 [`AppError::Aes`]，以便与设备不可用等
+/// 其他错误区分。
+pub trait CryptoBackend {
+    /// 写入文件头部的密钥信息
+    fn key_header(&self) -> KeyHeader;
+
+    /// 原地加密，认证标签附加在数据末尾
+    fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError>;
+
+    /// 原地校验并解密，去掉末尾的认证标签
+    fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError>;
+
+    /// 解密后需要换用的后端，例如旧版本的密码库改用更安全的密钥派生方式；返回的后端会
+    /// 用于重新加密并保存。默认不需要更换。
+    fn upgrade(&self) -> Result<Option<Upgrade>, AppError> {
+        Ok(None)
+    }
+}
+
+/// 文件头部中与密钥有关的字段，由 [`CryptoBackend::key_header`] 提供，打开密码库时
+/// 交给解锁函数
+///
+/// 头部参与加密校验，字段的顺序不能改变。
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct KeyHeader {
+    /// 密钥派生参数，密钥不从口令派生时（以及 SHA-256 派生密钥的旧版本密码库）为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    /// 为各个 age 接收者封装的密钥
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipients: Option<Vec<Stanza>>,
+    /// 以 GPG 公钥加密的密钥
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpg: Option<GpgKey>,
+    /// FIDO2 安全密钥上的凭据和 hmac-secret 的盐
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fido2: Option<Fido2Key>,
+    /// 密钥校验值，用于在解密前发现密钥错误；无法提供时为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_check: Option<String>,
+}
+
+/// 解密后换用的加密后端，见 [`CryptoBackend::upgrade`]
+pub struct Upgrade {
+    pub backend: Box<dyn CryptoBackend>,
+    /// 显示给用户的改动说明
+    pub change: &'static str,
+}
+
+/// 打开密码库时提供给解锁函数的信息，用来创建对应的 [`CryptoBackend`]
+pub enum Unlock<'a> {
+    /// 密码库文件不存在，将创建新的密码库
+    Create,
+    /// 已有的密码库，附带文件头部记录的密钥信息
+    Existing { header: &'a KeyHeader },
+}
+
+/// 密钥文件：作为主密钥之外的第二个因素参与密钥派生，只保存内容的 SHA-256 摘要
+pub struct Keyfile {
+    digest: SecretBytes,
+}
+
+impl Keyfile {
+    /// 读取密钥文件，任何内容的文件都可以作为密钥文件
+    pub fn read(path: &Path) -> Result<Self, AppError> {
+        let mut hasher = Sha256::new();
+        let mut file = File::open(path).map_err(|e| AppError::from(format!("无法读取密钥文件 {}: {}", path.display(), e)))?;
+        io::copy(&mut file, &mut hasher)?;
+        let mut digest = hasher.finalize();
+        let locked = SecretBytes::from_slice(&digest);
+        secmem::zero(&mut digest);
+        Ok(Keyfile { digest: locked })
+    }
+}
+
+/// 从主密钥派生密钥的后端，密钥保存在锁定的内存中
+pub struct PasswordBackend {
+    key: SecretBytes,
+    // 密钥派生参数，旧版本的密码库（SHA-256 派生密钥）为 None
+    kdf: Option<KdfParams>,
+    // 旧版本的密码库：升级时用来重新派生密钥的主密钥和参数
+    legacy: Option<(SecretBytes, KdfParams)>,
+}
+
+impl PasswordBackend {
+    /// 使用 Argon2id 和给定的参数从主密钥派生密钥
+    pub fn new(master_key: &str, kdf: KdfParams) -> Result<Self, AppError> {
+        Self::with_keyfile(master_key, None, kdf)
+    }
+
+    /// 与 [`PasswordBackend::new`] 相同，给出 `keyfile` 时同时以密钥文件派生密钥，之后
+    /// 解锁必须提供同一个密钥文件。`kdf` 设置了 [`yubikey`](KdfParams::yubikey) 时还会
+    /// 向 YubiKey 发送挑战，以它的响应参与派生
+    pub fn with_keyfile(master_key: &str, keyfile: Option<&Keyfile>, mut kdf: KdfParams) -> Result<Self, AppError> {
+        kdf.keyfile = keyfile.is_some();
+        let response = kdf.yubikey.as_ref().map(yubikey::challenge_response).transpose()?;
+        let factors: Vec<&[u8]> = keyfile.map(|keyfile| &*keyfile.digest).into_iter().chain(response.as_deref()).collect();
+        let mut key = kdf::derive_key_with(master_key, &factors, &kdf)?;
+        let locked = SecretBytes::from_slice(&key);
+        secmem::zero(&mut key);
+        Ok(PasswordBackend { key: locked, kdf: Some(kdf), legacy: None })
+    }
+
+    /// 打开头部信息为 `header` 的密码库；没有密钥派生参数的旧版本密码库使用 SHA-256
+    /// 派生的密钥，解密后以 Argon2id 和 `new_kdf` 重新派生密钥
+    pub fn unlock(master_key: &str, header: &KeyHeader, new_kdf: KdfParams) -> Result<Self, AppError> {
+        Self::unlock_with_keyfile(master_key, None, header, new_kdf)
+    }
+
+    /// 与 [`PasswordBackend::unlock`] 相同，头部要求密钥文件时使用 `keyfile`
+    pub fn unlock_with_keyfile(
+        master_key: &str,
+        keyfile: Option<&Keyfile>,
+        header: &KeyHeader,
+        new_kdf: KdfParams,
+    ) -> Result<Self, AppError> {
+        if header.recipients.is_some() {
+            return Err(AppError::from("此密码库以 age 接收者加密，需要用对应的身份解锁"));
+        }
+        if header.gpg.is_some() {
+            return Err(AppError::from("此密码库以 GPG 密钥加密，需要用 gpg 解锁"));
+        }
+        if header.fido2.is_some() {
+            return Err(AppError::from("此密码库以 FIDO2 安全密钥加密，需要用安全密钥解锁"));
+        }
+        let uses_keyfile = header.kdf.as_ref().is_some_and(|params| params.keyfile);
+        match (uses_keyfile, keyfile) {
+            (true, None) => return Err(AppError::from("此密码库需要密钥文件")),
+            (false, Some(_)) => return Err(AppError::from("此密码库没有使用密钥文件")),
+            _ => {}
+        }
+        if let Some(params) = &header.kdf {
+            return Self::with_keyfile(master_key, keyfile, params.clone());
+        }
+        let mut key = kdf::derive_legacy_key(master_key);
+        let locked = SecretBytes::from_slice(&key);
+        secmem::zero(&mut key);
+        let legacy = Some((SecretBytes::from_slice(master_key.as_bytes()), new_kdf));
+        Ok(PasswordBackend { key: locked, kdf: None, legacy })
+    }
+
+    /// 派生出的密钥，可以交给 [`KeyBackend`] 在以后直接使用（封存到 TPM、缓存解锁会话）
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+}
+
+/// 直接使用已知密钥的后端，密钥来自 TPM、解锁会话的缓存等其他地方；头部沿用密码库
+/// 原有的头部
+pub struct KeyBackend {
+    key: SecretBytes,
+    header: KeyHeader,
+}
+
+impl KeyBackend {
+    /// 以 `key` 打开头部为 `header` 的密码库，密钥必须与头部的校验值一致
+    pub fn new(key: &[u8], header: &KeyHeader) -> Result<Self, AppError> {
+        if header.key_check.as_deref() != Some(key_check(key).as_str()) {
+            return Err(AppError::from("密钥与密码库不符"));
+        }
+        Ok(KeyBackend { key: SecretBytes::from_slice(key), header: header.clone() })
+    }
+}
+
+impl CryptoBackend for KeyBackend {
+    fn key_header(&self) -> KeyHeader {
+        self.header.clone()
+    }
+
+    fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.encrypt(&self.key, nonce, aad, data)?)
+    }
+
+    fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.decrypt(&self.key, nonce, aad, data)?)
+    }
+}
+
+// 密钥校验值：加上固定前缀后的 SHA-256 摘要的前 16 字节，可以在解密前判断主密钥是否
+// 正确，又无法由它推出密钥
+fn key_check(key: &[u8]) -> String {
+    let digest = Sha256::new().chain_update(b"passman key check\0").chain_update(key).finalize();
+    general_purpose::STANDARD.encode(&digest[..16])
+}
+
+impl CryptoBackend for PasswordBackend {
+    fn key_header(&self) -> KeyHeader {
+        KeyHeader { kdf: self.kdf.clone(), key_check: Some(key_check(&self.key)), ..KeyHeader::default() }
+    }
+
+    fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.encrypt(&self.key, nonce, aad, data)?)
+    }
+
+    fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.decrypt(&self.key, nonce, aad, data)?)
+    }
+
+    fn upgrade(&self) -> Result<Option<Upgrade>, AppError> {
+        let Some((master_key, new_kdf)) = &self.legacy else {
+            return Ok(None);
+        };
+        let master_key = std::str::from_utf8(master_key).expect("主密钥来自字符串");
+        let backend = PasswordBackend::new(master_key, new_kdf.clone())?;
+        Ok(Some(Upgrade { backend: Box::new(backend), change: "改用 Argon2id 从主密钥派生加密密钥" }))
+    }
+}
@@ -0,0 +1,612 @@
+// `secret-service` 使用的精简 D-Bus 客户端
+//
+// 只实现会话总线上收发方法调用、回复和信号所需的部分：以 Unix 套接字连接
+// DBUS_SESSION_BUS_ADDRESS 中的地址，以 EXTERNAL 方式（当前用户的 ID）认证，消息为小端序；
+// 支持 y b i u t s o g v a ( ) { } 这些类型，不支持传递文件描述符。
+
+use std::env;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+
+use passman::AppError;
+
+use crate::i18n::t;
+
+/// 消息类型
+pub const METHOD_CALL: u8 = 1;
+pub const METHOD_RETURN: u8 = 2;
+pub const ERROR: u8 = 3;
+pub const SIGNAL: u8 = 4;
+
+/// 方法调用不需要回复
+pub const NO_REPLY_EXPECTED: u8 = 0x1;
+
+// 一个消息的最大长度（D-Bus 规范的上限）
+const MAX_MESSAGE: usize = 128 * 1024 * 1024;
+
+const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+
+/// 一个值，数组带有元素的类型签名，空数组也能编码
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    U64(u64),
+    Str(String),
+    Path(String),
+    Signature(String),
+    Variant(Box<Value>),
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    Entry(Box<Value>, Box<Value>),
+}
+
+/// 一个消息，头部字段已解析
+#[derive(Default)]
+pub struct Message {
+    pub kind: u8,
+    pub flags: u8,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+/// 与总线的连接
+pub struct Connection {
+    stream: UnixStream,
+    serial: u32,
+}
+
+impl Value {
+    pub fn str(text: impl Into<String>) -> Self {
+        Value::Str(text.into())
+    }
+
+    pub fn path(path: impl Into<String>) -> Self {
+        Value::Path(path.into())
+    }
+
+    pub fn variant(value: Value) -> Self {
+        Value::Variant(Box::new(value))
+    }
+
+    /// 对象路径的数组（`ao`）
+    pub fn paths(paths: impl IntoIterator<Item = String>) -> Self {
+        Value::Array("o".to_string(), paths.into_iter().map(Value::Path).collect())
+    }
+
+    /// 字节数组（`ay`）
+    pub fn bytes(bytes: &[u8]) -> Self {
+        Value::Array("y".to_string(), bytes.iter().copied().map(Value::Byte).collect())
+    }
+
+    /// 字典（`a{..}`），`signature` 为键和值的类型签名，例如 `ss`
+    pub fn dict(signature: &str, entries: impl IntoIterator<Item = (Value, Value)>) -> Self {
+        let entries = entries.into_iter().map(|(key, value)| Value::Entry(Box::new(key), Box::new(value))).collect();
+        Value::Array(format!("{{{}}}", signature), entries)
+    }
+
+    /// 类型签名
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => "y".to_string(),
+            Value::Bool(_) => "b".to_string(),
+            Value::I32(_) => "i".to_string(),
+            Value::U32(_) => "u".to_string(),
+            Value::U64(_) => "t".to_string(),
+            Value::Str(_) => "s".to_string(),
+            Value::Path(_) => "o".to_string(),
+            Value::Signature(_) => "g".to_string(),
+            Value::Variant(_) => "v".to_string(),
+            Value::Array(element, _) => format!("a{}", element),
+            Value::Struct(fields) => format!("({})", fields.iter().map(Value::signature).collect::<String>()),
+            Value::Entry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+        }
+    }
+
+    // 类型签名的第一个字符
+    fn type_code(&self) -> u8 {
+        match self {
+            Value::Byte(_) => b'y',
+            Value::Bool(_) => b'b',
+            Value::I32(_) => b'i',
+            Value::U32(_) => b'u',
+            Value::U64(_) => b't',
+            Value::Str(_) => b's',
+            Value::Path(_) => b'o',
+            Value::Signature(_) => b'g',
+            Value::Variant(_) => b'v',
+            Value::Array(_, _) => b'a',
+            Value::Struct(_) => b'(',
+            Value::Entry(_, _) => b'{',
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Str(text) | Value::Path(text) | Value::Signature(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            Value::U32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// 数组的元素
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(_, values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// 结构的字段
+    pub fn as_struct(&self) -> Option<&[Value]> {
+        match self {
+            Value::Struct(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// 字节数组的内容
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        self.as_array()?.iter().map(|value| if let Value::Byte(b) = value { Some(*b) } else { None }).collect()
+    }
+
+    /// 变体中的值，不是变体时为自身
+    pub fn unwrap_variant(&self) -> &Value {
+        match self {
+            Value::Variant(value) => value.unwrap_variant(),
+            value => value,
+        }
+    }
+
+    /// 字典的各项
+    pub fn entries(&self) -> Option<Vec<(&Value, &Value)>> {
+        self.as_array()?
+            .iter()
+            .map(|entry| if let Value::Entry(key, value) = entry { Some((&**key, &**value)) } else { None })
+            .collect()
+    }
+}
+
+impl Message {
+    pub fn method_call(destination: &str, path: &str, interface: &str, member: &str, body: Vec<Value>) -> Self {
+        Message {
+            kind: METHOD_CALL,
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            destination: Some(destination.to_string()),
+            body,
+            ..Message::default()
+        }
+    }
+
+    pub fn signal(path: &str, interface: &str, member: &str, body: Vec<Value>) -> Self {
+        Message {
+            kind: SIGNAL,
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            body,
+            ..Message::default()
+        }
+    }
+
+    /// 对方法调用 `call` 的回复
+    pub fn method_return(call: &Message, body: Vec<Value>) -> Self {
+        Message { kind: METHOD_RETURN, reply_serial: Some(call.serial), destination: call.sender.clone(), body, ..Message::default() }
+    }
+
+    /// 对方法调用 `call` 的错误回复，`name` 为错误名称
+    pub fn error(call: &Message, name: &str, text: &str) -> Self {
+        Message {
+            kind: ERROR,
+            error_name: Some(name.to_string()),
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            body: vec![Value::str(text)],
+            ..Message::default()
+        }
+    }
+
+    /// 消息内容的类型签名
+    pub fn signature(&self) -> String {
+        self.body.iter().map(Value::signature).collect()
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Writer::default();
+        for value in &self.body {
+            body.write(value);
+        }
+
+        let mut fields = Vec::new();
+        let mut field = |code: u8, value: Value| fields.push(Value::Struct(vec![Value::Byte(code), Value::variant(value)]));
+        let strings = [(1, &self.path), (2, &self.interface), (3, &self.member), (4, &self.error_name), (6, &self.destination), (7, &self.sender)];
+        for (code, text) in strings {
+            if let Some(text) = text {
+                field(code, if code == 1 { Value::path(text) } else { Value::str(text) });
+            }
+        }
+        if let Some(serial) = self.reply_serial {
+            field(5, Value::U32(serial));
+        }
+        if !self.body.is_empty() {
+            field(8, Value::Signature(self.signature()));
+        }
+
+        let mut message = Writer { data: vec![b'l', self.kind, self.flags, 1] };
+        message.write(&Value::U32(body.data.len() as u32));
+        message.write(&Value::U32(self.serial));
+        message.write(&Value::Array("(yv)".to_string(), fields));
+        message.align(8);
+        message.data.extend(body.data);
+        message.data
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        let mut header = Reader { data, pos: 12 };
+        let fields = header.read("a(yv)")?;
+        header.align(8)?;
+        let body_start = header.pos;
+
+        let mut message = Message { kind: data[1], flags: data[2], serial: u32::from_le_bytes(data[8..12].try_into().unwrap_or_default()), ..Message::default() };
+        let mut signature = String::new();
+        for field in fields.as_array().unwrap_or_default() {
+            let [Value::Byte(code), value] = field.as_struct().unwrap_or_default() else { continue };
+            let value = value.unwrap_variant();
+            let text = value.as_str().map(str::to_string);
+            match code {
+                1 => message.path = text,
+                2 => message.interface = text,
+                3 => message.member = text,
+                4 => message.error_name = text,
+                5 => message.reply_serial = value.as_u32(),
+                6 => message.destination = text,
+                7 => message.sender = text,
+                8 => signature = text.unwrap_or_default(),
+                _ => {}
+            }
+        }
+
+        let mut body = Reader { data: &data[body_start..], pos: 0 };
+        let mut rest = signature.as_str();
+        while !rest.is_empty() {
+            let (first, remaining) = split_type(rest)?;
+            message.body.push(body.read(first)?);
+            rest = remaining;
+        }
+        Ok(message)
+    }
+}
+
+impl Connection {
+    /// 连接会话总线并注册
+    pub fn session() -> Result<Self, AppError> {
+        let address = env::var("DBUS_SESSION_BUS_ADDRESS")
+            .ok()
+            .or_else(|| env::var("XDG_RUNTIME_DIR").ok().map(|dir| format!("unix:path={}/bus", dir)))
+            .ok_or_else(|| AppError::from(t!("找不到 D-Bus 会话总线（没有设置 DBUS_SESSION_BUS_ADDRESS）")))?;
+        let stream = address
+            .split(';')
+            .find_map(|address| connect(address).ok())
+            .ok_or_else(|| AppError::from(t!("无法连接 D-Bus 会话总线: {}", address)))?;
+        let mut connection = Connection { stream, serial: 0 };
+        connection.authenticate().map_err(|e| AppError::from(t!("D-Bus 会话总线拒绝了连接: {}", e)))?;
+        connection.call(Message::method_call(BUS_NAME, BUS_PATH, BUS_NAME, "Hello", Vec::new()))?;
+        Ok(connection)
+    }
+
+    /// 申请总线上的名称 `name`，返回总线的答复（1 表示成功，3 表示已被其他连接占用）
+    pub fn request_name(&mut self, name: &str) -> Result<u32, AppError> {
+        // DBUS_NAME_FLAG_DO_NOT_QUEUE：名称被占用时不排队等待
+        let reply = self.call(Message::method_call(BUS_NAME, BUS_PATH, BUS_NAME, "RequestName", vec![Value::str(name), Value::U32(4)]))?;
+        reply.body.first().and_then(Value::as_u32).ok_or_else(|| AppError::from(t!("D-Bus 返回了无效的答复")))
+    }
+
+    /// 另一个指向同一连接的句柄，可以在其他线程中关闭连接
+    pub fn try_clone_stream(&self) -> io::Result<UnixStream> {
+        self.stream.try_clone()
+    }
+
+    /// 发送消息，返回分配的序号
+    pub fn send(&mut self, mut message: Message) -> io::Result<u32> {
+        self.serial += 1;
+        message.serial = self.serial;
+        self.stream.write_all(&message.encode())?;
+        Ok(self.serial)
+    }
+
+    /// 读取下一个消息；连接关闭时返回 `UnexpectedEof` 错误
+    pub fn receive(&mut self) -> io::Result<Message> {
+        loop {
+            let mut fixed = [0u8; 16];
+            self.stream.read_exact(&mut fixed)?;
+            let body_length = u32::from_le_bytes(fixed[4..8].try_into().unwrap_or_default()) as usize;
+            let fields_length = u32::from_le_bytes(fixed[12..16].try_into().unwrap_or_default()) as usize;
+            let length = (16 + fields_length).next_multiple_of(8) + body_length;
+            // 本机的程序都使用小端序
+            if fixed[0] != b'l' || length > MAX_MESSAGE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, t!("不支持的 D-Bus 消息")));
+            }
+            let mut data = fixed.to_vec();
+            data.resize(length, 0);
+            self.stream.read_exact(&mut data[16..])?;
+            // 无法解析的消息（总线不会转发格式错误的消息）直接跳过
+            if let Ok(message) = Message::decode(&data) {
+                return Ok(message);
+            }
+        }
+    }
+
+    // 发送方法调用并等待回复，期间收到的其他消息丢弃
+    fn call(&mut self, message: Message) -> Result<Message, AppError> {
+        let serial = self.send(message)?;
+        loop {
+            let reply = self.receive()?;
+            if reply.reply_serial != Some(serial) {
+                continue;
+            }
+            return match reply.kind {
+                ERROR => {
+                    let text = reply.body.first().and_then(Value::as_str).unwrap_or_default();
+                    Err(AppError::from(format!("{}: {}", reply.error_name.unwrap_or_default(), text)))
+                }
+                _ => Ok(reply),
+            };
+        }
+    }
+
+    // SASL EXTERNAL 认证，以十六进制编码的用户 ID 作为身份
+    fn authenticate(&mut self) -> io::Result<()> {
+        let uid = unsafe { libc::getuid() }.to_string();
+        let hex: String = uid.bytes().map(|b| format!("{:02x}", b)).collect();
+        self.stream.write_all(format!("\0AUTH EXTERNAL {}\r\n", hex).as_bytes())?;
+        // 逐字节读取，避免读到 BEGIN 之后的消息
+        let mut line = Vec::new();
+        let mut byte = [0u8];
+        while !line.ends_with(b"\r\n") {
+            self.stream.read_exact(&mut byte)?;
+            line.push(byte[0]);
+            if line.len() > 512 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "AUTH"));
+            }
+        }
+        let line = String::from_utf8_lossy(&line);
+        if !line.starts_with("OK ") {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, line.trim().to_string()));
+        }
+        self.stream.write_all(b"BEGIN\r\n")
+    }
+}
+
+// 连接一个地址，例如 `unix:path=/run/user/1000/bus`；Linux 上也支持 `unix:abstract=`
+fn connect(address: &str) -> io::Result<UnixStream> {
+    let unsupported = || io::Error::new(io::ErrorKind::Unsupported, address.to_string());
+    let params = address.strip_prefix("unix:").ok_or_else(unsupported)?;
+    for param in params.split(',') {
+        let (key, value) = param.split_once('=').unwrap_or((param, ""));
+        let value = unescape(value);
+        match key {
+            "path" => return UnixStream::connect(value),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            "abstract" => {
+                use std::os::linux::net::SocketAddrExt;
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(value.as_bytes())?;
+                return UnixStream::connect_addr(&addr);
+            }
+            _ => {}
+        }
+    }
+    Err(unsupported())
+}
+
+// 地址中的值可能以 %XX 转义
+fn unescape(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%'
+            && let Some(hex) = tail.get(..2)
+            && let Some(decoded) = std::str::from_utf8(hex).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            bytes.push(decoded);
+            rest = &tail[2..];
+            continue;
+        }
+        bytes.push(b);
+        rest = tail;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// 签名开头的一个完整类型和剩余部分
+pub fn split_type(signature: &str) -> Result<(&str, &str), String> {
+    let bytes = signature.as_bytes();
+    let mut depth = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'a' => continue,
+            b'(' | b'{' => depth += 1,
+            b')' | b'}' => depth = depth.checked_sub(1).ok_or_else(|| format!("invalid signature {}", signature))?,
+            _ => {}
+        }
+        if depth == 0 {
+            return Ok(signature.split_at(i + 1));
+        }
+    }
+    Err(format!("invalid signature {}", signature))
+}
+
+// 各类型的对齐字节数
+fn alignment(code: u8) -> usize {
+    match code {
+        b'y' | b'g' | b'v' => 1,
+        b'b' | b'i' | b'u' | b's' | b'o' | b'a' => 4,
+        _ => 8,
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, n: usize) {
+        self.data.resize(self.data.len().next_multiple_of(n), 0);
+    }
+
+    fn write(&mut self, value: &Value) {
+        self.align(alignment(value.type_code()));
+        match value {
+            Value::Byte(b) => self.data.push(*b),
+            Value::Bool(b) => self.data.extend(u32::from(*b).to_le_bytes()),
+            Value::I32(n) => self.data.extend(n.to_le_bytes()),
+            Value::U32(n) => self.data.extend(n.to_le_bytes()),
+            Value::U64(n) => self.data.extend(n.to_le_bytes()),
+            Value::Str(text) | Value::Path(text) => {
+                self.data.extend((text.len() as u32).to_le_bytes());
+                self.data.extend(text.as_bytes());
+                self.data.push(0);
+            }
+            Value::Signature(text) => {
+                self.data.push(text.len() as u8);
+                self.data.extend(text.as_bytes());
+                self.data.push(0);
+            }
+            Value::Variant(inner) => {
+                self.write(&Value::Signature(inner.signature()));
+                self.write(inner);
+            }
+            Value::Array(element, values) => {
+                let length_at = self.data.len();
+                self.data.extend([0; 4]);
+                self.align(alignment(element.as_bytes()[0]));
+                let start = self.data.len();
+                for value in values {
+                    self.write(value);
+                }
+                let length = (self.data.len() - start) as u32;
+                self.data[length_at..length_at + 4].copy_from_slice(&length.to_le_bytes());
+            }
+            Value::Struct(fields) => {
+                for field in fields {
+                    self.write(field);
+                }
+            }
+            Value::Entry(key, value) => {
+                self.write(key);
+                self.write(value);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, n: usize) -> Result<(), String> {
+        self.take(self.pos.next_multiple_of(n) - self.pos).map(|_| ())
+    }
+
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        let bytes = self.data.get(self.pos..self.pos + n).ok_or("truncated message")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default()))
+    }
+
+    fn text(&mut self, length: usize) -> Result<String, String> {
+        let text = String::from_utf8(self.take(length)?.to_vec()).map_err(|e| e.to_string())?;
+        self.take(1)?;
+        Ok(text)
+    }
+
+    // 读取一个类型为 `signature`（一个完整类型）的值
+    fn read(&mut self, signature: &str) -> Result<Value, String> {
+        let code = signature.as_bytes()[0];
+        self.align(alignment(code))?;
+        Ok(match code {
+            b'y' => Value::Byte(self.take(1)?[0]),
+            b'b' => Value::Bool(self.u32()? != 0),
+            b'i' => Value::I32(self.u32()? as i32),
+            b'u' => Value::U32(self.u32()?),
+            b't' => Value::U64(u64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default())),
+            b's' | b'o' => {
+                let length = self.u32()? as usize;
+                let text = self.text(length)?;
+                if code == b's' { Value::Str(text) } else { Value::Path(text) }
+            }
+            b'g' => {
+                let length = self.take(1)?[0] as usize;
+                Value::Signature(self.text(length)?)
+            }
+            b'v' => {
+                let length = self.take(1)?[0] as usize;
+                let inner = self.text(length)?;
+                let (first, rest) = split_type(&inner)?;
+                if !rest.is_empty() {
+                    return Err(format!("invalid variant signature {}", inner));
+                }
+                Value::variant(self.read(first)?)
+            }
+            b'a' => {
+                let length = self.u32()? as usize;
+                let element = &signature[1..];
+                self.align(alignment(element.as_bytes()[0]))?;
+                let end = self.pos + length;
+                let mut values = Vec::new();
+                while self.pos < end {
+                    values.push(self.read(element)?);
+                }
+                Value::Array(element.to_string(), values)
+            }
+            b'(' | b'{' => {
+                let mut rest = &signature[1..signature.len() - 1];
+                let mut fields = Vec::new();
+                while !rest.is_empty() {
+                    let (first, remaining) = split_type(rest)?;
+                    fields.push(self.read(first)?);
+                    rest = remaining;
+                }
+                if code == b'(' {
+                    Value::Struct(fields)
+                } else if let Ok([key, value]) = <[Value; 2]>::try_from(fields) {
+                    Value::Entry(Box::new(key), Box::new(value))
+                } else {
+                    return Err(format!("invalid dict entry {}", signature));
+                }
+            }
+            _ => return Err(format!("unsupported type {}", signature)),
+        })
+    }
+}
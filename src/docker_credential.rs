@@ -0,0 +1,109 @@
+// Docker 的凭据助手（`passman docker-credential get|store|erase|list`）
+//
+// Docker 的配置中 `"credsStore": "passman"` 时，`docker login` 等命令启动 PATH 中的
+// `docker-credential-passman`，在参数中给出操作：store 从标准输入读取
+// `{"ServerURL", "Username", "Secret"}`，get 和 erase 读取仓库的地址，get 在标准输出回复同样
+// 的 JSON，list 回复地址到用户名的对象。找不到凭据时在标准输出写出 Docker 约定的
+// `credentials not found in native keychain` 并以 1 退出。
+//
+// 凭据保存为 docker 文件夹中的账号：网址为仓库的地址，用户名和密码为凭据。比较地址时忽略
+// 协议和末尾的 `/`。把 passman 链接为 `docker-credential-passman` 即可，以这个名字运行时
+// 自动加上 `docker-credential` 子命令。
+
+use std::collections::BTreeMap;
+use std::process;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use passman::{Account, AppError, Vault};
+
+use crate::i18n::t;
+
+/// Docker 启动的程序名称
+pub const PROGRAM: &str = "docker-credential-passman";
+/// 保存凭据的文件夹
+pub const FOLDER: &str = "docker";
+/// 找不到凭据时的回复，Docker 以它区分没有凭据和其他错误
+pub const NOT_FOUND: &str = "credentials not found in native keychain";
+
+/// Docker 传入的操作
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Action {
+    /// 读取一个仓库的凭据
+    Get,
+    /// 保存凭据
+    Store,
+    /// 删除一个仓库的凭据
+    Erase,
+    /// 列出已保存凭据的仓库和用户名
+    List,
+}
+
+/// 一个仓库的凭据，字段名称与 Docker 的相同
+#[derive(Serialize, Deserialize)]
+pub struct Credentials {
+    #[serde(rename = "ServerURL")]
+    pub server_url: String,
+    #[serde(rename = "Username")]
+    pub username: String,
+    #[serde(rename = "Secret")]
+    pub secret: String,
+}
+
+/// 保存仓库 `server_url` 的凭据的账号
+pub fn find<'a>(vault: &'a Vault, server_url: &str) -> Option<&'a Account> {
+    let wanted = normalize(server_url);
+    accounts(vault).find(|account| account.url.as_deref().is_some_and(|url| normalize(url) == wanted))
+}
+
+/// 告诉 Docker 没有凭据并退出
+pub fn not_found() -> ! {
+    println!("{}", NOT_FOUND);
+    process::exit(1)
+}
+
+/// 保存凭据，仓库已有凭据时替换；返回账号的 ID 和是否有修改
+pub fn store(vault: &mut Vault, credentials: &Credentials) -> Result<(String, bool), AppError> {
+    if let Some(id) = find(vault, &credentials.server_url).map(|account| account.id.clone()) {
+        let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+        let changed = account.username != credentials.username || account.password != credentials.secret;
+        if changed {
+            account.username = credentials.username.clone();
+            account.set_password(&credentials.secret);
+            account.touch();
+        }
+        return Ok((id, changed));
+    }
+    let mut account = Account::new(&credentials.username, &credentials.secret, "");
+    account.set_url(&credentials.server_url);
+    account.set_folder(FOLDER);
+    Ok((vault.add(account)?, true))
+}
+
+/// 删除仓库 `server_url` 的凭据，没有时返回 None
+pub fn erase(vault: &mut Vault, server_url: &str) -> Result<Option<Account>, AppError> {
+    match find(vault, server_url).map(|account| account.id.clone()) {
+        Some(id) => Ok(Some(vault.remove(&id)?)),
+        None => Ok(None),
+    }
+}
+
+/// 已保存凭据的仓库地址和用户名
+pub fn list(vault: &Vault) -> BTreeMap<String, String> {
+    accounts(vault).filter_map(|account| Some((account.url.clone()?, account.username.clone()))).collect()
+}
+
+fn accounts(vault: &Vault) -> impl Iterator<Item = &Account> {
+    vault.list().iter().filter(|account| account.in_folder(FOLDER))
+}
+
+// 去掉协议和末尾的 `/`，主机不区分大小写
+fn normalize(url: &str) -> String {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest).trim_end_matches('/');
+    match rest.split_once('/') {
+        Some((host, path)) => format!("{}/{}", host.to_lowercase(), path),
+        None => rest.to_lowercase(),
+    }
+}
@@ -0,0 +1,149 @@
+// passman doctor：检查密码库相关文件的权限
+//
+// Unix 上密码库文件、升级前的备份、锁文件、用户名索引、TPM 封存的密钥和恢复文件应只有当前用户可以读写（0600），
+// 密码库所在目录不应允许其他用户写入，否则其他用户可以删除或替换密码库文件。Windows
+// 上文件沿用用户目录的访问控制列表，不做检查。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use passman::recovery;
+
+use crate::i18n::t;
+use crate::name_index;
+use crate::tpm_store;
+
+/// 一项检查的结果
+pub struct Check {
+    /// 检查的文件
+    pub path: PathBuf,
+    /// 文件的用途，例如“密码库文件”
+    pub what: &'static str,
+    /// 发现的问题，没有问题时为 None
+    pub problem: Option<String>,
+    /// 修复问题时使用的权限
+    fix_mode: Option<u32>,
+}
+
+impl Check {
+    /// 问题能否通过修改权限修复
+    pub fn fixable(&self) -> bool {
+        self.fix_mode.is_some()
+    }
+
+    /// 把权限改为建议的值，返回是否修改过
+    pub fn fix(&self) -> std::io::Result<bool> {
+        #[cfg(unix)]
+        if let Some(mode) = self.fix_mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&self.path, fs::Permissions::from_mode(mode))?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+/// 检查密码库及相关文件
+pub fn check(vault_path: &Path) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let vault_path = fs::canonicalize(vault_path).unwrap_or_else(|_| vault_path.to_path_buf());
+    checks.push(check_file(&vault_path, t!("密码库文件")));
+
+    let file_name = vault_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    if let Some(dir) = vault_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        checks.push(check_dir(dir));
+
+        let lock = dir.join(format!(".{}.lock", file_name));
+        if lock.exists() {
+            checks.push(check_file(&lock, t!("锁文件")));
+        }
+        // 升级格式前的备份：<文件名>.v<版本>.bak
+        let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                name.strip_prefix(&file_name)
+                    .and_then(|rest| rest.strip_prefix(".v"))
+                    .and_then(|rest| rest.strip_suffix(".bak"))
+                    .is_some_and(|version| !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .collect();
+        backups.sort();
+        checks.extend(backups.iter().map(|path| check_file(path, t!("升级前的备份"))));
+    }
+
+    if let Some(index) = name_index::index_path(&vault_path).filter(|path| path.exists()) {
+        checks.push(check_file(&index, t!("用户名索引")));
+    }
+    if let Some(sealed) = tpm_store::sealed_path(&vault_path).filter(|path| path.exists()) {
+        checks.push(check_file(&sealed, t!("TPM 封存的密钥")));
+    }
+    let recovery = recovery::path(&vault_path);
+    if recovery.exists() {
+        checks.push(check_file(&recovery, t!("恢复文件")));
+    }
+    checks
+}
+
+/// 打开密码库时的权限检查：只检查密码库文件本身，没有问题或文件还没有创建时返回 None
+pub fn check_vault(vault_path: &Path) -> Option<Check> {
+    if !vault_path.exists() {
+        return None;
+    }
+    Some(check_file(vault_path, t!("密码库文件"))).filter(|check| check.problem.is_some())
+}
+
+// 文件应只有当前用户可以读写
+fn check_file(path: &Path, what: &'static str) -> Check {
+    let mut check = Check { path: path.to_path_buf(), what, problem: None, fix_mode: None };
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            check.problem = Some(t!("无法读取: {}", e));
+            return check;
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let mode = metadata.mode() & 0o777;
+        if metadata.uid() != unsafe { libc::getuid() } {
+            check.problem = Some(t!("文件属于其他用户 (uid {})", metadata.uid()));
+        } else if mode & 0o077 != 0 {
+            check.problem = Some(t!("权限为 {}，其他用户可以访问（应为 0600）", format!("{:04o}", mode)));
+            check.fix_mode = Some(0o600);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    check
+}
+
+// 目录不应允许其他用户写入（设置了粘滞位的目录除外，例如 /tmp）
+fn check_dir(dir: &Path) -> Check {
+    let mut check = Check { path: dir.to_path_buf(), what: t!("密码库所在目录"), problem: None, fix_mode: None };
+    let metadata = match fs::metadata(dir) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            check.problem = Some(t!("无法读取: {}", e));
+            return check;
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let mode = metadata.mode() & 0o7777;
+        if mode & 0o022 != 0 && mode & 0o1000 == 0 {
+            check.problem = Some(t!("权限为 {}，其他用户可以删除或替换其中的密码库文件", format!("{:04o}", mode)));
+            check.fix_mode = Some(mode & 0o755);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+    check
+}
@@ -0,0 +1,60 @@
+// 由密码库中的账号生成 .env 文件（`passman env`）
+//
+// 模板中的 `{{passman:账号.字段}}` 替换为解密后的值，其余内容原样输出；映射文件每行一项
+// `NAME=账号[#字段]`（与 `run --env` 相同），输出 `NAME=值`，值中有特殊字符时加上引号。
+
+use passman::AppError;
+
+use crate::i18n::t;
+
+const OPEN: &str = "{{passman:";
+const CLOSE: &str = "}}";
+
+/// 替换模板中的占位符，`resolve` 由占位符中的引用（`账号.字段`）和同一行中占位符之前已输出的
+/// 内容取得值
+pub fn render(template: &str, mut resolve: impl FnMut(&str, &str) -> Result<String, AppError>) -> Result<String, AppError> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(OPEN) {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + OPEN.len()..];
+        let Some(end) = after.find(CLOSE) else {
+            let line = template[..template.len() - rest.len() + start].matches('\n').count() + 1;
+            return Err(AppError::from(t!("第 {} 行的占位符缺少结尾的 }}", line)));
+        };
+        let line = &output[output.rfind('\n').map_or(0, |i| i + 1)..];
+        let value = resolve(after[..end].trim(), line)?;
+        output.push_str(&value);
+        rest = &after[end + CLOSE.len()..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// 映射文件中的各项（行号和内容），跳过空行和 `#` 开头的注释
+pub fn mapping_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// 写成 `NAME=值` 的一行，值按 [`quote`] 加上引号
+pub fn assignment(name: &str, value: &str) -> String {
+    format!("{}={}", name, quote(value))
+}
+
+/// 值只有常见的安全字符时原样返回，否则优先用单引号（不展开任何内容），含有单引号或换行时
+/// 用双引号并转义
+pub fn quote(value: &str) -> String {
+    let plain = value.chars().all(|c| c.is_ascii_alphanumeric() || "_-.,:/@+=%~".contains(c));
+    if plain {
+        value.to_string()
+    } else if !value.contains(['\'', '\n', '\r']) {
+        format!("'{}'", value)
+    } else {
+        format!("\"{}\"", escape(value))
+    }
+}
+
+/// 转义放在双引号中的值
+pub fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$").replace('\n', "\\n").replace('\r', "\\r")
+}
@@ -0,0 +1,68 @@
+// 胁迫密码打开的诱饵密码库
+//
+// 被迫解锁时输入另外设置的胁迫密码，打开的是另一个诱饵密码库，它与真正的密码库一样可以
+// 查看和修改。输入的密钥不能打开真正的密码库时才尝试诱饵密码库，胁迫密码与主密钥之间
+// 没有任何关联。诱饵密码库是普通的密码库文件，保存在数据目录中，以密码库绝对路径的哈希
+// 命名，不写入真正的密码库文件。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use passman::crypto::{PasswordBackend, Unlock};
+use passman::generator::{self, PasswordOptions};
+use passman::kdf::KdfParams;
+use passman::{Account, AppError, EntryKind, Vault};
+
+use crate::config;
+use crate::i18n::t;
+
+/// 诱饵密码库的文件路径
+pub fn decoy_path(vault_path: &Path) -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("decoy").join(format!("{}.json", config::vault_id(vault_path))))
+}
+
+/// 用 `password` 打开密码库 `vault_path` 的诱饵密码库，没有设置或密码不对时为 None
+pub fn open(vault_path: &Path, password: &str) -> Option<Vault> {
+    let path = decoy_path(vault_path).filter(|path| path.exists())?;
+    Vault::open_with_backend(path, |unlock| match unlock {
+        Unlock::Existing { header } => Ok(Box::new(PasswordBackend::unlock(password, header, KdfParams::generate())?)),
+        Unlock::Create => Err(AppError::from(t!("诱饵密码库不存在"))),
+    })
+    .ok()
+}
+
+/// 密码库 `vault_path` 是否设置了胁迫密码
+pub fn enabled(vault_path: &Path) -> bool {
+    decoy_path(vault_path).is_some_and(|path| path.exists())
+}
+
+/// 以 `password` 为密码库 `vault_path` 新建诱饵密码库
+pub fn create(vault_path: &Path, password: &str, kdf: KdfParams) -> Result<Vault, AppError> {
+    let path = decoy_path(vault_path).ok_or_else(|| AppError::from(t!("无法确定数据目录")))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Vault::open_with_kdf(path, password, kdf)
+}
+
+/// 删除诱饵密码库，返回是否存在
+pub fn remove(vault_path: &Path) -> bool {
+    decoy_path(vault_path).is_some_and(|path| fs::remove_file(path).is_ok())
+}
+
+/// 按 `vault` 中的登录条目填充诱饵密码库：沿用用户名、网址、标签、文件夹和时间，密码
+/// 重新随机生成，不复制备注、自定义字段、一次性密码、附件和其他类型的条目
+pub fn seed(decoy: &mut Vault, vault: &Vault, options: &PasswordOptions) -> Result<usize, AppError> {
+    let mut count = 0;
+    for account in vault.list().iter().filter(|account| account.kind == EntryKind::Login) {
+        let mut copy = Account::new(&account.username, &generator::generate_password(options)?, "");
+        copy.url = account.url.clone();
+        copy.tags = account.tags.clone();
+        copy.folder = account.folder.clone();
+        copy.created_at = account.created_at;
+        copy.updated_at = account.updated_at;
+        decoy.add(copy)?;
+        count += 1;
+    }
+    Ok(count)
+}
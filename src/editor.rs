@@ -0,0 +1,80 @@
+// 在外部编辑器中编辑文本
+//
+// 依次使用 VISUAL、EDITOR 环境变量指定的编辑器，都没有设置时使用 vi（Windows 上为
+// notepad）。临时文件只有当前用户可以读写，编辑结束后用零覆盖再删除。
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::i18n::t;
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+// 把 `text` 写入扩展名为 `extension` 的临时文件，在编辑器中打开，返回保存后的内容
+pub fn edit(text: &str, extension: &str) -> Result<String, String> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    // 编辑器可以带参数，例如 `code --wait`
+    let mut command = editor.split_whitespace();
+    let program = command.next().unwrap_or(DEFAULT_EDITOR);
+
+    let (path, mut file) = create_temp_file(extension).map_err(|e| t!("无法创建临时文件: {}", e))?;
+    let result = file
+        .write_all(text.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| t!("无法写入临时文件: {}", e))
+        .and_then(|_| {
+            let status = Command::new(program)
+                .args(command)
+                .arg(&path)
+                .status()
+                .map_err(|e| t!("无法启动编辑器 {}: {}", program, e))?;
+            if !status.success() {
+                return Err(t!("编辑器 {} 异常退出", program));
+            }
+            fs::read_to_string(&path).map_err(|e| t!("无法读取临时文件: {}", e))
+        });
+    drop(file);
+
+    secure_delete(&path);
+    result
+}
+
+/// 在临时目录中创建随机命名的新文件，优先使用只属于当前用户的 XDG_RUNTIME_DIR
+pub fn create_temp_file(extension: &str) -> Result<(PathBuf, File), io::Error> {
+    let dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|dir| dir.is_dir()).unwrap_or_else(env::temp_dir);
+    let name = format!("passman-{:016x}.{}", rand::random::<u64>(), extension);
+    let path = dir.join(name);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(&path)?;
+    Ok((path, file))
+}
+
+/// 用零覆盖文件内容并同步到磁盘后删除，尽量不在磁盘上留下明文
+pub fn secure_delete(path: &Path) {
+    if let Ok(mut file) = OpenOptions::new().write(true).open(path)
+        && let Ok(metadata) = file.metadata()
+    {
+        let _ = file
+            .write_all(&vec![0; metadata.len() as usize])
+            .and_then(|_| file.sync_all());
+    }
+    let _ = fs::remove_file(path);
+}
@@ -0,0 +1,330 @@
+// 在编辑器中修改账号以及批量添加账号时使用的 TOML 文档
+//
+// 与配置文件一样只支持 TOML 的一个子集：字符串、字符串数组、多行基本字符串
+// （用于备注）以及 [fields] 和 [secret_fields] 两个表。批量添加时每个账号以
+// [[entry]] 开始，其中的表写作 [entry.fields] 和 [entry.secret_fields]。
+// 批量添加也支持同样结构的 JSON 数组。
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use passman::timestamp;
+use passman::{Account, EntryKind};
+
+use crate::config::{format_key, quote, strip_comment, unescape};
+use crate::i18n::t;
+
+// 多行字符串的定界符
+const MULTILINE: &str = "\"\"\"";
+
+// 可以在编辑器中修改的账号内容
+#[derive(Clone, PartialEq, Default)]
+pub struct EntryFile {
+    pub username: String,
+    // 条目类型（login、card、identity、api-key、note、ssh-key、wifi），空字符串表示登录
+    pub kind: String,
+    pub password: String,
+    pub notes: String,
+    pub url: String,
+    pub folder: String,
+    pub tags: Vec<String>,
+    // 有效期（例如 90d）、过期日期（YYYY-MM-DD）或 never
+    pub expires: String,
+    pub fields: Vec<(String, String)>,
+    pub secret_fields: Vec<(String, String)>,
+}
+
+impl EntryFile {
+    pub fn from_account(account: &Account) -> Self {
+        let expires = match (account.rotation, account.expires_at) {
+            (Some(period), _) => format!("{}d", period / timestamp::DAY),
+            (None, Some(expires_at)) => timestamp::format(expires_at).split(' ').next().unwrap_or_default().to_string(),
+            (None, None) => "never".to_string(),
+        };
+        let fields = |secret: bool| {
+            account
+                .fields
+                .iter()
+                .filter(|field| field.secret == secret)
+                .map(|field| (field.name.clone(), field.value.clone()))
+                .collect()
+        };
+
+        EntryFile {
+            username: account.username.clone(),
+            kind: kind_name(account.kind),
+            password: account.password.clone(),
+            notes: account.notes.clone(),
+            url: account.url.clone().unwrap_or_default(),
+            folder: account.folder.clone().unwrap_or_default(),
+            tags: account.tags.clone(),
+            expires,
+            fields: fields(false),
+            secret_fields: fields(true),
+        }
+    }
+
+    // 输出为 TOML 文档，`header` 作为开头的注释
+    pub fn to_toml(&self, header: &str) -> String {
+        let mut lines: Vec<String> = header.lines().map(|line| format!("# {}", line)).collect();
+        lines.push(String::new());
+        lines.push(format!("username = {}", quote(&self.username)));
+        lines.push(format!("type = {}", quote(&self.kind)));
+        lines.push(format!("password = {}", quote(&self.password)));
+        lines.push(format!("url = {}", quote(&self.url)));
+        lines.push(format!("folder = {}", quote(&self.folder)));
+        let tags: Vec<String> = self.tags.iter().map(|tag| quote(tag)).collect();
+        lines.push(format!("tags = [{}]", tags.join(", ")));
+        lines.push(format!("expires = {}", quote(&self.expires)));
+        lines.push(format!("notes = {}", MULTILINE));
+        lines.push(format!("{}{}", escape_multiline(&self.notes), MULTILINE));
+
+        for (table, fields) in [("fields", &self.fields), ("secret_fields", &self.secret_fields)] {
+            lines.push(String::new());
+            lines.push(format!("[{}]", table));
+            for (name, value) in fields {
+                lines.push(format!("{} = {}", format_key(name), quote(value)));
+            }
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    // 条目类型
+    pub fn kind(&self) -> Result<EntryKind, String> {
+        if self.kind.trim().is_empty() {
+            return Ok(EntryKind::Login);
+        }
+        // 与命令行一样使用 api-key 的写法，也接受 api_key
+        let name = self.kind.trim().to_lowercase().replace('-', "_");
+        serde_json::from_value(serde_json::Value::String(name))
+            .map_err(|_| t!("未知的条目类型: {}（可选 login、card、identity、api-key、note、ssh-key、wifi）", self.kind))
+    }
+
+    // 解析编辑后的文档，省略的键视为空值
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::parse_lines(text.lines().enumerate(), "")
+    }
+
+    // 解析批量添加的 TOML 文档，每个 [[entry]] 单独返回解析结果
+    pub fn parse_list(text: &str) -> Result<Vec<Result<Self, String>>, String> {
+        let mut entries: Vec<Vec<(usize, &str)>> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let content = strip_comment(line).trim();
+            if content == "[[entry]]" {
+                entries.push(Vec::new());
+            } else if let Some(entry) = entries.last_mut() {
+                entry.push((number, line));
+            } else if !content.is_empty() {
+                return Err(t!("第 {} 行: 每个账号应以 [[entry]] 开始", number + 1));
+            }
+        }
+        Ok(entries.into_iter().map(|lines| Self::parse_lines(lines.into_iter(), "entry.")).collect())
+    }
+
+    // 解析批量添加的 JSON 数组，每个对象单独返回解析结果
+    pub fn parse_json_list(text: &str) -> Result<Vec<Result<Self, String>>, String> {
+        let values: Vec<serde_json::Value> = serde_json::from_str(text).map_err(|e| t!("无效的 JSON: {}", e))?;
+        Ok(values.into_iter().map(Self::from_json).collect())
+    }
+
+    // 解析一个 JSON 对象，键与批量添加时相同
+    pub fn parse_json(text: &str) -> Result<Self, String> {
+        Self::from_json(serde_json::from_str(text).map_err(|e| t!("无效的 JSON: {}", e))?)
+    }
+
+    fn from_json(value: serde_json::Value) -> Result<Self, String> {
+        // JSON 中的条目，键与 TOML 相同
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct JsonEntry {
+            #[serde(default)]
+            username: String,
+            #[serde(default, rename = "type")]
+            kind: String,
+            #[serde(default)]
+            password: String,
+            #[serde(default)]
+            notes: String,
+            #[serde(default)]
+            url: String,
+            #[serde(default)]
+            folder: String,
+            #[serde(default)]
+            tags: Vec<String>,
+            #[serde(default)]
+            expires: String,
+            #[serde(default)]
+            fields: BTreeMap<String, String>,
+            #[serde(default)]
+            secret_fields: BTreeMap<String, String>,
+        }
+
+        let entry: JsonEntry = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        let file = EntryFile {
+            username: entry.username,
+            kind: entry.kind,
+            password: entry.password,
+            notes: entry.notes,
+            url: entry.url,
+            folder: entry.folder,
+            tags: entry.tags,
+            expires: entry.expires,
+            fields: entry.fields.into_iter().collect(),
+            secret_fields: entry.secret_fields.into_iter().collect(),
+        };
+        if file.username.trim().is_empty() {
+            return Err(t!("username 不能为空").to_string());
+        }
+        Ok(file)
+    }
+
+    // 逐行解析，`prefix` 为表名的前缀（批量添加时为 `entry.`）
+    fn parse_lines<'a>(mut lines: impl Iterator<Item = (usize, &'a str)>, prefix: &str) -> Result<Self, String> {
+        let mut file = EntryFile { expires: "never".to_string(), ..Default::default() };
+        let mut table: Option<String> = None;
+
+        while let Some((number, line)) = lines.next() {
+            let error = |message: &str| t!("第 {} 行: {}", number + 1, message);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let header = name.strip_suffix(']').ok_or_else(|| error(t!("表头缺少 ]")))?.trim();
+                let name = header.strip_prefix(prefix).filter(|name| matches!(*name, "fields" | "secret_fields"));
+                let name = name.ok_or_else(|| {
+                    error(&t!("未知的表: {}（只支持 {}fields 和 {}secret_fields）", header, prefix, prefix))
+                })?;
+                table = Some(name.to_string());
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| error(t!("缺少 =")))?;
+            let key = parse_key(key.trim()).ok_or_else(|| error(t!("无效的键")))?;
+            let value = value.trim();
+
+            if let Some(table) = &table {
+                let value = parse_string(value).ok_or_else(|| error(t!("字段的值应为字符串")))?;
+                let fields = if table == "fields" { &mut file.fields } else { &mut file.secret_fields };
+                if fields.iter().any(|(name, _)| *name == key) {
+                    return Err(error(&t!("重复的字段: {}", key)));
+                }
+                fields.push((key, value));
+                continue;
+            }
+
+            match key.as_str() {
+                "tags" => file.tags = parse_string_array(value).ok_or_else(|| error(t!("tags 应为字符串数组")))?,
+                "notes" => {
+                    file.notes = match value.strip_prefix(MULTILINE) {
+                        // 多行字符串一直读到结束的定界符，紧跟开头定界符的换行不计入内容
+                        Some(first) => {
+                            let mut content = first.to_string();
+                            let mut closed = content.strip_suffix(MULTILINE).map(str::to_string);
+                            while closed.is_none() {
+                                let (_, line) = lines.next().ok_or_else(|| error(t!("多行字符串缺少结束的 \"\"\"")))?;
+                                content.push('\n');
+                                content.push_str(line);
+                                closed = content.trim_end().strip_suffix(MULTILINE).map(str::to_string);
+                            }
+                            let content = closed.unwrap_or_default();
+                            let content = content.strip_prefix('\n').unwrap_or(&content);
+                            unescape(content).ok_or_else(|| error(t!("备注中有无效的转义")))?
+                        }
+                        None => parse_string(value).ok_or_else(|| error(t!("notes 应为字符串")))?,
+                    };
+                }
+                _ => {
+                    let value = parse_string(value).ok_or_else(|| error(&t!("{} 应为字符串", key)))?;
+                    match key.as_str() {
+                        "username" => file.username = value,
+                        "type" => file.kind = value,
+                        "password" => file.password = value,
+                        "url" => file.url = value,
+                        "folder" => file.folder = value,
+                        "expires" => file.expires = value,
+                        _ => return Err(error(&t!("未知的键: {}", key))),
+                    }
+                }
+            }
+        }
+
+        if file.username.trim().is_empty() {
+            return Err(t!("username 不能为空").to_string());
+        }
+        Ok(file)
+    }
+}
+
+// 条目类型的名称，与命令行的 --type 相同
+fn kind_name(kind: EntryKind) -> String {
+    let name = serde_json::to_value(kind).ok().and_then(|value| value.as_str().map(str::to_string)).unwrap_or_default();
+    name.replace('_', "-")
+}
+
+// 多行基本字符串中需要转义反斜杠和引号，换行和制表符原样保留
+fn escape_multiline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' | '\t' => out.push(c),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// 裸键或带引号的键（字段名称可以包含任意字符）
+fn parse_key(key: &str) -> Option<String> {
+    if key.starts_with('"') || key.starts_with('\'') {
+        return parse_string(key);
+    }
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    bare.then(|| key.to_string())
+}
+
+// 基本字符串或字面量字符串
+fn parse_string(value: &str) -> Option<String> {
+    if let Some(inner) = value.strip_prefix('\'') {
+        return inner.strip_suffix('\'').filter(|inner| !inner.contains('\'')).map(str::to_string);
+    }
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    unescape(inner)
+}
+
+// 单行的字符串数组，例如 ["a", "b"]
+fn parse_string_array(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut items = Vec::new();
+    let mut rest = inner.trim_start();
+    while !rest.is_empty() {
+        // 找到与开头引号对应的结束引号（跳过转义的引号）
+        let quote = rest.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let mut escaped = false;
+        let end = rest.char_indices().skip(1).find(|&(_, c)| {
+            let found = c == quote && !escaped;
+            escaped = quote == '"' && c == '\\' && !escaped;
+            found
+        })?.0;
+        items.push(parse_string(&rest[..=end])?);
+
+        rest = rest[end + 1..].trim_start();
+        match rest.strip_prefix(',') {
+            Some(after) => rest = after.trim_start(),
+            None if rest.is_empty() => {}
+            None => return None,
+        }
+    }
+    Some(items)
+}
@@ -0,0 +1,64 @@
+use std::io;
+
+// 自定义错误类型以包装 aes_gcm::Error
+#[derive(Debug)]
+pub enum AppError {
+    Io(io::Error),
+    Serde(serde_json::Error),
+    Base64(base64::DecodeError),
+    Aes(String),
+    // 主密钥与密码库头部的校验值不符
+    WrongMasterKey,
+    Other(String),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Io(e) => write!(f, "IO错误: {}", e),
+            AppError::Serde(e) => write!(f, "序列化错误: {}", e),
+            AppError::Base64(e) => write!(f, "Base64解码错误: {}", e),
+            AppError::Aes(s) => write!(f, "加密/解密错误: {}", s),
+            AppError::WrongMasterKey => write!(f, "主密钥错误"),
+            AppError::Other(s) => write!(f, "其他错误: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Serde(err)
+    }
+}
+
+impl From<base64::DecodeError> for AppError {
+    fn from(err: base64::DecodeError) -> Self {
+        AppError::Base64(err)
+    }
+}
+
+impl From<aes_gcm::Error> for AppError {
+    fn from(_: aes_gcm::Error) -> Self {
+        AppError::Aes("加密/解密操作失败".to_string())
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(s: &str) -> Self {
+        AppError::Other(s.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(s: String) -> Self {
+        AppError::Other(s)
+    }
+}
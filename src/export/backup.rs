@@ -0,0 +1,8 @@
+use crate::backup;
+use crate::error::AppError;
+use crate::vault::Vault;
+
+/// 导出为加密备份文件，包含账号的全部数据
+pub fn export_backup(vault: &Vault, password: &str) -> Result<Vec<u8>, AppError> {
+    backup::create(vault.list(), password)
+}
@@ -0,0 +1,42 @@
+use crate::error::AppError;
+use crate::vault::{Account, Vault};
+
+/// 可以导出到 CSV 的字段
+pub const CSV_FIELDS: [&str; 7] = ["username", "password", "url", "notes", "folder", "tags", "otp"];
+
+/// 导出为明文 CSV，`fields` 指定导出的列及其顺序（取自 [`CSV_FIELDS`]）
+///
+/// 第一行是字段名，标签以逗号分隔，一次性密码以 otpauth:// URI 的形式导出。
+pub fn export_csv(vault: &Vault, fields: &[&str]) -> Result<Vec<u8>, AppError> {
+    if fields.is_empty() {
+        return Err(AppError::from("至少需要导出一个字段"));
+    }
+    if let Some(field) = fields.iter().find(|f| !CSV_FIELDS.contains(f)) {
+        return Err(AppError::from(format!("未知的字段: {}", field)));
+    }
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(fields).map_err(csv_error)?;
+
+    let mut accounts: Vec<&Account> = vault.list().iter().collect();
+    accounts.sort_by(|a, b| a.username.cmp(&b.username));
+    for account in accounts {
+        let username = &account.username;
+        let record = fields.iter().map(|&field| match field {
+            "username" => username.clone(),
+            "password" => account.password.clone(),
+            "url" => account.url.clone().unwrap_or_default(),
+            "notes" => account.notes.clone(),
+            "folder" => account.folder.clone().unwrap_or_default(),
+            "tags" => account.tags.join(","),
+            _ => account.otp.as_ref().map(|otp| otp.to_uri(username)).unwrap_or_default(),
+        });
+        writer.write_record(record).map_err(csv_error)?;
+    }
+
+    writer.into_inner().map_err(|e| AppError::from(format!("CSV 写入错误: {}", e)))
+}
+
+fn csv_error(err: csv::Error) -> AppError {
+    AppError::from(format!("CSV 写入错误: {}", err))
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use base64::{Engine as _, engine::general_purpose};
+
+use crate::error::AppError;
+use crate::vault::{Account, Vault};
+
+/// 导出为 Kubernetes Secret 的 YAML 清单，返回内容和导出的账号数量
+///
+/// 只导出带有 `tags` 中全部标签的账号：密码保存为以用户名命名的键，自定义字段保存为
+/// `用户名.字段名`，用户名中 Kubernetes 不允许的字符替换为 `_`。值按 Secret 的要求以 Base64
+/// 编码。两个账号得到同一个键时返回错误，以免其中一个被悄悄覆盖。
+pub fn export_k8s(vault: &Vault, tags: &[String], name: &str, namespace: Option<&str>) -> Result<(Vec<u8>, usize), AppError> {
+    if !valid_name(name, false) {
+        return Err(AppError::from(format!("无效的 Secret 名称: {}（只能包含小写字母、数字、- 和 .）", name)));
+    }
+    if let Some(namespace) = namespace.filter(|namespace| !valid_name(namespace, true)) {
+        return Err(AppError::from(format!("无效的命名空间: {}（只能包含小写字母、数字和 -）", namespace)));
+    }
+
+    let mut data: BTreeMap<String, (&str, &Account)> = BTreeMap::new();
+    let mut count = 0;
+    for account in vault.list().iter().filter(|account| tags.iter().all(|tag| account.tags.iter().any(|t| t == tag.trim()))) {
+        let base = key(&account.username);
+        let values = (!account.password.is_empty())
+            .then(|| (base.clone(), account.password.as_str()))
+            .into_iter()
+            .chain(account.fields.iter().map(|field| (format!("{}.{}", base, key(&field.name)), field.value.as_str())));
+        let mut exported = false;
+        for (key, value) in values {
+            if let Some((_, other)) = data.get(&key) {
+                return Err(AppError::from(format!("账号 {} 和 {} 都导出为键 {}，请修改其中一个的用户名", other.username, account.username, key)));
+            }
+            data.insert(key, (value, account));
+            exported = true;
+        }
+        count += usize::from(exported);
+    }
+
+    let mut yaml = String::from("apiVersion: v1\nkind: Secret\nmetadata:\n");
+    yaml.push_str(&format!("  name: {}\n", name));
+    if let Some(namespace) = namespace {
+        yaml.push_str(&format!("  namespace: {}\n", namespace));
+    }
+    yaml.push_str("type: Opaque\n");
+    if data.is_empty() {
+        yaml.push_str("data: {}\n");
+    } else {
+        yaml.push_str("data:\n");
+        for (key, (value, _)) in &data {
+            // 键总是加上引号，避免 true、1 这样的键被 YAML 当作其他类型
+            yaml.push_str(&format!("  \"{}\": {}\n", key, general_purpose::STANDARD.encode(value)));
+        }
+    }
+    Ok((yaml.into_bytes(), count))
+}
+
+// Secret 的键只能由字母、数字和 `-`、`_`、`.` 组成
+fn key(name: &str) -> String {
+    let key: String = name.chars().map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' }).collect();
+    match key.as_str() {
+        "" => "_".to_string(),
+        // `.` 和 `..` 不是有效的键
+        "." | ".." => key.replace('.', "_"),
+        _ => key,
+    }
+}
+
+// 名称需要是 DNS 子域名，命名空间需要是 DNS 标签（没有 `.`，最长 63 个字符）：小写字母、
+// 数字和 `-`，以字母或数字开头和结尾
+fn valid_name(name: &str, label: bool) -> bool {
+    !name.is_empty()
+        && name.len() <= if label { 63 } else { 253 }
+        && name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || (b == b'.' && !label))
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+}
@@ -0,0 +1,39 @@
+use crate::error::AppError;
+use crate::kdbx::{self, Database, Entry, Field};
+use crate::vault::{Account, Vault};
+
+/// 导出为 KeePass KDBX 4 数据库
+///
+/// 用户名同时作为条目标题，文件夹转换为分组，一次性密码写入 KeePassXC 的 `otp` 字段。
+pub fn export_kdbx(vault: &Vault, password: &str) -> Result<Vec<u8>, AppError> {
+    let mut accounts: Vec<&Account> = vault.list().iter().collect();
+    accounts.sort_by(|a, b| a.username.cmp(&b.username));
+
+    let entries = accounts
+        .into_iter()
+        .map(|account| {
+            let username = &account.username;
+            let mut fields: Vec<Field> = account
+                .fields
+                .iter()
+                .map(|field| Field { key: field.name.clone(), value: field.value.clone(), protected: field.secret })
+                .collect();
+            if let Some(otp) = &account.otp {
+                fields.push(Field { key: "otp".to_string(), value: otp.to_uri(username), protected: true });
+            }
+
+            Entry {
+                group: account.folder.iter().flat_map(|f| f.split('/')).map(String::from).collect(),
+                title: username.clone(),
+                username: username.clone(),
+                password: account.password.clone(),
+                url: account.url.clone().unwrap_or_default(),
+                notes: account.notes.clone(),
+                tags: account.tags.clone(),
+                fields,
+            }
+        })
+        .collect();
+
+    kdbx::write(&Database { entries }, password)
+}
@@ -0,0 +1,15 @@
+//! 将密码库导出为其他密码管理器可读取的格式
+
+mod backup;
+mod csv;
+mod k8s;
+mod kdbx;
+mod netrc;
+mod pass;
+
+pub use backup::export_backup;
+pub use csv::{CSV_FIELDS, export_csv};
+pub use k8s::export_k8s;
+pub use kdbx::export_kdbx;
+pub use netrc::export_netrc;
+pub use pass::export_pass;
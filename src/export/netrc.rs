@@ -0,0 +1,61 @@
+use crate::kind::EntryKind;
+use crate::vault::{Account, Vault};
+
+/// 导出为 netrc 文件的内容，返回内容和导出的账号数量
+///
+/// 只导出带有 `tags` 中全部标签的登录账号，每个账号一行 `machine 主机 login 用户名 password 密码`。
+/// 主机取自账号的网址（没有网址时取备注中第一个带协议的地址），找不到主机的账号跳过。同一
+/// 主机有多个账号时最近使用的在前，curl 等工具默认使用第一个。含有空白、引号或反斜杠的
+/// 值加上引号并转义（curl 7.84 起支持）。
+pub fn export_netrc(vault: &Vault, tags: &[String]) -> (Vec<u8>, usize) {
+    let mut entries: Vec<(&str, &Account)> = vault
+        .list()
+        .iter()
+        .filter(|account| account.kind == EntryKind::Login)
+        .filter(|account| tags.iter().all(|tag| account.tags.iter().any(|t| t == tag.trim())))
+        .filter_map(|account| Some((machine(account)?, account)))
+        .collect();
+    entries.sort_by(|(a, x), (b, y)| a.cmp(b).then(y.last_used_at.cmp(&x.last_used_at)));
+
+    let mut content = String::new();
+    for (host, account) in &entries {
+        content.push_str(&format!("machine {}", host));
+        if !account.username.is_empty() {
+            content.push_str(&format!(" login {}", quote(&account.username)));
+        }
+        content.push_str(&format!(" password {}\n", quote(&account.password)));
+    }
+    (content.into_bytes(), entries.len())
+}
+
+// 账号的主机名（不含用户信息和端口，netrc 只按主机名匹配）
+fn machine(account: &Account) -> Option<&str> {
+    let url = account.url.as_deref().or_else(|| account.notes.split_whitespace().find(|word| word.contains("://")))?;
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next()?.rsplit('@').next()?;
+    // IPv6 地址写在方括号中
+    let host = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next()?,
+        None => host.split(':').next()?,
+    };
+    (!host.is_empty() && !host.contains(char::is_whitespace)).then_some(host)
+}
+
+fn quote(value: &str) -> String {
+    if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        return value.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
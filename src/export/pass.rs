@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::atomic;
+use crate::error::AppError;
+use crate::vault::{Account, Vault};
+
+// pass 保存 GPG 接收者的文件
+const GPG_ID_FILE: &str = ".gpg-id";
+
+/// 导出到 pass (password-store) 目录，返回导出的账号数量
+///
+/// 每个账号写入 `文件夹/用户名.gpg`，没有文件夹时写入 `域名/用户名.gpg`（备注中没有网址时
+/// 直接写入 `用户名.gpg`）。文件内容遵循 pass 的约定：第一行是密码，随后是 `login:`、
+/// `tags:`、一次性密码的 otpauth:// URI（pass-otp）和备注。`recipients` 为空时使用目录中
+/// `.gpg-id` 记录的接收者；否则会写入 `.gpg-id`，与 `pass init` 相同。已存在的文件会被覆盖。
+pub fn export_pass(vault: &Vault, dir: &Path, recipients: &[String]) -> Result<usize, AppError> {
+    let gpg_id_path = dir.join(GPG_ID_FILE);
+    let recipients = if recipients.is_empty() {
+        let content = fs::read_to_string(&gpg_id_path).map_err(|_| {
+            AppError::from(format!("{} 中没有 {}，请使用 --gpg-id 指定 GPG 接收者", dir.display(), GPG_ID_FILE))
+        })?;
+        content.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect()
+    } else {
+        fs::create_dir_all(dir)?;
+        atomic::write(&gpg_id_path, recipients.join("\n") + "\n")?;
+        recipients.to_vec()
+    };
+
+    let mut accounts: Vec<&Account> = vault.list().iter().collect();
+    accounts.sort_by(|a, b| a.username.cmp(&b.username));
+    let mut written = HashSet::new();
+    for account in &accounts {
+        let mut path = entry_path(dir, account, "");
+        // 同一目录下用户名相同的账号以短 ID 区分
+        if !written.insert(path.clone()) {
+            path = entry_path(dir, account, &format!("-{}", account.short_id()));
+            written.insert(path.clone());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        gpg_encrypt(&path, &recipients, entry_content(&account.username, account).as_bytes())?;
+    }
+
+    Ok(accounts.len())
+}
+
+// 条目文件路径：有文件夹时使用文件夹，否则以网址（或备注中第一个网址）的域名作为目录
+fn entry_path(dir: &Path, account: &Account, suffix: &str) -> PathBuf {
+    let mut path = dir.to_path_buf();
+    let mut urls = account.url.iter().map(String::as_str).chain(account.notes.split_whitespace());
+    if let Some(folder) = &account.folder {
+        path.extend(folder.split('/').map(sanitize));
+    } else if let Some(domain) = urls.find_map(domain) {
+        path.push(sanitize(domain));
+    }
+    path.push(format!("{}{}.gpg", sanitize(&account.username), suffix));
+    path
+}
+
+// 从网址中取出域名
+fn domain(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+// 路径分隔符和开头的点不能出现在文件名中
+fn sanitize(name: &str) -> String {
+    let name: String = name.chars().map(|c| if c == '/' || c == '\\' { '_' } else { c }).collect();
+    match name.strip_prefix('.') {
+        Some(rest) => format!("_{}", rest),
+        None => name,
+    }
+}
+
+fn entry_content(username: &str, account: &Account) -> String {
+    let mut lines = vec![account.password.clone(), format!("login: {}", username)];
+    if let Some(url) = &account.url {
+        lines.push(format!("url: {}", url));
+    }
+    if !account.tags.is_empty() {
+        lines.push(format!("tags: {}", account.tags.join(", ")));
+    }
+    if let Some(otp) = &account.otp {
+        lines.push(otp.to_uri(username));
+    }
+    for field in &account.fields {
+        lines.push(format!("{}: {}", field.name, field.value));
+    }
+    if !account.notes.is_empty() {
+        lines.push(account.notes.clone());
+    }
+    lines.join("\n") + "\n"
+}
+
+// 调用 gpg 加密并写入文件
+fn gpg_encrypt(path: &Path, recipients: &[String], data: &[u8]) -> Result<(), AppError> {
+    let mut command = Command::new("gpg");
+    command.args(["--batch", "--yes", "--quiet", "--encrypt", "--output"]).arg(path);
+    for recipient in recipients {
+        command.args(["--recipient", recipient]);
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::from(format!("无法运行 gpg: {}", e)))?;
+    child.stdin.take().ok_or("无法写入 gpg")?.write_all(data)?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(AppError::from(format!(
+            "gpg 加密失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
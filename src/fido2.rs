@@ -0,0 +1,158 @@
+//! 以 FIDO2 安全密钥的 hmac-secret 扩展解锁密码库
+//!
+//! 在安全密钥上为 passman 注册一个凭据（启用 hmac-secret 扩展），密码库头部保存凭据 ID
+//! 和一个随机盐。解锁时以这个凭据请求断言，安全密钥在验证 PIN 和触摸之后用凭据内部的
+//! 密钥对盐计算 HMAC，密码库的密钥由这个结果派生，不需要输入主密钥。同一个盐只有注册
+//! 凭据的那个安全密钥才能算出同样的结果；每次重新加密时换一个新的盐。
+//!
+//! 通过 libfido2 的 `fido2-token`、`fido2-cred` 和 `fido2-assert` 与安全密钥通信；PIN
+//! 由它们直接在终端中请求，不经过 passman。
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use aes_gcm::aead::Buffer;
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{Cipher, CryptoBackend, KeyHeader};
+use crate::error::AppError;
+use crate::hkdf::hkdf_sha256;
+use crate::secmem::{self, SecretBytes};
+
+// 注册凭据时使用的依赖方 ID
+const RELYING_PARTY: &str = "passman";
+// hmac-secret 的盐和输出的长度
+const SECRET_LENGTH: usize = 32;
+
+/// 注册在 FIDO2 安全密钥上的凭据及 hmac-secret 的盐，保存在密码库头部
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Fido2Key {
+    /// 依赖方 ID
+    pub rp_id: String,
+    /// Base64 编码的凭据 ID
+    pub credential: String,
+    /// Base64 编码的随机盐
+    pub salt: String,
+}
+
+/// 由 FIDO2 安全密钥派生密钥的加密后端
+pub struct Fido2Backend {
+    key: SecretBytes,
+    header: Fido2Key,
+}
+
+impl Fido2Backend {
+    /// 在设备 `device` 上注册新的凭据并由它派生密钥；安全密钥需要设置 PIN，注册和派生
+    /// 各需要触摸一次
+    pub fn register(device: &str) -> Result<Self, AppError> {
+        let user_id = rand::random::<[u8; 16]>();
+        let input = [
+            encode(&rand::random::<[u8; 32]>()),
+            RELYING_PARTY.to_string(),
+            RELYING_PARTY.to_string(),
+            encode(&user_id),
+        ];
+        let output = fido2("fido2-cred", &["-M", "-h", "-v", device], &input)?;
+        // 输出依次为客户端数据摘要、依赖方 ID、格式、认证器数据、凭据 ID 和证明
+        let output = String::from_utf8_lossy(&output);
+        let credential = output.lines().nth(4).ok_or_else(|| malformed("fido2-cred"))?;
+        Self::with_credential(device, RELYING_PARTY, credential)
+    }
+
+    /// 以设备 `device` 上已经注册的凭据和新的随机盐派生密钥
+    pub fn with_credential(device: &str, rp_id: &str, credential: &str) -> Result<Self, AppError> {
+        let header = Fido2Key {
+            rp_id: rp_id.to_string(),
+            credential: credential.to_string(),
+            salt: encode(&rand::random::<[u8; SECRET_LENGTH]>()),
+        };
+        let key = derive(device, &header)?;
+        Ok(Fido2Backend { key, header })
+    }
+
+    /// 用设备 `device` 解锁头部为 `header` 的密码库
+    pub fn unlock(device: &str, header: &KeyHeader) -> Result<Self, AppError> {
+        let fido2_key = header.fido2.as_ref().ok_or("此密码库不是以 FIDO2 安全密钥加密的")?;
+        let key = derive(device, fido2_key)?;
+        Ok(Fido2Backend { key, header: fido2_key.clone() })
+    }
+}
+
+impl CryptoBackend for Fido2Backend {
+    fn key_header(&self) -> KeyHeader {
+        KeyHeader { fido2: Some(self.header.clone()), ..KeyHeader::default() }
+    }
+
+    fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.encrypt(&self.key, nonce, aad, data)?)
+    }
+
+    fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.decrypt(&self.key, nonce, aad, data)?)
+    }
+}
+
+/// 第一个连接的 FIDO2 设备
+pub fn default_device() -> Result<String, AppError> {
+    let output = fido2("fido2-token", &["-L"], &[])?;
+    // 每行为“设备路径: 厂商和产品信息”
+    String::from_utf8_lossy(&output)
+        .lines()
+        .find_map(|line| line.split_once(": ").map(|(device, _)| device.to_string()))
+        .ok_or_else(|| AppError::from("没有找到 FIDO2 安全密钥"))
+}
+
+// 请求断言并由 hmac-secret 的结果派生密码库的密钥
+fn derive(device: &str, fido2_key: &Fido2Key) -> Result<SecretBytes, AppError> {
+    let input = [
+        encode(&rand::random::<[u8; 32]>()),
+        fido2_key.rp_id.clone(),
+        fido2_key.credential.clone(),
+        fido2_key.salt.clone(),
+    ];
+    let mut output = fido2("fido2-assert", &["-G", "-h", "-v", device], &input)?;
+    // hmac-secret 的结果是输出的最后一行
+    let secret = String::from_utf8_lossy(&output)
+        .lines()
+        .last()
+        .and_then(|line| general_purpose::STANDARD.decode(line).ok());
+    secmem::zero(&mut output);
+    let mut secret = secret.filter(|secret| secret.len() == SECRET_LENGTH).ok_or_else(|| malformed("fido2-assert"))?;
+
+    let mut key = SecretBytes::from_slice(&[0u8; 32]);
+    hkdf_sha256(&secret, &[], b"passman vault key", &mut key);
+    secmem::zero(&mut secret);
+    Ok(key)
+}
+
+// 运行 libfido2 的工具，从标准输入逐行写入 `input`，返回标准输出；标准错误留给终端，
+// PIN 也由工具在终端中请求
+fn fido2(program: &str, args: &[&str], input: &[String]) -> Result<Vec<u8>, AppError> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| AppError::from(format!("无法运行 {}（需要安装 libfido2 的工具）: {}", program, e)))?;
+    let mut stdin = child.stdin.take().ok_or("无法写入 FIDO2 工具")?;
+    for line in input {
+        writeln!(stdin, "{}", line)?;
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(AppError::from(format!("{} 失败，退出码 {}", program, output.status.code().unwrap_or(-1))));
+    }
+    Ok(output.stdout)
+}
+
+fn malformed(program: &str) -> AppError {
+    AppError::from(format!("{} 的输出格式错误", program))
+}
+
+fn encode(data: &[u8]) -> String {
+    general_purpose::STANDARD.encode(data)
+}
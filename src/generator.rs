@@ -0,0 +1,144 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::error::AppError;
+
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>?/~";
+
+// 内置词表：1296 个单词（四个骰子），每个单词约 10.3 位熵；每行为骰子编号和单词，以制表符分隔，
+// 格式与 EFF 词表相同，可以直接替换
+const WORDLIST: &str = include_str!("../assets/wordlist.txt");
+
+/// 默认密码长度
+pub const DEFAULT_LENGTH: usize = 20;
+/// 默认口令单词数：内置词表下约 83 位熵，不低于 EFF 大词表（7776 个单词）的 6 个单词
+pub const DEFAULT_WORDS: usize = 8;
+
+/// 随机密码的生成规则
+#[derive(Clone)]
+pub struct PasswordOptions {
+    pub length: usize,
+    pub upper: bool,
+    pub lower: bool,
+    pub digits: bool,
+    pub symbols: bool,
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        PasswordOptions {
+            length: DEFAULT_LENGTH,
+            upper: true,
+            lower: true,
+            digits: true,
+            symbols: true,
+        }
+    }
+}
+
+impl PasswordOptions {
+    /// 使用默认字符集、指定长度
+    pub fn with_length(length: usize) -> Self {
+        PasswordOptions { length, ..Default::default() }
+    }
+}
+
+/// 生成随机密码，每种启用的字符类别至少出现一次
+pub fn generate_password(options: &PasswordOptions) -> Result<String, AppError> {
+    let classes: Vec<&[u8]> = [
+        (options.upper, UPPER),
+        (options.lower, LOWER),
+        (options.digits, DIGITS),
+        (options.symbols, SYMBOLS),
+    ]
+    .iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, chars)| chars.as_bytes())
+    .collect();
+
+    if classes.is_empty() {
+        return Err(AppError::from("至少需要启用一种字符类别"));
+    }
+
+    if options.length < classes.len() {
+        return Err(AppError::from(format!("密码长度至少为 {}", classes.len())));
+    }
+
+    let mut rng = rand::rngs::OsRng;
+    let all: Vec<u8> = classes.concat();
+
+    let mut password: Vec<u8> = classes
+        .iter()
+        .map(|chars| chars[rng.gen_range(0..chars.len())])
+        .collect();
+
+    while password.len() < options.length {
+        password.push(all[rng.gen_range(0..all.len())]);
+    }
+
+    password.shuffle(&mut rng);
+
+    Ok(String::from_utf8(password).expect("字符集均为 ASCII"))
+}
+
+/// 单词口令的生成规则
+#[derive(Clone)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            words: DEFAULT_WORDS,
+            separator: "-".to_string(),
+            capitalize: false,
+        }
+    }
+}
+
+/// 内置词表中的所有单词
+pub fn wordlist() -> Vec<&'static str> {
+    WORDLIST
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// 从内置词表随机选取单词组成口令（相当于掷骰子选词）
+pub fn generate_passphrase(options: &PassphraseOptions) -> Result<String, AppError> {
+    if options.words == 0 {
+        return Err(AppError::from("口令至少需要一个单词"));
+    }
+
+    let words = wordlist();
+    let mut rng = rand::rngs::OsRng;
+
+    let chosen: Vec<String> = (0..options.words)
+        .map(|_| {
+            let word = words[rng.gen_range(0..words.len())];
+            if options.capitalize {
+                capitalize(word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect();
+
+    Ok(chosen.join(&options.separator))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,202 @@
+// 保存在 git 仓库中的密码库
+//
+// `passman sync --init` 把密码库所在的目录设为 git 仓库（已经在仓库中时沿用），并在仓库
+// 的配置中设置 passman.autocommit；之后每次保存都提交密码库文件，`passman sync` 与远程
+// 仓库拉取和推送（`passman pull`、`passman push` 只做其中一步）。只提交密码库文件和恢复
+// 文件，同一目录中的锁文件、TPM 封存的密钥等不会加入仓库。提交的是加密后的文件，提交说明
+// 也只写修改的类型和数量，不写用户名，远程仓库只能看到修改的时间和次数。查看密码、生成
+// 一次性密码等只更新使用时间的保存也会提交，提交说明为“passman: 记录使用时间”。密码库是
+// 单个加密文件，两边都有修改时 git 无法合并。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use passman::recovery;
+use passman::Changed;
+
+use crate::i18n::t;
+
+// 仓库配置中表示由 passman 提交的键
+const AUTOCOMMIT: &str = "passman.autocommit";
+// 没有为当前分支配置远程仓库时使用的远程仓库
+const DEFAULT_REMOTE: &str = "origin";
+// 锁文件的名称，见 passman::lock
+const LOCK_PATTERN: &str = ".*.lock";
+
+/// 密码库是否在启用了自动提交的 git 仓库中
+pub fn enabled(vault_path: &Path) -> bool {
+    let Some((dir, _)) = split(vault_path) else { return false };
+    git(&dir, &["config", "--type=bool", AUTOCOMMIT]).is_ok_and(|value| value.trim() == "true")
+}
+
+/// 启用了自动提交时提交密码库文件，返回是否有新的提交
+pub fn commit(vault_path: &Path, message: &str) -> Result<bool, String> {
+    if !enabled(vault_path) {
+        return Ok(false);
+    }
+    let (dir, name) = split(vault_path).ok_or_else(|| t!("无效的密码库路径"))?;
+    git(&dir, &["add", "--", &name])?;
+    let recovery_path = recovery::path(vault_path);
+    let recovery_name = recovery_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    match recovery_path.exists() {
+        true => git(&dir, &["add", "--", &recovery_name])?,
+        false => git(&dir, &["rm", "--quiet", "--cached", "--ignore-unmatch", "--", &recovery_name])?,
+    };
+    if git(&dir, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(false);
+    }
+    git(&dir, &["commit", "--quiet", "--message", message])?;
+    Ok(true)
+}
+
+/// 保存时的提交说明，例如“passman: 添加 1 个账号，修改 2 个账号”；没有修改时为 None
+pub fn describe(changes: &[Changed]) -> Option<String> {
+    let count = |kind: fn(&Changed) -> bool| changes.iter().filter(|change| kind(change)).count();
+    let added = count(|change| matches!(change, Changed::Added(_)));
+    let updated = count(|change| matches!(change, Changed::Updated { .. }));
+    let deleted = count(|change| matches!(change, Changed::Deleted(_)));
+
+    let mut parts = Vec::new();
+    if added > 0 {
+        parts.push(t!("添加 {} 个账号", added));
+    }
+    if updated > 0 {
+        parts.push(t!("修改 {} 个账号", updated));
+    }
+    if deleted > 0 {
+        parts.push(t!("删除 {} 个账号", deleted));
+    }
+    (!parts.is_empty()).then(|| format!("passman: {}", parts.join(t!("，"))))
+}
+
+/// 在密码库所在的目录启用自动提交，需要时新建仓库并设置远程仓库，然后提交当前的密码库
+pub fn init(vault_path: &Path, remote: Option<&str>) -> Result<(), String> {
+    let (dir, _) = split(vault_path).ok_or_else(|| t!("无效的密码库路径"))?;
+    if git(&dir, &["rev-parse", "--git-dir"]).is_err() {
+        git(&dir, &["init", "--quiet"])?;
+    }
+    git(&dir, &["config", AUTOCOMMIT, "true"])?;
+    // 锁文件不加入仓库，也不在 git status 中显示
+    let exclude = dir.join(git(&dir, &["rev-parse", "--git-path", "info/exclude"])?.trim());
+    let excluded = fs::read_to_string(&exclude).unwrap_or_default();
+    if !excluded.lines().any(|line| line == LOCK_PATTERN) {
+        if let Some(parent) = exclude.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let separator = if excluded.is_empty() || excluded.ends_with('\n') { "" } else { "\n" };
+        fs::write(&exclude, format!("{}{}{}\n", excluded, separator, LOCK_PATTERN)).map_err(|e| e.to_string())?;
+    }
+    if let Some(remote) = remote {
+        match git(&dir, &["remote", "get-url", DEFAULT_REMOTE]) {
+            Ok(_) => git(&dir, &["remote", "set-url", DEFAULT_REMOTE, remote])?,
+            Err(_) => git(&dir, &["remote", "add", DEFAULT_REMOTE, remote])?,
+        };
+    }
+    commit(vault_path, t!("passman: 开始记录密码库的修改"))?;
+    Ok(())
+}
+
+/// 拉取远程仓库的修改再推送本地的提交，返回远程仓库的名称
+///
+/// 调用前应持有密码库的锁，拉取时会替换密码库文件。
+pub fn sync(vault_path: &Path) -> Result<String, String> {
+    pull(vault_path, false)?;
+    push(vault_path, false)
+}
+
+/// 拉取远程仓库的修改，返回远程仓库的名称和拉取的提交数；`dry_run` 时只获取远程分支并
+/// 计数，不修改本地的分支和密码库
+///
+/// 调用前应持有密码库的锁，拉取时会替换密码库文件。
+pub fn pull(vault_path: &Path, dry_run: bool) -> Result<(String, usize), String> {
+    let (dir, remote, branch) = upstream(vault_path, dry_run)?;
+    let count = fetch(&dir, &remote, &branch)?;
+    if count > 0 && !dry_run {
+        if let Err(e) = git(&dir, &["rebase", "--quiet", "FETCH_HEAD"]) {
+            let _ = git(&dir, &["rebase", "--abort"]);
+            return Err(t!("无法合并远程仓库的修改（两边都修改了密码库时需要在 {} 中手动处理）: {}", dir.display(), e));
+        }
+        restrict(vault_path);
+        restrict(&recovery::path(vault_path));
+    }
+    Ok((remote, count))
+}
+
+/// 推送本地的提交，返回远程仓库的名称；远程仓库有本地没有的提交时返回错误，需要先拉取。
+/// `dry_run` 时只检查能否推送
+pub fn push(vault_path: &Path, dry_run: bool) -> Result<String, String> {
+    let (dir, remote, branch) = upstream(vault_path, dry_run)?;
+    if fetch(&dir, &remote, &branch)? > 0 {
+        return Err(t!("远程仓库 {} 中有本地没有的提交，请先运行 passman pull", remote));
+    }
+    match dry_run {
+        true => git(&dir, &["push", "--quiet", "--dry-run", &remote, &branch])?,
+        false => git(&dir, &["push", "--quiet", "--set-upstream", &remote, &branch])?,
+    };
+    Ok(remote)
+}
+
+// 确认启用了自动提交，提交之前自动提交失败时留下的修改（`dry_run` 时不提交），返回仓库
+// 目录、远程仓库和当前分支
+fn upstream(vault_path: &Path, dry_run: bool) -> Result<(PathBuf, String, String), String> {
+    if !enabled(vault_path) {
+        return Err(t!("密码库不在启用了自动提交的 git 仓库中，请先运行 passman sync --init").to_string());
+    }
+    let (dir, _) = split(vault_path).ok_or_else(|| t!("无效的密码库路径"))?;
+    if !dry_run {
+        commit(vault_path, t!("passman: 同步前提交未提交的修改"))?;
+    }
+
+    let branch = git(&dir, &["symbolic-ref", "--short", "HEAD"])?.trim().to_string();
+    let remote = git(&dir, &["config", &format!("branch.{}.remote", branch)])
+        .map(|remote| remote.trim().to_string())
+        .unwrap_or_else(|_| DEFAULT_REMOTE.to_string());
+    if git(&dir, &["remote", "get-url", &remote]).is_err() {
+        return Err(t!("git 仓库没有远程仓库 {}，请用 passman sync --init --remote <URL> 设置", remote));
+    }
+    Ok((dir, remote, branch))
+}
+
+// 获取远程分支，返回其中本地没有的提交数；远程仓库还没有这个分支时（第一次推送前）为 0
+fn fetch(dir: &Path, remote: &str, branch: &str) -> Result<usize, String> {
+    if git(dir, &["ls-remote", "--heads", remote, branch])?.trim().is_empty() {
+        return Ok(0);
+    }
+    git(dir, &["fetch", "--quiet", remote, branch])?;
+    Ok(git(dir, &["rev-list", "--count", "HEAD..FETCH_HEAD"])?.trim().parse().unwrap_or_default())
+}
+
+// git 按 umask 写出拉取的文件，改回只有当前用户可以读写
+fn restrict(path: &Path) {
+    #[cfg(unix)]
+    if path.exists() {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+// 密码库所在的目录和文件名
+fn split(vault_path: &Path) -> Option<(PathBuf, String)> {
+    let path = fs::canonicalize(vault_path).ok()?;
+    Some((path.parent()?.to_path_buf(), path.file_name()?.to_string_lossy().into_owned()))
+}
+
+// 在 `dir` 中运行 git，返回标准输出；失败时返回 git 的错误信息
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| t!("无法运行 {}: {}", "git", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or_default();
+        return Err(t!("git {} 失败: {}", args[0], message));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
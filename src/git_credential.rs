@@ -0,0 +1,139 @@
+// git 的凭据助手（`passman git-credential get|store|erase`）
+//
+// git 在 `credential.helper = !passman git-credential` 时启动它，在标准输入写入若干行 `键=值`
+// （protocol、host、path、username、password 等）后以空行结束；get 在标准输出回复同样格式的
+// username 和 password，store 和 erase 不回复。标准输出只用于回复，提示和警告写到标准错误。
+// erase 不删除账号（其中可能还有一次性密码、附件等内容），只提示用户更新密码。
+//
+// 账号的网址或备注中的主机（包括端口）与请求的主机相同才算相符；带协议的网址还要求协议相同，
+// 避免把 HTTPS 站点的密码发给同一主机的 HTTP 地址。请求带用户名时只考虑这个用户名的账号。
+// 启用 `credential.useHttpPath` 后请求带有仓库路径，网址路径是它的前缀的账号优先；仍有多个
+// 账号相符时取最近使用的。
+
+use std::io::{self, BufRead, Write};
+
+use passman::{Account, AppError, EntryKind, Vault};
+
+use crate::i18n::t;
+
+/// git 发来的凭据，没有的字段为空
+#[derive(Default)]
+pub struct Request {
+    pub protocol: String,
+    pub host: String,
+    pub path: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+/// 从 `input` 读取请求，直到空行或输入结束；不认识的键忽略
+pub fn read_request(input: impl BufRead) -> Result<Request, AppError> {
+    let mut request = Request::default();
+    for line in input.lines() {
+        let line = line?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(AppError::from(t!("无效的凭据请求: {}", line)));
+        };
+        match key {
+            "protocol" => request.protocol = value.to_string(),
+            "host" => request.host = value.to_string(),
+            "path" => request.path = value.to_string(),
+            "username" => request.username = Some(value.to_string()),
+            "password" => request.password = Some(value.to_string()),
+            // 以 url 给出时拆成各个部分
+            "url" => {
+                let (protocol, rest) = value.split_once("://").unwrap_or(("", value));
+                let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+                let (user, host) = authority.rsplit_once('@').map_or((None, authority), |(user, host)| (Some(user), host));
+                request.protocol = protocol.to_string();
+                request.host = host.to_string();
+                request.path = path.to_string();
+                if let Some(user) = user {
+                    request.username = Some(user.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(request)
+}
+
+impl Request {
+    /// 与请求最相符的账号
+    pub fn find<'a>(&self, vault: &'a Vault) -> Option<&'a Account> {
+        let mut matches: Vec<&Account> = vault.list().iter().filter(|account| self.matches(account)).collect();
+        if !self.path.is_empty() && matches.iter().any(|account| self.path_matches(account)) {
+            matches.retain(|account| self.path_matches(account));
+        }
+        matches.into_iter().max_by_key(|account| (account.last_used_at, account.updated_at))
+    }
+
+    /// 以 git 的格式写出账号的用户名和密码，密码的过期时间一并告诉 git
+    pub fn write(&self, output: &mut impl Write, account: &Account) -> io::Result<()> {
+        writeln!(output, "username={}", account.username)?;
+        writeln!(output, "password={}", account.password)?;
+        if let Some(expires_at) = account.expires_at {
+            writeln!(output, "password_expiry_utc={}", expires_at)?;
+        }
+        output.flush()
+    }
+
+    /// 保存 git 验证成功的凭据：已有相符的账号时更新密码，否则添加账号；返回账号的 ID
+    /// 和是否有修改
+    pub fn store(&self, vault: &mut Vault) -> Result<(String, bool), AppError> {
+        let (Some(username), Some(password)) = (&self.username, &self.password) else {
+            return Err(AppError::from(t!("凭据中缺少用户名或密码")));
+        };
+        if let Some(id) = self.find(vault).map(|account| account.id.clone()) {
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            let changed = account.password != *password;
+            account.set_password(password);
+            return Ok((id, changed));
+        }
+        let mut account = Account::new(username, password, &self.host);
+        let url = match self.protocol.as_str() {
+            "" => self.host.clone(),
+            protocol => format!("{}://{}", protocol, self.host),
+        };
+        account.set_url(&url);
+        Ok((vault.add(account)?, true))
+    }
+
+    // 账号是登录，网址或备注中有与请求相同的主机，且用户名相符
+    fn matches(&self, account: &Account) -> bool {
+        if account.kind != EntryKind::Login || self.host.is_empty() {
+            return false;
+        }
+        if self.username.as_ref().is_some_and(|username| *username != account.username) {
+            return false;
+        }
+        let protocols: Vec<Option<&str>> = sites(account).filter(|(_, host, _)| host.eq_ignore_ascii_case(&self.host)).map(|(protocol, _, _)| protocol).collect();
+        // 备注中只写了主机时不限协议，但网址写明了协议时以网址为准
+        match protocols.iter().flatten().next() {
+            Some(_) => protocols.iter().flatten().any(|protocol| protocol.eq_ignore_ascii_case(&self.protocol)),
+            None => !protocols.is_empty(),
+        }
+    }
+
+    // 账号网址的路径是请求的仓库路径的前缀
+    fn path_matches(&self, account: &Account) -> bool {
+        let path = self.path.trim_matches('/').trim_end_matches(".git");
+        sites(account).any(|(_, host, own)| {
+            let own = own.trim_matches('/').trim_end_matches(".git");
+            host.eq_ignore_ascii_case(&self.host) && !own.is_empty() && (path == own || path.starts_with(&format!("{}/", own)))
+        })
+    }
+}
+
+// 账号的网址和备注中的各个地址：协议（没有时为 None）、主机（包括端口）和路径
+fn sites(account: &Account) -> impl Iterator<Item = (Option<&str>, &str, &str)> {
+    account.url.iter().map(String::as_str).chain(account.notes.split_whitespace()).map(|text| {
+        let (protocol, rest) = text.split_once("://").map_or((None, text), |(protocol, rest)| (Some(protocol), rest));
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        (protocol, host, path.split(['?', '#']).next().unwrap_or_default())
+    })
+}
@@ -0,0 +1,97 @@
+//! 以 GPG 公钥加密密码库
+//!
+//! 密码库的密钥随机生成，调用 `gpg` 加密给一个或多个 GPG 接收者后保存在文件头部。解锁时
+//! 由 gpg（和 gpg-agent）解密密钥，口令输入、智能卡和 pass 使用的同一套密钥都由 gpg 自己
+//! 处理。
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use aes_gcm::aead::Buffer;
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{Cipher, CryptoBackend, KeyHeader};
+use crate::error::AppError;
+use crate::secmem::{self, SecretBytes};
+
+// 密码库密钥的长度
+const KEY_LENGTH: usize = 32;
+
+/// 以 GPG 加密的密码库密钥，保存在密码库头部
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GpgKey {
+    /// 接收者（密钥 ID、指纹或用户 ID），用于重新加密时沿用同样的接收者
+    pub recipients: Vec<String>,
+    /// ASCII 封装的 OpenPGP 消息
+    pub key: String,
+}
+
+/// 用 GPG 加密密钥的加密后端
+pub struct GpgBackend {
+    key: SecretBytes,
+    header: GpgKey,
+}
+
+impl GpgBackend {
+    /// 随机生成密钥并加密给 `recipients`
+    pub fn new(recipients: &[String]) -> Result<Self, AppError> {
+        if recipients.is_empty() {
+            return Err(AppError::from("至少需要一个 GPG 接收者"));
+        }
+        let key = SecretBytes::from_slice(&rand::random::<[u8; KEY_LENGTH]>());
+        let mut args = vec!["--encrypt", "--armor"];
+        for recipient in recipients {
+            args.extend(["--recipient", recipient]);
+        }
+        let encrypted = gpg(&args, &key, |e| format!("gpg 加密失败: {}", e))?;
+        let header = GpgKey { recipients: recipients.to_vec(), key: String::from_utf8_lossy(&encrypted).into_owned() };
+        Ok(GpgBackend { key, header })
+    }
+
+    /// 调用 gpg 解密头部中的密钥，需要口令时由 gpg-agent 请求
+    pub fn unlock(header: &KeyHeader) -> Result<Self, AppError> {
+        let gpg_key = header.gpg.as_ref().ok_or("此密码库不是以 GPG 密钥加密的")?;
+        let mut decrypted = gpg(&["--decrypt"], gpg_key.key.as_bytes(), |e| format!("gpg 解密失败: {}", e))?;
+        let key = SecretBytes::from_slice(&decrypted);
+        secmem::zero(&mut decrypted);
+        if key.len() != KEY_LENGTH {
+            return Err(AppError::from("密码库文件已损坏（不完整或格式错误）"));
+        }
+        Ok(GpgBackend { key, header: gpg_key.clone() })
+    }
+}
+
+impl CryptoBackend for GpgBackend {
+    fn key_header(&self) -> KeyHeader {
+        KeyHeader { gpg: Some(self.header.clone()), ..KeyHeader::default() }
+    }
+
+    fn encrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.encrypt(&self.key, nonce, aad, data)?)
+    }
+
+    fn decrypt(&self, cipher: Cipher, nonce: &[u8], aad: &[u8], data: &mut dyn Buffer) -> Result<(), AppError> {
+        Ok(cipher.decrypt(&self.key, nonce, aad, data)?)
+    }
+}
+
+// 运行 gpg，从标准输入写入 `input`，返回标准输出；失败时用 `failure` 包装 gpg 的错误信息
+fn gpg(args: &[&str], input: &[u8], failure: impl FnOnce(&str) -> String) -> Result<Vec<u8>, AppError> {
+    let mut child = Command::new("gpg")
+        .args(["--batch", "--quiet"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::from(format!("无法运行 gpg: {}", e)))?;
+    child.stdin.take().ok_or("无法写入 gpg")?.write_all(input)?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let mut stdout = output.stdout;
+        secmem::zero(&mut stdout);
+        return Err(AppError::from(failure(String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,309 @@
+// 与 HashiCorp Vault 的 KV 机密引擎交换机密（`passman hashicorp-vault pull|push`）
+//
+// 通过 curl 访问 Vault 的 HTTP API（见 `remote::http`），令牌以请求头写在临时的 curl 配置
+// 文件中，不出现在命令行参数里。支持 KV 版本 1 和版本 2；版本 2 读写 `<挂载路径>/data/<路径>`，
+// 列出 `<挂载路径>/metadata/<路径>`。以令牌或 AppRole 登录，AppRole 的 role_id 和 secret_id
+// 可以保存在密码库的账号中。
+//
+// 每个机密对应一个账号：`username`、`password`、`url`、`notes` 键对应账号的同名字段（没有
+// `username` 键时用户名为机密名称），其余键保存为敏感的自定义字段。账号的 `vault_path` 字段
+// 记住机密在 Vault 中的位置，再次拉取时更新同一个账号，推送时写回原来的路径。推送前先读取
+// 远程的机密，内容相同时不写入，以免 KV 版本 2 产生没有变化的新版本。
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+
+use serde_json::{Value, json};
+
+use passman::{Account, AppError, Vault};
+
+use crate::i18n::t;
+use crate::remote::http;
+
+/// 记住机密位置（`挂载路径/路径`）的自定义字段
+pub const PATH_FIELD: &str = "vault_path";
+
+/// 一个机密中的键和值
+pub type Data = BTreeMap<String, String>;
+
+/// 登录 Vault 的方式
+pub enum Auth {
+    /// 已有的令牌
+    Token(String),
+    /// 以 AppRole 登录，`mount` 为认证方法的挂载路径
+    AppRole { mount: String, role_id: String, secret_id: String },
+}
+
+/// 令牌的默认来源：VAULT_TOKEN 环境变量，没有时读取 vault 命令保存的 ~/.vault-token
+pub fn default_token() -> Option<String> {
+    env::var("VAULT_TOKEN")
+        .ok()
+        .or_else(|| fs::read_to_string(dirs_next::home_dir()?.join(".vault-token")).ok())
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// 一个 KV 机密引擎
+pub struct Client {
+    addr: String,
+    mount: String,
+    kv2: bool,
+    token: String,
+    // Vault 企业版的命名空间，取自 VAULT_NAMESPACE 环境变量
+    namespace: Option<String>,
+}
+
+/// 拉取的结果
+#[derive(Default)]
+pub struct PullReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+/// 推送的结果
+#[derive(Default)]
+pub struct PushReport {
+    pub written: usize,
+    pub unchanged: usize,
+    /// 没有 vault_path，也没有指定 --path 的账号
+    pub skipped: usize,
+}
+
+impl Client {
+    /// 连接地址为 `addr` 的 Vault，使用挂载在 `mount` 的 KV 机密引擎，需要时以 AppRole 登录
+    pub fn connect(addr: &str, mount: &str, kv_version: u8, auth: Auth) -> Result<Self, AppError> {
+        let mut client = Client {
+            addr: addr.trim_end_matches('/').to_string(),
+            mount: mount.trim_matches('/').to_string(),
+            kv2: kv_version == 2,
+            token: String::new(),
+            namespace: env::var("VAULT_NAMESPACE").ok().filter(|namespace| !namespace.is_empty()),
+        };
+        client.token = match auth {
+            Auth::Token(token) => token,
+            Auth::AppRole { mount, role_id, secret_id } => {
+                let body = json!({ "role_id": role_id, "secret_id": secret_id });
+                let response = client.request("POST", &format!("auth/{}/login", mount.trim_matches('/')), Some(&body))?;
+                let response = response.ok_or_else(|| AppError::from(t!("AppRole 登录失败: 找不到认证方法 {}", mount)))?;
+                response["auth"]["client_token"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| AppError::from(t!("AppRole 登录失败: 响应中没有令牌")))?
+            }
+        };
+        Ok(client)
+    }
+
+    /// `path` 处的机密；以 `/` 结尾或是目录时递归取出其中的全部机密。返回机密的路径和内容
+    pub fn secrets(&self, path: &str) -> Result<Vec<(String, Data)>, AppError> {
+        let path = path.trim_start_matches('/');
+        if !path.is_empty()
+            && !path.ends_with('/')
+            && let Some(data) = self.read(path)?
+        {
+            return Ok(vec![(path.to_string(), data)]);
+        }
+        let mut secrets = Vec::new();
+        let mut pending = vec![path.trim_end_matches('/').to_string()];
+        let mut found = false;
+        while let Some(dir) = pending.pop() {
+            let Some(keys) = self.list(&dir)? else {
+                continue;
+            };
+            found = true;
+            for key in keys {
+                let child = if dir.is_empty() { key.clone() } else { format!("{}/{}", dir, key) };
+                if key.ends_with('/') {
+                    pending.push(child.trim_end_matches('/').to_string());
+                } else if let Some(data) = self.read(&child)? {
+                    secrets.push((child, data));
+                }
+            }
+        }
+        if !found {
+            return Err(AppError::from(t!("HashiCorp Vault 中没有 {}/{}", self.mount, path)));
+        }
+        secrets.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(secrets)
+    }
+
+    /// 读取 `path` 处的机密，不存在时返回 None；不是字符串的值以 JSON 写出
+    pub fn read(&self, path: &str) -> Result<Option<Data>, AppError> {
+        let api = if self.kv2 { format!("{}/data/{}", self.mount, path) } else { format!("{}/{}", self.mount, path) };
+        let Some(response) = self.request("GET", &api, None)? else {
+            return Ok(None);
+        };
+        let data = if self.kv2 { &response["data"]["data"] } else { &response["data"] };
+        // KV 版本 2 中已删除（但未销毁）的版本 data 为 null
+        let Some(data) = data.as_object() else {
+            return Ok(None);
+        };
+        let data = data
+            .iter()
+            .map(|(key, value)| (key.clone(), value.as_str().map_or_else(|| value.to_string(), str::to_string)))
+            .collect();
+        Ok(Some(data))
+    }
+
+    /// 写入 `path` 处的机密
+    pub fn write(&self, path: &str, data: &Data) -> Result<(), AppError> {
+        let (api, body) = if self.kv2 {
+            (format!("{}/data/{}", self.mount, path), json!({ "data": data }))
+        } else {
+            (format!("{}/{}", self.mount, path), json!(data))
+        };
+        self.request("POST", &api, Some(&body))?;
+        Ok(())
+    }
+
+    // 列出目录中的键，子目录以 `/` 结尾；目录不存在时返回 None
+    fn list(&self, dir: &str) -> Result<Option<Vec<String>>, AppError> {
+        let api = if self.kv2 { format!("{}/metadata/{}", self.mount, dir) } else { format!("{}/{}", self.mount, dir) };
+        let Some(response) = self.request("LIST", api.trim_end_matches('/'), None)? else {
+            return Ok(None);
+        };
+        let keys = response["data"]["keys"].as_array().map(|keys| keys.iter().filter_map(Value::as_str).map(str::to_string).collect());
+        Ok(keys)
+    }
+
+    // 以 `method` 请求 `/v1/<api>`，返回响应的 JSON（没有内容时为 null），404 时返回 None
+    fn request(&self, method: &str, api: &str, body: Option<&Value>) -> Result<Option<Value>, AppError> {
+        let url = format!("{}/v1/{}", self.addr, api.split('/').map(percent_encode).collect::<Vec<_>>().join("/"));
+        let mut secret = Vec::new();
+        if !self.token.is_empty() {
+            secret.push(http::option("header", &format!("X-Vault-Token: {}", self.token)));
+        }
+        if let Some(namespace) = &self.namespace {
+            secret.push(http::option("header", &format!("X-Vault-Namespace: {}", namespace)));
+        }
+        let input = body.map(|body| body.to_string().into_bytes());
+        let args = ["--request", method, "--header", "Content-Type: application/json"];
+        let response = http::request(&url, &args, &secret, input.as_deref())?;
+        let json = || serde_json::from_slice::<Value>(&response.body).unwrap_or(Value::Null);
+        match response.status {
+            200..=299 => Ok(Some(json())),
+            404 => Ok(None),
+            status => {
+                let errors: Vec<String> = json()["errors"].as_array().into_iter().flatten().filter_map(Value::as_str).map(str::to_string).collect();
+                let reason = if errors.is_empty() { String::from_utf8_lossy(&response.body).trim().to_string() } else { errors.join("; ") };
+                Err(AppError::from(t!("HashiCorp Vault 返回 {}: {}", status, reason)))
+            }
+        }
+    }
+
+    // 账号的 vault_path 在这个机密引擎中时，返回挂载路径之后的部分
+    fn source<'a>(&self, account: &'a Account) -> Option<&'a str> {
+        let path = &account.field(PATH_FIELD)?.value;
+        path.strip_prefix(&self.mount)?.strip_prefix('/')
+    }
+}
+
+/// 把 `path` 处的机密拉取到密码库：已有 vault_path 相同的账号时更新它，否则在 `folder`
+/// 中添加账号（机密所在的目录成为子文件夹）
+pub fn pull(vault: &mut Vault, client: &Client, path: &str, folder: &str) -> Result<PullReport, AppError> {
+    let mut report = PullReport::default();
+    for (path, data) in client.secrets(path)? {
+        let existing = vault.list().iter().find(|account| client.source(account) == Some(path.as_str())).map(|account| account.id.clone());
+        let Some(id) = existing else {
+            let mut account = Account::new("", "", "");
+            apply(&mut account, &client.mount, &path, &data)?;
+            match path.rsplit_once('/') {
+                Some((dir, _)) => account.set_folder(&format!("{}/{}", folder, dir)),
+                None => account.set_folder(folder),
+            }
+            vault.add(account)?;
+            report.added += 1;
+            continue;
+        };
+        let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+        let before = account.clone();
+        apply(account, &client.mount, &path, &data)?;
+        if account.same_content(&before) {
+            report.unchanged += 1;
+        } else {
+            account.touch();
+            report.updated += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// 把 `ids` 中的账号推送到 Vault：写回 vault_path 记住的路径；没有时写到 `prefix/用户名`
+/// 并记下这个路径，也没有指定 `prefix` 时跳过
+pub fn push(vault: &mut Vault, client: &Client, ids: &[String], prefix: Option<&str>) -> Result<PushReport, AppError> {
+    let mut report = PushReport::default();
+    for id in ids {
+        let account = vault.get(id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+        let path = match (client.source(account), prefix) {
+            (Some(path), _) => path.to_string(),
+            (None, Some(prefix)) => format!("{}/{}", prefix.trim_matches('/'), account.username.replace('/', "_")),
+            (None, None) => {
+                report.skipped += 1;
+                continue;
+            }
+        };
+        let remote = client.read(&path)?;
+        let data = to_data(account, &path, remote.as_ref());
+        if remote.as_ref() == Some(&data) {
+            report.unchanged += 1;
+        } else {
+            client.write(&path, &data)?;
+            report.written += 1;
+        }
+        if client.source(account).is_none() {
+            let account = vault.get_mut(id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            account.set_field(PATH_FIELD, &format!("{}/{}", client.mount, path), false)?;
+        }
+    }
+    Ok(report)
+}
+
+// 用机密的内容设置账号，自定义字段换成机密中的其余键
+fn apply(account: &mut Account, mount: &str, path: &str, data: &Data) -> Result<(), AppError> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    account.username = data.get("username").cloned().unwrap_or_else(|| name.to_string());
+    account.set_password(data.get("password").map(String::as_str).unwrap_or_default());
+    account.url = data.get("url").filter(|url| !url.is_empty()).cloned();
+    account.notes = data.get("notes").cloned().unwrap_or_default();
+    account.fields.clear();
+    account.set_field(PATH_FIELD, &format!("{}/{}", mount, path), false)?;
+    for (key, value) in data {
+        if !matches!(key.as_str(), "username" | "password" | "url" | "notes" | PATH_FIELD) {
+            account.set_field(key, value, true)?;
+        }
+    }
+    Ok(())
+}
+
+// 账号对应的机密内容；用户名与机密名称相同、字段为空时省略对应的键，除非远程的机密中有它
+fn to_data(account: &Account, path: &str, remote: Option<&Data>) -> Data {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let had = |key: &str| remote.is_some_and(|remote| remote.contains_key(key));
+    let mut data = Data::new();
+    if account.username != name || had("username") {
+        data.insert("username".to_string(), account.username.clone());
+    }
+    if !account.password.is_empty() || had("password") {
+        data.insert("password".to_string(), account.password.clone());
+    }
+    if let Some(url) = account.url.as_ref().filter(|url| !url.is_empty()) {
+        data.insert("url".to_string(), url.clone());
+    }
+    if !account.notes.is_empty() || had("notes") {
+        data.insert("notes".to_string(), account.notes.clone());
+    }
+    for field in account.fields.iter().filter(|field| field.name != PATH_FIELD) {
+        data.insert(field.name.clone(), field.value.clone());
+    }
+    data
+}
+
+// 对路径中的一段进行百分号转义
+fn percent_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| if b.is_ascii_alphanumeric() || b"-._~".contains(&b) { (b as char).to_string() } else { format!("%{:02X}", b) })
+        .collect()
+}
@@ -0,0 +1,40 @@
+//! HMAC-SHA256 与 HKDF-SHA256 (RFC 2104、RFC 5869)
+//!
+//! 用于 age 接收者的密钥封装，以及由 FIDO2、恢复代码、同步密钥等密钥材料派生子密钥。
+
+use sha2::{Digest, Sha256};
+
+const BLOCK_SIZE: usize = 64;
+
+/// 计算 HMAC-SHA256
+pub fn hmac_sha256(key: &[u8], message: &[&[u8]]) -> [u8; 32] {
+    let mut block_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.map(|b| b ^ 0x36));
+    for part in message {
+        inner.update(part);
+    }
+    let inner = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.map(|b| b ^ 0x5c));
+    outer.update(inner);
+    outer.finalize().into()
+}
+
+/// 由输入密钥材料 `ikm` 派生 `out.len()` 字节（最多 255 × 32 字节）
+pub fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8], out: &mut [u8]) {
+    let prk = hmac_sha256(salt, &[ikm]);
+    let mut previous: Vec<u8> = Vec::new();
+    for (i, chunk) in out.chunks_mut(32).enumerate() {
+        let block = hmac_sha256(&prk, &[&previous, info, &[i as u8 + 1]]);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        previous = block.to_vec();
+    }
+}
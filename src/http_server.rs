@@ -0,0 +1,187 @@
+// `serve-sync` 和 `serve` 使用的精简 HTTP/1.1 服务器端
+//
+// 每个连接只处理一个请求，响应带 `Connection: close`。上传的内容必须带 Content-Length，
+// 不支持分块传输；客户端带 `Expect: 100-continue` 时先回复 100 再读取内容。
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+// 请求行和请求头的最大长度
+const MAX_HEAD: u64 = 16 * 1024;
+// 读写一个请求或响应的最长等待时间
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 一个连接
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+/// 解析后的请求行和请求头，路径和查询参数已解码
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+}
+
+pub struct Response {
+    pub status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        Ok(Connection { writer: stream.try_clone()?, reader: BufReader::new(stream) })
+    }
+
+    /// 读取请求行和请求头，格式不对时返回 None
+    pub fn read_request(&mut self) -> io::Result<Option<Request>> {
+        let mut head = Vec::new();
+        let mut limited = (&mut self.reader).take(MAX_HEAD);
+        loop {
+            let start = head.len();
+            if limited.read_until(b'\n', &mut head)? == 0 {
+                return Ok(None);
+            }
+            if matches!(&head[start..], b"\r\n" | b"\n") {
+                break;
+            }
+        }
+        let Ok(text) = String::from_utf8(head) else { return Ok(None) };
+        let mut lines = text.lines();
+        let mut parts = lines.next().unwrap_or_default().split(' ');
+        let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next()) else {
+            return Ok(None);
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Ok(None);
+        }
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Some((percent_decode(name, true)?, percent_decode(value, true)?))
+            })
+            .collect();
+        let Some(path) = percent_decode(path, false) else { return Ok(None) };
+        Ok(Some(Request { method: method.to_string(), path, query, headers }))
+    }
+
+    /// 读取请求的内容，最长 `max` 字节；没有 Content-Length、内容过长或不完整时返回对应的错误响应
+    pub fn read_body(&mut self, request: &Request, max: u64) -> Result<Vec<u8>, Response> {
+        let length = match request.header("content-length").map(|length| length.parse::<u64>()) {
+            Some(Ok(length)) if length > max => return Err(Response::status(413)),
+            Some(Ok(length)) => length,
+            _ => return Err(Response::status(411)),
+        };
+        // curl 上传较大的内容时先等待服务器同意
+        if request.header("expect").is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue")) {
+            self.writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").map_err(|_| Response::status(400))?;
+        }
+        let mut body = Vec::new();
+        match (&mut self.reader).take(length).read_to_end(&mut body) {
+            Ok(_) if body.len() as u64 == length => Ok(body),
+            _ => Err(Response::status(400)),
+        }
+    }
+
+    pub fn respond(&mut self, response: Response) -> io::Result<()> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason(response.status));
+        if !response.headers.iter().any(|(name, _)| *name == "Content-Length") {
+            head.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+        }
+        for (name, value) in &response.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        self.writer.write_all(head.as_bytes())?;
+        self.writer.write_all(&response.body)?;
+        self.writer.flush()
+    }
+}
+
+impl Request {
+    /// 名为 `name`（小写）的请求头
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone())
+    }
+
+    /// 名为 `name` 的查询参数
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    /// `Authorization` 请求头中 `scheme`（Basic、Bearer）之后的凭据
+    pub fn credentials(&self, scheme: &str) -> Option<String> {
+        let value = self.header("authorization")?;
+        let (name, credentials) = value.split_once(' ')?;
+        name.eq_ignore_ascii_case(scheme).then(|| credentials.trim().to_string())
+    }
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Response { status, headers: vec![("Content-Type", content_type.to_string())], body }
+    }
+
+    /// 只有状态码的响应，内容为状态的说明
+    pub fn status(status: u16) -> Self {
+        Self::new(status, "text/plain", format!("{}\n", reason(status)).into_bytes())
+    }
+
+    /// 加上一个响应头
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        100 => "Continue",
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        411 => "Length Required",
+        412 => "Precondition Failed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+// 解码 URL 中的 %XX，`plus` 为真时（查询参数）`+` 解码为空格；不是有效的 UTF-8 时返回 None
+fn percent_decode(text: &str, plus: bool) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = text.get(i + 1..i + 3)?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+                continue;
+            }
+            b'+' if plus => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,353 @@
+// 界面语言
+//
+// 与 gettext 类似：源代码中的中文文字就是消息 ID，其他语言的消息目录（po/ 目录中的
+// .po 文件）把消息 ID 映射为译文，目录中没有的消息原样显示。语言按 --lang、配置项
+// lang、LC_ALL / LC_MESSAGES / LANG 环境变量的顺序决定，未设置或为 C / POSIX 时使用
+// 简体中文。
+//
+// 命令行中的文字用 `t!` 翻译；库返回的文字（错误信息、强度建议等）在显示前用
+// `translate` 翻译，带参数的消息按消息 ID 中的 `{}` 匹配。
+
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use clap::builder::{EnumValueParser, PossibleValue, TypedValueParser};
+use clap::{Command, ValueEnum};
+
+use passman::AppError;
+
+use crate::config::Config;
+
+/// 翻译消息并填入参数，例如 `t!("已删除账号 {}", name)`
+macro_rules! t {
+    ($msgid:literal) => {
+        $crate::i18n::tr($msgid)
+    };
+    ($msgid:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format($crate::i18n::tr($msgid), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+pub(crate) use t;
+
+/// 界面语言
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Lang {
+    /// 简体中文
+    #[value(name = "zh-CN")]
+    ZhCn,
+    /// English
+    #[value(name = "en-US")]
+    EnUs,
+}
+
+impl Lang {
+    /// 解析语言标签或 locale，例如 `en`、`en-US`、`zh_CN.UTF-8`
+    pub fn parse(text: &str) -> Option<Lang> {
+        match text.split(['_', '-', '.', '@']).next()?.to_ascii_lowercase().as_str() {
+            "zh" => Some(Lang::ZhCn),
+            "en" => Some(Lang::EnUs),
+            _ => None,
+        }
+    }
+
+    // 消息目录，简体中文不需要
+    fn catalog(self) -> Option<&'static str> {
+        match self {
+            Lang::ZhCn => None,
+            Lang::EnUs => Some(include_str!("../po/en-US.po")),
+        }
+    }
+}
+
+// 当前语言的消息目录：消息 ID 到译文
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+#[derive(Default)]
+struct Catalog {
+    messages: HashMap<String, String>,
+    // 带参数的消息 ID 按 `{}` 拆开的片段与译文，用于翻译库返回的文字
+    templates: Vec<(Vec<String>, String)>,
+}
+
+/// 选择界面语言并加载消息目录，在解析命令行和输出任何内容前调用一次
+pub fn init(config: Option<&Config>) {
+    let lang = lang_arg()
+        .or_else(|| config.and_then(|config| config.get_str("lang")).and_then(Lang::parse))
+        .or_else(env_lang)
+        .unwrap_or(Lang::ZhCn);
+    let catalog = lang.catalog().map(parse_catalog).unwrap_or_default();
+    let _ = CATALOG.set(catalog);
+}
+
+// 命令行中的 --lang（此时还没有解析命令行，无效的值留给 clap 报错）
+fn lang_arg() -> Option<Lang> {
+    let mut args = env::args_os().skip(1).take_while(|arg| arg != "--");
+    while let Some(arg) = args.next() {
+        let value = match arg.to_str() {
+            Some("--lang") => args.next(),
+            Some(arg) => arg.strip_prefix("--lang=").map(OsString::from),
+            None => None,
+        };
+        if let Some(lang) = value.as_ref().and_then(|value| value.to_str()).and_then(Lang::parse) {
+            return Some(lang);
+        }
+    }
+    None
+}
+
+// 与 gettext 相同，依次查看 LC_ALL、LC_MESSAGES 和 LANG，第一个非空的生效；
+// 不支持的语言使用英文
+fn env_lang() -> Option<Lang> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())?;
+    if locale == "C" || locale == "POSIX" || locale.starts_with("C.") {
+        return None;
+    }
+    Some(Lang::parse(&locale).unwrap_or(Lang::EnUs))
+}
+
+/// 检查配置项 lang 的值
+pub fn check_lang(text: &str) -> Result<(), AppError> {
+    match Lang::parse(text) {
+        Some(_) => Ok(()),
+        None => Err(AppError::from(format!("不支持的语言: {}（可用: zh-CN、en-US）", text))),
+    }
+}
+
+// 解析 .po 文件，只处理 msgid 和 msgstr，未翻译（msgstr 为空）的消息忽略
+fn parse_catalog(text: &str) -> Catalog {
+    let mut catalog = Catalog::default();
+    let mut msgid: Option<String> = None;
+    let mut current: Option<(bool, String)> = None;
+    let mut finish = |msgid: &mut Option<String>, current: &mut Option<(bool, String)>| match current.take() {
+        Some((false, id)) => *msgid = Some(id),
+        Some((true, msgstr)) => {
+            if let Some(id) = msgid.take().filter(|id| !id.is_empty() && !msgstr.is_empty()) {
+                if id.contains("{}") {
+                    catalog.templates.push((id.split("{}").map(str::to_string).collect(), msgstr.clone()));
+                }
+                catalog.messages.insert(id, msgstr);
+            }
+        }
+        None => {}
+    };
+
+    for line in text.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            finish(&mut msgid, &mut current);
+            current = Some((false, unquote(rest)));
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            finish(&mut msgid, &mut current);
+            current = Some((true, unquote(rest)));
+        } else if line.starts_with('"') {
+            if let Some((_, value)) = current.as_mut() {
+                value.push_str(&unquote(line));
+            }
+        } else {
+            finish(&mut msgid, &mut current);
+        }
+    }
+    finish(&mut msgid, &mut current);
+    catalog
+}
+
+// .po 中带引号的字符串
+fn unquote(text: &str) -> String {
+    let text = text.trim().strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(text);
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some(other) => value.push(other),
+            None => {}
+        }
+    }
+    value
+}
+
+fn catalog() -> Option<&'static Catalog> {
+    CATALOG.get().filter(|catalog| !catalog.messages.is_empty())
+}
+
+/// 翻译固定的消息，目录中没有时原样返回
+pub fn tr(msgid: &str) -> &str {
+    catalog().and_then(|catalog| catalog.messages.get(msgid)).map_or(msgid, String::as_str)
+}
+
+/// 把参数依次填入译文中的 `{}`；译文也可以用 `{0}`、`{1}` 按位置引用参数
+pub fn format(template: &str, args: &[&dyn Display]) -> String {
+    let mut text = String::new();
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text += &rest[..start];
+        rest = &rest[start + 1..];
+        let placeholder = rest.find('}').map(|end| &rest[..end]).filter(|index| index.bytes().all(|b| b.is_ascii_digit()));
+        let Some(index) = placeholder else {
+            text.push('{');
+            continue;
+        };
+        let arg = if index.is_empty() {
+            next += 1;
+            args.get(next - 1)
+        } else {
+            index.parse().ok().and_then(|index: usize| args.get(index))
+        };
+        if let Some(arg) = arg {
+            text += &arg.to_string();
+        }
+        rest = &rest[index.len() + 1..];
+    }
+    text + rest
+}
+
+/// 翻译库返回的文字：先按整条消息查找，再按带参数的消息 ID 匹配，参数也会被翻译
+pub fn translate(text: &str) -> String {
+    let Some(catalog) = catalog() else {
+        return text.to_string();
+    };
+    if let Some(message) = catalog.messages.get(text) {
+        return message.clone();
+    }
+    if text.is_ascii() {
+        return text.to_string();
+    }
+
+    // 多条消息都能匹配时使用固定部分最长的一条
+    let best = catalog
+        .templates
+        .iter()
+        .filter_map(|(parts, message)| {
+            let args = match_template(parts, text)?;
+            let fixed: usize = parts.iter().map(String::len).sum();
+            Some((fixed, args, message))
+        })
+        .max_by_key(|(fixed, _, _)| *fixed);
+    match best {
+        Some((_, args, message)) => {
+            let args: Vec<String> = args.into_iter().map(translate).collect();
+            let args: Vec<&dyn Display> = args.iter().map(|arg| arg as &dyn Display).collect();
+            format(message, &args)
+        }
+        None => text.to_string(),
+    }
+}
+
+// 按消息 ID 的固定部分匹配文字，返回各参数
+fn match_template<'a>(parts: &[String], text: &'a str) -> Option<Vec<&'a str>> {
+    // 固定部分不含中文的消息 ID（例如 `{}: {}`）能匹配任何文字，不参与匹配
+    if parts.iter().all(|part| part.is_ascii()) {
+        return None;
+    }
+    let (first, rest) = parts.split_first()?;
+    let (last, middle) = rest.split_last()?;
+    let mut remaining = text.strip_prefix(first.as_str())?.strip_suffix(last.as_str())?;
+    let mut args = Vec::new();
+    for part in middle {
+        let end = remaining.find(part.as_str())?;
+        args.push(&remaining[..end]);
+        remaining = &remaining[end + part.len()..];
+    }
+    args.push(remaining);
+    Some(args)
+}
+
+/// 错误信息（不带“错误”前缀），库返回的错误会被翻译
+pub fn error_message(err: &(dyn std::error::Error + 'static)) -> String {
+    match err.downcast_ref::<AppError>() {
+        Some(AppError::Io(e)) => t!("IO错误: {}", e),
+        Some(AppError::Serde(e)) => t!("序列化错误: {}", e),
+        Some(AppError::Base64(e)) => t!("Base64解码错误: {}", e),
+        Some(AppError::Aes(s)) => t!("加密/解密错误: {}", translate(s)),
+        Some(AppError::WrongMasterKey) => t!("主密钥错误").to_string(),
+        Some(AppError::Other(s)) => translate(s),
+        None => translate(&err.to_string()),
+    }
+}
+
+/// 翻译命令行定义中的说明文字（帮助、补全脚本和 man 手册都使用翻译后的定义）
+///
+/// clap 显示的 `[default: ...]` 不能翻译，改为在说明后面加上 `[默认: ...]`，与默认值取决于配置、
+/// 在说明中写明默认值的参数格式相同。
+pub fn localize(mut command: Command) -> Command {
+    let translated = catalog().is_some();
+    if translated {
+        if let Some(about) = command.get_about().map(ToString::to_string) {
+            command = command.about(translate(&about));
+        }
+        if let Some(long_about) = command.get_long_about().map(ToString::to_string) {
+            command = command.long_about(translate(&long_about));
+        }
+    }
+
+    let ids: Vec<String> = command.get_arguments().map(|arg| arg.get_id().to_string()).collect();
+    for id in ids {
+        command = command.mut_arg(id, |mut arg| {
+            if translated {
+                if let Some(help) = arg.get_help().map(ToString::to_string) {
+                    arg = arg.help(translate(&help));
+                }
+                if let Some(long_help) = arg.get_long_help().map(ToString::to_string) {
+                    arg = arg.long_help(translate(&long_help));
+                }
+                // 标题只接受 'static 的文字，直接取目录中的译文
+                if let Some(heading) = arg.get_help_heading().and_then(|heading| catalog()?.messages.get(heading)) {
+                    arg = arg.help_heading(heading.as_str());
+                }
+            }
+            if !arg.is_hide_default_value_set() && !arg.get_default_values().is_empty() {
+                let values: Vec<String> = arg.get_default_values().iter().map(|value| value.to_string_lossy().into_owned()).collect();
+                let default = t!("[默认: {}]", values.join(", "));
+                if let Some(help) = arg.get_help().map(ToString::to_string) {
+                    arg = arg.help(format!("{} {}", help, default));
+                }
+                if let Some(long_help) = arg.get_long_help().map(ToString::to_string) {
+                    arg = arg.long_help(format!("{} {}", long_help, default));
+                }
+                arg = arg.hide_default_value(true);
+            }
+            arg
+        });
+    }
+
+    let names: Vec<String> = command.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    for name in names {
+        command = command.mut_subcommand(name, localize);
+    }
+    command
+}
+
+/// 可选值的说明会被翻译的 ValueEnum 解析器，用于 `#[arg(value_parser = ...)]`
+pub fn enum_parser<E: ValueEnum + Clone + Send + Sync + 'static>() -> LocalizedValues<EnumValueParser<E>> {
+    LocalizedValues(EnumValueParser::new())
+}
+
+#[derive(Clone)]
+pub struct LocalizedValues<P>(P);
+
+impl<P: TypedValueParser> TypedValueParser for LocalizedValues<P> {
+    type Value = P::Value;
+
+    fn parse_ref(&self, cmd: &Command, arg: Option<&clap::Arg>, value: &std::ffi::OsStr) -> Result<Self::Value, clap::Error> {
+        self.0.parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let values = self.0.possible_values()?;
+        Some(Box::new(values.map(|value| match value.get_help().map(ToString::to_string) {
+            Some(help) => value.help(translate(&help)),
+            None => value,
+        })))
+    }
+}
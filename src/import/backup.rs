@@ -0,0 +1,17 @@
+use crate::backup;
+use crate::error::AppError;
+use crate::vault::Vault;
+
+use super::ImportReport;
+
+/// 从加密备份文件恢复账号，已存在的账号会被跳过
+pub fn import_backup(vault: &mut Vault, data: &[u8], password: &str) -> Result<ImportReport, AppError> {
+    let backup = backup::open(data, password)?;
+    let mut report = ImportReport::default();
+
+    for account in backup.accounts {
+        report.add(vault, account);
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,277 @@
+use serde::Deserialize;
+
+use crate::error::AppError;
+use crate::kind::EntryKind;
+use crate::otp::{self, Otp, OtpKind};
+use crate::vault::{Account, Vault};
+
+use super::ImportReport;
+
+// Bitwarden 条目类型
+const TYPE_LOGIN: u8 = 1;
+const TYPE_SECURE_NOTE: u8 = 2;
+const TYPE_CARD: u8 = 3;
+const TYPE_IDENTITY: u8 = 4;
+
+// Bitwarden 自定义字段类型
+const FIELD_HIDDEN: u8 = 1;
+const FIELD_LINKED: u8 = 3;
+
+// Bitwarden 未加密 JSON 导出文件
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    #[serde(rename = "type")]
+    kind: u8,
+    #[serde(default)]
+    name: String,
+    notes: Option<String>,
+    #[serde(rename = "folderId")]
+    folder_id: Option<String>,
+    login: Option<Login>,
+    card: Option<Card>,
+    identity: Option<Identity>,
+    #[serde(default)]
+    fields: Vec<ItemField>,
+}
+
+#[derive(Deserialize)]
+struct ItemField {
+    #[serde(default)]
+    name: String,
+    value: Option<String>,
+    #[serde(rename = "type", default)]
+    kind: u8,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    uris: Option<Vec<Uri>>,
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Card {
+    cardholder_name: Option<String>,
+    number: Option<String>,
+    exp_month: Option<String>,
+    exp_year: Option<String>,
+    code: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+    first_name: Option<String>,
+    middle_name: Option<String>,
+    last_name: Option<String>,
+    address1: Option<String>,
+    address2: Option<String>,
+    address3: Option<String>,
+    city: Option<String>,
+    state: Option<String>,
+    postal_code: Option<String>,
+    country: Option<String>,
+    email: Option<String>,
+    phone: Option<String>,
+    ssn: Option<String>,
+    passport_number: Option<String>,
+}
+
+/// 导入 Bitwarden 的未加密 JSON 导出文件
+///
+/// 登录条目的用户名为空时使用条目名称，条目名称和第一个以外的网址写入备注；
+/// 安全笔记、银行卡和身份以条目名称作为用户名并导入为对应的类型，银行卡和身份的内容
+/// 导入为专用字段。条目所在的文件夹（Bitwarden 以 `/` 表示嵌套）和自定义字段会被保留，
+/// 隐藏字段导入为敏感字段。
+pub fn import_bitwarden(vault: &mut Vault, json: &str) -> Result<ImportReport, AppError> {
+    let export: Export = serde_json::from_str(json)?;
+    if export.encrypted {
+        return Err(AppError::from("不支持加密的 Bitwarden 导出文件，请导出为未加密的 JSON"));
+    }
+
+    let mut report = ImportReport::default();
+
+    for item in export.items {
+        let folder = item
+            .folder_id
+            .as_deref()
+            .and_then(|id| export.folders.iter().find(|f| f.id == id))
+            .map(|f| f.name.as_str())
+            .unwrap_or("");
+
+        match item.kind {
+            TYPE_LOGIN => {
+                let login = item.login.unwrap_or(Login {
+                    username: None,
+                    password: None,
+                    totp: None,
+                    uris: None,
+                });
+
+                let username = match login.username.as_deref().map(str::trim) {
+                    Some(username) if !username.is_empty() => username.to_string(),
+                    _ => item.name.clone(),
+                };
+                if username.is_empty() {
+                    report.skip("(未命名)", "缺少用户名和名称");
+                    continue;
+                }
+
+                // 第一个网址作为账号网址，其余的写入备注
+                let mut uris = login.uris.unwrap_or_default().into_iter().filter_map(|u| u.uri);
+                let url = uris.next();
+                let mut lines = vec![item.name.clone()];
+                lines.extend(uris);
+                lines.extend(item.notes.clone());
+
+                let otp = match login.totp.as_deref().map(str::trim) {
+                    Some(totp) if !totp.is_empty() => match parse_totp(totp) {
+                        Ok(otp) => Some(otp),
+                        Err(_) => {
+                            // 无法识别的 TOTP 保留在备注中，避免丢失
+                            lines.push(format!("TOTP: {}", totp));
+                            None
+                        }
+                    },
+                    _ => None,
+                };
+
+                let mut account = Account::new(&username, &login.password.unwrap_or_default(), &join_lines(lines));
+                account.set_folder(folder);
+                account.set_url(url.as_deref().unwrap_or(""));
+                account.otp = otp;
+                add_fields(&mut account, &item.fields);
+                report.add(vault, account);
+            }
+            TYPE_SECURE_NOTE | TYPE_CARD | TYPE_IDENTITY => {
+                if item.name.is_empty() {
+                    report.skip("(未命名)", "缺少名称");
+                    continue;
+                }
+                let mut account = Account::new(&item.name, "", item.notes.as_deref().unwrap_or(""));
+                account.set_folder(folder);
+                match item.kind {
+                    TYPE_CARD => set_card(&mut account, item.card.unwrap_or_default()),
+                    TYPE_IDENTITY => set_identity(&mut account, item.identity.unwrap_or_default()),
+                    _ => account.kind = EntryKind::Note,
+                }
+                add_fields(&mut account, &item.fields);
+                report.add(vault, account);
+            }
+            _ => report.skip(&item.name, "不支持的条目类型"),
+        }
+    }
+
+    Ok(report)
+}
+
+// Bitwarden 的 TOTP 字段可以是 otpauth URI、Steam 令牌的 steam://<密钥> 或 Base32 密钥
+fn parse_totp(totp: &str) -> Result<Otp, AppError> {
+    if totp.starts_with("otpauth://") || totp.starts_with("steam://") {
+        Otp::from_uri(totp)
+    } else {
+        Otp::new(totp, otp::DEFAULT_DIGITS, OtpKind::Totp { period: otp::DEFAULT_PERIOD })
+    }
+}
+
+// 银行卡信息，有效期转换为 MM/YY 或 MM/YYYY
+fn set_card(account: &mut Account, card: Card) {
+    let month = card.exp_month.unwrap_or_default();
+    let year = card.exp_year.unwrap_or_default();
+    let expiry = match (month.trim(), year.trim()) {
+        ("", "") => String::new(),
+        (month, year) => format!("{:0>2}/{}", month, year),
+    };
+    let values = [
+        ("cardholder", card.cardholder_name.unwrap_or_default()),
+        ("number", card.number.unwrap_or_default()),
+        ("expiry", expiry),
+        ("cvv", card.code.unwrap_or_default()),
+    ];
+    set_kind_fields(account, EntryKind::Card, values);
+}
+
+// 身份信息，姓名和地址的各部分分别合并为一个字段
+fn set_identity(account: &mut Account, identity: Identity) {
+    let join = |parts: Vec<Option<String>>, separator: &str| {
+        parts
+            .into_iter()
+            .flatten()
+            .filter(|part| !part.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join(separator)
+    };
+    let values = [
+        ("full_name", join(vec![identity.first_name, identity.middle_name, identity.last_name], " ")),
+        ("email", identity.email.unwrap_or_default()),
+        ("phone", identity.phone.unwrap_or_default()),
+        (
+            "address",
+            join(
+                vec![
+                    identity.address1,
+                    identity.address2,
+                    identity.address3,
+                    identity.city,
+                    identity.state,
+                    identity.postal_code,
+                    identity.country,
+                ],
+                ", ",
+            ),
+        ),
+        ("id_number", identity.ssn.or(identity.passport_number).unwrap_or_default()),
+    ];
+    set_kind_fields(account, EntryKind::Identity, values);
+}
+
+// 导出文件中的值不做校验，原样保存
+fn set_kind_fields<const N: usize>(account: &mut Account, kind: EntryKind, values: [(&str, String); N]) {
+    account.kind = kind;
+    for (name, value) in values.iter().filter(|(_, value)| !value.trim().is_empty()) {
+        let secret = kind.field(name).is_some_and(|field| field.secret);
+        let _ = account.set_field(name, value.trim(), secret);
+    }
+}
+
+// 导入自定义字段，链接字段（引用其他字段）没有自己的值，忽略
+fn add_fields(account: &mut Account, fields: &[ItemField]) {
+    for field in fields.iter().filter(|field| field.kind != FIELD_LINKED) {
+        let value = field.value.as_deref().unwrap_or("");
+        if account.set_field(&field.name, value, field.kind == FIELD_HIDDEN).is_err() && !value.is_empty() {
+            account.notes = join_lines(vec![account.notes.clone(), value.to_string()]);
+        }
+    }
+}
+
+fn join_lines(lines: Vec<String>) -> String {
+    lines
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,75 @@
+use crate::error::AppError;
+use crate::vault::{Account, Vault};
+
+use super::ImportReport;
+
+/// 导入 Chrome/Firefox 导出的 CSV 密码文件
+///
+/// 根据表头识别 `url`、`username`、`password` 列，Chrome 的 `name`、`note` 列
+/// 写入备注。与现有账号完全相同的条目视为重复并跳过。
+pub fn import_browser_csv(vault: &mut Vault, csv: &str) -> Result<ImportReport, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+
+    let (Some(url_col), Some(username_col), Some(password_col)) =
+        (column("url"), column("username"), column("password"))
+    else {
+        return Err(AppError::from("CSV 文件缺少 url、username 或 password 列"));
+    };
+    let name_col = column("name");
+    let note_col = column("note");
+
+    let mut report = ImportReport::default();
+
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim().to_string();
+
+        let url = field(Some(url_col));
+        let username = field(Some(username_col));
+        let password = field(Some(password_col));
+
+        if username.is_empty() {
+            report.skip(&url, "缺少用户名");
+            continue;
+        }
+
+        let notes = [field(name_col), field(note_col)]
+            .into_iter()
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // 与现有账号内容相同则视为重复
+        if vault
+            .find(&username)
+            .any(|existing| existing.password == password && same_url(existing, &url))
+        {
+            report.skip(&username, "重复条目");
+            continue;
+        }
+
+        let mut account = Account::new(&username, &password, &notes);
+        account.set_url(&url);
+        report.add(vault, account);
+    }
+
+    Ok(report)
+}
+
+// 忽略末尾的 `/` 比较网址，旧版本导入的账号网址保存在备注中
+fn same_url(account: &Account, url: &str) -> bool {
+    let url = url.trim_end_matches('/');
+    match &account.url {
+        Some(own) => own.trim_end_matches('/') == url,
+        None => account.notes.contains(url),
+    }
+}
+
+fn csv_error(err: csv::Error) -> AppError {
+    AppError::from(format!("CSV 解析错误: {}", err))
+}
@@ -0,0 +1,67 @@
+use crate::error::AppError;
+use crate::kdbx;
+use crate::otp::{self, Otp, OtpKind};
+use crate::vault::{Account, Vault};
+
+use super::ImportReport;
+
+/// 导入 KeePass KDBX 数据库
+///
+/// 条目的用户名为空时使用标题，分组路径作为文件夹，网址和标签原样导入；标题和
+/// 备注依次写入备注；自定义字段原样导入（受保护的字段为敏感字段），KeePassXC 的
+/// `otp` 字段会转换为一次性密码配置。
+pub fn import_kdbx(vault: &mut Vault, data: &[u8], password: &str) -> Result<ImportReport, AppError> {
+    let database = kdbx::read(data, password)?;
+    let mut report = ImportReport::default();
+
+    for entry in database.entries {
+        let username = if entry.username.trim().is_empty() {
+            entry.title.trim().to_string()
+        } else {
+            entry.username.trim().to_string()
+        };
+        if username.is_empty() {
+            report.skip("(未命名)", "缺少用户名和标题");
+            continue;
+        }
+
+        // 标题与用户名相同时（例如由 passman 导出的文件）不再重复写入备注
+        let title = if entry.title.trim() == username { String::new() } else { entry.title.clone() };
+        let notes = [title, entry.notes.clone()]
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut account = Account::new(&username, &entry.password, &notes);
+        account.set_folder(&entry.group.join("/"));
+        account.set_url(&entry.url);
+        account.add_tags(&entry.tags);
+        for field in &entry.fields {
+            if account.otp.is_none()
+                && let Some(otp) = parse_otp_field(&field.key, &field.value)
+            {
+                account.otp = Some(otp);
+                continue;
+            }
+            // 名称为空的字段无法保存，移入备注
+            if account.set_field(&field.key, &field.value, field.protected).is_err() && !field.value.is_empty() {
+                account.notes = format!("{}\n{}", account.notes, field.value).trim().to_string();
+            }
+        }
+        report.add(vault, account);
+    }
+
+    Ok(report)
+}
+
+// 识别 KeePassXC (`otp`) 与 KeePass 2.47+ (`TimeOtp-Secret-Base32`) 的 TOTP 字段
+fn parse_otp_field(key: &str, value: &str) -> Option<Otp> {
+    match key {
+        "otp" => Otp::from_uri(value).ok(),
+        "TimeOtp-Secret-Base32" => {
+            Otp::new(value, otp::DEFAULT_DIGITS, OtpKind::Totp { period: otp::DEFAULT_PERIOD }).ok()
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,46 @@
+//! 从其他密码管理器的导出文件导入账号
+
+mod backup;
+mod bitwarden;
+mod browser;
+mod kdbx;
+mod vault;
+
+pub use backup::import_backup;
+pub use bitwarden::import_bitwarden;
+pub use browser::import_browser_csv;
+pub use kdbx::import_kdbx;
+pub use vault::{import_vault, OnConflict};
+
+use crate::vault::{Account, Vault};
+
+/// 导入结果
+#[derive(Default)]
+pub struct ImportReport {
+    /// 成功导入的用户名
+    pub imported: Vec<String>,
+    /// 被跳过的条目名称及原因
+    pub skipped: Vec<(String, String)>,
+    /// 被覆盖的现有账号的用户名
+    pub replaced: Vec<String>,
+}
+
+impl ImportReport {
+    // 添加账号，ID 相同或者用户名、网址和备注都相同的账号已存在时跳过
+    fn add(&mut self, vault: &mut Vault, account: Account) {
+        if vault::existing(vault, &account).is_some() {
+            self.skip(&account.username, "账号已存在");
+            return;
+        }
+
+        let username = account.username.clone();
+        match vault.add(account) {
+            Ok(_) => self.imported.push(username),
+            Err(e) => self.skip(&username, &e.to_string()),
+        }
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.skipped.push((name.to_string(), reason.to_string()));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::vault::{new_id, Account, AccountStore, Vault};
+
+use super::ImportReport;
+
+/// 合并另一个密码库时，账号已存在的处理方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum OnConflict {
+    /// 跳过，保留当前密码库中的账号
+    Skip,
+    /// 另一个密码库中的账号修改时间较晚时覆盖当前的账号
+    OverwriteNewer,
+    /// 以新的 ID 另存为一个账号，用户名加上序号，例如 `alice (2)`
+    Rename,
+}
+
+/// 把另一个密码库中的账号合并到 `vault`
+///
+/// 已存在的判断与其他导入方式相同（ID 相同，或者用户名、网址和备注都相同）；内容完全相同的
+/// 账号总是跳过，其余按 `on_conflict` 处理，被覆盖的账号保留原来的 ID。
+pub fn import_vault(vault: &mut Vault, accounts: AccountStore, on_conflict: OnConflict) -> ImportReport {
+    let mut report = ImportReport::default();
+    for account in accounts {
+        let Some(existing) = existing(vault, &account).cloned() else {
+            report.add(vault, account);
+            continue;
+        };
+        let unchanged = Account { id: existing.id.clone(), ..account.clone() }.same_content(&existing);
+        if unchanged {
+            report.skip(&account.username, "账号已存在");
+            continue;
+        }
+        match on_conflict {
+            OnConflict::Skip => report.skip(&account.username, "账号已存在"),
+            OnConflict::OverwriteNewer if account.updated_at <= existing.updated_at => {
+                report.skip(&account.username, "当前密码库中的账号较新");
+            }
+            OnConflict::OverwriteNewer => {
+                if let Some(current) = vault.get_mut(&existing.id) {
+                    *current = Account { id: existing.id, ..account };
+                    report.replaced.push(current.username.clone());
+                }
+            }
+            OnConflict::Rename => {
+                let username = (2..).map(|n| format!("{} ({})", account.username, n)).find(|name| vault.find(name).next().is_none()).unwrap_or_default();
+                report.add(vault, Account { id: new_id(), username, ..account });
+            }
+        }
+    }
+    report
+}
+
+// ID 相同，或者用户名、网址和备注都相同的现有账号
+pub(super) fn existing<'a>(vault: &'a Vault, account: &Account) -> Option<&'a Account> {
+    vault.get(&account.id).or_else(|| vault.find(&account.username).find(|existing| existing.url == account.url && existing.notes == account.notes))
+}
@@ -0,0 +1,166 @@
+// 修改记录：每次保存密码库时记录被修改账号原来的内容，用于撤销
+//
+// 记录与账号一起加密保存在密码库中，只保留最近的 MAX_ENTRIES 次修改。
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::timestamp;
+use crate::vault::{Account, AccountStore};
+
+// 保留的修改记录数
+const MAX_ENTRIES: usize = 20;
+
+// 一次保存中的修改
+#[derive(Serialize, Deserialize, Clone)]
+struct JournalEntry {
+    // 保存时间（Unix 时间戳，秒）
+    at: u64,
+    changes: Vec<Change>,
+}
+
+// 单个账号的修改，保存撤销所需的内容
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum Change {
+    Added { id: String },
+    Updated { before: Account },
+    // 删除前在账号列表中的位置
+    Deleted { index: usize, before: Account },
+}
+
+/// 尚未保存的修改，见 [`Vault::changes`](crate::Vault::changes)
+pub enum Changed<'a> {
+    /// 新添加的账号
+    Added(&'a Account),
+    /// 修改前后的账号
+    Updated { before: &'a Account, after: &'a Account },
+    /// 被删除的账号
+    Deleted(&'a Account),
+}
+
+/// 撤销的修改
+pub enum Undone {
+    /// 撤销添加，删除了此账号
+    Added(Account),
+    /// 恢复了此账号修改前的内容
+    Updated(Account),
+    /// 恢复了被删除的此账号
+    Deleted(Account),
+}
+
+impl<'a> Changed<'a> {
+    /// 比较两组账号，返回从 `before` 到 `after` 的修改；只更新了使用时间的账号不算修改
+    pub fn between(before: &'a AccountStore, after: &'a AccountStore) -> Vec<Self> {
+        compare(before, after)
+    }
+}
+
+impl JournalEntry {
+    // 比较保存前后的账号，没有变化时返回 None
+    fn diff(before: &AccountStore, after: &AccountStore) -> Option<Self> {
+        let changes: Vec<Change> = compare(before, after)
+            .into_iter()
+            .map(|change| match change {
+                Changed::Added(account) => Change::Added { id: account.id.clone() },
+                Changed::Updated { before, .. } => Change::Updated { before: before.clone() },
+                Changed::Deleted(account) => {
+                    let index = before.iter().position(|other| std::ptr::eq(other, account)).unwrap_or_default();
+                    Change::Deleted { index, before: account.clone() }
+                }
+            })
+            .collect();
+
+        (!changes.is_empty()).then(|| JournalEntry { at: timestamp::now(), changes })
+    }
+
+    // 把账号恢复到这次修改之前的状态
+    fn revert(self, accounts: &mut AccountStore) -> Vec<Undone> {
+        let mut undone = Vec::new();
+        let mut deleted = Vec::new();
+        for change in self.changes {
+            match change {
+                Change::Added { id } => {
+                    if let Some(index) = accounts.iter().position(|account| account.id == id) {
+                        undone.push(Undone::Added(accounts.remove(index)));
+                    }
+                }
+                Change::Updated { before } => {
+                    if let Some(account) = accounts.iter_mut().find(|account| account.id == before.id) {
+                        *account = before.clone();
+                        undone.push(Undone::Updated(before));
+                    }
+                }
+                Change::Deleted { index, before } => deleted.push((index, before)),
+            }
+        }
+
+        // 按原来的位置从前往后插回被删除的账号
+        deleted.sort_by_key(|(index, _)| *index);
+        for (index, account) in deleted {
+            accounts.insert(index.min(accounts.len()), account.clone());
+            undone.push(Undone::Deleted(account));
+        }
+        undone
+    }
+}
+
+// 比较保存前后的账号，只更新了使用时间的账号不算修改
+pub(crate) fn compare<'a>(before: &'a AccountStore, after: &'a AccountStore) -> Vec<Changed<'a>> {
+    let old: HashMap<&str, &Account> = before.iter().map(|account| (account.id.as_str(), account)).collect();
+    let new: HashMap<&str, &Account> = after.iter().map(|account| (account.id.as_str(), account)).collect();
+
+    let mut changes = Vec::new();
+    for account in before {
+        match new.get(account.id.as_str()) {
+            None => changes.push(Changed::Deleted(account)),
+            Some(&current) if !current.same_content(account) => changes.push(Changed::Updated { before: account, after: current }),
+            Some(_) => {}
+        }
+    }
+    for account in after.iter().filter(|account| !old.contains_key(account.id.as_str())) {
+        changes.push(Changed::Added(account));
+    }
+    changes
+}
+
+// 最近的修改记录，最早的在前
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub(crate) struct Journal(Vec<JournalEntry>);
+
+impl Journal {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // 记录从 `before` 到 `after` 的修改
+    pub(crate) fn record(&mut self, before: &AccountStore, after: &AccountStore) {
+        if let Some(entry) = JournalEntry::diff(before, after) {
+            self.0.push(entry);
+            if self.0.len() > MAX_ENTRIES {
+                self.0.remove(0);
+            }
+        }
+    }
+
+    // 撤销最近 `n` 次修改后的账号，以及其中最早一次修改的时间；记录不足 `n` 次时返回 None
+    pub(crate) fn before(&self, accounts: &AccountStore, n: usize) -> Option<(u64, AccountStore)> {
+        if n == 0 || n > self.0.len() {
+            return None;
+        }
+        let mut accounts = accounts.clone();
+        let mut at = 0;
+        for entry in self.0.iter().rev().take(n) {
+            at = entry.at;
+            entry.clone().revert(&mut accounts);
+        }
+        Some((at, accounts))
+    }
+
+    // 撤销最近一次修改，返回修改的时间和撤销的内容，没有记录时返回 None
+    pub(crate) fn undo(&mut self, accounts: &mut AccountStore) -> Option<(u64, Vec<Undone>)> {
+        self.0.pop().map(|entry| (entry.at, entry.revert(accounts)))
+    }
+}
@@ -0,0 +1,201 @@
+// KDBX 使用的密码学原语
+
+use aes::Aes256;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit, generic_array::GenericArray};
+use sha2::{Digest, Sha256};
+
+use crate::chacha20::ChaCha20;
+
+// AES-256-CBC 解密并去除 PKCS#7 填充
+pub fn aes_cbc_decrypt(key: &[u8; 32], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    if iv.len() != 16 || data.is_empty() || !data.len().is_multiple_of(16) {
+        return Err("AES-CBC 数据长度无效".to_string());
+    }
+
+    let cipher = Aes256::new(GenericArray::from_slice(key));
+    let mut prev: [u8; 16] = iv.try_into().unwrap();
+    let mut out = Vec::with_capacity(data.len());
+
+    for chunk in data.chunks_exact(16) {
+        let mut block = GenericArray::clone_from_slice(chunk);
+        cipher.decrypt_block(&mut block);
+        for (b, p) in block.iter_mut().zip(prev.iter()) {
+            *b ^= p;
+        }
+        out.extend_from_slice(&block);
+        prev.copy_from_slice(chunk);
+    }
+
+    let pad = *out.last().unwrap() as usize;
+    if pad == 0 || pad > 16 || out[out.len() - pad..].iter().any(|&b| b as usize != pad) {
+        return Err("主密码错误或文件已损坏".to_string());
+    }
+    out.truncate(out.len() - pad);
+    Ok(out)
+}
+
+// AES-256-CBC 加密并添加 PKCS#7 填充
+pub fn aes_cbc_encrypt(key: &[u8; 32], iv: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let cipher = Aes256::new(GenericArray::from_slice(key));
+    let pad = 16 - data.len() % 16;
+    let mut padded = data.to_vec();
+    padded.extend(std::iter::repeat_n(pad as u8, pad));
+
+    let mut prev = *iv;
+    let mut out = Vec::with_capacity(padded.len());
+    for chunk in padded.chunks_exact(16) {
+        let mut block = GenericArray::clone_from_slice(chunk);
+        for (b, p) in block.iter_mut().zip(prev.iter()) {
+            *b ^= p;
+        }
+        cipher.encrypt_block(&mut block);
+        prev.copy_from_slice(&block);
+        out.extend_from_slice(&block);
+    }
+    out
+}
+
+// AES-KDF：以种子为密钥对组合密钥重复进行 AES-ECB 加密，最后取 SHA-256
+pub fn aes_kdf(composite: &[u8; 32], seed: &[u8], rounds: u64) -> Result<[u8; 32], String> {
+    if seed.len() != 32 {
+        return Err("AES-KDF 种子长度无效".to_string());
+    }
+
+    let cipher = Aes256::new(GenericArray::from_slice(seed));
+    let mut key = *composite;
+    for half in key.chunks_exact_mut(16) {
+        let block = GenericArray::from_mut_slice(half);
+        for _ in 0..rounds {
+            cipher.encrypt_block(block);
+        }
+    }
+
+    Ok(Sha256::digest(key).into())
+}
+
+// HMAC-SHA256
+pub fn hmac_sha256(key: &[u8], message: &[&[u8]]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block_key = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.map(|b| b ^ 0x36));
+    for part in message {
+        inner.update(part);
+    }
+    let inner = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.map(|b| b ^ 0x5c));
+    outer.update(inner);
+    outer.finalize().into()
+}
+
+// Salsa20 流密码（KDBX 3.1 的内部随机流）
+pub struct Salsa20 {
+    state: [u32; 16],
+    buffer: [u8; 64],
+    offset: usize,
+}
+
+impl Salsa20 {
+    pub fn new(key: &[u8; 32], nonce: &[u8; 8]) -> Self {
+        let word = |b: &[u8]| u32::from_le_bytes(b.try_into().unwrap());
+        let mut state = [0u32; 16];
+        state[0] = 0x61707865;
+        state[5] = 0x3320646e;
+        state[10] = 0x79622d32;
+        state[15] = 0x6b206574;
+        for i in 0..4 {
+            state[1 + i] = word(&key[i * 4..i * 4 + 4]);
+            state[11 + i] = word(&key[16 + i * 4..16 + i * 4 + 4]);
+        }
+        state[6] = word(&nonce[..4]);
+        state[7] = word(&nonce[4..]);
+
+        Salsa20 { state, buffer: [0u8; 64], offset: 64 }
+    }
+
+    fn next_block(&mut self) {
+        let mut x = self.state;
+        for _ in 0..10 {
+            // 列轮
+            salsa_qr(&mut x, 0, 4, 8, 12);
+            salsa_qr(&mut x, 5, 9, 13, 1);
+            salsa_qr(&mut x, 10, 14, 2, 6);
+            salsa_qr(&mut x, 15, 3, 7, 11);
+            // 行轮
+            salsa_qr(&mut x, 0, 1, 2, 3);
+            salsa_qr(&mut x, 5, 6, 7, 4);
+            salsa_qr(&mut x, 10, 11, 8, 9);
+            salsa_qr(&mut x, 15, 12, 13, 14);
+        }
+        for (i, (word, initial)) in x.iter().zip(self.state.iter()).enumerate() {
+            self.buffer[i * 4..i * 4 + 4].copy_from_slice(&word.wrapping_add(*initial).to_le_bytes());
+        }
+
+        // 64 位块计数器
+        self.state[8] = self.state[8].wrapping_add(1);
+        if self.state[8] == 0 {
+            self.state[9] = self.state[9].wrapping_add(1);
+        }
+        self.offset = 0;
+    }
+
+    pub fn apply(&mut self, data: &mut [u8]) {
+        for byte in data {
+            if self.offset == 64 {
+                self.next_block();
+            }
+            *byte ^= self.buffer[self.offset];
+            self.offset += 1;
+        }
+    }
+}
+
+fn salsa_qr(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+    x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+    x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+    x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+// 保护字段使用的内部随机流
+pub enum InnerStream {
+    Salsa20(Salsa20),
+    ChaCha20(ChaCha20),
+}
+
+impl InnerStream {
+    // 根据流 ID 和密钥创建
+    pub fn new(id: u32, key: &[u8]) -> Result<Self, String> {
+        match id {
+            2 => {
+                let key: [u8; 32] = Sha256::digest(key).into();
+                Ok(InnerStream::Salsa20(Salsa20::new(
+                    &key,
+                    &[0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A],
+                )))
+            }
+            3 => {
+                let hash = sha2::Sha512::digest(key);
+                let key: [u8; 32] = hash[..32].try_into().unwrap();
+                let nonce: [u8; 12] = hash[32..44].try_into().unwrap();
+                Ok(InnerStream::ChaCha20(ChaCha20::new(&key, &nonce, 0)))
+            }
+            _ => Err(format!("不支持的内部随机流: {}", id)),
+        }
+    }
+
+    pub fn apply(&mut self, data: &mut [u8]) {
+        match self {
+            InnerStream::Salsa20(s) => s.apply(data),
+            InnerStream::ChaCha20(c) => c.apply(data),
+        }
+    }
+}
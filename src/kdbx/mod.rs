@@ -0,0 +1,467 @@
+//! KeePass KDBX 数据库读写（读取支持 KDBX 3.1 与 KDBX 4，写入 KDBX 4）
+
+mod crypto;
+mod inflate;
+mod write;
+mod xml;
+
+use base64::{Engine as _, engine::general_purpose};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+
+use crate::argon2::{self, Variant};
+use crate::error::AppError;
+use self::crypto::InnerStream;
+use self::xml::Element;
+
+/// KDBX 中的一个条目
+pub struct Entry {
+    /// 所在分组路径（不含根分组）
+    pub group: Vec<String>,
+    pub title: String,
+    pub username: String,
+    pub password: String,
+    pub url: String,
+    pub notes: String,
+    pub tags: Vec<String>,
+    /// 标准字段以外的自定义字段
+    pub fields: Vec<Field>,
+}
+
+/// 条目的自定义字段
+pub struct Field {
+    pub key: String,
+    pub value: String,
+    /// 在文件中受内层加密保护（KeePass 中显示为隐藏）
+    pub protected: bool,
+}
+
+/// KDBX 数据库内容
+pub struct Database {
+    pub entries: Vec<Entry>,
+}
+
+// 文件签名
+const SIGNATURE_1: u32 = 0x9AA2_D903;
+const SIGNATURE_2: u32 = 0xB54B_FB67;
+
+// 外层加密算法
+const CIPHER_AES256: [u8; 16] = uuid(0x31c1f2e6_bf71_4350_be58_05216afc5aff);
+const CIPHER_CHACHA20: [u8; 16] = uuid(0xd6038a2b_8b6f_4cb5_a524_339a31dbb59a);
+
+// 密钥派生算法
+const KDF_AES: [u8; 16] = uuid(0xc9d9f39a_628a_4460_bf74_0d08c18a4fea);
+const KDF_ARGON2D: [u8; 16] = uuid(0xef636ddf_8c29_444b_91f7_a9a403e30a0c);
+const KDF_ARGON2ID: [u8; 16] = uuid(0x9e298b19_56db_4773_b23d_fc3ec6f0a1e6);
+
+// 标准字段名称
+const STANDARD_FIELDS: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+const fn uuid(value: u128) -> [u8; 16] {
+    value.to_be_bytes()
+}
+
+// 外层头部
+struct Header {
+    cipher: [u8; 16],
+    compressed: bool,
+    master_seed: Vec<u8>,
+    iv: Vec<u8>,
+    kdf: HashMap<String, Vec<u8>>,
+    // 以下仅用于 KDBX 3.1
+    protected_stream_key: Vec<u8>,
+    stream_start_bytes: Vec<u8>,
+    inner_stream_id: u32,
+}
+
+/// 使用密码解密并解析 KDBX 文件
+pub fn read(data: &[u8], password: &str) -> Result<Database, AppError> {
+    read_database(data, password).map_err(AppError::from)
+}
+
+/// 使用密码加密并生成 KDBX 4 文件
+pub fn write(database: &Database, password: &str) -> Result<Vec<u8>, AppError> {
+    write::write_database(database, password).map_err(AppError::from)
+}
+
+fn read_database(data: &[u8], password: &str) -> Result<Database, String> {
+    let mut cursor = Cursor { data, pos: 0 };
+    if cursor.u32()? != SIGNATURE_1 || cursor.u32()? != SIGNATURE_2 {
+        return Err("不是 KeePass KDBX 文件".to_string());
+    }
+
+    let major = cursor.u32()? >> 16;
+    if major != 3 && major != 4 {
+        return Err(format!("不支持的 KDBX 版本: {}", major));
+    }
+
+    let header = read_header(&mut cursor, major)?;
+    let header_bytes = &data[..cursor.pos];
+
+    let composite: [u8; 32] = Sha256::digest(Sha256::digest(password.as_bytes())).into();
+    let transformed = transform_key(&header, &composite)?;
+    let cipher_key: [u8; 32] = Sha256::new()
+        .chain_update(&header.master_seed)
+        .chain_update(transformed)
+        .finalize()
+        .into();
+
+    let (xml, stream) = if major == 4 {
+        read_v4_payload(&mut cursor, &header, header_bytes, &transformed, &cipher_key)?
+    } else {
+        read_v3_payload(&mut cursor, &header, &cipher_key)?
+    };
+
+    let text = String::from_utf8(xml).map_err(|_| "KDBX XML 编码无效".to_string())?;
+    let mut document = xml::parse(&text)?;
+    unprotect(&mut document, stream)?;
+
+    Ok(Database { entries: collect_entries(&document) })
+}
+
+fn read_header(cursor: &mut Cursor, major: u32) -> Result<Header, String> {
+    let mut header = Header {
+        cipher: [0u8; 16],
+        compressed: false,
+        master_seed: Vec::new(),
+        iv: Vec::new(),
+        kdf: HashMap::new(),
+        protected_stream_key: Vec::new(),
+        stream_start_bytes: Vec::new(),
+        inner_stream_id: 0,
+    };
+    let mut transform_seed = Vec::new();
+    let mut transform_rounds = Vec::new();
+
+    loop {
+        let id = cursor.u8()?;
+        let size = if major == 4 { cursor.u32()? as usize } else { cursor.u16()? as usize };
+        let value = cursor.take(size)?.to_vec();
+
+        match id {
+            0 => break,
+            2 => header.cipher = value.as_slice().try_into().map_err(|_| "加密算法 ID 无效")?,
+            3 => header.compressed = le_u32(&value)? == 1,
+            4 => header.master_seed = value,
+            5 => transform_seed = value,
+            6 => transform_rounds = value,
+            7 => header.iv = value,
+            8 => header.protected_stream_key = value,
+            9 => header.stream_start_bytes = value,
+            10 => header.inner_stream_id = le_u32(&value)?,
+            11 => header.kdf = read_variant_dictionary(&value)?,
+            _ => {}
+        }
+    }
+
+    // KDBX 3.1 的 AES-KDF 参数直接保存在头部
+    if major == 3 {
+        header.kdf.insert("$UUID".to_string(), KDF_AES.to_vec());
+        header.kdf.insert("S".to_string(), transform_seed);
+        header.kdf.insert("R".to_string(), transform_rounds);
+    }
+
+    Ok(header)
+}
+
+// 解析 KDBX 4 的 VariantDictionary（只保留原始字节）
+fn read_variant_dictionary(data: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    let mut cursor = Cursor { data, pos: 0 };
+    let version = cursor.u16()?;
+    if version >> 8 > 1 {
+        return Err("不支持的 KDF 参数格式".to_string());
+    }
+
+    let mut map = HashMap::new();
+    loop {
+        let kind = cursor.u8()?;
+        if kind == 0 {
+            break;
+        }
+        let key_len = cursor.u32()? as usize;
+        let key = String::from_utf8_lossy(cursor.take(key_len)?).into_owned();
+        let value_len = cursor.u32()? as usize;
+        let value = cursor.take(value_len)?.to_vec();
+        map.insert(key, value);
+    }
+
+    Ok(map)
+}
+
+// 根据 KDF 参数变换组合密钥
+fn transform_key(header: &Header, composite: &[u8; 32]) -> Result<[u8; 32], String> {
+    let param = |name: &str| header.kdf.get(name).ok_or_else(|| format!("缺少 KDF 参数: {}", name));
+    let kdf_uuid = param("$UUID")?.as_slice();
+
+    if kdf_uuid == KDF_AES {
+        let rounds = le_u64(param("R")?)?;
+        return crypto::aes_kdf(composite, param("S")?, rounds);
+    }
+
+    let variant = if kdf_uuid == KDF_ARGON2D {
+        Variant::Argon2d
+    } else if kdf_uuid == KDF_ARGON2ID {
+        Variant::Argon2id
+    } else {
+        return Err("不支持的 KDF 算法".to_string());
+    };
+
+    if le_u32(param("V")?)? != 0x13 {
+        return Err("不支持的 Argon2 版本".to_string());
+    }
+    let memory_kib = le_u64(param("M")?)? / 1024;
+    let iterations = le_u64(param("I")?)?;
+    let parallelism = le_u32(param("P")?)?;
+
+    let mut key = [0u8; 32];
+    argon2::hash(
+        variant,
+        composite,
+        param("S")?,
+        memory_kib as u32,
+        iterations as u32,
+        parallelism,
+        &mut key,
+    );
+    Ok(key)
+}
+
+// 读取 KDBX 4 的负载：头部校验、HMAC 分块、解密、解压和内部头部
+fn read_v4_payload(
+    cursor: &mut Cursor,
+    header: &Header,
+    header_bytes: &[u8],
+    transformed: &[u8; 32],
+    cipher_key: &[u8; 32],
+) -> Result<(Vec<u8>, InnerStream), String> {
+    let header_hash = cursor.take(32)?;
+    if Sha256::digest(header_bytes).as_slice() != header_hash {
+        return Err("KDBX 头部已损坏".to_string());
+    }
+
+    let hmac_base: [u8; 64] = Sha512::new()
+        .chain_update(&header.master_seed)
+        .chain_update(transformed)
+        .chain_update([1u8])
+        .finalize()
+        .into();
+
+    let header_hmac = cursor.take(32)?;
+    if crypto::hmac_sha256(&block_hmac_key(&hmac_base, u64::MAX), &[header_bytes]) != header_hmac {
+        return Err("主密码错误".to_string());
+    }
+
+    // HMAC 分块
+    let mut encrypted = Vec::new();
+    for index in 0u64.. {
+        let hmac = cursor.take(32)?.to_vec();
+        let len_bytes = cursor.take(4)?.to_vec();
+        let len = le_u32(&len_bytes)? as usize;
+        let block = cursor.take(len)?;
+
+        let expected = crypto::hmac_sha256(
+            &block_hmac_key(&hmac_base, index),
+            &[&index.to_le_bytes(), &len_bytes, block],
+        );
+        if expected.as_slice() != hmac {
+            return Err("KDBX 数据块校验失败，文件已损坏".to_string());
+        }
+
+        if len == 0 {
+            break;
+        }
+        encrypted.extend_from_slice(block);
+    }
+
+    let mut payload = decrypt_payload(header, cipher_key, &encrypted)?;
+    if header.compressed {
+        payload = inflate::gunzip(&payload)?;
+    }
+
+    // 内部头部
+    let mut inner = Cursor { data: &payload, pos: 0 };
+    let mut stream_id = 0;
+    let mut stream_key = Vec::new();
+    loop {
+        let id = inner.u8()?;
+        let size = inner.u32()? as usize;
+        let value = inner.take(size)?;
+        match id {
+            0 => break,
+            1 => stream_id = le_u32(value)?,
+            2 => stream_key = value.to_vec(),
+            _ => {}
+        }
+    }
+
+    let stream = InnerStream::new(stream_id, &stream_key)?;
+    Ok((payload[inner.pos..].to_vec(), stream))
+}
+
+// 读取 KDBX 3.1 的负载：解密、起始字节校验、哈希分块和解压
+fn read_v3_payload(
+    cursor: &mut Cursor,
+    header: &Header,
+    cipher_key: &[u8; 32],
+) -> Result<(Vec<u8>, InnerStream), String> {
+    let encrypted = &cursor.data[cursor.pos..];
+    let decrypted = decrypt_payload(header, cipher_key, encrypted).map_err(|_| "主密码错误".to_string())?;
+
+    let start_len = header.stream_start_bytes.len();
+    if decrypted.len() < start_len || decrypted[..start_len] != header.stream_start_bytes[..] {
+        return Err("主密码错误".to_string());
+    }
+
+    let mut blocks = Cursor { data: &decrypted, pos: start_len };
+    let mut payload = Vec::new();
+    loop {
+        let _index = blocks.u32()?;
+        let hash = blocks.take(32)?.to_vec();
+        let size = blocks.u32()? as usize;
+        if size == 0 {
+            break;
+        }
+        let block = blocks.take(size)?;
+        if Sha256::digest(block).as_slice() != hash {
+            return Err("KDBX 数据块校验失败，文件已损坏".to_string());
+        }
+        payload.extend_from_slice(block);
+    }
+
+    if header.compressed {
+        payload = inflate::gunzip(&payload)?;
+    }
+
+    let stream = InnerStream::new(header.inner_stream_id, &header.protected_stream_key)?;
+    Ok((payload, stream))
+}
+
+fn decrypt_payload(header: &Header, key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, String> {
+    if header.cipher == CIPHER_AES256 {
+        crypto::aes_cbc_decrypt(key, &header.iv, data)
+    } else if header.cipher == CIPHER_CHACHA20 {
+        let nonce: [u8; 12] = header.iv.as_slice().try_into().map_err(|_| "ChaCha20 IV 长度无效")?;
+        let mut out = data.to_vec();
+        crate::chacha20::ChaCha20::new(key, &nonce, 0).apply(&mut out);
+        Ok(out)
+    } else {
+        Err("不支持的 KDBX 加密算法".to_string())
+    }
+}
+
+fn block_hmac_key(hmac_base: &[u8; 64], index: u64) -> [u8; 64] {
+    Sha512::new()
+        .chain_update(index.to_le_bytes())
+        .chain_update(hmac_base)
+        .finalize()
+        .into()
+}
+
+// 按文档顺序解密所有受保护的值
+fn unprotect(document: &mut Element, mut stream: InnerStream) -> Result<(), String> {
+    document.walk_mut(&mut |element| {
+        if element.name == "Value" && element.attribute("Protected") == Some("True") {
+            let mut bytes = general_purpose::STANDARD
+                .decode(element.text.trim())
+                .map_err(|e| format!("受保护的值无效: {}", e))?;
+            stream.apply(&mut bytes);
+            element.text = String::from_utf8_lossy(&bytes).into_owned();
+        }
+        Ok(())
+    })
+}
+
+// 遍历分组收集条目，跳过回收站与历史记录
+fn collect_entries(document: &Element) -> Vec<Entry> {
+    let meta = document.child("Meta");
+    let recycle_bin = meta
+        .filter(|m| m.child_text("RecycleBinEnabled") == "True")
+        .map(|m| m.child_text("RecycleBinUUID").to_string());
+
+    let mut entries = Vec::new();
+    if let Some(root) = document.child("Root").and_then(|r| r.child("Group")) {
+        collect_group(root, &mut Vec::new(), recycle_bin.as_deref(), &mut entries);
+    }
+    entries
+}
+
+fn collect_group(group: &Element, path: &mut Vec<String>, recycle_bin: Option<&str>, entries: &mut Vec<Entry>) {
+    for entry in group.children_named("Entry") {
+        let mut fields: HashMap<&str, &str> = HashMap::new();
+        let mut custom = Vec::new();
+        for string in entry.children_named("String") {
+            let key = string.child_text("Key");
+            let value = string.child_text("Value");
+            if STANDARD_FIELDS.contains(&key) {
+                fields.insert(key, value);
+            } else {
+                let protected = string.child("Value").and_then(|v| v.attribute("Protected")) == Some("True");
+                custom.push(Field { key: key.to_string(), value: value.to_string(), protected });
+            }
+        }
+        let field = |name: &str| fields.get(name).copied().unwrap_or("").to_string();
+
+        entries.push(Entry {
+            group: path.clone(),
+            title: field("Title"),
+            username: field("UserName"),
+            password: field("Password"),
+            url: field("URL"),
+            notes: field("Notes"),
+            tags: entry
+                .child_text("Tags")
+                .split([';', ','])
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+            fields: custom,
+        });
+    }
+
+    for child in group.children_named("Group") {
+        if recycle_bin.is_some_and(|uuid| !uuid.is_empty() && child.child_text("UUID") == uuid) {
+            continue;
+        }
+        path.push(child.child_text("Name").to_string());
+        collect_group(child, path, recycle_bin, entries);
+        path.pop();
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> Result<u32, String> {
+    bytes.try_into().map(u32::from_le_bytes).map_err(|_| "整数字段长度无效".to_string())
+}
+
+fn le_u64(bytes: &[u8]) -> Result<u64, String> {
+    bytes.try_into().map(u64::from_le_bytes).map_err(|_| "整数字段长度无效".to_string())
+}
+
+// 顺序读取字节
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.pos + len > self.data.len() {
+            return Err("KDBX 文件不完整".to_string());
+        }
+        let slice = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        le_u32(self.take(4)?)
+    }
+}
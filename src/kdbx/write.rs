@@ -0,0 +1,271 @@
+// 生成 KDBX 4 文件：AES-256-CBC 加密、Argon2id 密钥派生、ChaCha20 保护密码字段
+
+use base64::{Engine as _, engine::general_purpose};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::argon2;
+use super::crypto::{self, InnerStream};
+use super::xml::Element;
+use super::{CIPHER_AES256, Database, Entry, KDF_ARGON2ID, SIGNATURE_1, SIGNATURE_2, block_hmac_key};
+
+// KDBX 4.0
+const VERSION: u32 = 0x0004_0000;
+
+// Argon2id 参数（内存以字节为单位）
+const ARGON2_MEMORY: u64 = 64 * 1024 * 1024;
+const ARGON2_ITERATIONS: u64 = 2;
+const ARGON2_PARALLELISM: u32 = 2;
+
+// ChaCha20 内部随机流
+const INNER_STREAM_CHACHA20: u32 = 3;
+
+// 每个 HMAC 数据块的大小
+const BLOCK_SIZE: usize = 1024 * 1024;
+
+// 0001-01-01 到 1970-01-01 的秒数
+const UNIX_EPOCH_OFFSET: i64 = 62_135_596_800;
+
+pub fn write_database(database: &Database, password: &str) -> Result<Vec<u8>, String> {
+    let master_seed = random_bytes::<32>();
+    let iv = random_bytes::<16>();
+    let salt = random_bytes::<32>();
+    let stream_key = random_bytes::<64>();
+
+    // 外层头部
+    let mut kdf = VariantDictionary::default();
+    kdf.bytes("$UUID", &KDF_ARGON2ID);
+    kdf.u32("V", 0x13);
+    kdf.u64("M", ARGON2_MEMORY);
+    kdf.u64("I", ARGON2_ITERATIONS);
+    kdf.u32("P", ARGON2_PARALLELISM);
+    kdf.bytes("S", &salt);
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&SIGNATURE_1.to_le_bytes());
+    header.extend_from_slice(&SIGNATURE_2.to_le_bytes());
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header_field(&mut header, 2, &CIPHER_AES256);
+    header_field(&mut header, 3, &0u32.to_le_bytes());
+    header_field(&mut header, 4, &master_seed);
+    header_field(&mut header, 7, &iv);
+    header_field(&mut header, 11, &kdf.finish());
+    header_field(&mut header, 0, b"\r\n\r\n");
+
+    // 密钥
+    let composite: [u8; 32] = Sha256::digest(Sha256::digest(password.as_bytes())).into();
+    let mut transformed = [0u8; 32];
+    argon2::argon2id(
+        &composite,
+        &salt,
+        (ARGON2_MEMORY / 1024) as u32,
+        ARGON2_ITERATIONS as u32,
+        ARGON2_PARALLELISM,
+        &mut transformed,
+    );
+    let cipher_key: [u8; 32] = Sha256::new()
+        .chain_update(master_seed)
+        .chain_update(transformed)
+        .finalize()
+        .into();
+    let hmac_base: [u8; 64] = Sha512::new()
+        .chain_update(master_seed)
+        .chain_update(transformed)
+        .chain_update([1u8])
+        .finalize()
+        .into();
+
+    // 内部头部与 XML（不压缩）
+    let mut payload = Vec::new();
+    header_field(&mut payload, 1, &INNER_STREAM_CHACHA20.to_le_bytes());
+    header_field(&mut payload, 2, &stream_key);
+    header_field(&mut payload, 0, &[]);
+
+    let mut document = build_document(database);
+    protect(&mut document, InnerStream::new(INNER_STREAM_CHACHA20, &stream_key)?)?;
+    let mut text = String::from("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n");
+    document.write(&mut text);
+    payload.extend_from_slice(text.as_bytes());
+
+    let encrypted = crypto::aes_cbc_encrypt(&cipher_key, &iv, &payload);
+
+    // 头部、头部哈希、头部 HMAC 和 HMAC 分块
+    let mut out = header.clone();
+    out.extend_from_slice(&Sha256::digest(&header));
+    out.extend_from_slice(&crypto::hmac_sha256(&block_hmac_key(&hmac_base, u64::MAX), &[&header]));
+
+    let blocks = encrypted.chunks(BLOCK_SIZE).chain(std::iter::once(&[][..]));
+    for (index, block) in (0u64..).zip(blocks) {
+        let len_bytes = (block.len() as u32).to_le_bytes();
+        out.extend_from_slice(&crypto::hmac_sha256(
+            &block_hmac_key(&hmac_base, index),
+            &[&index.to_le_bytes(), &len_bytes, block],
+        ));
+        out.extend_from_slice(&len_bytes);
+        out.extend_from_slice(block);
+    }
+
+    Ok(out)
+}
+
+fn header_field(out: &mut Vec<u8>, id: u8, value: &[u8]) {
+    out.push(id);
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+// KDBX 4 的 VariantDictionary
+#[derive(Default)]
+struct VariantDictionary {
+    data: Vec<u8>,
+}
+
+impl VariantDictionary {
+    fn item(&mut self, kind: u8, key: &str, value: &[u8]) {
+        self.data.push(kind);
+        self.data.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(key.as_bytes());
+        self.data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        self.data.extend_from_slice(value);
+    }
+
+    fn u32(&mut self, key: &str, value: u32) {
+        self.item(0x04, key, &value.to_le_bytes());
+    }
+
+    fn u64(&mut self, key: &str, value: u64) {
+        self.item(0x05, key, &value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, key: &str, value: &[u8]) {
+        self.item(0x42, key, value);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut out = 0x0100u16.to_le_bytes().to_vec();
+        out.extend_from_slice(&self.data);
+        out.push(0);
+        out
+    }
+}
+
+// 构造 KeePass XML 文档，条目按分组路径放入对应的分组
+fn build_document(database: &Database) -> Element {
+    let now = timestamp();
+
+    let mut meta = Element::new("Meta");
+    meta.children.push(Element::with_text("Generator", "passman"));
+    meta.children.push(Element::with_text("DatabaseName", "passman"));
+    meta.children.push(Element::with_text("RecycleBinEnabled", "False"));
+
+    let mut root_group = group_element("passman", &now);
+    for entry in &database.entries {
+        let mut group = &mut root_group;
+        for name in &entry.group {
+            let index = match group
+                .children
+                .iter()
+                .position(|c| c.name == "Group" && c.child_text("Name") == name.as_str())
+            {
+                Some(index) => index,
+                None => {
+                    group.children.push(group_element(name, &now));
+                    group.children.len() - 1
+                }
+            };
+            group = &mut group.children[index];
+        }
+        group.children.push(entry_element(entry, &now));
+    }
+
+    let mut root = Element::new("Root");
+    root.children.push(root_group);
+
+    let mut document = Element::new("KeePassFile");
+    document.children.push(meta);
+    document.children.push(root);
+    document
+}
+
+fn group_element(name: &str, now: &str) -> Element {
+    let mut group = Element::new("Group");
+    group.children.push(Element::with_text("UUID", &new_uuid()));
+    group.children.push(Element::with_text("Name", name));
+    group.children.push(times_element(now));
+    group
+}
+
+fn entry_element(entry: &Entry, now: &str) -> Element {
+    let mut element = Element::new("Entry");
+    element.children.push(Element::with_text("UUID", &new_uuid()));
+    if !entry.tags.is_empty() {
+        element.children.push(Element::with_text("Tags", &entry.tags.join(";")));
+    }
+    element.children.push(times_element(now));
+
+    let standard = [
+        ("Title", &entry.title),
+        ("UserName", &entry.username),
+        ("Password", &entry.password),
+        ("URL", &entry.url),
+        ("Notes", &entry.notes),
+    ];
+    let standard = standard.into_iter().map(|(key, value)| (key, value, key == "Password"));
+    let custom = entry.fields.iter().map(|f| (f.key.as_str(), &f.value, f.protected));
+    for (key, value, protected) in standard.chain(custom) {
+        let mut value_element = Element::with_text("Value", value);
+        if protected {
+            value_element.attributes.push(("Protected".to_string(), "True".to_string()));
+        }
+
+        let mut string = Element::new("String");
+        string.children.push(Element::with_text("Key", key));
+        string.children.push(value_element);
+        element.children.push(string);
+    }
+
+    element
+}
+
+fn times_element(now: &str) -> Element {
+    let mut times = Element::new("Times");
+    for name in ["CreationTime", "LastModificationTime", "LastAccessTime", "ExpiryTime", "LocationChanged"] {
+        times.children.push(Element::with_text(name, now));
+    }
+    times.children.push(Element::with_text("Expires", "False"));
+    times.children.push(Element::with_text("UsageCount", "0"));
+    times
+}
+
+// 按文档顺序加密所有受保护的值
+fn protect(document: &mut Element, mut stream: InnerStream) -> Result<(), String> {
+    document.walk_mut(&mut |element| {
+        if element.name == "Value" && element.attribute("Protected") == Some("True") {
+            let mut bytes = element.text.as_bytes().to_vec();
+            stream.apply(&mut bytes);
+            element.text = general_purpose::STANDARD.encode(bytes);
+        }
+        Ok(())
+    })
+}
+
+// KDBX 4 的时间：自 0001-01-01 起的秒数，Base64 编码的小端 i64
+fn timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    general_purpose::STANDARD.encode((seconds + UNIX_EPOCH_OFFSET).to_le_bytes())
+}
+
+fn new_uuid() -> String {
+    general_purpose::STANDARD.encode(random_bytes::<16>())
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
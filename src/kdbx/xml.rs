@@ -0,0 +1,262 @@
+// KeePass XML 所需的最小 XML 解析与输出
+
+// XML 元素
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Element {
+            name: name.to_string(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    // 带文本内容的元素
+    pub fn with_text(name: &str, text: &str) -> Self {
+        let mut element = Element::new(name);
+        element.text = text.to_string();
+        element
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    pub fn child_text(&self, name: &str) -> &str {
+        self.child(name).map(|c| c.text.as_str()).unwrap_or("")
+    }
+
+    // 按文档顺序访问所有元素
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Element) -> Result<(), String>) -> Result<(), String> {
+        f(self)?;
+        for child in &mut self.children {
+            child.walk_mut(f)?;
+        }
+        Ok(())
+    }
+
+    // 输出为 XML 文本
+    pub fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (key, value) in &self.attributes {
+            out.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+
+        if self.children.is_empty() && self.text.is_empty() {
+            out.push_str("/>");
+            return;
+        }
+
+        out.push('>');
+        out.push_str(&escape(&self.text));
+        for child in &self.children {
+            child.write(out);
+        }
+        out.push_str(&format!("</{}>", self.name));
+    }
+}
+
+// 转义特殊字符
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// 解析 XML 文档，返回根元素
+pub fn parse(input: &str) -> Result<Element, String> {
+    let mut parser = Parser { input, pos: 0 };
+    parser.skip_misc()?;
+    let root = parser.element()?;
+    Ok(root)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn skip_until(&mut self, end: &str) -> Result<(), String> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(format!("XML 缺少 {}", end)),
+        }
+    }
+
+    // 跳过声明、注释、处理指令和文档类型
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with('\u{feff}') {
+                self.pos += '\u{feff}'.len_utf8();
+            } else if rest.starts_with("<?") {
+                self.skip_until("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if rest.starts_with("<!") {
+                self.skip_until(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err("XML 元素名称无效".to_string());
+        }
+        self.pos += end;
+        Ok(rest[..end].to_string())
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        if !self.rest().starts_with('<') {
+            return Err("XML 缺少元素".to_string());
+        }
+        self.pos += 1;
+        let mut element = Element::new(&self.name()?);
+
+        // 属性
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let key = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err("XML 属性格式无效".to_string());
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+
+            let quote = self.rest().chars().next().ok_or("XML 不完整")?;
+            if quote != '"' && quote != '\'' {
+                return Err("XML 属性格式无效".to_string());
+            }
+            self.pos += 1;
+            let end = self.rest().find(quote).ok_or("XML 属性未结束")?;
+            let value = unescape(&self.rest()[..end])?;
+            self.pos += end + 1;
+            element.attributes.push((key, value));
+        }
+
+        // 内容
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(format!("XML 结束标签不匹配: {}", name));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err("XML 结束标签无效".to_string());
+                }
+                self.pos += 1;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_until("-->")?;
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").ok_or("XML CDATA 未结束")?;
+                element.text.push_str(&cdata[..end]);
+                self.pos += "<![CDATA[".len() + end + 3;
+            } else if rest.starts_with('<') {
+                let child = self.element()?;
+                element.children.push(child);
+            } else if rest.is_empty() {
+                return Err(format!("XML 元素未结束: {}", element.name));
+            } else {
+                let end = rest.find('<').unwrap_or(rest.len());
+                element.text.push_str(&unescape(&rest[..end])?);
+                self.pos += end;
+            }
+        }
+    }
+}
+
+// 还原实体引用
+fn unescape(text: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = rest.find(';').ok_or("XML 实体未结束")?;
+        let entity = &rest[1..end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32).ok_or_else(|| format!("未知的 XML 实体: {}", entity))?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Digest};
+use base64::{Engine as _, engine::general_purpose};
+
+use crate::argon2;
+use crate::secmem::SecretBytes;
+use crate::yubikey::YubikeyParams;
+
+// 密钥派生算法名称
+pub const ARGON2ID: &str = "argon2id";
+// 盐长度
+const SALT_LENGTH: usize = 16;
+// 默认内存开销 (KiB)
+const DEFAULT_M_COST: u32 = 19456;
+// 默认迭代次数
+const DEFAULT_T_COST: u32 = 2;
+// 默认并行度
+const DEFAULT_P_COST: u32 = 1;
+
+// 密钥派生参数，保存在密码库头部
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KdfParams {
+    // 算法名称
+    pub algorithm: String,
+    // Base64 编码的随机盐
+    pub salt: String,
+    // 内存开销 (KiB)
+    pub m_cost: u32,
+    // 迭代次数
+    pub t_cost: u32,
+    // 并行度
+    pub p_cost: u32,
+    // 是否同时以密钥文件派生（见 derive_key_with），没有此字段的密码库不使用密钥文件
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub keyfile: bool,
+    // 同时以 YubiKey 的挑战-响应派生时的槽位和挑战
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yubikey: Option<YubikeyParams>,
+}
+
+impl KdfParams {
+    // 使用默认参数和新的随机盐
+    pub fn generate() -> Self {
+        Self::random_salt(DEFAULT_M_COST, DEFAULT_T_COST, DEFAULT_P_COST)
+    }
+
+    // 使用指定的开销参数和新的随机盐
+    pub fn with_costs(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Self, String> {
+        if p_cost == 0 || t_cost == 0 {
+            return Err("Argon2 的迭代次数和并行度必须大于 0".to_string());
+        }
+        if m_cost < 8 * p_cost {
+            return Err("Argon2 的内存开销至少为并行度的 8 倍 (KiB)".to_string());
+        }
+        Ok(Self::random_salt(m_cost, t_cost, p_cost))
+    }
+
+    fn random_salt(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let salt = rand::random::<[u8; SALT_LENGTH]>();
+        KdfParams {
+            algorithm: ARGON2ID.to_string(),
+            salt: general_purpose::STANDARD.encode(salt),
+            m_cost,
+            t_cost,
+            p_cost,
+            keyfile: false,
+            yubikey: None,
+        }
+    }
+}
+
+// 使用 Argon2id 从主密钥派生加密密钥
+pub fn derive_key(master_key: &str, params: &KdfParams) -> Result<[u8; 32], String> {
+    derive_key_with(master_key, &[], params)
+}
+
+// 使用 Argon2id 从主密钥和其他因素派生加密密钥，`factors`（密钥文件内容的 SHA-256 摘要、
+// YubiKey 的响应）依次接在主密钥之后作为 Argon2id 的输入
+pub fn derive_key_with(master_key: &str, factors: &[&[u8]], params: &KdfParams) -> Result<[u8; 32], String> {
+    if params.algorithm != ARGON2ID {
+        return Err(format!("不支持的密钥派生算法: {}", params.algorithm));
+    }
+
+    let salt = general_purpose::STANDARD
+        .decode(&params.salt)
+        .map_err(|e| format!("无效的盐: {}", e))?;
+
+    let mut password = SecretBytes::from_slice(master_key.as_bytes());
+    for factor in factors {
+        password.extend_from_slice(factor);
+    }
+
+    let mut key = [0u8; 32];
+    argon2::argon2id(
+        &password,
+        &salt,
+        params.m_cost,
+        params.t_cost,
+        params.p_cost,
+        &mut key,
+    );
+    Ok(key)
+}
+
+// 旧版本密码库使用的 SHA-256 密钥派生，仅用于迁移
+pub fn derive_legacy_key(master_key: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(master_key.as_bytes());
+    let result = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&result);
+    key
+}
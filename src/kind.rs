@@ -0,0 +1,172 @@
+// 条目类型及各类型的专用字段
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// 条目类型
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    /// 网站或应用的登录信息
+    #[default]
+    Login,
+    /// 银行卡
+    Card,
+    /// 身份信息
+    Identity,
+    /// API 密钥
+    ApiKey,
+    /// 安全笔记，内容保存在备注中
+    Note,
+    /// SSH 私钥，口令（如果有）保存在密码中
+    SshKey,
+    /// WiFi 网络，密码保存在密码中
+    Wifi,
+}
+
+/// 条目类型的专用字段，以自定义字段的形式保存
+pub struct KindField {
+    /// 字段名称
+    pub name: &'static str,
+    /// 显示名称
+    pub label: &'static str,
+    /// 是否为敏感字段
+    pub secret: bool,
+    /// 值是否有多行，例如私钥
+    pub multiline: bool,
+}
+
+const fn field(name: &'static str, label: &'static str, secret: bool) -> KindField {
+    KindField { name, label, secret, multiline: false }
+}
+
+const CARD_FIELDS: &[KindField] = &[
+    field("cardholder", "持卡人", false),
+    field("number", "卡号", true),
+    field("expiry", "有效期 (MM/YY)", false),
+    field("cvv", "安全码", true),
+];
+
+const IDENTITY_FIELDS: &[KindField] = &[
+    field("full_name", "姓名", false),
+    field("email", "邮箱", false),
+    field("phone", "电话", false),
+    field("address", "地址", false),
+    field("id_number", "证件号码", true),
+];
+
+const API_KEY_FIELDS: &[KindField] = &[
+    field("token", "令牌", true),
+    field("endpoint", "接口地址", false),
+];
+
+const SSH_KEY_FIELDS: &[KindField] = &[
+    KindField { name: "private_key", label: "私钥", secret: true, multiline: true },
+    field("public_key", "公钥", false),
+];
+
+const WIFI_FIELDS: &[KindField] = &[
+    field("ssid", "网络名称 (SSID)", false),
+    field("security", "加密方式 (WPA、WPA3、WEP 或 nopass)", false),
+    field("hidden", "隐藏网络 (yes/no)", false),
+];
+
+impl EntryKind {
+    /// 类型的显示名称
+    pub fn label(self) -> &'static str {
+        match self {
+            EntryKind::Login => "登录",
+            EntryKind::Card => "银行卡",
+            EntryKind::Identity => "身份",
+            EntryKind::ApiKey => "API 密钥",
+            EntryKind::Note => "安全笔记",
+            EntryKind::SshKey => "SSH 密钥",
+            EntryKind::Wifi => "WiFi 网络",
+        }
+    }
+
+    /// 类型的专用字段
+    pub fn fields(self) -> &'static [KindField] {
+        match self {
+            EntryKind::Login | EntryKind::Note => &[],
+            EntryKind::Card => CARD_FIELDS,
+            EntryKind::Identity => IDENTITY_FIELDS,
+            EntryKind::ApiKey => API_KEY_FIELDS,
+            EntryKind::SshKey => SSH_KEY_FIELDS,
+            EntryKind::Wifi => WIFI_FIELDS,
+        }
+    }
+
+    /// 专用字段的定义
+    pub fn field(self, name: &str) -> Option<&'static KindField> {
+        self.fields().iter().find(|field| field.name == name)
+    }
+
+    pub fn is_login(&self) -> bool {
+        *self == EntryKind::Login
+    }
+
+    /// 检查专用字段的值，例如卡号的校验位和有效期的格式
+    pub fn check_field(self, name: &str, value: &str) -> Result<(), AppError> {
+        match (self, name) {
+            (EntryKind::Card, "number") if !luhn_valid(value) => Err(AppError::from("卡号无效（校验位错误）")),
+            (EntryKind::Card, "expiry") if !expiry_valid(value) => Err(AppError::from("有效期格式应为 MM/YY 或 MM/YYYY")),
+            (EntryKind::Card, "cvv") if !cvv_valid(value) => Err(AppError::from("安全码应为 3 或 4 位数字")),
+            (EntryKind::SshKey, "private_key") if !private_key_valid(value) => {
+                Err(AppError::from("私钥应为 OpenSSH 或 PEM 格式（-----BEGIN ... PRIVATE KEY-----）"))
+            }
+            (EntryKind::Wifi, "security") if !["wpa", "wpa2", "wpa3", "sae", "wep", "nopass"].contains(&value.trim().to_lowercase().as_str()) => {
+                Err(AppError::from("加密方式应为 WPA、WPA2、WPA3、WEP 或 nopass"))
+            }
+            (EntryKind::Wifi, "hidden") if !["yes", "no", "true", "false"].contains(&value.trim().to_lowercase().as_str()) => {
+                Err(AppError::from("隐藏网络应为 yes 或 no"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Luhn 校验（忽略空格和连字符）
+fn luhn_valid(number: &str) -> bool {
+    let digits: Option<Vec<u32>> = number.chars().filter(|c| !matches!(c, ' ' | '-')).map(|c| c.to_digit(10)).collect();
+    let Some(digits) = digits.filter(|d| (12..=19).contains(&d.len())) else {
+        return false;
+    };
+
+    // 从右往左，偶数位乘 2，超过 9 时减 9
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn cvv_valid(cvv: &str) -> bool {
+    (3..=4).contains(&cvv.len()) && cvv.bytes().all(|b| b.is_ascii_digit())
+}
+
+// MM/YY 或 MM/YYYY
+fn expiry_valid(expiry: &str) -> bool {
+    let Some((month, year)) = expiry.trim().split_once('/') else {
+        return false;
+    };
+    let month_ok = month.len() == 2 && month.parse::<u32>().is_ok_and(|m| (1..=12).contains(&m));
+    let year_ok = matches!(year.len(), 2 | 4) && year.bytes().all(|b| b.is_ascii_digit());
+    month_ok && year_ok
+}
+
+// 以 `-----BEGIN ... PRIVATE KEY-----` 开头、对应的 END 行结尾
+fn private_key_valid(key: &str) -> bool {
+    let key = key.trim();
+    let Some(label) = key.strip_prefix("-----BEGIN ").and_then(|rest| rest.split_once("-----")).map(|(label, _)| label) else {
+        return false;
+    };
+    label.ends_with("PRIVATE KEY") && key.ends_with(&format!("-----END {}-----", label))
+}
@@ -0,0 +1,56 @@
+//! passman 的存储与加密核心
+//!
+//! 命令行工具只是这个库之上的一层薄封装，其他程序可以直接通过 [`Vault`]
+//! 读写 passman 密码库，而无需调用命令行。
+//!
+//! ```no_run
+//! use passman::{Account, Vault};
+//!
+//! let mut vault = Vault::open(".passman_data.json", "master key")?;
+//! let id = vault.add(Account::new("alice", "secret", "github"))?;
+//! println!("{}", vault.get(&id).unwrap().password);
+//! vault.save()?;
+//! # Ok::<(), passman::AppError>(())
+//! ```
+
+pub mod age;
+pub mod argon2;
+pub mod atomic;
+pub mod audit;
+pub mod backup;
+pub mod crypto;
+pub mod export;
+pub mod fido2;
+pub mod generator;
+pub mod gpg;
+pub mod hkdf;
+pub mod import;
+pub mod kdbx;
+pub mod kdf;
+pub mod lock;
+pub mod merge;
+pub mod otp;
+pub mod qr;
+pub mod recovery;
+pub mod search;
+pub mod secmem;
+pub mod strength;
+pub mod timestamp;
+pub mod tpm;
+pub mod yubikey;
+mod bech32;
+mod chacha20;
+mod error;
+mod journal;
+mod kind;
+mod migrate;
+mod poly1305;
+mod sha1;
+mod vault;
+mod x25519;
+mod xchacha20poly1305;
+
+pub use error::AppError;
+pub use journal::{Changed, Undone};
+pub use kind::{EntryKind, KindField};
+pub use vault::{Account, AccountStore, Attachment, CustomField, Migration, PasswordHistory, Selector, Vault};
@@ -0,0 +1,70 @@
+//! 密码库文件锁
+//!
+//! 保存时会用新文件替换密码库文件，因此锁加在同一目录下单独的锁文件（例如
+//! `.vault.json.lock`）上。打开密码库时获取锁，`Vault` 被释放时解除，从读取到保存
+//! 之间其他进程无法同时打开同一个密码库，不会覆盖彼此的修改。锁是建议性的（Unix 上
+//! 为 flock），进程退出时由系统释放，残留的锁文件不影响下次使用。
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+
+// 密码库被占用时最多等待的时间
+const TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 密码库的锁，持有期间其他进程无法打开同一个密码库
+pub struct VaultLock {
+    _file: File,
+}
+
+impl VaultLock {
+    /// 获取密码库的锁，被其他进程占用时最多等待 10 秒，超时返回错误；密码库所在目录
+    /// 还不存在时没有需要保护的文件，返回 `None`
+    ///
+    /// 不打开密码库而直接替换密码库文件时（例如从远程同步）也应先获取锁。
+    pub fn acquire(path: &Path) -> Result<Option<VaultLock>, AppError> {
+        // 通过不同的符号链接打开同一个密码库时使用同一个锁文件
+        let path = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path)?,
+            _ => path.to_path_buf(),
+        };
+        let Some(file_name) = path.file_name() else {
+            return Err(AppError::from("无效的密码库路径"));
+        };
+        if path.parent().is_some_and(|dir| !dir.as_os_str().is_empty() && !dir.is_dir()) {
+            return Ok(None);
+        }
+
+        let mut lock_name = OsString::from(".");
+        lock_name.push(file_name);
+        lock_name.push(".lock");
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(false);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(path.with_file_name(lock_name))?;
+
+        let start = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Some(VaultLock { _file: file })),
+                Err(TryLockError::WouldBlock) if start.elapsed() < TIMEOUT => thread::sleep(RETRY_INTERVAL),
+                Err(TryLockError::WouldBlock) => {
+                    return Err(AppError::from(format!(
+                        "密码库正被另一个 passman 进程使用（例如正在编辑或打开了交互式界面），请稍后再试: {}",
+                        path.display()
+                    )));
+                }
+                Err(TryLockError::Error(e)) => return Err(AppError::from(e)),
+            }
+        }
+    }
+}
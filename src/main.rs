@@ -1,22 +1,121 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use prettytable::{Table, Row, Cell};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::{self, Write};
-use std::path::Path;
-use aes_gcm::{
-    aead::{Aead, KeyInit},
-    Aes256Gcm, Nonce,
-};
-use sha2::{Sha256, Digest};
-use base64::{Engine as _, engine::general_purpose};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use passman::age::{self, AgeBackend, Identity, Recipient};
+use passman::atomic;
+use passman::audit::{self, BloomFilter, Breach, BreachChecker};
+use passman::backup;
+use passman::crypto::{Cipher, CryptoBackend, KeyBackend, KeyHeader, Keyfile, PasswordBackend, Unlock};
+use passman::export;
+use passman::fido2::{self, Fido2Backend};
+use passman::generator::{self, PassphraseOptions, PasswordOptions};
+use passman::gpg::GpgBackend;
+use passman::import::{self, ImportReport};
+use passman::kdf::KdfParams;
+use passman::lock::VaultLock;
+use passman::merge::{self, Conflict, Resolution};
+use passman::otp::{self, Otp, OtpAlgorithm, OtpKind};
+use passman::qr::QrCode;
+use passman::recovery;
+use passman::search;
+use passman::secmem::SecretBytes;
+use passman::strength;
+use passman::timestamp;
+use passman::tpm::{self, SealedKey};
+use passman::yubikey::YubikeyParams;
+use passman::{Account, AccountStore, AppError, Changed, EntryKind, Selector, Undone, Vault};
+
+use color::Role;
+use config::Config;
+use entry_file::EntryFile;
+use i18n::t;
+use output::EntryJson;
+use remote::Outcome;
+
+mod agent;
+mod ansible_vault;
+mod api_server;
+mod autolock;
+mod browser;
+mod browser_host;
+mod clipboard;
+mod color;
+mod completions;
+mod config;
+#[cfg(unix)]
+mod dbus;
+mod docker_credential;
+mod doctor;
+mod dotenv;
+mod duress;
+mod editor;
+mod entry_file;
+mod git;
+mod git_credential;
+mod hashicorp;
+mod http_server;
+mod i18n;
+mod manpage;
+mod name_index;
+mod output;
+mod pager;
+mod recovery_kit;
+mod remote;
+#[cfg(unix)]
+mod secret_service;
+mod session;
+mod sops;
+mod ssh;
+mod sync_server;
+mod tpm_store;
+mod tui;
+mod wifi;
 
 // 主程序参数结构
 #[derive(Parser)]
 #[command(name = "passman")]
 #[command(about = "一个简单的密码管理命令行工具", long_about = None)]
+// 全局参数在各子命令的帮助中单独列在命令自己的选项之后
+#[command(next_help_heading = "全局选项")]
 struct Cli {
+    /// 密码库文件路径，或配置文件 [profiles] 中的档案名称
+    #[arg(long, global = true, value_name = "PATH_OR_NAME")]
+    vault: Option<String>,
+    
+    /// 输出格式（list、search、get 和 audit 支持 json）
+    #[arg(long, global = true, value_enum, value_parser = i18n::enum_parser::<OutputFormat>(), default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    
+    /// 何时使用彩色输出（auto 时遵循 NO_COLOR 环境变量）
+    #[arg(long, global = true, value_enum, value_parser = i18n::enum_parser::<color::ColorMode>(), default_value_t = color::ColorMode::Auto)]
+    color: color::ColorMode,
+    
+    /// list 和 search 的输出超过一屏时不使用分页程序 ($PAGER)
+    #[arg(long, global = true)]
+    no_pager: bool,
+    
+    /// 界面语言（默认按配置项 lang 或 LANG 环境变量）
+    #[arg(long, global = true, value_enum, value_parser = i18n::enum_parser::<i18n::Lang>())]
+    lang: Option<i18n::Lang>,
+    
+    /// 解锁以 age 接收者加密的密码库的身份文件（age-keygen 生成的私钥或 OpenSSH ssh-ed25519 私钥），可以重复指定（默认按配置项 age.identity）
+    #[arg(long = "identity", global = true, value_name = "FILE")]
+    identities: Vec<PathBuf>,
+    
+    /// 密钥文件，作为主密钥之外的第二个解锁因素（新建密码库时指定则之后解锁都需要它）
+    #[arg(long, global = true, value_name = "FILE")]
+    keyfile: Option<PathBuf>,
+    
+    /// 解锁以 FIDO2 安全密钥加密的密码库时使用的设备（默认按配置项 fido2.device，未配置时使用第一个连接的设备）
+    #[arg(long, global = true, value_name = "DEVICE")]
+    fido2_device: Option<String>,
+    
     #[command(subcommand)]
     command: Commands,
 }
@@ -26,357 +125,4741 @@ struct Cli {
 enum Commands {
     /// 添加新账号
     Add {
-        /// 用户名
+        /// 用户名（银行卡等类型为条目名称），省略时在终端中逐项输入各项信息
         #[arg(short, long)]
-        username: String,
+        username: Option<String>,
+        
+        /// 条目类型，非登录类型缺少的专用字段会依次提示输入
+        #[arg(long = "type", value_enum, value_parser = i18n::enum_parser::<EntryType>(), value_name = "TYPE")]
+        kind: Option<EntryType>,
+        
+        /// 密码（登录类型必填）
+        #[arg(short, long)]
+        password: Option<String>,
+        
+        /// 生成指定长度的随机密码代替手动输入
+        #[arg(short, long, value_name = "LENGTH", conflicts_with = "password")]
+        generate: Option<usize>,
         
-        /// 密码
+        /// 备注信息（包含网站或应用信息，登录类型必填）
         #[arg(short, long)]
-        password: String,
+        notes: Option<String>,
+        
+        /// 网址
+        #[arg(long)]
+        url: Option<String>,
         
-        /// 备注信息（包含网站或应用信息）
+        /// 标签，可以重复指定
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// 文件夹路径，以 / 分隔各级，例如 工作/服务器
         #[arg(short, long)]
-        notes: String,
+        folder: Option<String>,
+        
+        /// 密码有效期（例如 90d、12w、6m、1y，更换密码后重新计时）或过期日期（YYYY-MM-DD）
+        #[arg(long, value_name = "WHEN")]
+        expires: Option<String>,
+        
+        /// 自定义字段，可以重复指定
+        #[arg(long = "field", value_name = "NAME=VALUE", value_parser = parse_field)]
+        fields: Vec<(String, String)>,
+        
+        /// 敏感的自定义字段（查看时默认隐藏），可以重复指定
+        #[arg(long = "secret-field", value_name = "NAME=VALUE", value_parser = parse_field)]
+        secret_fields: Vec<(String, String)>,
+        
+        /// 从 TOML 或 JSON（.json）文件批量添加账号
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["username", "kind", "password", "generate", "notes", "url", "tags", "folder", "expires", "fields", "secret_fields"])]
+        from: Option<PathBuf>,
     },
     
     /// 删除账号
     Delete {
-        /// 用户名
-        #[arg(short, long)]
-        username: String,
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 不要求确认，直接删除（用于脚本）
+        #[arg(long)]
+        force: bool,
     },
     
     /// 更新账号信息
     Update {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        #[command(flatten)]
+        changes: EntryChanges,
+    },
+    
+    /// 在编辑器（$VISUAL 或 $EDITOR）中修改账号信息
+    Edit {
         /// 用户名
-        #[arg(short, long)]
+        #[arg(required_unless_present = "id")]
+        username: Option<String>,
+        
+        /// 账号 ID（可以只写开头几位）
+        #[arg(long)]
+        id: Option<String>,
+        
+        /// 网站：网址、备注或文件夹中包含的文字
+        #[arg(long)]
+        site: Option<String>,
+    },
+    
+    /// 修改账号的用户名，其他内容（ID、历史密码、附件等）保持不变
+    Rename {
+        /// 原用户名
+        old: String,
+        
+        /// 新用户名
+        new: String,
+        
+        /// 账号 ID（原用户名有多个账号时用于区分，可以只写开头几位）
+        #[arg(long)]
+        id: Option<String>,
+        
+        /// 网站：网址、备注或文件夹中包含的文字
+        #[arg(long)]
+        site: Option<String>,
+    },
+    
+    /// 以现有账号为模板创建新账号（不复制历史密码、一次性密码和附件）
+    Clone {
+        /// 作为模板的账号的用户名
         username: String,
         
-        /// 新密码（可选）
+        /// 新账号的用户名
+        new_name: String,
+        
+        /// 账号 ID（用户名有多个账号时用于区分，可以只写开头几位）
+        #[arg(long)]
+        id: Option<String>,
+        
+        /// 网站：网址、备注或文件夹中包含的文字
+        #[arg(long)]
+        site: Option<String>,
+        
+        /// 为新账号生成指定长度的随机密码，而不是复制原密码
+        #[arg(short, long, value_name = "LENGTH")]
+        generate: Option<usize>,
+    },
+    
+    /// 查看所有账号信息
+    List {
+        /// 只显示带有此标签的账号，重复指定时需要带有全部标签
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// 只显示此文件夹（包括子文件夹）中的账号
         #[arg(short, long)]
-        password: Option<String>,
+        folder: Option<String>,
+        
+        /// 只显示此类型的条目
+        #[arg(long = "type", value_enum, value_parser = i18n::enum_parser::<EntryType>(), value_name = "TYPE")]
+        kind: Option<EntryType>,
+        
+        /// 只显示用户名或网址匹配的账号（忽略大小写，支持通配符 * 和 ?）
+        #[arg(short = 'm', long = "match", value_name = "PATTERN")]
+        pattern: Option<String>,
         
-        /// 新备注信息（可选）
+        /// 按标签或文件夹分组显示
+        #[arg(short, long, value_enum, value_parser = i18n::enum_parser::<GroupBy>())]
+        group_by: Option<GroupBy>,
+        
+        /// 排序方式（按时间排序时最新的在前）
+        #[arg(short, long, value_enum, value_parser = i18n::enum_parser::<SortBy>(), default_value_t = SortBy::Username)]
+        sort: SortBy,
+        
+        /// 反向排序
         #[arg(short, long)]
-        notes: Option<String>,
+        reverse: bool,
+        
+        /// 显示密码（默认显示为 ••••••）
+        #[arg(long)]
+        show_passwords: bool,
+        
+        /// 显示的列，以逗号分隔，例如 username,url,tags,modified（默认为配置中的 list.columns）
+        #[arg(short, long, value_name = "COLUMNS")]
+        columns: Option<String>,
     },
     
-    /// 查看所有账号信息
-    List,
+    /// 在用户名、备注、网址、标签和文件夹中搜索账号
+    Search {
+        /// 搜索内容（默认为忽略大小写的子串匹配）
+        query: String,
+        
+        /// 模糊匹配：查询中的字符按顺序出现即可，结果按匹配程度排序
+        #[arg(short = 'z', long, conflicts_with = "regex")]
+        fuzzy: bool,
+        
+        /// 按正则表达式匹配（以 (?i) 开头时忽略大小写）
+        #[arg(short = 'e', long)]
+        regex: bool,
+        
+        /// 只在指定字段中搜索，可以重复指定（默认搜索全部字段）
+        #[arg(long = "in", value_enum, value_parser = i18n::enum_parser::<SearchField>(), value_name = "FIELD")]
+        fields: Vec<SearchField>,
+        
+        /// 显示密码（默认显示为 ••••••）
+        #[arg(long)]
+        show_passwords: bool,
+    },
     
     /// 查看特定账号信息
     Get {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 显示敏感自定义字段的值
+        #[arg(long)]
+        reveal: bool,
+        
+        /// 显示密码（默认显示为 ••••••）
+        #[arg(long)]
+        show_passwords: bool,
+        
+        /// 只输出一个字段的值：username、password、notes、url 或 custom:<名称>
+        #[arg(long, value_name = "FIELD", value_parser = parse_entry_field)]
+        field: Option<EntryField>,
+        
+        /// 以二维码显示密码（或 --field 指定的字段），用手机扫描即可取得，不必手动输入
+        #[arg(long, conflicts_with = "show_passwords")]
+        qr: bool,
+    },
+    
+    /// 查看账号，或只输出密码供脚本使用
+    Show {
+        /// 用户名
+        #[arg(required_unless_present = "id")]
+        username: Option<String>,
+        
+        /// 账号 ID（可以只写开头几位）
+        #[arg(long)]
+        id: Option<String>,
+        
+        /// 网站：网址、备注或文件夹中包含的文字
+        #[arg(long)]
+        site: Option<String>,
+        
+        /// 只输出密码本身，不显示表格
+        #[arg(long)]
+        password_only: bool,
+        
+        /// 输出密码后不换行
+        #[arg(short = 'n', long, requires = "password_only")]
+        no_newline: bool,
+    },
+    
+    /// 在浏览器中打开账号的网址
+    Open {
         /// 用户名
+        #[arg(required_unless_present = "id")]
+        username: Option<String>,
+        
+        /// 账号 ID（可以只写开头几位）
+        #[arg(long)]
+        id: Option<String>,
+        
+        /// 网站：网址、备注或文件夹中包含的文字
+        #[arg(long)]
+        site: Option<String>,
+        
+        /// 同时把密码复制到剪贴板
         #[arg(short, long)]
-        username: String,
+        copy: bool,
+    },
+    
+    /// 运行命令，把账号中的密码等作为环境变量传给它，不必写在 shell 历史或配置文件中
+    Run {
+        /// 环境变量和它的值所在的账号：NAME=用户名[#字段]，字段默认为 password（写法同 get --field），用户名也可以写为 id:<ID>；可以重复指定
+        #[arg(short, long = "env", value_name = "NAME=ENTRY", value_parser = parse_env_binding, required = true)]
+        envs: Vec<EnvBinding>,
+        
+        /// 要运行的命令及其参数
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    
+    /// 由模板或映射文件生成 .env 文件的内容，写到标准输出
+    #[command(group = clap::ArgGroup::new("source").required(true))]
+    Env {
+        /// 模板文件，其中的 {{passman:账号.字段}} 替换为账号中的值（字段默认为 password），其余内容原样输出
+        #[arg(long, value_name = "FILE", group = "source")]
+        template: Option<PathBuf>,
+        
+        /// 映射文件，每行一项 NAME=账号[#字段]（与 run --env 相同），输出 NAME=值
+        #[arg(long, value_name = "FILE", group = "source")]
+        mapping: Option<PathBuf>,
+    },
+    
+    /// 运行时替换文件中引用账号的占位符并交给命令使用（类似 sops exec-file 和 exec-env），提交到仓库的文件中不必有机密
+    SopsExec {
+        /// 带有 {{passman:账号.字段}} 占位符的文件（写法同 env --template）
+        file: PathBuf,
+        
+        /// 把替换后文件中顶层的键值作为环境变量传给命令，而不是写到临时文件
+        #[arg(long)]
+        env: bool,
+        
+        /// 文件的格式（默认由扩展名判断），决定值的转义方式
+        #[arg(long, value_enum, value_parser = i18n::enum_parser::<sops::Format>(), value_name = "FORMAT")]
+        input_type: Option<sops::Format>,
+        
+        /// 要运行的命令及其参数，其中的 {} 替换为临时文件的路径（没有 {} 时把路径加在最后）
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    
+    /// 查看或恢复账号的历史密码
+    History {
+        /// 用户名
+        #[arg(required_unless_present = "id")]
+        username: Option<String>,
+        
+        /// 账号 ID（可以只写开头几位）
+        #[arg(long)]
+        id: Option<String>,
+        
+        /// 网站：网址、备注或文件夹中包含的文字
+        #[arg(long)]
+        site: Option<String>,
+        
+        /// 恢复第 N 个历史密码（序号见历史列表，当前密码会记入历史）
+        #[arg(long, value_name = "N")]
+        restore: Option<usize>,
+    },
+    
+    /// 列出已过期或即将过期的密码
+    Expiring {
+        /// 提前提醒的时间（例如 14d、4w）
+        #[arg(short, long, value_name = "DURATION", default_value = "14d")]
+        within: String,
+    },
+    
+    /// 安全笔记：不属于任何账号的加密文本，例如恢复短语、许可证密钥
+    Note {
+        #[command(subcommand)]
+        action: NoteAction,
+    },
+    
+    /// 账号的加密附件（小文件，例如备用验证码、密钥文件）
+    Attach {
+        #[command(subcommand)]
+        action: AttachAction,
+    },
+    
+    /// 清除缓存在系统钥匙串中的解锁会话并停止 agent，之后需要重新输入主密钥
+    Lock {
+        /// 清除所有密码库的会话并停止所有 agent
+        #[arg(long)]
+        all: bool,
+    },
+    
+    /// 在后台保存解锁后的密钥，之后的命令通过本地套接字使用它，不再请求主密钥
+    Agent {
+        /// 在前台运行，不转入后台
+        #[arg(long)]
+        foreground: bool,
+        
+        /// 运行指定的分钟数后退出（默认按配置项 agent.timeout，未配置时一直运行）
+        #[arg(long, value_name = "MINUTES")]
+        timeout: Option<u64>,
+        
+        /// 指定的分钟数内没有使用时退出（默认按配置项 autolock.idle）
+        #[arg(long, value_name = "MINUTES")]
+        idle: Option<u64>,
+        
+        /// 停止这个密码库的 agent
+        #[arg(long, conflicts_with_all = ["foreground", "timeout", "idle"])]
+        stop: bool,
+    },
+    
+    /// 撤销最近一次修改（添加、更新、删除等）
+    Undo,
+    
+    /// 比较另一个密码库或之前保存的版本，显示新增、删除和修改的账号
+    Diff {
+        /// 另一个密码库文件或 passman 加密备份 (.pmbk)，作为修改前的版本
+        #[arg(required_unless_present = "backup", conflicts_with = "backup")]
+        other: Option<PathBuf>,
+        
+        /// 与 N 次保存之前的版本比较（由修改记录还原，最多 20 次）
+        #[arg(long, value_name = "N")]
+        backup: Option<usize>,
+        
+        /// 显示密码（默认显示为 ••••••）
+        #[arg(long, alias = "show")]
+        show_passwords: bool,
+    },
+    
+    /// 把另一个密码库（或加密备份）中的账号合并到当前密码库
+    Merge {
+        /// 另一个密码库文件或 passman 加密备份 (.pmbk)
+        other: PathBuf,
+        
+        /// 账号已存在且内容不同时的处理方式
+        #[arg(long, value_enum, value_parser = i18n::enum_parser::<MergeStrategy>(), default_value_t = MergeStrategy::Skip)]
+        on_conflict: MergeStrategy,
+    },
+    
+    /// 交互式界面
+    Tui,
+    
+    /// 更换主密钥（以 age 接收者、GPG 公钥或 FIDO2 安全密钥加密的密码库改回使用主密钥）
+    ChangeMaster {
+        /// 之后解锁同时需要这个密钥文件（默认保持解锁时使用的密钥文件）
+        #[arg(long, value_name = "FILE")]
+        new_keyfile: Option<PathBuf>,
+        
+        /// 不再使用密钥文件
+        #[arg(long, conflicts_with = "new_keyfile")]
+        no_keyfile: bool,
+        
+        /// 之后解锁同时需要 YubiKey 这个槽位（1 或 2）的 HMAC-SHA1 挑战-响应（默认保持原有设置）
+        #[arg(long, value_name = "SLOT", value_parser = clap::value_parser!(u8).range(1..=2))]
+        yubikey_slot: Option<u8>,
+        
+        /// 不再使用 YubiKey
+        #[arg(long, conflicts_with = "yubikey_slot")]
+        no_yubikey: bool,
+    },
+    
+    /// 使用新的随机盐、密钥和初始化向量重新加密密码库（同时应用配置中的密钥派生参数），或改为以 age 接收者、GPG 公钥或 FIDO2 安全密钥加密
+    Rekey {
+        /// 改为以 age 接收者加密：接收者的公钥（age1... 或 ssh-ed25519 AAAA...），可以重复指定
+        #[arg(long = "recipient", value_name = "KEY")]
+        recipients: Vec<String>,
+        
+        /// 从文件读取 age 接收者（每行一个，# 开头的行为注释），可以重复指定
+        #[arg(long = "recipients-file", value_name = "FILE")]
+        recipients_files: Vec<PathBuf>,
+        
+        /// 改为以 GPG 公钥加密：接收者的密钥 ID、指纹或用户 ID，可以重复指定
+        #[arg(long, value_name = "KEY_ID", conflicts_with_all = ["recipients", "recipients_files"])]
+        gpg_id: Vec<String>,
+        
+        /// 改为由 FIDO2 安全密钥解锁：在安全密钥上注册新的凭据，以 hmac-secret 扩展派生密钥（需要 PIN 和触摸）
+        #[arg(long, conflicts_with_all = ["recipients", "recipients_files", "gpg_id"])]
+        fido2: bool,
+    },
+    
+    /// 把密码库的密钥封存到本机的 TPM，之后在这台机器上只需输入 PIN 即可解锁
+    Tpm {
+        #[command(subcommand)]
+        action: TpmAction,
+    },
+    
+    /// 胁迫密码：被迫解锁时输入它，打开的是另一个诱饵密码库
+    Duress {
+        #[command(subcommand)]
+        action: DuressAction,
+    },
+    
+    /// 与远程存储（WebDAV、S3、SFTP、rclone）或远程 git 仓库同步密码库
+    Sync {
+        /// 在密码库所在的目录启用 git（不是仓库时新建），之后每次保存都自动提交
+        #[arg(long)]
+        init: bool,
+        
+        /// 远程存储的地址，例如 webdav://host/path/vault.json、s3://bucket/path/vault.json、ssh://host/path/vault.json，之后可以省略；与 --init 一起使用时为远程仓库 origin 的 URL
+        #[arg(long)]
+        remote: Option<String>,
+        
+        /// 两边都修改了同一个账号时保留哪一边的版本，不逐个询问；远程的密码库无法用本地的密钥解密时整个保留一边
+        #[arg(long, value_enum, value_parser = i18n::enum_parser::<remote::Keep>(), conflicts_with = "init")]
+        keep: Option<remote::Keep>,
+        
+        /// 以同步密钥隐藏远程文件的名称和内容格式（第一次使用时生成密钥，其他设备上输入同一个密钥），之后自动沿用
+        #[arg(long, conflicts_with = "init")]
+        conceal: bool,
+    },
+    
+    /// 把本地的密码库推送到远程存储或远程 git 仓库，不拉取远程的修改
+    Push {
+        /// 远程存储的地址，省略时使用上次同步的远程存储；没有远程存储时推送到远程 git 仓库
+        #[arg(long)]
+        remote: Option<String>,
+        
+        /// 远程存储中有本地没有的修改时仍然覆盖
+        #[arg(long)]
+        force: bool,
+        
+        /// 只显示将会怎样推送，不上传
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// 从远程存储或远程 git 仓库拉取修改，不上传本地的修改
+    Pull {
+        /// 远程存储的地址，省略时使用上次同步的远程存储；没有远程存储时从远程 git 仓库拉取
+        #[arg(long)]
+        remote: Option<String>,
+        
+        /// 两边都修改了同一个账号时保留哪一边的版本，不逐个询问；远程的密码库无法用本地的密钥解密时整个保留一边
+        #[arg(long, value_enum, value_parser = i18n::enum_parser::<remote::Keep>())]
+        keep: Option<remote::Keep>,
+        
+        /// 只显示将会怎样拉取，不修改本地的密码库
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// 在本机运行 REST API，供图形界面、编辑器插件和启动器列出、读取和添加账号
+    Serve {
+        /// 监听的地址和端口，只能是本机地址
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:7599")]
+        listen: String,
+        
+        /// 重新生成访问令牌，之前的令牌随即失效
+        #[arg(long)]
+        new_token: bool,
+    },
+    
+    /// git 的凭据助手，按主机从密码库读取和保存 HTTPS 凭据（在 git 配置中设为 `!passman git-credential`）
+    GitCredential {
+        /// git 传入的操作：get、store 或 erase，其他操作忽略
+        action: String,
+    },
+    
+    /// Docker 的凭据助手，把 `docker login` 的凭据保存在密码库中（链接为 docker-credential-passman 后由 Docker 启动）
+    DockerCredential {
+        /// Docker 传入的操作
+        #[arg(value_enum, value_parser = i18n::enum_parser::<docker_credential::Action>())]
+        action: docker_credential::Action,
+    },
+    
+    /// Ansible Vault 的密码客户端，按 vault ID 输出保存在密码库中的 Vault 密码（链接为 passman-vault-client 后用作 Ansible 的密码文件）
+    AnsibleVaultClient {
+        /// Vault ID，由 Ansible 传入
+        #[arg(long, value_name = "ID", default_value = ansible_vault::DEFAULT_ID)]
+        vault_id: String,
+        
+        /// 设置此 vault ID 的密码（在终端上输入两次），而不是输出它
+        #[arg(long)]
+        set: bool,
+    },
+    
+    /// 在 D-Bus 会话总线上提供 Secret Service，让 NetworkManager、git-credential-libsecret 等程序在密码库中保存和读取密码（Linux）
+    SecretService {
+        /// 保存这些程序的条目的文件夹
+        #[arg(long, value_name = "FOLDER", default_value = "secret-service")]
+        folder: String,
+    },
+    
+    /// 在 HashiCorp Vault 的 KV 机密引擎和密码库之间拉取或推送机密
+    HashicorpVault {
+        #[command(subcommand)]
+        action: HashicorpAction,
+    },
+    
+    /// 导入 SSH 私钥，或把密码库中的私钥加载到 ssh-agent
+    Ssh {
+        #[command(subcommand)]
+        action: SshAction,
+    },
+    
+    /// WiFi 网络条目的连接二维码
+    Wifi {
+        #[command(subcommand)]
+        action: WifiAction,
+    },
+    
+    /// 为 Chrome、Chromium、Brave 和 Firefox 注册本地消息主机，让浏览器扩展查询当前网站的账号
+    InstallBrowserHost {
+        /// 允许连接的浏览器扩展的 ID（可多次指定）
+        #[arg(long = "extension-id", value_name = "ID", required_unless_present = "uninstall")]
+        extension_ids: Vec<String>,
+        
+        /// 只为这个浏览器注册（可多次指定，默认为本机已安装的所有浏览器）
+        #[arg(long, value_enum, value_parser = i18n::enum_parser::<browser_host::Browser>())]
+        browser: Vec<browser_host::Browser>,
+        
+        /// 删除已注册的主机
+        #[arg(long, conflicts_with = "extension_ids")]
+        uninstall: bool,
+    },
+    
+    /// 运行自托管的同步服务器，为各台设备保存加密后的密码库文件（设备以 passman:// 地址同步）
+    ServeSync {
+        /// 保存文件和设备的目录（默认为数据目录中的 sync-server）
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+        
+        /// 监听的地址和端口
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8750")]
+        listen: String,
+        
+        /// 添加一台设备并显示它的访问令牌，不启动服务器
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["remove_device", "devices"])]
+        add_device: Option<String>,
+        
+        /// 移除一台设备，它的访问令牌随即失效
+        #[arg(long, value_name = "NAME", conflicts_with = "devices")]
+        remove_device: Option<String>,
+        
+        /// 列出可以访问服务器的设备
+        #[arg(long)]
+        devices: bool,
+    },
+    
+    /// 紧急恢复包：生成一次性的恢复代码，忘记主密钥时用它重新设置
+    RecoveryKit {
+        #[command(subcommand)]
+        action: RecoveryAction,
+    },
+    
+    /// 生成随机密码
+    Generate {
+        /// 密码长度 [默认: 20]
+        #[arg(short, long)]
+        length: Option<usize>,
+        
+        /// 不包含大写字母
+        #[arg(long)]
+        no_upper: bool,
+        
+        /// 不包含小写字母
+        #[arg(long)]
+        no_lower: bool,
+        
+        /// 不包含数字
+        #[arg(long)]
+        no_digits: bool,
+        
+        /// 不包含符号
+        #[arg(long)]
+        no_symbols: bool,
+        
+        /// 生成由单词组成的口令
+        #[arg(long, conflicts_with_all = ["length", "no_upper", "no_lower", "no_digits", "no_symbols"])]
+        passphrase: bool,
+        
+        /// 口令单词数 [默认: 8]
+        #[arg(short, long, requires = "passphrase")]
+        words: Option<usize>,
+        
+        /// 口令单词分隔符 [默认: -]
+        #[arg(short, long, requires = "passphrase")]
+        separator: Option<String>,
+        
+        /// 口令单词首字母大写
+        #[arg(long, requires = "passphrase")]
+        capitalize: bool,
+        
+        /// 复制到剪贴板而不是打印
+        #[arg(short, long)]
+        copy: bool,
+    },
+    
+    /// 一次性密码 (TOTP/HOTP)
+    Otp {
+        #[command(subcommand)]
+        action: OtpAction,
     },
+    
+    /// 检查已存储密码的安全问题
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    
+    /// 从其他密码管理器或加密备份导入账号
+    Import {
+        /// 导入文件格式（省略时自动识别 passman 加密备份）
+        #[arg(short, long, value_enum, value_parser = i18n::enum_parser::<ImportFormat>())]
+        format: Option<ImportFormat>,
+        
+        /// 导入文件路径
+        file: PathBuf,
+    },
+    
+    /// 查看或修改配置
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    
+    /// 检查密码库及相关文件的权限
+    Doctor {
+        /// 把权限过宽的文件和目录改为只有当前用户可以访问
+        #[arg(long)]
+        fix: bool,
+    },
+    
+    /// 输出 shell 补全脚本
+    Completions {
+        /// shell 类型
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+    
+    /// 生成 man 手册（未指定 --dir 时输出 passman(1) 到标准输出）
+    Man {
+        /// 把主命令和所有子命令的手册写入该目录
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+    
+    /// 输出补全候选项（由补全脚本调用）
+    #[command(name = "_complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        target: CompleteTarget,
+    },
+    
+    /// 浏览器扩展的本地消息主机（由浏览器启动）
+    #[command(name = "browser-host", hide = true)]
+    BrowserHost {
+        /// 浏览器传入的参数（扩展的来源或清单路径），不使用
+        #[arg(num_args = 0.., allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    
+    /// 在指定秒数后清除剪贴板（由 clipboard.timeout 在后台调用）
+    #[command(name = "clear-clipboard", hide = true)]
+    ClearClipboard {
+        seconds: u64,
+    },
+    
+    /// 导出账号到其他密码管理器或加密备份
+    Export {
+        /// 导出文件格式
+        #[arg(short, long, value_enum, value_parser = i18n::enum_parser::<ExportFormat>(), required_unless_present = "encrypted")]
+        format: Option<ExportFormat>,
+        
+        /// 导出为加密的 passman 备份文件 (.pmbk)
+        #[arg(long, conflicts_with = "format")]
+        encrypted: bool,
+        
+        /// 导出的字段及顺序，以逗号分隔（仅 CSV）
+        #[arg(long, value_delimiter = ',', value_parser = export::CSV_FIELDS)]
+        fields: Vec<String>,
+        
+        /// 导出明文 CSV 时不再确认
+        #[arg(long)]
+        yes_i_know: bool,
+        
+        /// pass 使用的 GPG 接收者，可以重复指定（默认读取目录中的 .gpg-id）
+        #[arg(long)]
+        gpg_id: Vec<String>,
+        
+        /// 只导出带有此标签的账号，重复指定时需要带有全部标签（仅 netrc 和 k8s）
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// Secret 的名称（k8s 格式时必须指定）
+        #[arg(long, required_if_eq("format", "k8s"))]
+        name: Option<String>,
+        
+        /// Secret 所在的命名空间（仅 k8s，默认由 kubectl 决定）
+        #[arg(long)]
+        namespace: Option<String>,
+        
+        /// 用 kubectl apply -f - 把 Secret 应用到集群，不写入文件（仅 k8s）
+        #[arg(long, conflicts_with_all = ["file", "command"])]
+        apply: bool,
+        
+        /// 导出文件路径（pass 格式为 password-store 目录；netrc 和 k8s 格式为 - 时写到标准输出）
+        #[arg(required_unless_present_any = ["command", "apply"])]
+        file: Option<PathBuf>,
+        
+        /// 把导出的内容写入临时文件后运行的命令，参数中的 {} 替换为文件路径，netrc 格式时 NETRC 环境变量也指向它；命令结束后删除文件（仅 netrc 和 k8s）
+        #[arg(last = true, value_name = "COMMAND", conflicts_with = "file")]
+        command: Vec<String>,
+    },
+}
+
+// 补全候选项的类型
+#[derive(Clone, Copy, ValueEnum)]
+enum CompleteTarget {
+    /// 用户名（读取解锁密码库时保存的索引）
+    Usernames,
+}
+
+// 输出格式
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// 表格
+    Table,
+    
+    /// 机器可读的 JSON
+    Json,
+}
+
+// list 的显示方式
+#[derive(Clone, Copy)]
+struct ListOptions<'a> {
+    columns: &'a [String],
+    group_by: Option<GroupBy>,
+    sort: SortBy,
+    reverse: bool,
+    show_passwords: bool,
+    json: bool,
+    // 输出超过一屏时使用分页程序
+    pager: bool,
+}
+
+/// 定位账号的条件，同一用户名有多个账号时可以用 ID 或网站区分
+#[derive(Args)]
+struct EntrySelector {
+    /// 用户名，与 -u 相同
+    #[arg(value_name = "USERNAME", conflicts_with = "username", required_unless_present_any = ["username", "id"])]
+    user: Option<String>,
+    
+    /// 用户名
+    #[arg(short, long)]
+    username: Option<String>,
+    
+    /// 账号 ID（list 或 get 中显示，可以只写开头几位）
+    #[arg(long)]
+    id: Option<String>,
+    
+    /// 网站：网址、备注或文件夹中包含的文字
+    #[arg(long)]
+    site: Option<String>,
+}
+
+/// 更新账号时可以修改的内容
+#[derive(Args)]
+struct EntryChanges {
+    /// 新密码（可选）
+    #[arg(short, long)]
+    password: Option<String>,
+    
+    /// 新备注信息（可选）
+    #[arg(short, long)]
+    notes: Option<String>,
+    
+    /// 新网址（空字符串表示清除）
+    #[arg(long)]
+    url: Option<String>,
+    
+    /// 添加标签，可以重复指定
+    #[arg(short, long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+    
+    /// 移除标签，可以重复指定
+    #[arg(long = "untag", value_name = "TAG")]
+    untags: Vec<String>,
+    
+    /// 移动到文件夹（空字符串表示移出文件夹）
+    #[arg(short, long)]
+    folder: Option<String>,
+    
+    /// 更改条目类型
+    #[arg(long = "type", value_enum, value_parser = i18n::enum_parser::<EntryType>(), value_name = "TYPE")]
+    kind: Option<EntryType>,
+    
+    /// 密码有效期（例如 90d、6m，更换密码后重新计时）或过期日期（YYYY-MM-DD），never 表示取消
+    #[arg(long, value_name = "WHEN")]
+    expires: Option<String>,
+    
+    /// 设置自定义字段，可以重复指定
+    #[arg(long = "field", value_name = "NAME=VALUE", value_parser = parse_field)]
+    fields: Vec<(String, String)>,
+    
+    /// 设置敏感的自定义字段（查看时默认隐藏），可以重复指定
+    #[arg(long = "secret-field", value_name = "NAME=VALUE", value_parser = parse_field)]
+    secret_fields: Vec<(String, String)>,
+    
+    /// 移除自定义字段，可以重复指定
+    #[arg(long = "remove-field", value_name = "NAME")]
+    remove_fields: Vec<String>,
+}
+
+impl EntrySelector {
+    fn selector(&self) -> Selector<'_> {
+        Selector {
+            id: self.id.as_deref(),
+            username: self.user.as_deref().or(self.username.as_deref()),
+            site: self.site.as_deref(),
+            kind: None,
+        }
+    }
+}
+
+/// 定位安全笔记的条件
+#[derive(Args)]
+struct NoteSelector {
+    /// 笔记标题
+    #[arg(required_unless_present = "id")]
+    title: Option<String>,
+    
+    /// 笔记 ID（可以只写开头几位）
+    #[arg(long)]
+    id: Option<String>,
+}
+
+impl NoteSelector {
+    fn selector(&self) -> Selector<'_> {
+        Selector {
+            id: self.id.as_deref(),
+            username: self.title.as_deref(),
+            site: None,
+            kind: Some(EntryKind::Note),
+        }
+    }
+}
+
+// 列表分组方式
+#[derive(Clone, Copy, ValueEnum)]
+enum GroupBy {
+    /// 按标签分组（带有多个标签的账号会出现在每个标签下）
+    Tag,
+    
+    /// 按文件夹分组
+    Folder,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SortBy {
+    /// 按用户名
+    #[value(alias = "name")]
+    Username,
+    
+    /// 按创建时间
+    Created,
+    
+    /// 按最后修改时间
+    #[value(alias = "modified")]
+    Updated,
+    
+    /// 按最后使用时间（查看密码、打开网址或生成一次性密码），从未使用的在最后
+    LastUsed,
+}
+
+// 条目类型
+#[derive(Clone, Copy, ValueEnum)]
+enum EntryType {
+    /// 登录信息
+    Login,
+    
+    /// 银行卡
+    Card,
+    
+    /// 身份信息
+    Identity,
+    
+    /// API 密钥
+    ApiKey,
+    
+    /// 安全笔记
+    Note,
+    
+    /// SSH 私钥
+    SshKey,
+    
+    /// WiFi 网络
+    Wifi,
+}
+
+// 一次性密码的 HMAC 算法
+#[derive(Clone, Copy, ValueEnum)]
+enum OtpHash {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl From<OtpHash> for OtpAlgorithm {
+    fn from(hash: OtpHash) -> Self {
+        match hash {
+            OtpHash::Sha1 => OtpAlgorithm::Sha1,
+            OtpHash::Sha256 => OtpAlgorithm::Sha256,
+            OtpHash::Sha512 => OtpAlgorithm::Sha512,
+        }
+    }
+}
+
+impl From<EntryType> for EntryKind {
+    fn from(kind: EntryType) -> Self {
+        match kind {
+            EntryType::Login => EntryKind::Login,
+            EntryType::Card => EntryKind::Card,
+            EntryType::Identity => EntryKind::Identity,
+            EntryType::ApiKey => EntryKind::ApiKey,
+            EntryType::Note => EntryKind::Note,
+            EntryType::SshKey => EntryKind::SshKey,
+            EntryType::Wifi => EntryKind::Wifi,
+        }
+    }
+}
+
+// 搜索字段
+#[derive(Clone, Copy, ValueEnum)]
+enum SearchField {
+    /// 用户名
+    Username,
+    
+    /// 备注
+    Notes,
+    
+    /// 网址（包括备注中的网址）
+    Url,
+    
+    /// 标签
+    Tags,
+    
+    /// 文件夹
+    Folder,
+}
+
+// 导入文件格式
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportFormat {
+    /// Bitwarden 未加密 JSON 导出
+    Bitwarden,
+    
+    /// Chrome/Firefox 导出的 CSV 密码文件
+    #[value(alias = "chrome", alias = "firefox")]
+    Csv,
+    
+    /// KeePass KDBX 数据库
+    Kdbx,
+    
+    /// passman 加密备份 (.pmbk)
+    Pmbk,
+}
+
+// 合并密码库时账号已存在的处理方式
+#[derive(Clone, Copy, ValueEnum)]
+enum MergeStrategy {
+    /// 跳过，保留当前密码库中的账号
+    Skip,
+    
+    /// 另一个密码库中的账号修改时间较晚时覆盖当前的账号
+    OverwriteNewer,
+    
+    /// 另存为一个新账号，用户名加上序号
+    Rename,
+}
+
+impl From<MergeStrategy> for import::OnConflict {
+    fn from(strategy: MergeStrategy) -> Self {
+        match strategy {
+            MergeStrategy::Skip => import::OnConflict::Skip,
+            MergeStrategy::OverwriteNewer => import::OnConflict::OverwriteNewer,
+            MergeStrategy::Rename => import::OnConflict::Rename,
+        }
+    }
+}
+
+// 导出文件格式
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// KeePass KDBX 4 数据库
+    Kdbx,
+    
+    /// 明文 CSV（包含未加密的密码）
+    Csv,
+    
+    /// pass (password-store) 目录，每个账号一个 GPG 加密文件
+    Pass,
+    
+    /// netrc 文件（权限为 0600），供 curl 等只读取 netrc 的工具使用
+    Netrc,
+    
+    /// Kubernetes Secret 的 YAML 清单（值以 Base64 编码）
+    K8s,
+}
+
+// 配置子命令
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// 查看配置项的值
+    Get {
+        /// 配置项，例如 generator.length
+        key: String,
+    },
+    
+    /// 设置配置项
+    Set {
+        /// 配置项，例如 generator.length
+        key: String,
+        
+        /// 新值
+        value: String,
+    },
+    
+    /// 删除配置项，恢复默认值
+    Unset {
+        /// 配置项
+        key: String,
+    },
+    
+    /// 列出所有配置项及当前值
+    List,
+}
+
+// 一次性密码子命令
+#[derive(Subcommand)]
+enum OtpAction {
+    /// 为账号设置一次性密码密钥
+    Set {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// Base32 编码的密钥
+        #[arg(short, long)]
+        secret: String,
+        
+        /// 使用基于计数器的 HOTP（默认为基于时间的 TOTP）
+        #[arg(long)]
+        hotp: bool,
+        
+        /// Steam 令牌 (Steam Guard)，验证码为 5 个字母和数字
+        #[arg(long, conflicts_with_all = ["hotp", "digits", "period"])]
+        steam: bool,
+        
+        /// HOTP 初始计数器
+        #[arg(long, default_value_t = 0, requires = "hotp")]
+        counter: u64,
+        
+        /// 验证码位数
+        #[arg(short, long, default_value_t = otp::DEFAULT_DIGITS)]
+        digits: u32,
+        
+        /// TOTP 时间步长（秒）
+        #[arg(long, default_value_t = otp::DEFAULT_PERIOD, conflicts_with = "hotp")]
+        period: u64,
+        
+        /// HMAC 哈希算法，需要与服务端一致
+        #[arg(long, value_enum, value_parser = i18n::enum_parser::<OtpHash>(), default_value_t = OtpHash::Sha1, conflicts_with = "steam")]
+        algorithm: OtpHash,
+    },
+    
+    /// 生成验证码
+    Code {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 复制到剪贴板而不是打印
+        #[arg(short, long)]
+        copy: bool,
+        
+        /// 持续显示当前验证码和剩余秒数，按 Ctrl+C 退出（不支持 HOTP）
+        #[arg(short, long, conflicts_with = "copy")]
+        watch: bool,
+    },
+    
+    /// 输出一次性密码的 otpauth:// URI，可以导入其他身份验证器
+    Uri {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 以二维码显示，用手机上的身份验证器扫描即可添加
+        #[arg(long)]
+        qr: bool,
+    },
+    
+    /// 移除账号的一次性密码
+    Remove {
+        #[command(flatten)]
+        entry: EntrySelector,
+    },
+}
+
+// 安全笔记子命令
+#[derive(Subcommand)]
+enum NoteAction {
+    /// 添加安全笔记（未指定 --content 时从标准输入读取内容）
+    Add {
+        /// 笔记标题
+        title: String,
+        
+        /// 笔记内容
+        #[arg(short, long)]
+        content: Option<String>,
+        
+        /// 标签，可以重复指定
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// 文件夹路径，以 / 分隔各级
+        #[arg(short, long)]
+        folder: Option<String>,
+    },
+    
+    /// 查看安全笔记的内容
+    Get {
+        #[command(flatten)]
+        note: NoteSelector,
+        
+        /// 复制到剪贴板而不是打印
+        #[arg(short, long)]
+        copy: bool,
+    },
+    
+    /// 修改安全笔记的内容（未指定 --content 时从标准输入读取内容）
+    Edit {
+        #[command(flatten)]
+        note: NoteSelector,
+        
+        /// 新的笔记内容
+        #[arg(short, long)]
+        content: Option<String>,
+        
+        /// 追加到原有内容之后而不是替换
+        #[arg(short, long)]
+        append: bool,
+    },
+}
+
+// 附件子命令
+#[derive(Subcommand)]
+enum AttachAction {
+    /// 为账号添加附件，同名附件会被替换
+    #[command(allow_missing_positional = true)]
+    Add {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 要添加的文件
+        file: PathBuf,
+        
+        /// 附件名称 [默认: 文件名]
+        #[arg(long)]
+        name: Option<String>,
+    },
+    
+    /// 取出附件
+    #[command(allow_missing_positional = true)]
+    Get {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 附件名称
+        name: String,
+        
+        /// 保存路径（默认为当前目录下的同名文件），- 表示输出到标准输出
+        // 不用 --output 和参数名 output：它们属于全局的输出格式
+        #[arg(short = 'o', id = "path", value_name = "PATH")]
+        output: Option<PathBuf>,
+        
+        /// 覆盖已存在的文件
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// 列出账号的附件
+    List {
+        #[command(flatten)]
+        entry: EntrySelector,
+    },
+    
+    /// 删除附件
+    #[command(allow_missing_positional = true)]
+    Remove {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 附件名称
+        name: String,
+    },
+}
+
+// 密码审计子命令
+#[derive(Subcommand)]
+enum AuditAction {
+    /// 估计每个密码的强度，列出较弱的密码
+    Strength {
+        /// 列出所有账号而不仅是较弱的密码
+        #[arg(long)]
+        all: bool,
+    },
+    
+    /// 查找多个账号共用的相同或近似密码
+    Reuse {
+        /// 只查找完全相同的密码
+        #[arg(long)]
+        exact: bool,
+    },
+    
+    /// 通过 Have I Been Pwned 检查密码是否出现在已知泄露中（只发送摘要的前 5 个字符）
+    Breach {
+        /// 不联网，只使用以前查询时保存的本地缓存
+        #[arg(long)]
+        offline: bool,
+        
+        /// 不联网，使用本地的泄露数据库文件（由 audit build-db 生成的布隆过滤器）
+        #[arg(long, value_name = "FILE", conflicts_with = "offline")]
+        db: Option<PathBuf>,
+    },
+    
+    /// 由 HIBP 下载的 SHA-1 泄露密码列表生成本地泄露数据库
+    #[command(name = "build-db")]
+    BuildDb {
+        /// 允许的误报率
+        #[arg(long, default_value_t = 0.001)]
+        false_positive_rate: f64,
+        
+        /// SHA-1 泄露密码列表（每行 摘要:次数）
+        input: PathBuf,
+        
+        /// 输出的数据库文件
+        // 参数名 output 属于全局的输出格式
+        #[arg(id = "database", value_name = "OUTPUT")]
+        output: PathBuf,
+    },
+}
+
+// TPM 子命令
+#[derive(Subcommand)]
+enum TpmAction {
+    /// 验证主密钥后把派生出的密钥封存到 TPM，并设置解锁用的 PIN
+    Enroll {
+        /// 策略绑定的 PCR（逗号分隔），固件或安全启动设置改变后需要重新封存
+        #[arg(long, default_value = tpm::DEFAULT_PCRS)]
+        pcrs: String,
+    },
+    
+    /// 删除本机为这个密码库封存的密钥，之后只能用主密钥解锁
+    Remove,
+}
+
+// HashiCorp Vault 子命令
+#[derive(Subcommand)]
+enum HashicorpAction {
+    /// 把 Vault 中的机密拉取到密码库，以前拉取过的账号就地更新
+    Pull {
+        #[command(flatten)]
+        server: HashicorpOptions,
+        
+        /// 机密的路径（相对于挂载路径），是目录或以 / 结尾时拉取其中的全部机密
+        path: String,
+        
+        /// 新账号所在的文件夹，机密所在的目录成为它的子文件夹
+        #[arg(long, value_name = "FOLDER", default_value = "hashicorp-vault")]
+        folder: String,
+    },
+    
+    /// 把账号推送到 Vault，从 Vault 拉取的账号写回原来的路径
+    Push {
+        #[command(flatten)]
+        server: HashicorpOptions,
+        
+        /// 只推送带有此标签的账号，重复指定时需要带有全部标签
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// 只推送此文件夹（包括子文件夹）中的账号
+        #[arg(short, long)]
+        folder: Option<String>,
+        
+        /// 不是从 Vault 拉取的账号推送到此路径下，以用户名为机密名称（默认跳过这些账号）
+        #[arg(long, value_name = "PATH")]
+        path: Option<String>,
+    },
+}
+
+// SSH 子命令
+#[derive(Subcommand)]
+enum SshAction {
+    /// 把私钥文件导入为 SSH 密钥条目，旁边同名的 .pub 文件作为公钥
+    Import {
+        /// 私钥文件，例如 ~/.ssh/id_ed25519
+        file: PathBuf,
+        
+        /// 条目名称（默认为文件名）
+        #[arg(short, long)]
+        username: Option<String>,
+        
+        /// 文件夹路径，以 / 分隔各级
+        #[arg(short, long)]
+        folder: Option<String>,
+        
+        /// 标签，可以重复指定
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        
+        /// 导入后覆盖并删除私钥文件
+        #[arg(long)]
+        remove: bool,
+    },
+    
+    /// 把条目中的私钥加载到运行中的 ssh-agent，私钥不写到磁盘
+    Add {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 私钥在 ssh-agent 中保留的时长，例如 3600、30m、8h（写法同 ssh-add -t）
+        #[arg(short = 't', long, value_name = "LIFE")]
+        lifetime: Option<String>,
+        
+        /// 每次使用私钥时都要求确认（同 ssh-add -c，需要 ssh-askpass）
+        #[arg(short, long)]
+        confirm: bool,
+    },
+}
+
+// WiFi 子命令
+#[derive(Subcommand)]
+enum WifiAction {
+    /// 在终端中显示连接二维码，手机扫描后即可加入网络
+    Qr {
+        #[command(flatten)]
+        entry: EntrySelector,
+        
+        /// 只输出二维码的内容（WIFI:...），不显示二维码
+        #[arg(long)]
+        text: bool,
+    },
+}
+
+// 连接 HashiCorp Vault 的参数
+#[derive(Args)]
+struct HashicorpOptions {
+    /// Vault 服务器的地址，例如 https://vault.example.com:8200（默认为 VAULT_ADDR 环境变量）
+    #[arg(long, value_name = "URL")]
+    addr: Option<String>,
+    
+    /// KV 机密引擎的挂载路径
+    #[arg(long, value_name = "PATH", default_value = "secret")]
+    mount: String,
+    
+    /// KV 机密引擎的版本
+    #[arg(long, value_name = "VERSION", default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=2))]
+    kv_version: u8,
+    
+    /// 令牌所在的账号，取它的密码（默认使用 VAULT_TOKEN 环境变量或 vault login 保存的 ~/.vault-token）
+    #[arg(long, value_name = "ENTRY", conflicts_with = "approle")]
+    token: Option<String>,
+    
+    /// 以 AppRole 登录，取此账号的用户名为 role_id、密码为 secret_id
+    #[arg(long, value_name = "ENTRY")]
+    approle: Option<String>,
+    
+    /// AppRole 认证方法的挂载路径
+    #[arg(long, value_name = "PATH", default_value = "approle")]
+    approle_mount: String,
+}
+
+// 胁迫密码子命令
+#[derive(Subcommand)]
+enum DuressAction {
+    /// 验证主密钥后设置胁迫密码，并创建它打开的诱饵密码库
+    Enable {
+        /// 用真实登录条目的用户名、网址、标签和文件夹（配上随机生成的密码）填充诱饵密码库
+        #[arg(long)]
+        seed: bool,
+    },
+    
+    /// 验证主密钥后删除胁迫密码和诱饵密码库
+    Disable,
+}
+
+// 紧急恢复包子命令
+#[derive(Subcommand)]
+enum RecoveryAction {
+    /// 验证主密钥后生成新的恢复代码，并输出可以打印的恢复包（Markdown）
+    Create {
+        /// 恢复包的保存路径，不指定时输出到标准输出
+        file: Option<PathBuf>,
+    },
+    
+    /// 忘记主密钥时输入恢复代码，为密码库设置新的主密钥
+    Restore,
+}
+
+// 旧版本在当前目录中使用的密码库文件
+const LEGACY_VAULT: &str = ".passman_data.json";
+// 隐藏的敏感字段显示为
+const SECRET_MASK: &str = "••••••";
+// 主密钥错误时最多尝试的次数
+const MASTER_KEY_ATTEMPTS: u32 = 3;
+
+// 打开密码库所需的设置
+struct VaultConfig {
+    path: PathBuf,
+    // 未指定 --vault 也未配置 vault，使用平台数据目录中的默认密码库
+    is_default: bool,
+    // 新建或升级密码库时使用的密钥派生参数；配置有误时只在用到时报告，config 命令仍然可以修正
+    kdf: Result<KdfParams, String>,
+    // 新建或重新加密密码库时使用的加密算法，未配置时新建的密码库使用默认算法，
+    // 重新加密时保持原有算法
+    cipher: Option<Cipher>,
+    // 解锁以 age 接收者加密的密码库的身份文件；指定了身份文件时新建的密码库以这些身份
+    // 对应的接收者加密
+    identities: Vec<PathBuf>,
+    // 与主密钥一起派生密钥的密钥文件
+    keyfile: Option<PathBuf>,
+    // FIDO2 安全密钥的设备路径，None 时使用第一个连接的设备
+    fido2_device: Option<String>,
+    // 解锁会话在系统钥匙串中保留的分钟数，0 表示不缓存
+    session_timeout: u64,
+    // 是否保存补全用的用户名索引
+    index_names: bool,
+}
+
+impl VaultConfig {
+    fn kdf(&self) -> Result<KdfParams, AppError> {
+        self.kdf.clone().map_err(AppError::from)
+    }
+}
+
+// 命令行参数；以凭据助手的程序名称运行时在参数前加上对应的子命令
+fn arguments() -> Vec<std::ffi::OsString> {
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let name = args.first().and_then(|program| Path::new(program).file_stem()).map(|name| name.to_string_lossy().into_owned());
+    let subcommand = match name.as_deref() {
+        Some(docker_credential::PROGRAM) => "docker-credential",
+        Some(ansible_vault::PROGRAM) => "ansible-vault-client",
+        _ => return args,
+    };
+    args.insert(1, subcommand.into());
+    args
+}
+
+fn main() {
+    ssh::answer_askpass();
+    // 先选择界面语言，帮助信息和命令行错误才能使用对应的语言
+    let config = Config::load();
+    i18n::init(config.as_ref().ok());
+    let cli = Cli::from_arg_matches(&cli_command().get_matches_from(arguments())).unwrap_or_else(|e| e.exit());
+    
+    if let Err(e) = run(cli, config) {
+        eprintln!("{} {}", t!("错误:"), i18n::error_message(e.as_ref()));
+        std::process::exit(1);
+    }
+}
+
+// 翻译为当前语言的命令行定义
+fn cli_command() -> clap::Command {
+    i18n::localize(Cli::command())
+}
+
+fn run(cli: Cli, config: Result<Config, AppError>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = config?;
+    color::init(cli.color, &config);
+    let kdf = config.kdf_params();
+    let cipher = config.get_str("cipher").and_then(Cipher::from_name);
+    let index_names = config.get_bool("completion.usernames").unwrap_or(true);
+    let identities = match cli.identities.is_empty() {
+        true => config.get_path("age.identity").into_iter().collect(),
+        false => cli.identities.clone(),
+    };
+    let keyfile = cli.keyfile.clone();
+    let fido2_device = cli.fido2_device.clone().or(config.get_str("fido2.device").map(String::from));
+    let session_timeout = config.get_int("session.timeout").map_or(0, |minutes| minutes.max(0) as u64);
+    let vault_config = match cli.vault.as_deref().or(config.get_str("vault")) {
+        Some(vault) => VaultConfig {
+            path: config.resolve_vault(vault)?,
+            is_default: false,
+            kdf,
+            cipher,
+            identities,
+            keyfile,
+            fido2_device,
+            session_timeout,
+            index_names,
+        },
+        None => VaultConfig {
+            path: config::default_vault_path()?,
+            is_default: true,
+            kdf,
+            cipher,
+            identities,
+            keyfile,
+            fido2_device,
+            session_timeout,
+            index_names,
+        },
+    };
+    
+    let json = cli.output == OutputFormat::Json;
+    
+    // 根据子命令执行相应操作
+    match &cli.command {
+        Commands::Add { username, kind, password, generate, notes, url, tags, folder, expires, fields, secret_fields, from } => {
+            if let Some(path) = from {
+                return add_from_file(&vault_config, path);
+            }
+            
+            let kind = kind.map(EntryKind::from).unwrap_or_default();
+            match username {
+                None if !io::stdin().is_terminal() => {
+                    return Err(AppError::from(t!("缺少 --username（在终端中省略 --username 可以逐项输入）")).into());
+                }
+                Some(_) if kind.is_login() && password.is_none() && generate.is_none() => {
+                    return Err(AppError::from(t!("登录类型的账号需要 --password 或 --generate")).into());
+                }
+                Some(_) if kind.is_login() && notes.is_none() => {
+                    return Err(AppError::from(t!("登录类型的账号需要 --notes")).into());
+                }
+                _ => {}
+            }
+            
+            let mut vault = open_vault(&vault_config)?;
+            let password = match (password, generate) {
+                (Some(password), _) => Some(password.clone()),
+                (None, Some(length)) => {
+                    let password = generator::generate_password(&password_options(&config, Some(*length)))?;
+                    println!("{}", t!("生成的密码: {}", password));
+                    Some(password)
+                }
+                (None, None) => None,
+            };
+            let mut account = Account::new(
+                username.as_deref().unwrap_or(""),
+                password.as_deref().unwrap_or(""),
+                notes.as_deref().unwrap_or(""),
+            );
+            account.kind = kind;
+            account.add_tags(tags);
+            account.set_folder(folder.as_deref().unwrap_or(""));
+            account.set_url(url.as_deref().unwrap_or(""));
+            // 未指定用户名时逐项询问命令行中没有给出的内容
+            if username.is_none() {
+                let given = PromptedFields { password: password.is_some(), notes: notes.is_some(), url: url.is_some(), tags: !tags.is_empty() };
+                prompt_entry(&mut account, &given, &config)?;
+            }
+            if let Some(expires) = expires {
+                set_expiry(&mut account, expires)?;
+            }
+            set_fields(&mut account, fields, secret_fields)?;
+            prompt_kind_fields(&mut account)?;
+            let username = account.username.clone();
+            let id = add_account(&mut vault, account)?;
+            println!("{} (ID: {})", success(t!("账号添加成功"), &username), id);
+        }
+        
+        Commands::Delete { entry, force } => {
+            let mut vault = open_vault(&vault_config)?;
+            match delete_account(&mut vault, &entry.selector(), *force)? {
+                Some(account) => println!("{}", success(t!("账号删除成功"), &describe_account(&account))),
+                None => println!("{}", t!("已取消删除")),
+            }
+        }
+        
+        Commands::Update { entry, changes } => {
+            let mut vault = open_vault(&vault_config)?;
+            let username = update_account(&mut vault, &entry.selector(), changes)?;
+            println!("{}", success(t!("账号更新成功"), &username));
+        }
+        
+        Commands::Edit { username, id, site } => {
+            let mut vault = open_vault(&vault_config)?;
+            let selector = Selector { id: id.as_deref(), username: username.as_deref(), site: site.as_deref(), kind: None };
+            edit_account(&mut vault, &selector)?;
+        }
+        
+        Commands::Rename { old, new, id, site } => {
+            let new = new.trim();
+            if new.is_empty() {
+                return Err(AppError::from(t!("用户名不能为空")).into());
+            }
+            let mut vault = open_vault(&vault_config)?;
+            let selector = Selector { id: id.as_deref(), username: Some(old), site: site.as_deref(), kind: None };
+            let id = vault.select(&selector)?.id.clone();
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            account.username = new.to_string();
+            account.touch();
+            save_vault(&mut vault)?;
+            println!("{}", t!("已重命名: {} -> {}", old, new));
+        }
+        
+        Commands::Clone { username, new_name, id, site, generate } => {
+            let mut vault = open_vault(&vault_config)?;
+            let selector = Selector { id: id.as_deref(), username: Some(username), site: site.as_deref(), kind: None };
+            let mut account = vault.select(&selector)?.duplicate(new_name.trim());
+            if let Some(length) = generate {
+                account.password = generator::generate_password(&password_options(&config, Some(*length)))?;
+                println!("{}", t!("生成的密码: {}", account.password));
+            }
+            let id = add_account(&mut vault, account)?;
+            println!("{} (ID: {})", success(t!("账号添加成功"), new_name.trim()), id);
+        }
+        
+        Commands::List { tags, folder, kind, pattern, group_by, sort, reverse, show_passwords, columns } => {
+            let query = pattern.as_deref().map(|pattern| search::Query::new(pattern, search::Mode::Glob)).transpose()?;
+            let columns = match columns {
+                Some(columns) => config::parse_columns(columns)?,
+                None => list_columns(&config),
+            };
+            let vault = open_vault(&vault_config)?;
+            let filter = AccountFilter { tags, folder: folder.as_deref(), kind: kind.map(EntryKind::from), query: query.as_ref() };
+            let options = ListOptions {
+                columns: &columns,
+                group_by: *group_by,
+                sort: *sort,
+                reverse: *reverse,
+                show_passwords: *show_passwords,
+                json,
+                pager: !cli.no_pager,
+            };
+            list_accounts(&vault, &filter, &options)?;
+        }
+        
+        Commands::Search { query, fuzzy, regex, fields, show_passwords } => {
+            let mode = match (fuzzy, regex) {
+                (true, _) => search::Mode::Fuzzy,
+                (_, true) => search::Mode::Regex,
+                _ => search::Mode::Substring,
+            };
+            let fields: Vec<search::Field> = if fields.is_empty() {
+                search::ALL_FIELDS.to_vec()
+            } else {
+                fields.iter().map(|field| match field {
+                    SearchField::Username => search::Field::Username,
+                    SearchField::Notes => search::Field::Notes,
+                    SearchField::Url => search::Field::Url,
+                    SearchField::Tags => search::Field::Tags,
+                    SearchField::Folder => search::Field::Folder,
+                }).collect()
+            };
+            
+            // 先检查查询是否有效，再提示输入主密钥
+            let query = search::Query::new(query, mode)?;
+            let vault = open_vault(&vault_config)?;
+            let results = search::search(vault.list(), &query, &fields);
+            if json {
+                let entries: Vec<EntryJson> = results.iter().map(|account| EntryJson::summary(account, *show_passwords)).collect();
+                output::print(&entries)?;
+            } else if results.is_empty() {
+                println!("{}", t!("没有找到匹配的账号"));
+            } else {
+                let text = format!("{}\n{}", t!("找到 {} 个账号", results.len()), accounts_table(&results, &list_columns(&config), *show_passwords));
+                pager::show(&text, !cli.no_pager)?;
+            }
+        }
+        
+        Commands::Get { entry, reveal, show_passwords, field, qr } => {
+            let mut vault = open_vault(&vault_config)?;
+            let selector = entry.selector();
+            let account = vault.select(&selector)?;
+            let id = account.id.clone();
+            match field {
+                Some(field) if *qr => print_qr(entry_field(account, field, *reveal)?)?,
+                None if *qr => print_qr(&account.password)?,
+                Some(field) => println!("{}", entry_field(account, field, *reveal)?),
+                None => get_account(&vault, &selector, *reveal, *show_passwords, json)?,
+            }
+            record_use(&mut vault, &id);
+        }
+        
+        Commands::Show { username, id, site, password_only, no_newline } => {
+            let mut vault = open_vault(&vault_config)?;
+            let selector = Selector { id: id.as_deref(), username: username.as_deref(), site: site.as_deref(), kind: None };
+            let account = vault.select(&selector)?;
+            let id = account.id.clone();
+            if !*password_only {
+                get_account(&vault, &selector, false, false, json)?;
+            } else if *no_newline {
+                print!("{}", account.password);
+                io::stdout().flush()?;
+            } else {
+                println!("{}", account.password);
+            }
+            record_use(&mut vault, &id);
+        }
+        
+        Commands::Run { envs, command } => {
+            let mut vault = open_vault(&vault_config)?;
+            let mut values = Vec::new();
+            let mut used: Vec<String> = Vec::new();
+            for binding in envs {
+                let account = resolve_entry(&vault, &binding.entry)?;
+                // 命令行中点名的字段，敏感的自定义字段也不需要 --reveal
+                values.push((binding.name.clone(), entry_field(account, &binding.field, true)?.to_string()));
+                if !used.contains(&account.id) {
+                    used.push(account.id.clone());
+                }
+            }
+            for id in &used {
+                record_use(&mut vault, id);
+            }
+            drop(vault);
+            run_with_env(command, values)?;
+        }
+        
+        Commands::Env { template, mapping } => {
+            let path = template.as_ref().or(mapping.as_ref()).expect("clap 保证指定了模板或映射文件");
+            let text = fs::read_to_string(path).map_err(|e| AppError::from(t!("无法读取文件 {}: {}", path.display(), e)))?;
+            let mut vault = open_vault(&vault_config)?;
+            let mut used: Vec<String> = Vec::new();
+            let output = if template.is_some() {
+                dotenv::render(&text, |reference, _| {
+                    let (account, field) = resolve_reference(&vault, reference)?;
+                    if !used.contains(&account.id) {
+                        used.push(account.id.clone());
+                    }
+                    entry_field(account, &field, true).map(str::to_string)
+                })?
+            } else {
+                let mut output = String::new();
+                for (line, item) in dotenv::mapping_lines(&text) {
+                    let binding = parse_env_binding(item).map_err(|e| AppError::from(t!("{} 第 {} 行: {}", path.display(), line, e)))?;
+                    let account = resolve_entry(&vault, &binding.entry)?;
+                    output.push_str(&dotenv::assignment(&binding.name, entry_field(account, &binding.field, true)?));
+                    output.push('\n');
+                    if !used.contains(&account.id) {
+                        used.push(account.id.clone());
+                    }
+                }
+                output
+            };
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(output.as_bytes())?;
+            stdout.flush()?;
+            for id in &used {
+                record_use(&mut vault, id);
+            }
+        }
+        
+        Commands::SopsExec { file, env, input_type, command } => {
+            let text = fs::read_to_string(file).map_err(|e| AppError::from(t!("无法读取文件 {}: {}", file.display(), e)))?;
+            let format = input_type.unwrap_or_else(|| sops::Format::detect(file));
+            let mut vault = open_vault(&vault_config)?;
+            let mut used: Vec<String> = Vec::new();
+            let content = dotenv::render(&text, |reference, line| {
+                let (account, field) = resolve_reference(&vault, reference)?;
+                if !used.contains(&account.id) {
+                    used.push(account.id.clone());
+                }
+                sops::escape(format, line, entry_field(account, &field, true)?)
+                    .ok_or_else(|| AppError::from(t!("{} 的值含有单引号或换行，不能放在单引号中，请改用双引号", reference)))
+            })?;
+            for id in &used {
+                record_use(&mut vault, id);
+            }
+            drop(vault);
+            if *env {
+                run_with_env(command, sops::variables(format, &content)?)?;
+            } else {
+                let mut command = command.clone();
+                if !command[1..].iter().any(|arg| arg.contains("{}")) {
+                    command.push("{}".to_string());
+                }
+                let extension = file.extension().and_then(|extension| extension.to_str()).unwrap_or(format.extension());
+                write_plaintext(content.as_bytes(), used.len(), None, &command, extension)?;
+            }
+        }
+        
+        Commands::Open { username, id, site, copy } => {
+            let mut vault = open_vault(&vault_config)?;
+            let selector = Selector { id: id.as_deref(), username: username.as_deref(), site: site.as_deref(), kind: None };
+            let account = vault.select(&selector)?;
+            let url = account.url.as_deref().ok_or_else(|| AppError::from(t!("该账号没有设置网址")))?;
+            if *copy {
+                output_secret(&account.password, true, &config)?;
+            }
+            browser::open(url)?;
+            println!("{}", t!("已打开: {}", url));
+            let id = account.id.clone();
+            record_use(&mut vault, &id);
+        }
+        
+        Commands::History { username, id, site, restore } => {
+            let mut vault = open_vault(&vault_config)?;
+            let selector = Selector { id: id.as_deref(), username: username.as_deref(), site: site.as_deref(), kind: None };
+            let id = vault.select(&selector)?.id.clone();
+            match restore {
+                Some(n) => {
+                    let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+                    account.restore_password(n.checked_sub(1).ok_or_else(|| AppError::from(t!("序号从 1 开始")))?)?;
+                    let username = account.username.clone();
+                    save_vault(&mut vault)?;
+                    println!("{}", t!("已恢复第 {} 个历史密码: {}", n, username));
+                }
+                None => show_history(vault.get(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?),
+            }
+        }
+        
+        Commands::Expiring { within } => {
+            let within = timestamp::parse_duration(within)
+                .ok_or_else(|| AppError::from(t!("无法识别的时长: {}（例如 14d、4w）", within)))?;
+            let vault = open_vault(&vault_config)?;
+            list_expiring(&vault, within);
+        }
+        
+        Commands::Note { action } => {
+            let mut vault = open_vault(&vault_config)?;
+            note_command(&mut vault, action, &config)?;
+        }
+        
+        Commands::Attach { action } => {
+            attach_command(&vault_config, action)?;
+        }
+        
+        Commands::Undo => {
+            let mut vault = open_vault(&vault_config)?;
+            match vault.undo()? {
+                Some((at, undone)) => {
+                    println!("{}", t!("已撤销 {} 的修改:", format_time(at)));
+                    for change in &undone {
+                        match change {
+                            Undone::Added(account) => println!("{}", t!("  删除了添加的账号 {}", describe_account(account))),
+                            Undone::Updated(account) => println!("{}", t!("  恢复了账号 {} 修改前的内容", describe_account(account))),
+                            Undone::Deleted(account) => println!("{}", t!("  恢复了被删除的账号 {}", describe_account(account))),
+                        }
+                    }
+                }
+                None => println!("{}", t!("没有可以撤销的修改")),
+            }
+            autocommit(vault.path(), t!("passman: 撤销上次修改"));
+        }
+        
+        Commands::Diff { other, backup, show_passwords } => {
+            let vault = open_vault(&vault_config)?;
+            let (before, label) = match (other, backup) {
+                (_, Some(n)) => {
+                    let (at, accounts) = vault.revision(*n).ok_or_else(|| AppError::from(t!("修改记录中没有 {} 次之前的保存", n)))?;
+                    (accounts, t!("{} 次保存之前（{}）", n, format_time(at)))
+                }
+                (Some(path), None) => (read_other_accounts(&vault, path)?, path.display().to_string()),
+                (None, None) => unreachable!("clap 保证 other 与 --backup 至少指定一个"),
+            };
+            print_diff(&before, vault.list(), &label, *show_passwords);
+        }
+        
+        Commands::Merge { other, on_conflict } => {
+            let mut vault = open_vault(&vault_config)?;
+            let accounts = read_other_accounts(&vault, other)?;
+            let report = import::import_vault(&mut vault, accounts, (*on_conflict).into());
+            save_vault(&mut vault)?;
+            print_import_report(&report);
+        }
+        
+        Commands::Lock { all } => {
+            if *all {
+                session::clear_all();
+                agent::stop_all();
+                println!("{}", t!("已清除所有密码库的解锁会话并停止所有 agent"));
+            } else {
+                let cleared = session::clear(&vault_config.path);
+                let stopped = agent::stop(&vault_config.path);
+                if cleared {
+                    println!("{}", t!("已清除解锁会话"));
+                }
+                if stopped {
+                    println!("{}", t!("已停止 agent"));
+                }
+                if !cleared && !stopped {
+                    println!("{}", t!("没有缓存的解锁会话或运行中的 agent"));
+                }
+            }
+        }
+        
+        Commands::Agent { foreground, timeout, idle, stop } => {
+            if *stop {
+                match agent::stop(&vault_config.path) {
+                    true => println!("{}", t!("已停止 agent")),
+                    false => println!("{}", t!("这个密码库没有运行中的 agent")),
+                }
+                return Ok(());
+            }
+            require_vault(&vault_config)?;
+            let minutes = timeout.or_else(|| config.get_int("agent.timeout").map(|minutes| minutes.max(0) as u64));
+            let timeout = minutes.and_then(autolock::minutes_to_duration);
+            let mut autolock = autolock::Settings::from_config(&config);
+            if let Some(idle) = idle {
+                autolock.idle = autolock::minutes_to_duration(*idle);
+            }
+            let credentials = load_credentials(&vault_config)?;
+            agent::run(&vault_config.path, *foreground, timeout, autolock, || {
+                prompt_unlock(&vault_config, &credentials).map(|(vault, _)| vault.into_backend())
+            })?;
+        }
+        
+        Commands::Tui => {
+            let autolock = autolock::Settings::from_config(&config).start();
+            let mut credentials = load_credentials(&vault_config)?;
+            loop {
+                let (mut vault, _) = prompt_unlock(&vault_config, &credentials)?;
+                autolock.reset();
+                let reason = match tui::run(&mut vault, &autolock)? {
+                    tui::Exit::Quit => break,
+                    tui::Exit::Locked(reason) => reason,
+                };
+                drop(vault);
+                match reason {
+                    autolock::Reason::Idle => println!("{}", t!("空闲时间过长，已自动锁定")),
+                    autolock::Reason::Suspend => println!("{}", t!("系统休眠，已自动锁定")),
+                }
+                // 重新解锁时不使用 agent 和缓存的解锁会话
+                credentials.session = None;
+                credentials.agent = None;
+            }
+        }
+        
+        Commands::ChangeMaster { new_keyfile, no_keyfile, yubikey_slot, no_yubikey } => {
+            require_vault(&vault_config)?;
+            let credentials = load_credentials(&vault_config)?;
+            let new_keyfile = new_keyfile.as_deref().map(Keyfile::read).transpose()?;
+            let (mut vault, _) = prompt_unlock(&vault_config, &credentials)?;
+            let new_master_key = read_new_password(t!("请输入新的主密钥: "), t!("请再次输入新的主密钥: "))?;
+            if let Some(cipher) = vault_config.cipher {
+                vault.set_cipher(cipher);
+            }
+            let keyfile = match new_keyfile {
+                Some(_) => new_keyfile.as_ref(),
+                None if *no_keyfile => None,
+                None => credentials.keyfile.as_ref(),
+            };
+            let slot = match yubikey_slot {
+                Some(slot) => Some(*slot),
+                None if *no_yubikey => None,
+                None => yubikey_slot_of(&vault.key_header()),
+            };
+            let mut kdf = vault_config.kdf()?;
+            kdf.yubikey = slot.map(YubikeyParams::generate);
+            wait_for_yubikey(&kdf);
+            vault.change_backend(Box::new(PasswordBackend::with_keyfile(&new_master_key, keyfile, kdf)?))?;
+            println!("{}", t!("主密钥已更换"));
+            if keyfile.is_some() {
+                println!("{}", t!("之后解锁需要同时用 --keyfile 指定密钥文件"));
+            }
+            if let Some(slot) = slot {
+                println!("{}", t!("之后解锁需要插入同一个 YubiKey（槽位 {}）", slot));
+            }
+            forget_old_key(&vault);
+            autocommit(vault.path(), t!("passman: 更换主密钥"));
+        }
+        
+        Commands::Rekey { recipients, recipients_files, gpg_id, fido2 } => {
+            require_vault(&vault_config)?;
+            let recipients = read_recipients(recipients, recipients_files)?;
+            let mut credentials = load_credentials(&vault_config)?;
+            // 继续用主密钥加密时需要主密钥
+            credentials.require_master_key();
+            let identities = &credentials.identities;
+            // 以免重新加密后无法再用当前的身份解锁
+            if !recipients.is_empty() && !identities.is_empty() && !identities.iter().any(|identity| recipients.contains(&identity.recipient())) {
+                return Err(AppError::from(t!("新的接收者中没有身份文件对应的公钥，重新加密后将无法再用它解锁（请同时指定自己的公钥）")).into());
+            }
+            let fido2_device = fido2.then(|| resolve_fido2_device(&vault_config)).transpose()?;
+            let (mut vault, master_key) = prompt_unlock(&vault_config, &credentials)?;
+            
+            if let Some(cipher) = vault_config.cipher {
+                vault.set_cipher(cipher);
+            }
+            let header = vault.key_header();
+            // 继续用主密钥加密时沿用原有的 YubiKey 槽位，换一个新的挑战
+            let mut kdf = vault_config.kdf()?;
+            kdf.yubikey = yubikey_slot_of(&header).map(YubikeyParams::generate);
+            let uses_fido2 = *fido2 || (master_key.is_none() && header.fido2.is_some());
+            if uses_fido2 {
+                wait_for_fido2();
+            } else if master_key.is_some() && recipients.is_empty() && gpg_id.is_empty() {
+                wait_for_yubikey(&kdf);
+            }
+            eprint!("{}", t!("正在派生新密钥并重新加密..."));
+            let backend: Box<dyn CryptoBackend> = match (&master_key, &header.gpg, &header.fido2) {
+                _ if !recipients.is_empty() => Box::new(AgeBackend::new(&recipients)?),
+                _ if !gpg_id.is_empty() => Box::new(GpgBackend::new(gpg_id)?),
+                _ if let Some(device) = &fido2_device => Box::new(Fido2Backend::register(device)?),
+                (Some(master_key), _, _) => Box::new(PasswordBackend::with_keyfile(master_key, credentials.keyfile.as_ref(), kdf)?),
+                // 不是用主密钥解锁的密码库沿用原有的接收者或凭据
+                (None, Some(gpg), _) => Box::new(GpgBackend::new(&gpg.recipients)?),
+                (None, None, Some(fido2)) => {
+                    Box::new(Fido2Backend::with_credential(&resolve_fido2_device(&vault_config)?, &fido2.rp_id, &fido2.credential)?)
+                }
+                (None, None, None) => Box::new(AgeBackend::new(&age::recipients(&header)?)?),
+            };
+            vault.change_backend(backend)?;
+            eprintln!("{}", t!(" 完成"));
+            
+            vault.verify(|done, total| {
+                eprint!("\r{}", t!("正在校验: {}/{}", done, total));
+                let _ = io::stderr().flush();
+            })?;
+            if !vault.list().is_empty() {
+                eprintln!();
+            }
+            println!("{}", t!("已重新加密 {} 个账号", vault.list().len()));
+            if !recipients.is_empty() {
+                let count = vault.key_header().recipients.map_or(0, |stanzas| stanzas.len());
+                println!("{}", t!("密码库已改为以 {} 个 age 接收者加密，之后用 --identity 或配置项 age.identity 指定对应的私钥解锁", count));
+            } else if !gpg_id.is_empty() {
+                println!("{}", t!("密码库已改为以 GPG 公钥加密，之后由 gpg 解锁"));
+            } else if *fido2 {
+                println!("{}", t!("密码库已改为由 FIDO2 安全密钥解锁，之后解锁需要插入它、输入 PIN 并触摸"));
+            }
+            forget_old_key(&vault);
+            autocommit(vault.path(), t!("passman: 重新加密密码库"));
+        }
+        
+        Commands::Tpm { action } => match action {
+            TpmAction::Enroll { pcrs } => {
+                require_vault(&vault_config)?;
+                tpm::pcr_selection(pcrs)?;
+                let mut credentials = load_credentials(&vault_config)?;
+                // 封存的是由主密钥派生的密钥，需要主密钥
+                credentials.require_master_key();
+                let (vault, master_key) = prompt_unlock(&vault_config, &credentials)?;
+                let Some(master_key) = master_key else {
+                    return Err(AppError::from(t!("只有用主密钥加密的密码库可以封存到 TPM")).into());
+                };
+                let header = vault.key_header();
+                if let Some(kdf) = &header.kdf {
+                    wait_for_yubikey(kdf);
+                }
+                let backend = PasswordBackend::unlock_with_keyfile(&master_key, credentials.keyfile.as_ref(), &header, vault_config.kdf()?)?;
+                let pin = read_new_password(t!("请设置 TPM PIN: "), t!("请再次输入 TPM PIN: "))?;
+                let sealed = tpm::seal(&backend, &pin, pcrs)?;
+                tpm_store::write(vault.path(), &sealed)?;
+                println!("{}", t!("已把密码库的密钥封存到本机 TPM（绑定 PCR {}），之后在这台机器上输入 PIN 即可解锁", pcrs));
+            }
+            TpmAction::Remove => match tpm_store::remove(&vault_config.path) {
+                true => println!("{}", t!("已删除本机 TPM 中为这个密码库封存的密钥")),
+                false => println!("{}", t!("这个密码库没有在本机 TPM 中封存密钥")),
+            },
+        },
+        
+        Commands::Duress { action } => {
+            require_vault(&vault_config)?;
+            let mut credentials = load_credentials(&vault_config)?;
+            credentials.require_master_key();
+            let (vault, master_key) = prompt_unlock(&vault_config, &credentials)?;
+            // 输入的是胁迫密码时打开的是诱饵密码库，与主密钥错误时一样处理
+            if vault.path() != vault_config.path {
+                return Err(AppError::WrongMasterKey.into());
+            }
+            match action {
+                DuressAction::Enable { seed } => {
+                    let Some(master_key) = master_key else {
+                        return Err(AppError::from(t!("只有用主密钥加密的密码库可以设置胁迫密码")).into());
+                    };
+                    if duress::enabled(vault.path()) {
+                        return Err(AppError::from(t!("已经设置了胁迫密码，请先运行 passman duress disable")).into());
+                    }
+                    let password = read_new_password(t!("请设置胁迫密码: "), t!("请再次输入胁迫密码: "))?;
+                    if password == master_key {
+                        return Err(AppError::from(t!("胁迫密码不能与主密钥相同")).into());
+                    }
+                    let mut decoy = duress::create(vault.path(), &password, vault_config.kdf()?)?;
+                    if let Some(cipher) = vault_config.cipher {
+                        decoy.set_cipher(cipher);
+                    }
+                    let count = match seed {
+                        true => duress::seed(&mut decoy, &vault, &password_options(&config, None))?,
+                        false => 0,
+                    };
+                    decoy.save()?;
+                    println!("{}", t!("已设置胁迫密码，诱饵密码库中有 {} 个账号；解锁时输入胁迫密码即打开诱饵密码库", count));
+                }
+                DuressAction::Disable => match duress::remove(vault.path()) {
+                    true => println!("{}", t!("已删除胁迫密码和诱饵密码库")),
+                    false => println!("{}", t!("这个密码库没有设置胁迫密码")),
+                },
+            }
+        }
+        
+        Commands::Sync { init, remote, keep, conceal } => {
+            if *init {
+                require_vault(&vault_config)?;
+                git::init(&vault_config.path, remote.as_deref()).map_err(AppError::from)?;
+                println!("{}", t!("已在 git 仓库中记录密码库，之后每次保存都会自动提交"));
+                return Ok(());
+            }
+            match remote.clone().or_else(|| remote::saved_remote(&vault_config.path)) {
+                Some(remote) => {
+                    let (key, generated) = match remote::saved_key(&vault_config.path, &remote) {
+                        Some(key) => (Some(key), false),
+                        None if *conceal => {
+                            let text = read_password(t!("请输入其他设备上的同步密钥（第一次使用时直接回车生成新的密钥）: "))?;
+                            match text.trim().is_empty() {
+                                true => (Some(remote::SyncKey::generate()), true),
+                                false => (Some(remote::SyncKey::parse(&text).ok_or_else(|| AppError::from(t!("同步密钥格式不正确")))?), false),
+                            }
+                        }
+                        None => (None, false),
+                    };
+                    sync_remote(&vault_config, &remote, key.as_ref(), remote::Options { keep: *keep, ..Default::default() })?;
+                    if let Some(key) = key.filter(|_| generated) {
+                        println!("{}", t!("同步密钥（在其他设备上同步时用 --conceal 输入，请妥善保管）: {}", key.encode()));
+                    }
+                }
+                None if *conceal => return Err(AppError::from(t!("--conceal 仅适用于远程存储")).into()),
+                None if keep.is_some() => return Err(AppError::from(t!("--keep 仅适用于远程存储，git 仓库中的冲突需要手动处理")).into()),
+                None => {
+                    require_vault(&vault_config)?;
+                    // 拉取时会替换密码库文件
+                    let _lock = VaultLock::acquire(&vault_config.path)?;
+                    let remote = git::sync(&vault_config.path).map_err(AppError::from)?;
+                    println!("{}", t!("已与远程仓库 {} 同步", remote));
+                }
+            }
+        }
+        
+        Commands::Push { remote, force, dry_run } => match remote.clone().or_else(|| remote::saved_remote(&vault_config.path)) {
+            Some(remote) => {
+                let key = remote::saved_key(&vault_config.path, &remote);
+                let options = remote::Options { direction: Some(remote::Direction::Push), keep: force.then_some(remote::Keep::Local), dry_run: *dry_run };
+                sync_remote(&vault_config, &remote, key.as_ref(), options)?;
+            }
+            None if *force => return Err(AppError::from(t!("--force 仅适用于远程存储")).into()),
+            None => {
+                require_vault(&vault_config)?;
+                let _lock = VaultLock::acquire(&vault_config.path)?;
+                let remote = git::push(&vault_config.path, *dry_run).map_err(AppError::from)?;
+                match dry_run {
+                    true => println!("{}", t!("可以推送到远程仓库 {}（--dry-run，没有推送）", remote)),
+                    false => println!("{}", t!("已推送到远程仓库 {}", remote)),
+                }
+            }
+        },
+        
+        Commands::Pull { remote, keep, dry_run } => match remote.clone().or_else(|| remote::saved_remote(&vault_config.path)) {
+            Some(remote) => {
+                let key = remote::saved_key(&vault_config.path, &remote);
+                let options = remote::Options { direction: Some(remote::Direction::Pull), keep: *keep, dry_run: *dry_run };
+                sync_remote(&vault_config, &remote, key.as_ref(), options)?;
+            }
+            None if keep.is_some() => return Err(AppError::from(t!("--keep 仅适用于远程存储，git 仓库中的冲突需要手动处理")).into()),
+            None => {
+                require_vault(&vault_config)?;
+                // 拉取时会替换密码库文件
+                let _lock = VaultLock::acquire(&vault_config.path)?;
+                let (remote, count) = git::pull(&vault_config.path, *dry_run).map_err(AppError::from)?;
+                match (count, dry_run) {
+                    (0, _) => println!("{}", t!("远程仓库 {} 中没有新的修改", remote)),
+                    (count, true) => println!("{}", t!("远程仓库 {} 中有 {} 个新的提交（--dry-run，没有拉取）", remote, count)),
+                    (count, false) => println!("{}", t!("已从远程仓库 {} 拉取 {} 个提交", remote, count)),
+                }
+            }
+        },
+        
+        Commands::Serve { listen, new_token } => {
+            require_vault(&vault_config)?;
+            let listener = api_server::bind(listen)?;
+            let token_file = api_server::token_file(*new_token)?;
+            let (vault, _) = prompt_unlock(&vault_config, &load_credentials(&vault_config)?)?;
+            // 诱饵密码库解锁后同样只提供诱饵密码库
+            let path = vault.path().to_path_buf();
+            let autolock = autolock::Settings::from_config(&config);
+            let reason = api_server::run(listener, &path, vault.into_backend(), &token_file, password_options(&config, None), autolock)?;
+            match reason {
+                Some(autolock::Reason::Idle) => println!("{}", t!("空闲时间过长，已自动锁定")),
+                Some(autolock::Reason::Suspend) => println!("{}", t!("系统休眠，已自动锁定")),
+                None => {}
+            }
+        }
+        
+        Commands::GitCredential { action } => {
+            // git 以后可能加入新的操作，不认识的操作按协议忽略
+            if !matches!(action.as_str(), "get" | "store" | "erase") {
+                return Ok(());
+            }
+            let request = git_credential::read_request(io::stdin().lock())?;
+            require_vault(&vault_config)?;
+            let mut vault = open_for_helper(&vault_config)?;
+            match action.as_str() {
+                "get" => {
+                    if let Some(account) = request.find(&vault) {
+                        request.write(&mut io::stdout().lock(), account)?;
+                        let id = account.id.clone();
+                        record_use(&mut vault, &id);
+                    }
+                }
+                "store" => {
+                    let (id, changed) = request.store(&mut vault)?;
+                    if changed {
+                        save_vault(&mut vault)?;
+                        if let Some(account) = vault.get(&id) {
+                            eprintln!("{}", t!("已将 {} 在 {} 的凭据保存到密码库", account.username, request.host));
+                        }
+                    }
+                }
+                _ => {
+                    // 账号中可能还有一次性密码、附件等内容（GitHub 也不接受网站的登录密码），不删除账号，只提示更新
+                    if let Some(account) = request.find(&vault).filter(|account| request.password.as_ref() == Some(&account.password)) {
+                        eprintln!("{}", t!("{} 拒绝了账号 {} 的密码，请用 passman edit 更新（例如改为访问令牌）", request.host, describe_account(account)));
+                    }
+                }
+            }
+        }
+        
+        Commands::DockerCredential { action } => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            require_vault(&vault_config)?;
+            let mut vault = open_for_helper(&vault_config)?;
+            match action {
+                docker_credential::Action::Get => {
+                    let server_url = input.trim();
+                    let Some(account) = docker_credential::find(&vault, server_url) else {
+                        docker_credential::not_found();
+                    };
+                    let credentials = docker_credential::Credentials { server_url: server_url.to_string(), username: account.username.clone(), secret: account.password.clone() };
+                    println!("{}", serde_json::to_string(&credentials)?);
+                    let id = account.id.clone();
+                    record_use(&mut vault, &id);
+                }
+                docker_credential::Action::Store => {
+                    let credentials: docker_credential::Credentials = serde_json::from_str(&input)?;
+                    if docker_credential::store(&mut vault, &credentials)?.1 {
+                        save_vault(&mut vault)?;
+                    }
+                }
+                docker_credential::Action::Erase => {
+                    if docker_credential::erase(&mut vault, input.trim())?.is_none() {
+                        docker_credential::not_found();
+                    }
+                    save_vault(&mut vault)?;
+                }
+                docker_credential::Action::List => println!("{}", serde_json::to_string(&docker_credential::list(&vault))?),
+            }
+        }
+        
+        Commands::AnsibleVaultClient { vault_id, set } => {
+            require_vault(&vault_config)?;
+            let mut vault = open_for_helper(&vault_config)?;
+            if *set {
+                let password = read_new_password(t!("请输入 Vault 密码: "), t!("请再次输入 Vault 密码: "))?;
+                if ansible_vault::set(&mut vault, vault_id, &password)? {
+                    save_vault(&mut vault)?;
+                }
+                eprintln!("{}", t!("已保存 vault ID {} 的密码", vault_id));
+            } else {
+                let Some(account) = ansible_vault::find(&vault, vault_id) else {
+                    eprintln!("{}", t!("密码库中没有 vault ID {} 的密码，请用 passman ansible-vault-client --vault-id {} --set 设置", vault_id, vault_id));
+                    std::process::exit(ansible_vault::UNKNOWN_ID);
+                };
+                println!("{}", account.password);
+                let id = account.id.clone();
+                record_use(&mut vault, &id);
+            }
+        }
+        
+        #[cfg(unix)]
+        Commands::SecretService { folder } => {
+            require_vault(&vault_config)?;
+            let (vault, _) = prompt_unlock(&vault_config, &load_credentials(&vault_config)?)?;
+            let path = vault.path().to_path_buf();
+            let autolock = autolock::Settings::from_config(&config);
+            match secret_service::run(&path, vault.into_backend(), folder, autolock)? {
+                Some(autolock::Reason::Idle) => println!("{}", t!("空闲时间过长，已自动锁定")),
+                Some(autolock::Reason::Suspend) => println!("{}", t!("系统休眠，已自动锁定")),
+                None => {}
+            }
+        }
+        
+        #[cfg(not(unix))]
+        Commands::SecretService { .. } => {
+            return Err(AppError::from(t!("Secret Service 只能在使用 D-Bus 的系统（例如 Linux）上提供")).into());
+        }
+        
+        Commands::Ssh { action } => match action {
+            SshAction::Import { file, username, folder, tags, remove } => {
+                let (private_key, public_key) = ssh::read_key_file(file)?;
+                EntryKind::SshKey.check_field("private_key", &private_key)?;
+                let username = username.clone().unwrap_or_else(|| file.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default());
+                let passphrase = if ssh::encrypted(&private_key) && io::stdin().is_terminal() {
+                    read_password(t!("私钥的口令（保存后 ssh add 自动填写，直接回车则每次由 ssh-add 询问）: "))?
+                } else {
+                    String::new()
+                };
+                let mut vault = open_vault(&vault_config)?;
+                let mut account = Account::new(&username, &passphrase, "");
+                account.kind = EntryKind::SshKey;
+                account.add_tags(tags);
+                account.set_folder(folder.as_deref().unwrap_or(""));
+                account.set_field("private_key", &private_key, true)?;
+                if let Some(public_key) = &public_key {
+                    account.set_field("public_key", public_key, false)?;
+                }
+                let id = add_account(&mut vault, account)?;
+                println!("{} (ID: {})", success(t!("账号添加成功"), &username), id);
+                if *remove {
+                    editor::secure_delete(file);
+                    println!("{}", t!("已删除私钥文件 {}", file.display()));
+                }
+            }
+            SshAction::Add { entry, lifetime, confirm } => {
+                let mut vault = open_vault(&vault_config)?;
+                let account = vault.select(&entry.selector())?;
+                let key = account
+                    .field("private_key")
+                    .filter(|_| account.kind == EntryKind::SshKey)
+                    .ok_or_else(|| AppError::from(t!("{} 不是带有私钥的 SSH 密钥条目", account.username)))?;
+                let status = ssh::add(&key.value, &account.password, lifetime.as_deref(), *confirm)?;
+                let id = account.id.clone();
+                record_use(&mut vault, &id);
+                if !status.success() {
+                    std::process::exit(status.code().unwrap_or(1));
+                }
+            }
+        },
+        
+        Commands::Wifi { action: WifiAction::Qr { entry, text } } => {
+            let mut vault = open_vault(&vault_config)?;
+            let account = vault.select(&entry.selector())?;
+            if account.kind != EntryKind::Wifi {
+                return Err(AppError::from(t!("{} 不是 WiFi 网络条目", account.username)).into());
+            }
+            let payload = wifi::payload(account);
+            if *text {
+                println!("{}", payload);
+            } else {
+                print_qr(&payload)?;
+                println!("{}", t!("用手机相机扫描二维码即可连接 {}", wifi::ssid(account)));
+            }
+            let id = account.id.clone();
+            record_use(&mut vault, &id);
+        }
+        
+        Commands::HashicorpVault { action } => {
+            let (HashicorpAction::Pull { server, .. } | HashicorpAction::Push { server, .. }) = action;
+            let mut vault = open_vault(&vault_config)?;
+            let client = connect_hashicorp(&vault, server)?;
+            match action {
+                HashicorpAction::Pull { path, folder, .. } => {
+                    let report = hashicorp::pull(&mut vault, &client, path, folder)?;
+                    if report.added + report.updated > 0 {
+                        save_vault(&mut vault)?;
+                    }
+                    let total = report.added + report.updated + report.unchanged;
+                    println!("{}", t!("已从 HashiCorp Vault 拉取 {} 个机密：新增 {} 个，更新 {} 个，{} 个没有变化", total, report.added, report.updated, report.unchanged));
+                }
+                HashicorpAction::Push { tags, folder, path, .. } => {
+                    let filter = AccountFilter { tags, folder: folder.as_deref(), kind: None, query: None };
+                    let ids: Vec<String> = vault.list().iter().filter(|account| filter.matches(account)).map(|account| account.id.clone()).collect();
+                    let report = hashicorp::push(&mut vault, &client, &ids, path.as_deref())?;
+                    // 推送到 --path 的账号记下了 vault_path
+                    if !vault.changes().is_empty() {
+                        save_vault(&mut vault)?;
+                    }
+                    println!("{}", t!("已推送 {} 个账号到 HashiCorp Vault，{} 个没有变化", report.written, report.unchanged));
+                    // 没有筛选条件时只推送拉取过的账号，其余账号不必提示
+                    if report.skipped > 0 && (!tags.is_empty() || folder.is_some()) {
+                        eprintln!("{}", t!("{} 跳过了 {} 个不是从 HashiCorp Vault 拉取的账号（用 --path 指定推送的位置）", color::paint(Role::Warning, t!("警告:")), report.skipped));
+                    }
+                }
+            }
+        }
+        
+        Commands::InstallBrowserHost { extension_ids, browser, uninstall } => {
+            if *uninstall {
+                let removed = browser_host::uninstall(browser)?;
+                if removed.is_empty() {
+                    println!("{}", t!("没有已注册的本地消息主机"));
+                }
+                for (browser, path) in removed {
+                    println!("{}", t!("已删除 {} 的本地消息主机: {}", browser.name(), path.display()));
+                }
+                return Ok(());
+            }
+            require_vault(&vault_config)?;
+            let vault_path = fs::canonicalize(&vault_config.path)?;
+            for (browser, path) in browser_host::install(browser, extension_ids, &vault_path)? {
+                println!("{}", t!("已为 {} 注册本地消息主机: {}", browser.name(), path.display()));
+            }
+            println!("{}", t!("浏览器扩展会通过运行中的 agent 读取密码库，请先运行 passman agent"));
+        }
+        
+        Commands::BrowserHost { .. } => {
+            require_vault(&vault_config)?;
+            browser_host::run(|| open_unattended(&vault_config))?;
+        }
+        
+        Commands::ServeSync { dir, listen, add_device, remove_device, devices } => {
+            let dir = match dir {
+                Some(dir) => dir.clone(),
+                None => config::data_dir().ok_or_else(|| AppError::from(t!("无法确定数据目录")))?.join("sync-server"),
+            };
+            if let Some(name) = add_device {
+                let token = sync_server::add_device(&dir, name)?;
+                println!("{}", t!("已添加设备 {}，它的访问令牌（只显示这一次）：", name));
+                println!("{}", token);
+                println!("{}", t!("在这台设备的 ~/.netrc 中写入 `machine <服务器地址> login {} password <令牌>`，再以 passman://<服务器地址>/vault.json 同步", name));
+            } else if let Some(name) = remove_device {
+                match sync_server::remove_device(&dir, name)? {
+                    true => println!("{}", t!("已移除设备 {}", name)),
+                    false => return Err(AppError::from(t!("没有名为 {} 的设备", name)).into()),
+                }
+            } else if *devices {
+                let devices = sync_server::devices(&dir)?;
+                if devices.is_empty() {
+                    println!("{}", t!("还没有添加设备"));
+                }
+                for device in devices {
+                    let added = timestamp::format(device.added_at);
+                    match device.last_seen_at {
+                        Some(seen) => println!("{}", t!("{}  添加于 {}，最近连接于 {}", device.name, added, timestamp::format(seen))),
+                        None => println!("{}", t!("{}  添加于 {}，还没有连接过", device.name, added)),
+                    }
+                }
+            } else {
+                sync_server::run(&dir, listen)?;
+            }
+        }
+        
+        Commands::RecoveryKit { action } => match action {
+            RecoveryAction::Create { file } => {
+                require_vault(&vault_config)?;
+                let mut credentials = load_credentials(&vault_config)?;
+                // 恢复代码加密的是由主密钥派生的密钥，需要主密钥
+                credentials.require_master_key();
+                let (vault, master_key) = prompt_unlock(&vault_config, &credentials)?;
+                if vault.path() != vault_config.path {
+                    return Err(AppError::WrongMasterKey.into());
+                }
+                let Some(master_key) = master_key else {
+                    return Err(AppError::from(t!("只有用主密钥加密的密码库可以生成恢复包")).into());
+                };
+                let header = vault.key_header();
+                if let Some(kdf) = &header.kdf {
+                    wait_for_yubikey(kdf);
+                }
+                let backend = PasswordBackend::unlock_with_keyfile(&master_key, credentials.keyfile.as_ref(), &header, vault_config.kdf()?)?;
+                let (code, recovery_key) = recovery::create(backend.key(), &header)?;
+                let sheet = recovery_kit::render(vault.path(), &code, &recovery_key);
+                recovery::write(vault.path(), &recovery_key)?;
+                match file {
+                    Some(file) => {
+                        atomic::write_private(file, sheet)?;
+                        println!("{}", t!("已生成恢复包 {}，请打印后删除这个文件", file.display()));
+                    }
+                    None => print!("{}", sheet),
+                }
+            }
+            RecoveryAction::Restore => {
+                require_vault(&vault_config)?;
+                let Some(recovery_key) = recovery::read(&vault_config.path) else {
+                    return Err(AppError::from(t!("找不到恢复文件 {}", recovery::path(&vault_config.path).display())).into());
+                };
+                let code = read_password(t!("请输入恢复代码: "))?;
+                let mut vault = unlock_vault(&vault_config, |unlock| match unlock {
+                    Unlock::Existing { header } => Ok(Box::new(recovery::unlock(&recovery_key, &code, header)?)),
+                    Unlock::Create => Err(AppError::from(t!("密码库不存在: {}", vault_config.path.display()))),
+                })?;
+                let new_key = read_new_password(t!("请输入新的主密钥: "), t!("请再次输入新的主密钥: "))?;
+                vault.change_master_key(&new_key, vault_config.kdf()?)?;
+                // 恢复代码只能使用一次
+                recovery::remove(vault.path());
+                forget_old_key(&vault);
+                autocommit(vault.path(), t!("passman: 用恢复代码重新设置主密钥"));
+                println!("{}", t!("已设置新的主密钥，恢复代码已失效；请运行 passman recovery-kit create 生成新的恢复包"));
+            }
+        },
+        
+        Commands::Generate {
+            length,
+            no_upper,
+            no_lower,
+            no_digits,
+            no_symbols,
+            passphrase,
+            words,
+            separator,
+            capitalize,
+            copy,
+        } => {
+            let password = if *passphrase {
+                let defaults = PassphraseOptions::default();
+                let config_words = config.get_int("generator.words").map(|w| w as usize);
+                let config_separator = config.get_str("generator.separator").map(String::from);
+                generator::generate_passphrase(&PassphraseOptions {
+                    words: words.or(config_words).unwrap_or(defaults.words),
+                    separator: separator.clone().or(config_separator).unwrap_or(defaults.separator),
+                    capitalize: *capitalize,
+                })?
+            } else {
+                let mut options = password_options(&config, *length);
+                options.upper &= !no_upper;
+                options.lower &= !no_lower;
+                options.digits &= !no_digits;
+                options.symbols &= !no_symbols;
+                generator::generate_password(&options)?
+            };
+            output_secret(&password, *copy, &config)?;
+        }
+        
+        Commands::Otp { action } => {
+            otp_command(open_vault(&vault_config)?, action, &config)?;
+        }
+        
+        Commands::Audit { action } => {
+            audit_command(&vault_config, action, json)?;
+        }
+        
+        Commands::Config { action } => {
+            config_command(&mut config, action)?;
+        }
+        
+        Commands::Doctor { fix } => {
+            require_vault(&vault_config)?;
+            doctor_command(&vault_config.path, *fix)?;
+        }
+        
+        Commands::Completions { shell } => {
+            print!("{}", completions::generate(*shell, cli_command()));
+        }
+        
+        Commands::Man { dir } => {
+            let pages = manpage::pages(cli_command());
+            match dir {
+                Some(dir) => {
+                    fs::create_dir_all(dir)?;
+                    for page in &pages {
+                        atomic::write(dir.join(&page.file_name), &page.content)?;
+                    }
+                    println!("{}", t!("已生成 {} 个手册页到 {}", pages.len(), dir.display()));
+                }
+                None => print!("{}", pages[0].content),
+            }
+        }
+        
+        Commands::Complete { target: CompleteTarget::Usernames } => {
+            for name in name_index::read(&vault_config.path) {
+                println!("{}", name);
+            }
+        }
+        
+        Commands::ClearClipboard { seconds } => {
+            clipboard::run_clear(*seconds)?;
+        }
+        
+        Commands::Import { format, file } => {
+            let content = fs::read(file)?;
+            let format = match format {
+                Some(format) => *format,
+                None if backup::is_backup(&content) => ImportFormat::Pmbk,
+                None => return Err(AppError::from(t!("无法识别导入文件格式，请使用 --format 指定")).into()),
+            };
+            let mut vault = open_vault(&vault_config)?;
+            let report = match format {
+                ImportFormat::Bitwarden => import::import_bitwarden(&mut vault, &String::from_utf8_lossy(&content))?,
+                ImportFormat::Csv => import::import_browser_csv(&mut vault, &String::from_utf8_lossy(&content))?,
+                ImportFormat::Kdbx => {
+                    let password = read_password(t!("请输入 KDBX 文件密码: "))?;
+                    import::import_kdbx(&mut vault, &content, &password)?
+                }
+                ImportFormat::Pmbk => {
+                    let password = read_password(t!("请输入备份密码: "))?;
+                    import::import_backup(&mut vault, &content, &password)?
+                }
+            };
+            save_vault(&mut vault)?;
+            print_import_report(&report);
+        }
+        
+        Commands::Export { format, encrypted, fields, yes_i_know, gpg_id, tags, name, namespace, apply, file, command } => {
+            if !fields.is_empty() && !matches!(format, Some(ExportFormat::Csv)) {
+                return Err(AppError::from(t!("--fields 仅适用于 CSV 导出")).into());
+            }
+            if !gpg_id.is_empty() && !matches!(format, Some(ExportFormat::Pass)) {
+                return Err(AppError::from(t!("--gpg-id 仅适用于 pass 导出")).into());
+            }
+            let plaintext = matches!(format, Some(ExportFormat::Netrc | ExportFormat::K8s));
+            if (!tags.is_empty() || !command.is_empty()) && !plaintext {
+                return Err(AppError::from(t!("--tag 和运行命令仅适用于 netrc 和 k8s 导出")).into());
+            }
+            if (name.is_some() || namespace.is_some() || *apply) && !matches!(format, Some(ExportFormat::K8s)) {
+                return Err(AppError::from(t!("--name、--namespace 和 --apply 仅适用于 k8s 导出")).into());
+            }
+            if matches!(format, Some(ExportFormat::Csv)) && !*yes_i_know && !confirm_plaintext_export()? {
+                println!("{}", t!("已取消导出"));
+                return Ok(());
+            }
+            
+            let vault = open_vault(&vault_config)?;
+            if matches!(format, Some(ExportFormat::Netrc)) {
+                let (content, count) = export::export_netrc(&vault, tags);
+                if count == 0 {
+                    eprintln!("{}", t!("{} 没有可以导出的账号（需要带有网址的登录账号）", color::paint(Role::Warning, t!("警告:"))));
+                }
+                drop(vault);
+                return write_plaintext(&content, count, file.as_deref(), command, "netrc");
+            }
+            if matches!(format, Some(ExportFormat::K8s)) {
+                let name = name.as_deref().expect("clap 保证 k8s 格式时指定了 --name");
+                let (content, count) = export::export_k8s(&vault, tags, name, namespace.as_deref())?;
+                if count == 0 {
+                    eprintln!("{}", t!("{} 没有可以导出的账号", color::paint(Role::Warning, t!("警告:"))));
+                }
+                drop(vault);
+                if *apply {
+                    return kubectl_apply(&content);
+                }
+                return write_plaintext(&content, count, file.as_deref(), command, "yaml");
+            }
+            let Some(file) = file else {
+                unreachable!("clap 保证不运行命令时指定了导出文件");
+            };
+            let content = match format {
+                _ if *encrypted => {
+                    let password = read_new_password(t!("请设置备份密码: "), t!("请再次输入备份密码: "))?;
+                    export::export_backup(&vault, &password)?
+                }
+                Some(ExportFormat::Kdbx) => {
+                    let password = read_new_password(t!("请输入 KDBX 文件密码: "), t!("请再次输入 KDBX 文件密码: "))?;
+                    export::export_kdbx(&vault, &password)?
+                }
+                Some(ExportFormat::Csv) => {
+                    let fields: Vec<&str> = if fields.is_empty() {
+                        export::CSV_FIELDS.to_vec()
+                    } else {
+                        fields.iter().map(String::as_str).collect()
+                    };
+                    export::export_csv(&vault, &fields)?
+                }
+                Some(ExportFormat::Pass) => {
+                    let count = export::export_pass(&vault, file, gpg_id)?;
+                    println!("{}", t!("已导出 {} 个账号到 {}", count, file.display()));
+                    return Ok(());
+                }
+                Some(ExportFormat::Netrc | ExportFormat::K8s) => unreachable!("netrc 和 k8s 已在前面导出"),
+                None => unreachable!("clap 保证 --format 与 --encrypted 至少指定一个"),
+            };
+            // 明文 CSV 含有所有密码，加密的导出也不应让其他用户读取
+            atomic::write_private(file, content)?;
+            println!("{}", t!("已导出 {} 个账号到 {}", vault.list().len(), file.display()));
+        }
+    }
+    
+    Ok(())
+}
+
+// 写出 netrc、k8s 等明文格式：`-` 写到标准输出，管道等特殊文件直接写入，普通文件原子地写入
+// 并设置权限为 0600；没有文件时写入扩展名为 `extension` 的临时文件后运行命令，命令结束后删除
+// 文件并以命令的退出码退出
+fn write_plaintext(content: &[u8], count: usize, file: Option<&Path>, command: &[String], extension: &str) -> Result<(), Box<dyn std::error::Error>> {
+    match file {
+        Some(path) if path == Path::new("-") => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(content)?;
+            stdout.flush()?;
+        }
+        Some(path) if fs::metadata(path).is_ok_and(|metadata| !metadata.is_file()) => {
+            fs::OpenOptions::new().write(true).open(path)?.write_all(content)?;
+        }
+        Some(path) => {
+            atomic::write_private(path, content)?;
+            println!("{}", t!("已导出 {} 个账号到 {}", count, path.display()));
+        }
+        None => {
+            let (path, mut temp) = editor::create_temp_file(extension).map_err(|e| AppError::from(t!("无法创建临时文件: {}", e)))?;
+            let result = temp.write_all(content).and_then(|_| temp.sync_all()).map_err(|e| AppError::from(t!("无法写入临时文件: {}", e)));
+            drop(temp);
+            let status = result.and_then(|_| {
+                let (program, args) = command.split_first().expect("clap 保证没有导出文件时指定了命令");
+                let args = args.iter().map(|arg| arg.replace("{}", &path.to_string_lossy()));
+                let mut child = std::process::Command::new(program);
+                child.args(args);
+                if extension == "netrc" {
+                    child.env("NETRC", &path);
+                }
+                let mut child = child.spawn().map_err(|e| AppError::from(t!("无法运行 {}: {}", program, e)))?;
+                // 终端的 Ctrl-C 同时发给命令，passman 等命令退出后再删除文件
+                #[cfg(unix)]
+                unsafe {
+                    libc::signal(libc::SIGINT, libc::SIG_IGN);
+                    libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+                }
+                child.wait().map_err(AppError::from)
+            });
+            editor::secure_delete(&path);
+            let status = status?;
+            if !status.success() {
+                std::process::exit(status.code().unwrap_or(1));
+            }
+        }
+    }
+    Ok(())
+}
+
+// 把清单从标准输入交给 `kubectl apply -f -`，kubectl 失败时以它的退出码退出
+fn kubectl_apply(content: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = std::process::Command::new("kubectl")
+        .args(["apply", "-f", "-"])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::from(t!("无法运行 {}: {}", "kubectl", e)))?;
+    let written = child.stdin.take().expect("已设置为管道").write_all(content);
+    let status = child.wait()?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    written?;
+    Ok(())
+}
+
+// 检查密码库相关文件的权限，发现未修复的问题时返回错误
+fn doctor_command(vault_path: &Path, fix: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if cfg!(not(unix)) {
+        println!("{}", t!("当前平台不检查文件权限"));
+        return Ok(());
+    }
+    
+    let mut problems = 0;
+    let mut fixable = 0;
+    for check in doctor::check(vault_path) {
+        let item = format!("{} {}", check.what, check.path.display());
+        match &check.problem {
+            None => println!("{} {}", color::paint(Role::Success, t!("正常")), item),
+            Some(problem) if fix && check.fix()? => {
+                println!("{} {}: {}", color::paint(Role::Success, t!("已修复")), item, problem);
+            }
+            Some(problem) => {
+                println!("{} {}: {}", color::paint(Role::Warning, t!("问题")), item, problem);
+                problems += 1;
+                fixable += usize::from(check.fixable());
+            }
+        }
+    }
+    
+    match problems {
+        0 => Ok(()),
+        _ if fixable > 0 => Err(AppError::from(t!("发现 {} 个问题（使用 --fix 修复权限）", problems)).into()),
+        _ => Err(AppError::from(t!("发现 {} 个问题", problems)).into()),
+    }
+}
+
+// 请求主密钥（或读取身份文件）并解锁密码库
+fn open_vault(vault_config: &VaultConfig) -> Result<Vault, Box<dyn std::error::Error>> {
+    prompt_unlock(vault_config, &load_credentials(vault_config)?).map(|(vault, _)| vault)
+}
+
+// 解锁密码库，主密钥错误时重新请求，返回密码库和主密钥；以 age 接收者加密的密码库
+// 用身份解锁，以 GPG 公钥加密的密码库由 gpg 解锁，都不请求主密钥，返回的主密钥为 None
+fn prompt_unlock(vault_config: &VaultConfig, credentials: &Credentials) -> Result<(Vault, Option<String>), Box<dyn std::error::Error>> {
+    let Credentials { identities, keyfile, sealed, session, agent } = credentials;
+    // 只在第一次尝试时使用 agent、解锁会话和 TPM 封存的密钥，失败后改为请求主密钥
+    let mut agent = agent.as_deref();
+    let mut session = session.as_ref();
+    let mut sealed = sealed.as_ref();
+    let mut attempts = 1;
+    loop {
+        // 只有用主密钥加密的密码库才请求主密钥
+        let mut master_key = None;
+        let mut uses_yubikey = false;
+        // 用主密钥解锁后缓存的会话密钥
+        let mut new_session = None;
+        // 输入的主密钥，不能打开密码库时用来尝试诱饵密码库
+        let mut entered = None;
+        let result = unlock_vault(vault_config, |unlock| {
+            let backend: Box<dyn CryptoBackend> = match unlock {
+                Unlock::Existing { header } if let Some(backend) = agent.take().and_then(|socket| agent::connect(socket, header)) => backend,
+                Unlock::Existing { header } if let Some(backend) = session.take().and_then(|key| resume_session(vault_config, key, header)) => {
+                    Box::new(backend)
+                }
+                Unlock::Existing { header } if header.recipients.is_some() => {
+                    if identities.is_empty() {
+                        return Err(AppError::from(t!("此密码库以 age 接收者加密，请用 --identity 指定身份文件")));
+                    }
+                    Box::new(AgeBackend::unlock(identities, header)?)
+                }
+                Unlock::Existing { header } if header.gpg.is_some() => Box::new(GpgBackend::unlock(header)?),
+                Unlock::Existing { header } if header.fido2.is_some() => {
+                    let device = resolve_fido2_device(vault_config)?;
+                    wait_for_fido2();
+                    Box::new(Fido2Backend::unlock(&device, header)?)
+                }
+                Unlock::Create if !identities.is_empty() => {
+                    let recipients: Vec<Recipient> = identities.iter().map(Identity::recipient).collect();
+                    Box::new(AgeBackend::new(&recipients)?)
+                }
+                Unlock::Existing { header } if let Some(backend) = sealed.take().and_then(|sealed| unseal_with_pin(sealed, header)) => {
+                    Box::new(backend)
+                }
+                Unlock::Existing { header } if keyfile.is_none() && header.kdf.as_ref().is_some_and(|kdf| kdf.keyfile) => {
+                    return Err(AppError::from(t!("此密码库需要密钥文件，请用 --keyfile 指定")));
+                }
+                unlock => {
+                    let key = read_password(t!("请输入主密钥: "))?;
+                    // 旧版本的密码库解锁后会换用新的密钥，不缓存
+                    let cacheable = match unlock {
+                        Unlock::Create => true,
+                        Unlock::Existing { header } => header.kdf.is_some(),
+                    };
+                    let backend = match unlock {
+                        Unlock::Create => PasswordBackend::with_keyfile(&key, keyfile.as_ref(), vault_config.kdf()?)?,
+                        Unlock::Existing { header } => {
+                            entered = Some(key.clone());
+                            if let Some(kdf) = &header.kdf {
+                                uses_yubikey = kdf.yubikey.is_some();
+                                wait_for_yubikey(kdf);
+                            }
+                            PasswordBackend::unlock_with_keyfile(&key, keyfile.as_ref(), header, vault_config.kdf()?)?
+                        }
+                    };
+                    if cacheable && vault_config.session_timeout > 0 {
+                        new_session = Some(SecretBytes::from_slice(backend.key()));
+                    }
+                    master_key = Some(key);
+                    Box::new(backend)
+                }
+            };
+            Ok(backend)
+        });
+        let wrong_key = matches!(result.as_ref().err().and_then(|e| e.downcast_ref()), Some(AppError::WrongMasterKey));
+        if wrong_key && let Some(key) = entered && let Some(vault) = duress::open(&vault_config.path, &key) {
+            return Ok((vault, Some(key)));
+        }
+        match result {
+            Err(e) if attempts < MASTER_KEY_ATTEMPTS && matches!(e.downcast_ref(), Some(AppError::WrongMasterKey)) => {
+                match (keyfile, uses_yubikey) {
+                    (_, true) => eprintln!("{}", t!("主密钥错误，或不是同一个 YubiKey，请重试")),
+                    (Some(_), false) => eprintln!("{}", t!("主密钥或密钥文件错误，请重试")),
+                    (None, false) => eprintln!("{}", t!("主密钥错误，请重试")),
+                }
+                attempts += 1;
+            }
+            result => {
+                if result.is_ok()
+                    && let Some(key) = new_session
+                    && let Err(e) = session::store(&vault_config.path, &key, vault_config.session_timeout)
+                {
+                    eprintln!("{}", t!("无法保存解锁会话: {}", e));
+                }
+                return result.map(|vault| (vault, master_key));
+            }
+        }
+    }
+}
+
+// 以 YubiKey 派生密钥前提示用户，槽位设置为需要触摸时 YubiKey 会一直等待触摸
+fn wait_for_yubikey(kdf: &KdfParams) {
+    if kdf.yubikey.is_some() {
+        eprintln!("{}", t!("正在等待 YubiKey 响应（如果它在闪烁，请触摸它）..."));
+    }
+}
+
+// FIDO2 工具会在终端中请求 PIN，但不会提示触摸
+fn wait_for_fido2() {
+    eprintln!("{}", t!("输入 PIN 后，请在 FIDO2 安全密钥闪烁时触摸它"));
+}
+
+// 配置的 FIDO2 设备，未配置时使用第一个连接的设备
+fn resolve_fido2_device(vault_config: &VaultConfig) -> Result<String, AppError> {
+    match &vault_config.fido2_device {
+        Some(device) => Ok(device.clone()),
+        None => fido2::default_device(),
+    }
+}
+
+// 用主密钥加密的密码库使用的 YubiKey 槽位
+fn yubikey_slot_of(header: &KeyHeader) -> Option<u8> {
+    header.kdf.as_ref()?.yubikey.as_ref().map(|yubikey| yubikey.slot)
+}
+
+// 使用缓存的解锁会话，密码库的密钥已经更换时清除会话
+fn resume_session(vault_config: &VaultConfig, key: &SecretBytes, header: &KeyHeader) -> Option<KeyBackend> {
+    let backend = KeyBackend::new(key, header).ok();
+    if backend.is_none() {
+        session::clear(&vault_config.path);
+    }
+    backend
+}
+
+// 不请求主密钥，只用 agent、解锁会话或 age 身份文件打开密码库，都不可用时返回 None；
+// 不在标准输出中显示任何内容（浏览器的本地消息主机以标准输出传递消息）
+fn open_unattended(vault_config: &VaultConfig) -> Result<Option<Vault>, AppError> {
+    let credentials = load_credentials(vault_config)?;
+    let mut locked = false;
+    let result = Vault::open_with_backend(&vault_config.path, |unlock| {
+        let Unlock::Existing { header } = unlock else {
+            return Err(AppError::from(t!("密码库不存在: {}", vault_config.path.display())));
+        };
+        if let Some(backend) = credentials.agent.as_deref().and_then(|socket| agent::connect(socket, header)) {
+            return Ok(backend);
+        }
+        if let Some(backend) = credentials.session.as_ref().and_then(|key| resume_session(vault_config, key, header)) {
+            return Ok(Box::new(backend));
+        }
+        if header.recipients.is_some() && !credentials.identities.is_empty() {
+            return Ok(Box::new(AgeBackend::unlock(&credentials.identities, header)?));
+        }
+        locked = true;
+        Err(AppError::WrongMasterKey)
+    });
+    match result {
+        Ok(vault) => Ok(Some(vault)),
+        Err(_) if locked => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 凭据助手打开密码库：优先使用 agent 或解锁会话，都没有时在终端上请求主密钥（标准输入是请求）
+fn open_for_helper(vault_config: &VaultConfig) -> Result<Vault, Box<dyn std::error::Error>> {
+    match open_unattended(vault_config)? {
+        Some(vault) => Ok(vault),
+        None => Ok(prompt_unlock(vault_config, &load_credentials(vault_config)?)?.0),
+    }
+}
+
+// 更换密钥后本机 TPM 封存的旧密钥、缓存的解锁会话和 agent 不再可用
+fn forget_old_key(vault: &Vault) {
+    if tpm_store::remove(vault.path()) {
+        println!("{}", t!("已删除本机 TPM 中封存的旧密钥，需要时请重新运行 passman tpm enroll"));
+    }
+    if recovery::remove(vault.path()) {
+        println!("{}", t!("恢复包已失效，需要时请重新运行 passman recovery-kit create"));
+    }
+    session::clear(vault.path());
+    if agent::stop(vault.path()) {
+        println!("{}", t!("已停止使用旧密钥的 agent"));
+    }
+}
+
+// 用本机 TPM 封存的密钥解锁，失败或直接回车时返回 None，改为请求主密钥
+fn unseal_with_pin(sealed: &SealedKey, header: &KeyHeader) -> Option<KeyBackend> {
+    if !sealed.matches(header) {
+        eprintln!("{}", t!("TPM 中封存的密钥已过期（主密钥已更换或密码库已重新加密），请用主密钥解锁后重新运行 passman tpm enroll"));
+        return None;
+    }
+    let pin = read_password(t!("请输入 TPM PIN（直接回车改用主密钥）: ")).ok().filter(|pin| !pin.is_empty())?;
+    match tpm::unseal(sealed, &pin, header) {
+        Ok(backend) => Some(backend),
+        Err(e) => {
+            eprintln!("{}", t!("TPM 解锁失败: {}", i18n::error_message(&e)));
+            None
+        }
+    }
+}
+
+// 主密钥之外解锁密码库用到的文件，以及本机 TPM 封存的密钥、缓存的解锁会话和 agent 的套接字
+struct Credentials {
+    identities: Vec<Identity>,
+    keyfile: Option<Keyfile>,
+    sealed: Option<SealedKey>,
+    session: Option<SecretBytes>,
+    agent: Option<PathBuf>,
+}
+
+impl Credentials {
+    // 不使用 TPM、解锁会话和 agent，用主密钥解锁
+    fn require_master_key(&mut self) {
+        self.sealed = None;
+        self.session = None;
+        self.agent = None;
+    }
+}
+
+// 读取配置的身份文件和密钥文件
+fn load_credentials(vault_config: &VaultConfig) -> Result<Credentials, AppError> {
+    let mut identities = Vec::new();
+    for path in &vault_config.identities {
+        let text = fs::read_to_string(path).map_err(|e| AppError::from(t!("无法读取身份文件 {}: {}", path.display(), e)))?;
+        let parsed = age::parse_identities(&text)
+            .map_err(|e| AppError::from(t!("身份文件 {}: {}", path.display(), i18n::error_message(&e))))?;
+        identities.extend(parsed);
+    }
+    let keyfile = vault_config.keyfile.as_deref().map(Keyfile::read).transpose()?;
+    let sealed = tpm_store::read(&vault_config.path);
+    let session = (vault_config.session_timeout > 0).then(|| session::load(&vault_config.path)).flatten();
+    let agent = agent::socket(&vault_config.path);
+    Ok(Credentials { identities, keyfile, sealed, session, agent })
+}
+
+// 读取 rekey 指定的 age 接收者
+fn read_recipients(keys: &[String], files: &[PathBuf]) -> Result<Vec<Recipient>, AppError> {
+    let mut recipients = keys.iter().map(|key| Recipient::parse(key)).collect::<Result<Vec<_>, _>>()?;
+    for path in files {
+        let text = fs::read_to_string(path).map_err(|e| AppError::from(t!("无法读取接收者文件 {}: {}", path.display(), e)))?;
+        recipients.extend(age::parse_recipients(&text)?);
+    }
+    Ok(recipients)
+}
+
+// 确认密码库文件已存在（必要时先迁移旧密码库）
+fn require_vault(vault_config: &VaultConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if vault_config.is_default {
+        migrate_legacy_vault(&vault_config.path)?;
+    }
+    if !vault_config.path.exists() {
+        return Err(AppError::from(t!("密码库不存在: {}", vault_config.path.display())).into());
+    }
+    Ok(())
+}
+
+// 使用 unlock 创建的加密后端解锁密码库
+fn unlock_vault(
+    vault_config: &VaultConfig,
+    unlock: impl FnOnce(Unlock<'_>) -> Result<Box<dyn CryptoBackend>, AppError>,
+) -> Result<Vault, Box<dyn std::error::Error>> {
+    if vault_config.is_default {
+        migrate_legacy_vault(&vault_config.path)?;
+    }
+    
+    let created = !vault_config.path.exists();
+    let mut vault = Vault::open_with_backend(&vault_config.path, unlock)?;
+    if created && let Some(cipher) = vault_config.cipher {
+        vault.set_cipher(cipher);
+    }
+    
+    if let Some(migration) = vault.migration() {
+        println!("{}", t!("密码库已从格式版本 {} 升级到 {}，升级前的文件已备份为 {}", migration.from, migration.to, migration.backup.display()));
+        for change in &migration.changes {
+            println!("  - {}", i18n::tr(change));
+        }
+        autocommit(vault.path(), t!("passman: 升级密码库格式"));
+    }
+    if passman::secmem::lock_denied() {
+        eprintln!("{}", t!("{} 无法锁定内存，密钥和解密后的数据可能被交换到磁盘（可以用 ulimit -l 提高限制）", color::paint(Role::Warning, t!("警告:"))));
+    }
+    if let Some(check) = doctor::check_vault(vault.path()) {
+        let problem = check.problem.as_deref().unwrap_or_default();
+        eprintln!("{}", t!("{} 密码库文件 {}: {}", color::paint(Role::Warning, t!("警告:")), check.path.display(), problem));
+        if check.fixable() {
+            eprintln!("{}", t!("运行 passman doctor --fix 修复"));
+        }
+    }
+    if vault_config.index_names {
+        name_index::write(&vault);
+    } else {
+        name_index::remove(&vault_config.path);
+    }
+    
+    Ok(vault)
+}
+
+// 操作成功的提示，例如 `账号添加成功: alice`
+fn success(message: &str, name: &str) -> String {
+    format!("{}: {}", color::paint(Role::Success, message), color::paint(Role::Name, name))
+}
+
+// 以本地时间显示时间戳
+fn format_time(timestamp: u64) -> String {
+    i18n::translate(&timestamp::format(timestamp))
+}
+
+// 记录账号的使用时间并保存（用于 list --sort last-used），失败时只给出警告
+fn record_use(vault: &mut Vault, id: &str) {
+    let Some(account) = vault.get_mut(id) else {
+        return;
+    };
+    account.mark_used();
+    if let Err(e) = save_vault(vault) {
+        eprintln!("{}", t!("{} 无法记录使用时间: {}", color::paint(Role::Warning, t!("警告:")), i18n::error_message(&e)));
+        return;
+    }
+    // 使用时间不算作对账号的修改，save_vault 不会提交，单独提交以免仓库中留下未提交的密码库
+    autocommit(vault.path(), t!("passman: 记录使用时间"));
+}
+
+// 保存密码库，更新补全用的用户名索引，并在启用了 git 时提交
+fn save_vault(vault: &mut Vault) -> Result<(), AppError> {
+    let message = git::describe(&vault.changes());
+    vault.save()?;
+    name_index::refresh(vault);
+    if let Some(message) = message {
+        autocommit(vault.path(), &message);
+    }
+    Ok(())
+}
+
+// 与远程存储同步、推送或拉取并显示结果；两边都有修改时逐个账号合并，拉取时合并的结果只保存到本地
+fn sync_remote(vault_config: &VaultConfig, remote: &str, key: Option<&remote::SyncKey>, options: remote::Options) -> Result<(), Box<dyn std::error::Error>> {
+    let shown = remote::display(remote);
+    let pull = options.direction == Some(remote::Direction::Pull);
+    // 下载时会替换密码库文件
+    let mut _lock = Some(VaultLock::acquire(&vault_config.path)?);
+    // 推送时 keep 表示覆盖远程的修改，其他时候用于选择合并时冲突的账号
+    let keep = options.keep.filter(|_| options.direction == Some(remote::Direction::Push));
+    let mut outcome = remote::sync(&vault_config.path, remote, key, remote::Options { keep, ..options })?;
+    if let Outcome::Diverged(diverged) = &outcome {
+        // 打开密码库时会再次获取锁
+        _lock = None;
+        let whole = match merge_remote(vault_config, diverged, options.keep, options.dry_run)? {
+            Some(summary) => {
+                println!("{}", summary);
+                if options.dry_run {
+                    return Ok(());
+                }
+                remote::merged(&vault_config.path, remote, key, diverged)?;
+                if pull {
+                    println!("{}", t!("合并的结果还没有上传，需要时运行 passman push"));
+                    return Ok(());
+                }
+                None
+            }
+            None => options.keep,
+        };
+        _lock = Some(VaultLock::acquire(&vault_config.path)?);
+        outcome = remote::sync(&vault_config.path, remote, key, remote::Options { keep: whole, ..options })?;
+    }
+    match outcome {
+        Outcome::UpToDate => println!("{}", t!("密码库与 {} 相同，不需要同步", shown)),
+        Outcome::Uploaded { .. } if options.dry_run => println!("{}", t!("将把本地的密码库上传到 {}（--dry-run，没有上传）", shown)),
+        Outcome::Uploaded { replaced } => {
+            println!("{}", t!("已把本地的密码库上传到 {}", shown));
+            if let Some(version) = replaced {
+                println!("{}", t!("被覆盖的远程版本仍保留在存储桶的版本历史中（版本 ID: {}）", version));
+            }
+        }
+        Outcome::Downloaded if options.dry_run => println!("{}", t!("将从 {} 下载更新的密码库（--dry-run，没有修改本地的密码库）", shown)),
+        Outcome::Downloaded => println!("{}", t!("已从 {} 下载更新的密码库", shown)),
+        Outcome::Ahead => println!("{}", t!("{} 中没有新的修改，本地的修改还没有推送", shown)),
+        Outcome::Behind => return Err(AppError::from(t!("{} 中有本地没有的修改，请先运行 passman pull（或用 --force 覆盖远程的修改）", shown)).into()),
+        Outcome::Diverged(_) => return Err(AppError::from(t!("合并期间远程的密码库又被修改，请重新同步")).into()),
+    }
+    Ok(())
+}
+
+// 逐个账号合并远程的密码库并保存，返回合并结果的说明；冲突由 `keep` 决定，没有给出时在
+// 终端中逐个询问，`dry_run` 时只说明会怎样合并。远程的密码库无法用本地的密钥解密（例如在
+// 其他设备上更换了主密钥）而给出了 `keep` 时返回 None，由调用者整个保留一边
+fn merge_remote(vault_config: &VaultConfig, diverged: &remote::Diverged, keep: Option<remote::Keep>, dry_run: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut vault = open_vault(vault_config)?;
+    if vault.path() != vault_config.path {
+        return Err(AppError::WrongMasterKey.into());
+    }
+    let theirs = match vault.read_accounts(&diverged.remote) {
+        Ok(accounts) => accounts,
+        Err(_) if keep.is_some() => return Ok(None),
+        Err(e) => {
+            return Err(AppError::from(t!("无法解密远程的密码库（{}），可能在其他设备上更换了主密钥；请用 --keep local 或 --keep remote 选择保留哪一边的整个密码库", e)).into());
+        }
+    };
+    // 共同祖先无法解密时（例如本地更换过主密钥）当作没有共同祖先
+    let base = diverged.base.as_deref().and_then(|base| vault.read_accounts(base).ok()).unwrap_or_default();
+    let merge = merge::merge(&base, vault.list(), &theirs);
+    let (added, updated, deleted, conflicts) = (merge.added, merge.updated, merge.deleted, merge.conflicts().len());
+    if dry_run {
+        return Ok(Some(t!("将合并远程的修改：新增 {} 个、更新 {} 个、删除 {} 个账号，有 {} 个冲突（--dry-run，没有修改）", added, updated, deleted, conflicts)));
+    }
+    if conflicts > 0 && keep.is_none() && !io::stdin().is_terminal() {
+        return Err(AppError::from(t!("有 {} 个账号在本地和远程都被修改，请在终端中运行以逐个选择，或用 --keep local 或 --keep remote 选择保留哪一边", conflicts)).into());
+    }
+    let accounts = merge.resolve(|conflict| match keep {
+        Some(remote::Keep::Local) => Ok(Resolution::Local),
+        Some(remote::Keep::Remote) => Ok(Resolution::Remote),
+        None => prompt_resolution(conflict),
+    })?;
+    vault.replace_accounts(accounts);
+    save_vault(&mut vault)?;
+    Ok(Some(t!("已合并远程的修改：新增 {} 个、更新 {} 个、删除 {} 个账号，解决了 {} 个冲突", added, updated, deleted, conflicts)))
+}
+
+// 显示一个冲突的两个版本，询问保留哪一边
+fn prompt_resolution(conflict: &Conflict) -> Result<Resolution, io::Error> {
+    let version = |account: Option<&Account>| match account {
+        Some(account) => t!("{}，修改于 {}", describe_account(account), format_time(account.updated_at)),
+        None => t!("已删除").to_string(),
+    };
+    let name = conflict.local.as_ref().or(conflict.remote.as_ref()).map(|account| account.username.as_str()).unwrap_or_default();
+    println!("{}", t!("{} 在本地和远程都被修改:", color::paint(Role::Name, name)));
+    println!("  {} {}", t!("本地:"), version(conflict.local.as_ref()));
+    println!("  {} {}", t!("远程:"), version(conflict.remote.as_ref()));
+    if let (Some(local), Some(remote)) = (&conflict.local, &conflict.remote) {
+        let names: Vec<_> = changed_fields(local, remote, false).into_iter().map(|(name, _)| name).collect();
+        println!("  {} {}", t!("不同的字段:"), names.join(t!("、")));
+    }
+    loop {
+        let prompt = match conflict.local.is_some() && conflict.remote.is_some() {
+            true => t!("保留哪一边? 本地 (l) / 远程 (r) / 都保留 (b): "),
+            false => t!("保留哪一边? 本地 (l) / 远程 (r): "),
+        };
+        match read_input(prompt)?.trim() {
+            "l" | "L" => return Ok(Resolution::Local),
+            "r" | "R" => return Ok(Resolution::Remote),
+            "b" | "B" if conflict.local.is_some() && conflict.remote.is_some() => return Ok(Resolution::Both),
+            _ => {}
+        }
+    }
+}
+
+// 两个版本中内容不同的字段，能在一行中显示的附带两边的值；`show_passwords` 为 false 时隐藏密码
+fn changed_fields(a: &Account, b: &Account, show_passwords: bool) -> Vec<(&'static str, Option<(String, String)>)> {
+    let values = |value: &dyn Fn(&Account) -> String| Some((value(a), value(b)));
+    let fields = [
+        (a.kind != b.kind, t!("类型"), values(&|account| i18n::tr(account.kind.label()).to_string())),
+        (a.username != b.username, t!("用户名"), values(&|account| account.username.clone())),
+        (a.password != b.password, t!("密码"), values(&|account| masked_password(account, show_passwords).to_string())),
+        (a.notes != b.notes, t!("备注"), values(&|account| account.notes.lines().collect::<Vec<_>>().join(" ⏎ "))),
+        (a.url != b.url, t!("网址"), values(&|account| account.url.clone().unwrap_or_default())),
+        (a.tags != b.tags, t!("标签"), values(&|account| account.tags.join(", "))),
+        (a.folder != b.folder, t!("文件夹"), values(&|account| account.folder.clone().unwrap_or_default())),
+        (a.otp != b.otp, t!("一次性密码"), None),
+        (a.expires_at != b.expires_at || a.rotation != b.rotation, t!("过期时间"), values(&|account| account.expires_at.map(format_time).unwrap_or_default())),
+        (a.fields != b.fields, t!("自定义字段"), None),
+        (a.attachments != b.attachments, t!("附件"), None),
+    ];
+    fields.into_iter().filter(|(changed, _, _)| *changed).map(|(_, name, values)| (name, values)).collect()
+}
+
+// 读取另一个密码库或加密备份中的账号；密码库先用当前密码库的密钥解密，不能解密时请求它的主密钥
+fn read_other_accounts(vault: &Vault, path: &Path) -> Result<AccountStore, Box<dyn std::error::Error>> {
+    let content = fs::read(path)?;
+    if backup::is_backup(&content) {
+        let password = read_password(t!("请输入备份密码: "))?;
+        return Ok(backup::open(&content, &password)?.accounts);
+    }
+    if let Ok(accounts) = vault.read_accounts(&content) {
+        return Ok(accounts);
+    }
+    let master_key = read_password(&t!("请输入 {} 的主密钥: ", path.display()))?;
+    Ok(Vault::open(path, &master_key)?.list().clone())
+}
+
+// 显示从 `before` 到 `after` 新增、删除和修改的账号
+fn print_diff(before: &AccountStore, after: &AccountStore, label: &str, show_passwords: bool) {
+    let changes = Changed::between(before, after);
+    if changes.is_empty() {
+        println!("{}", t!("与 {} 相比没有不同", label));
+        return;
+    }
+    println!("{}", t!("与 {} 相比:", label));
+    let (mut added, mut deleted, mut updated) = (0, 0, 0);
+    for change in &changes {
+        match change {
+            Changed::Added(account) => {
+                added += 1;
+                println!("  + {}", color::paint(Role::Name, &describe_account(account)));
+            }
+            Changed::Deleted(account) => {
+                deleted += 1;
+                println!("  - {}", color::paint(Role::Name, &describe_account(account)));
+            }
+            Changed::Updated { before, after } => {
+                updated += 1;
+                println!("  ~ {}", color::paint(Role::Name, &describe_account(after)));
+                for (name, values) in changed_fields(before, after, show_passwords) {
+                    match values {
+                        Some((old, new)) => println!("      {}: {} → {}", name, old, new),
+                        None => println!("      {}", name),
+                    }
+                }
+            }
+        }
+    }
+    println!("{}", t!("新增 {} 个、删除 {} 个、修改 {} 个账号", added, deleted, updated));
+}
+
+// 密码库在启用了自动提交的 git 仓库中时提交，失败时只给出警告
+fn autocommit(vault_path: &Path, message: &str) {
+    if let Err(e) = git::commit(vault_path, message) {
+        eprintln!("{}", t!("{} 无法提交到 git 仓库: {}", color::paint(Role::Warning, t!("警告:")), e));
+    }
+}
+
+// 随机密码选项：命令行指定的长度优先，其余取自配置
+fn password_options(config: &Config, length: Option<usize>) -> PasswordOptions {
+    let defaults = PasswordOptions::default();
+    let class = |key: &str, default: bool| config.get_bool(key).unwrap_or(default);
+    PasswordOptions {
+        length: length
+            .or(config.get_int("generator.length").map(|l| l as usize))
+            .unwrap_or(defaults.length),
+        upper: class("generator.upper", defaults.upper),
+        lower: class("generator.lower", defaults.lower),
+        digits: class("generator.digits", defaults.digits),
+        symbols: class("generator.symbols", defaults.symbols),
+    }
+}
+
+// list 显示的列
+fn list_columns(config: &Config) -> Vec<String> {
+    match config.get_str("list.columns").map(config::parse_columns) {
+        Some(Ok(columns)) => columns,
+        _ => config::DEFAULT_LIST_COLUMNS.iter().map(|c| c.to_string()).collect(),
+    }
+}
+
+// 处理配置子命令
+fn config_command(config: &mut Config, action: &ConfigAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        ConfigAction::Get { key } => match config.get(key) {
+            Some(value) => println!("{}", value),
+            None => return Err(AppError::from(t!("配置项未设置: {}", key)).into()),
+        },
+        
+        ConfigAction::Set { key, value } => {
+            config.set(key, value)?;
+            println!("{}", t!("已设置 {} = {}", key, value));
+        }
+        
+        ConfigAction::Unset { key } => {
+            if config.unset(key)? {
+                println!("{}", t!("已删除配置项: {}", key));
+            } else {
+                println!("{}", t!("配置项未设置: {}", key));
+            }
+        }
+        
+        ConfigAction::List => {
+            if let Some(path) = config.path() {
+                println!("{}", t!("配置文件: {}", path.display()));
+            }
+            
+            let mut table = Table::new();
+            table.add_row(Row::new(vec![Cell::new(t!("配置项")), Cell::new(t!("当前值")), Cell::new(t!("说明"))]));
+            for (key, _, description) in config::KEYS {
+                let value = config.get(key).map(|v| v.to_string()).unwrap_or_default();
+                table.add_row(Row::new(vec![Cell::new(key), Cell::new(&value), Cell::new(i18n::tr(description))]));
+            }
+            for (key, value) in config.values().iter().filter(|(k, _)| k.starts_with("profiles.")) {
+                table.add_row(Row::new(vec![Cell::new(key), Cell::new(&value.to_string()), Cell::new(t!("配置档案"))]));
+            }
+            color::print_table(&table);
+        }
+    }
+    
+    Ok(())
+}
+
+// 将当前目录中的旧密码库移动到数据目录
+fn migrate_legacy_vault(path: &Path) -> Result<(), io::Error> {
+    let legacy = Path::new(LEGACY_VAULT);
+    if !legacy.is_file() {
+        return Ok(());
+    }
+    
+    if path.exists() {
+        eprintln!("{}", t!("提示: 当前目录中的 {} 不再默认使用，如需打开请使用 --vault ./{}", LEGACY_VAULT, LEGACY_VAULT));
+        return Ok(());
+    }
+    
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // 可能跨文件系统，因此先复制再删除
+    fs::copy(legacy, path)?;
+    fs::remove_file(legacy)?;
+    println!("{}", t!("已将当前目录中的密码库迁移到 {}", path.display()));
+    
+    Ok(())
+}
+
+// 打印机密信息，或复制到剪贴板
+fn output_secret(secret: &str, copy: bool, config: &Config) -> Result<(), AppError> {
+    if copy {
+        clipboard::copy(secret)?;
+        match config.get_int("clipboard.timeout") {
+            Some(seconds) if seconds > 0 => {
+                clipboard::clear_after(secret, seconds as u64)?;
+                println!("{}", t!("已复制到剪贴板，将在 {} 秒后清除", seconds));
+            }
+            _ => println!("{}", t!("已复制到剪贴板")),
+        }
+    } else {
+        println!("{}", secret);
+    }
+    
+    Ok(())
+}
+
+// 持续显示 TOTP 验证码和剩余秒数，直到被 Ctrl+C 中断；输出到终端时在同一行刷新，
+// 否则每出现一个新验证码输出一行
+fn watch_otp(mut otp: Otp, period: u64) -> Result<(), AppError> {
+    let terminal = io::stdout().is_terminal();
+    let mut last = String::new();
+    loop {
+        let code = otp.generate()?;
+        let remaining = period - timestamp::now() % period;
+        if terminal {
+            let countdown = t!("剩余 {} 秒", remaining);
+            let countdown = if remaining <= 5 { color::paint(Role::Expiring, &countdown) } else { countdown };
+            print!("\r{}  {}\x1b[K", color::paint(Role::Name, &code), countdown);
+            io::stdout().flush()?;
+        } else if code != last {
+            println!("{}", code);
+        }
+        last = code;
+        // 在下一整秒时刷新，倒计时不会跳过或重复某一秒
+        let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.subsec_nanos()).unwrap_or(0);
+        thread::sleep(Duration::from_nanos(1_000_000_000 - u64::from(elapsed)));
+    }
+}
+
+// 在终端中显示内容的二维码；输出到终端时用 ANSI 颜色固定为白底黑码
+fn print_qr(data: &str) -> Result<(), AppError> {
+    let qr = QrCode::encode(data.as_bytes())?;
+    print!("{}", qr.render(io::stdout().is_terminal()));
+    Ok(())
 }
 
-// 账号信息结构
-#[derive(Serialize, Deserialize, Clone)]
-struct Account {
-    password: String,
-    notes: String,
+// 读取密码（不回显）
+fn read_password(prompt: &str) -> Result<String, io::Error> {
+    // 标准输出被重定向时（例如 `$(passman show foo --password-only)`）提示写到标准错误，避免混入输出
+    if io::stdout().is_terminal() {
+        print!("{}", prompt);
+        io::stdout().flush()?;
+    } else {
+        eprint!("{}", prompt);
+    }
+    
+    let password = rpassword::read_password()?;
+    Ok(password)
 }
 
-// 密码库结构
-#[derive(Serialize, Deserialize)]
-struct PasswordStore {
-    // 使用随机生成的初始化向量(IV)
-    iv: String,
-    // 加密后的数据
-    encrypted_data: String,
+// 请求输入新密码并确认
+fn read_new_password(prompt: &str, confirm_prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let password = read_password(prompt)?;
+    if password.is_empty() {
+        return Err(AppError::from(t!("密码不能为空")).into());
+    }
+    if read_password(confirm_prompt)? != password {
+        return Err(AppError::from(t!("两次输入的密码不一致")).into());
+    }
+    Ok(password)
 }
 
-// 存储实际账号数据的结构
-type AccountStore = HashMap<String, Account>;
-
-// 数据文件路径
-const DATA_FILE: &str = ".passman_data.json";
-// 初始化向量长度
-const NONCE_LENGTH: usize = 12;
+// 明文导出前请求用户确认
+fn confirm_plaintext_export() -> Result<bool, io::Error> {
+    println!("{}", t!("{} CSV 文件中的密码不会加密，任何能读取该文件的人都能看到你的所有密码。", color::paint(Role::Warning, t!("警告:"))));
+    print!("{}", t!("确定要导出吗? (y/N) "));
+    io::stdout().flush()?;
+    
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+// 添加账号，返回新账号的 ID
+fn add_account(vault: &mut Vault, account: Account) -> Result<String, Box<dyn std::error::Error>> {
+    let id = vault.add(account)?;
+    save_vault(vault)?;
     
-    // 请求主密钥
-    let master_key = read_password("请输入主密钥: ")?;
-    let key = derive_key(&master_key);
+    Ok(id)
+}
+
+// 从 TOML 或 JSON 文件批量添加账号，只解锁和保存一次；有误的条目逐个报告，其余照常添加
+fn add_from_file(vault_config: &VaultConfig, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let entries = if is_json { EntryFile::parse_json_list(&text) } else { EntryFile::parse_list(&text) };
+    let entries = entries.map_err(AppError::from)?;
+    if entries.is_empty() {
+        return Err(AppError::from(t!("文件中没有账号")).into());
+    }
     
-    // 根据子命令执行相应操作
-    match &cli.command {
-        Commands::Add { username, password, notes } => {
-            add_account(&key, username, password, notes)?;
-            println!("账号添加成功: {}", username);
-        }
-        
-        Commands::Delete { username } => {
-            delete_account(&key, username)?;
-            println!("账号删除成功: {}", username);
-        }
-        
-        Commands::Update { username, password, notes } => {
-            update_account(&key, username, password, notes)?;
-            println!("账号更新成功: {}", username);
-        }
-        
-        Commands::List => {
-            list_accounts(&key)?;
-        }
-        
-        Commands::Get { username } => {
-            get_account(&key, username)?;
+    let mut vault = open_vault(vault_config)?;
+    let mut added = 0;
+    let mut failures = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let label = match &entry {
+            Ok(file) => t!("第 {} 个账号 ({})", index + 1, file.username.trim()),
+            Err(_) => t!("第 {} 个账号", index + 1),
+        };
+        let result = entry.map_err(AppError::from).and_then(|file| new_account(&file)).and_then(|account| vault.add(account));
+        match result {
+            Ok(_) => added += 1,
+            Err(e) => failures.push(format!("{}: {}", label, i18n::error_message(&e))),
         }
     }
     
+    if added > 0 {
+        save_vault(&mut vault)?;
+    }
+    println!("{}", t!("已添加 {} 个账号", added));
+    if !failures.is_empty() {
+        eprintln!("{}", t!("以下 {} 个账号没有添加:", failures.len()));
+        for failure in &failures {
+            eprintln!("  {}", failure);
+        }
+        return Err(AppError::from(t!("{} 个账号添加失败", failures.len())).into());
+    }
     Ok(())
 }
 
-// 从主密钥派生加密密钥
-fn derive_key(master_key: &str) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(master_key.as_bytes());
-    let result = hasher.finalize();
+// 由批量添加文件中的条目创建账号，检查与 add 命令相同
+fn new_account(file: &EntryFile) -> Result<Account, AppError> {
+    let mut account = Account::new("", "", "");
+    apply_entry_file(&mut account, file, &EntryFile::default())?;
+    if account.kind.is_login() && account.password.is_empty() {
+        return Err(AppError::from(t!("登录类型的账号需要 password")));
+    }
+    if account.kind.is_login() && account.notes.trim().is_empty() {
+        return Err(AppError::from(t!("登录类型的账号需要 notes")));
+    }
+    Ok(account)
+}
+
+// 删除账号，返回被删除的账号；未指定 force 时需要输入用户名确认，取消时返回 None
+fn delete_account(vault: &mut Vault, selector: &Selector, force: bool) -> Result<Option<Account>, Box<dyn std::error::Error>> {
+    let account = vault.select(selector)?;
+    if !force && !confirm_delete(account)? {
+        return Ok(None);
+    }
     
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&result);
-    key
+    let id = account.id.clone();
+    let account = vault.remove(&id)?;
+    save_vault(vault)?;
+    
+    Ok(Some(account))
 }
 
-// 读取密码（不回显）
-fn read_password(prompt: &str) -> Result<String, io::Error> {
-    print!("{}", prompt);
+// 删除前显示账号并要求输入用户名确认
+fn confirm_delete(account: &Account) -> Result<bool, io::Error> {
+    println!("{}", t!("将要删除: {}", describe_account(account)));
+    println!("{}", t!("删除后可以用 passman undo 恢复。"));
+    print!("{}", t!("请输入用户名 {} 以确认删除: ", account.username));
     io::stdout().flush()?;
     
-    let password = rpassword::read_password()?;
-    Ok(password)
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim_end_matches(['\r', '\n']) == account.username)
 }
 
-// 自定义错误类型以包装 aes_gcm::Error
-#[derive(Debug)]
-enum AppError {
-    IoError(io::Error),
-    SerdeError(serde_json::Error),
-    Base64Error(base64::DecodeError),
-    AesError(String),
-    OtherError(String),
+// 用户名、短 ID 以及网址或备注的第一行，用于确认和提示
+fn describe_account(account: &Account) -> String {
+    let site = account.url.as_deref().or_else(|| account.notes.lines().next()).unwrap_or("");
+    if site.trim().is_empty() {
+        format!("{} ({})", account.username, account.short_id())
+    } else {
+        format!("{} ({}, {})", account.username, account.short_id(), site.trim())
+    }
+}
+
+// 更新账号，返回账号的用户名
+fn update_account(vault: &mut Vault, selector: &Selector, changes: &EntryChanges) -> Result<String, Box<dyn std::error::Error>> {
+    let id = vault.select(selector)?.id.clone();
+    vault.update(&id, changes.password.as_deref(), changes.notes.as_deref())?;
+    
+    let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+    account.add_tags(&changes.tags);
+    account.tags.retain(|tag| !changes.untags.iter().any(|untag| untag.trim() == tag));
+    if let Some(folder) = &changes.folder {
+        account.set_folder(folder);
+    }
+    if let Some(url) = &changes.url {
+        account.set_url(url);
+    }
+    if let Some(kind) = changes.kind {
+        account.kind = kind.into();
+    }
+    if let Some(expires) = &changes.expires {
+        set_expiry(account, expires)?;
+    }
+    set_fields(account, &changes.fields, &changes.secret_fields)?;
+    for name in &changes.remove_fields {
+        if !account.remove_field(name) {
+            return Err(AppError::from(t!("字段不存在: {}", name)).into());
+        }
+    }
+    let username = account.username.clone();
+    save_vault(vault)?;
+    
+    Ok(username)
 }
 
-impl std::fmt::Display for AppError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            AppError::IoError(e) => write!(f, "IO错误: {}", e),
-            AppError::SerdeError(e) => write!(f, "序列化错误: {}", e),
-            AppError::Base64Error(e) => write!(f, "Base64解码错误: {}", e),
-            AppError::AesError(s) => write!(f, "加密/解密错误: {}", s),
-            AppError::OtherError(s) => write!(f, "其他错误: {}", s),
+// 在编辑器中修改账号，内容有误时可以重新编辑
+fn edit_account(vault: &mut Vault, selector: &Selector) -> Result<(), Box<dyn std::error::Error>> {
+    let account = vault.select(selector)?.clone();
+    let original = EntryFile::from_account(&account);
+    let header = t!("编辑账号 {} (ID: {})，保存并退出编辑器后生效，清空文件可以取消修改。\n\
+         expires 可以是有效期（例如 90d）、过期日期（YYYY-MM-DD）或 never；\n\
+         [secret_fields] 中的字段为敏感字段。",
+        account.username,
+        account.short_id()
+    );
+    let mut text = original.to_toml(&header);
+    
+    loop {
+        text = editor::edit(&text, "toml")?;
+        if text.trim().is_empty() {
+            println!("{}", t!("已取消修改"));
+            return Ok(());
         }
+        
+        let mut edited = account.clone();
+        let result = EntryFile::parse(&text)
+            .map_err(AppError::from)
+            .and_then(|file| if file == original { Ok(false) } else { apply_entry_file(&mut edited, &file, &original).map(|_| true) });
+        match result {
+            Ok(false) => {
+                println!("{}", t!("没有修改"));
+                return Ok(());
+            }
+            Ok(true) => {
+                let username = edited.username.clone();
+                *vault.get_mut(&account.id).ok_or_else(|| AppError::from(t!("账号不存在")))? = edited;
+                save_vault(vault)?;
+                println!("{}", success(t!("账号更新成功"), &username));
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{}", i18n::error_message(&e));
+                print!("{}", t!("重新编辑？[Y/n] "));
+                io::stdout().flush()?;
+                let mut answer = String::new();
+                if io::stdin().read_line(&mut answer)? == 0 || matches!(answer.trim(), "n" | "N" | "no") {
+                    println!("{}", t!("已取消修改"));
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// 把编辑后的内容写回账号，只检查改动过的专用字段
+fn apply_entry_file(account: &mut Account, file: &EntryFile, original: &EntryFile) -> Result<(), AppError> {
+    account.username = file.username.trim().to_string();
+    if file.kind != original.kind {
+        account.kind = file.kind()?;
+    }
+    account.set_password(&file.password);
+    account.notes = file.notes.clone();
+    account.set_url(&file.url);
+    account.set_folder(&file.folder);
+    account.tags.clear();
+    account.add_tags(&file.tags);
+    // 有效期按原样写回时不重新计时
+    if file.expires != original.expires {
+        set_expiry(account, &file.expires)?;
+    }
+    
+    account.fields.retain(|field| file.fields.iter().chain(&file.secret_fields).any(|(name, _)| name.trim() == field.name));
+    let changed = |fields: &[(String, String)], secret: bool| -> Vec<(String, String)> {
+        fields
+            .iter()
+            .filter(|(name, value)| account.field(name.trim()).is_none_or(|field| field.value != *value || field.secret != secret))
+            .cloned()
+            .collect()
+    };
+    let (fields, secret_fields) = (changed(&file.fields, false), changed(&file.secret_fields, true));
+    set_fields(account, &fields, &secret_fields)?;
+    account.touch();
+    
+    Ok(())
+}
+
+// get --field 输出的字段
+#[derive(Clone)]
+enum EntryField {
+    Username,
+    Password,
+    Notes,
+    Url,
+    Custom(String),
+}
+
+fn parse_entry_field(text: &str) -> Result<EntryField, String> {
+    match text {
+        "username" => Ok(EntryField::Username),
+        "password" => Ok(EntryField::Password),
+        "notes" => Ok(EntryField::Notes),
+        "url" => Ok(EntryField::Url),
+        _ => match text.strip_prefix("custom:").map(str::trim) {
+            Some(name) if !name.is_empty() => Ok(EntryField::Custom(name.to_string())),
+            _ => Err(t!("应为 username、password、notes、url 或 custom:<名称>").to_string()),
+        },
+    }
+}
+
+// 取出账号中的一个字段，敏感的自定义字段需要 --reveal
+fn entry_field<'a>(account: &'a Account, field: &EntryField, reveal: bool) -> Result<&'a str, AppError> {
+    match field {
+        EntryField::Username => Ok(&account.username),
+        EntryField::Password => Ok(&account.password),
+        EntryField::Notes => Ok(&account.notes),
+        EntryField::Url => account.url.as_deref().ok_or_else(|| AppError::from(t!("该账号没有设置网址"))),
+        EntryField::Custom(name) => match account.field(name) {
+            Some(field) if field.secret && !reveal => {
+                Err(AppError::from(t!("{} 是敏感字段，使用 --reveal 显示", name)))
+            }
+            Some(field) => Ok(&field.value),
+            None => Err(AppError::from(t!("该账号没有字段 {}", name))),
+        },
     }
 }
 
-impl std::error::Error for AppError {}
+// run --env 的一项：环境变量名、账号和字段
+#[derive(Clone)]
+struct EnvBinding {
+    name: String,
+    entry: String,
+    field: EntryField,
+}
 
-impl From<io::Error> for AppError {
-    fn from(err: io::Error) -> Self {
-        AppError::IoError(err)
+// 解析 NAME=ENTRY[#FIELD]；# 后面不是字段名时整个作为用户名
+fn parse_env_binding(text: &str) -> Result<EnvBinding, String> {
+    let Some((name, entry)) = text.split_once('=') else {
+        return Err(t!("格式应为 NAME=ENTRY").to_string());
+    };
+    let name = name.trim();
+    if name.is_empty() || name.contains('\0') {
+        return Err(t!("无效的环境变量名: {}", name));
     }
+    let (entry, field) = match entry.rsplit_once('#').and_then(|(entry, field)| Some((entry, parse_entry_field(field).ok()?))) {
+        Some((entry, field)) => (entry, field),
+        None => (entry, EntryField::Password),
+    };
+    if entry.is_empty() {
+        return Err(t!("格式应为 NAME=ENTRY").to_string());
+    }
+    Ok(EnvBinding { name: name.to_string(), entry: entry.to_string(), field })
 }
 
-impl From<serde_json::Error> for AppError {
-    fn from(err: serde_json::Error) -> Self {
-        AppError::SerdeError(err)
+// 按用户名或 id:<ID> 找到 run --env 指定的账号
+fn resolve_entry<'a>(vault: &'a Vault, entry: &str) -> Result<&'a Account, AppError> {
+    if let Some(id) = entry.strip_prefix("id:") {
+        return vault.select(&Selector { id: Some(id), username: None, site: None, kind: None });
+    }
+    let matches: Vec<&Account> = vault.find(entry).collect();
+    match matches.as_slice() {
+        [] => Err(AppError::from(t!("账号不存在: {}", entry))),
+        [account] => Ok(account),
+        _ => {
+            let ids: Vec<&str> = matches.iter().map(|account| account.short_id()).collect();
+            Err(AppError::from(t!("有 {} 个用户名为 {} 的账号（{}），请改用 id:<ID>", matches.len(), entry, ids.join(", "))))
+        }
     }
 }
 
-impl From<base64::DecodeError> for AppError {
-    fn from(err: base64::DecodeError) -> Self {
-        AppError::Base64Error(err)
+// 解析模板中的 `账号.字段`：去掉最后一个 `.` 及后面的部分能找到账号时以它为字段（username、
+// password、notes、url 或自定义字段的名称），否则整个作为账号，字段为 password
+fn resolve_reference<'a>(vault: &'a Vault, reference: &str) -> Result<(&'a Account, EntryField), AppError> {
+    let Some((entry, field)) = reference.rsplit_once('.') else {
+        return Ok((resolve_entry(vault, reference)?, EntryField::Password));
+    };
+    match resolve_entry(vault, entry) {
+        Ok(account) => Ok((account, parse_entry_field(field).unwrap_or_else(|_| EntryField::Custom(field.to_string())))),
+        // 用户名本身可以带有 `.`，都找不到时报告去掉字段后的账号
+        Err(e) => resolve_entry(vault, reference).map(|account| (account, EntryField::Password)).map_err(|_| e),
     }
 }
 
-impl From<aes_gcm::Error> for AppError {
-    fn from(_: aes_gcm::Error) -> Self {
-        AppError::AesError("加密/解密操作失败".to_string())
+// 按参数登录 HashiCorp Vault：AppRole 或令牌所在的账号从密码库中读取
+fn connect_hashicorp(vault: &Vault, server: &HashicorpOptions) -> Result<hashicorp::Client, Box<dyn std::error::Error>> {
+    let addr = server
+        .addr
+        .clone()
+        .or_else(|| std::env::var("VAULT_ADDR").ok())
+        .filter(|addr| !addr.is_empty())
+        .ok_or_else(|| AppError::from(t!("请用 --addr 或 VAULT_ADDR 环境变量指定 HashiCorp Vault 的地址")))?;
+    let auth = match (&server.approle, &server.token) {
+        (Some(entry), _) => {
+            let account = resolve_entry(vault, entry)?;
+            hashicorp::Auth::AppRole { mount: server.approle_mount.clone(), role_id: account.username.clone(), secret_id: account.password.clone() }
+        }
+        (None, Some(entry)) => hashicorp::Auth::Token(resolve_entry(vault, entry)?.password.clone()),
+        (None, None) => hashicorp::Auth::Token(
+            hashicorp::default_token().ok_or_else(|| AppError::from(t!("没有 HashiCorp Vault 的令牌，请设置 VAULT_TOKEN、运行 vault login，或使用 --token 或 --approle")))?,
+        ),
+    };
+    Ok(hashicorp::Client::connect(&addr, &server.mount, server.kv_version, auth)?)
+}
+
+// 带着环境变量运行命令；Unix 上以命令替换当前进程，解密的密码库不会留在 passman 的内存中，
+// 其他平台上等待命令结束后以它的退出码退出
+fn run_with_env(command: &[String], env: Vec<(String, String)>) -> Result<(), Box<dyn std::error::Error>> {
+    let (program, args) = command.split_first().expect("clap 保证指定了命令");
+    let mut child = std::process::Command::new(program);
+    child.args(args).envs(env);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let e = child.exec();
+        Err(AppError::from(t!("无法运行 {}: {}", program, e)).into())
+    }
+    #[cfg(not(unix))]
+    {
+        let status = child.status().map_err(|e| AppError::from(t!("无法运行 {}: {}", program, e)))?;
+        drop(child);
+        if !status.success() {
+            std::process::exit(status.code().unwrap_or(1));
+        }
+        Ok(())
     }
 }
 
-impl From<&str> for AppError {
-    fn from(s: &str) -> Self {
-        AppError::OtherError(s.to_string())
+// 解析 NAME=VALUE 形式的自定义字段
+fn parse_field(text: &str) -> Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.to_string())),
+        _ => Err(t!("格式应为 NAME=VALUE").to_string()),
     }
 }
 
-impl From<String> for AppError {
-    fn from(s: String) -> Self {
-        AppError::OtherError(s)
+// 设置命令行中指定的自定义字段
+// 类型的专用字段按定义决定是否为敏感字段，并检查其格式
+fn set_fields(account: &mut Account, fields: &[(String, String)], secret_fields: &[(String, String)]) -> Result<(), AppError> {
+    let fields = fields.iter().map(|field| (field, false)).chain(secret_fields.iter().map(|field| (field, true)));
+    for ((name, value), secret) in fields {
+        let kind_field = account.kind.field(name);
+        if kind_field.is_some() {
+            account.kind.check_field(name, value)?;
+        }
+        account.set_field(name, value, secret || kind_field.is_some_and(|field| field.secret))?;
     }
+    Ok(())
+}
+
+// 交互式添加时命令行中已经给出的内容
+struct PromptedFields {
+    password: bool,
+    notes: bool,
+    url: bool,
+    tags: bool,
 }
 
-// 加载账号存储
-fn load_accounts(key: &[u8; 32]) -> Result<AccountStore, AppError> {
-    if !Path::new(DATA_FILE).exists() {
-        return Ok(AccountStore::new());
+// 交互式添加：依次询问用户名、密码、网址、标签和备注，密码不回显
+fn prompt_entry(account: &mut Account, given: &PromptedFields, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    while account.username.trim().is_empty() {
+        account.username = read_input(t!("用户名: "))?.trim().to_string();
     }
     
-    let file_content = fs::read_to_string(DATA_FILE)?;
-    let store: PasswordStore = serde_json::from_str(&file_content)?;
-    
-    // 解码IV
-    let iv = general_purpose::STANDARD.decode(&store.iv)?;
-    let nonce = Nonce::from_slice(&iv);
-    
-    // 解码加密数据
-    let encrypted_data = general_purpose::STANDARD.decode(&store.encrypted_data)?;
-    
-    // 解密
-    let cipher = Aes256Gcm::new(key.into());
-    let decrypted_data = cipher.decrypt(nonce, encrypted_data.as_ref())?;
+    if !given.password {
+        let prompt = if account.kind.is_login() { t!("密码（直接回车生成随机密码）: ") } else { t!("密码（可选）: ") };
+        loop {
+            let password = read_password(prompt)?;
+            if password.is_empty() {
+                if account.kind.is_login() {
+                    account.password = generator::generate_password(&password_options(config, None))?;
+                    println!("{}", t!("生成的密码: {}", account.password));
+                }
+                break;
+            }
+            if read_password(t!("请再次输入密码: "))? == password {
+                account.password = password;
+                break;
+            }
+            eprintln!("{}", t!("两次输入的密码不一致，请重新输入"));
+        }
+    }
     
-    // 解析账号数据
-    let accounts: AccountStore = serde_json::from_slice(&decrypted_data)?;
+    if !given.url {
+        account.set_url(&read_input(t!("网址（可选）: "))?);
+    }
+    if !given.tags {
+        account.add_tags(read_input(t!("标签（以逗号分隔，可选）: "))?.split(','));
+    }
+    if !given.notes {
+        let prompt = if account.kind.is_login() { t!("备注（网站或应用信息）: ") } else { t!("备注（可选）: ") };
+        loop {
+            account.notes = read_input(prompt)?;
+            if !account.kind.is_login() || !account.notes.trim().is_empty() {
+                break;
+            }
+        }
+    }
     
-    Ok(accounts)
+    Ok(())
 }
 
-// 保存账号存储
-fn save_accounts(key: &[u8; 32], accounts: &AccountStore) -> Result<(), AppError> {
-    // 序列化账号数据
-    let data = serde_json::to_vec(accounts)?;
-    
-    // 生成随机IV
-    let iv = rand::random::<[u8; NONCE_LENGTH]>();
-    let nonce = Nonce::from_slice(&iv);
-    
-    // 加密
-    let cipher = Aes256Gcm::new(key.into());
-    let encrypted_data = cipher.encrypt(nonce, data.as_ref())?;
+// 读取一行输入（回显），去掉结尾的换行
+fn read_input(prompt: &str) -> Result<String, io::Error> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
     
-    // 创建密码库结构
-    let store = PasswordStore {
-        iv: general_purpose::STANDARD.encode(iv),
-        encrypted_data: general_purpose::STANDARD.encode(encrypted_data),
-    };
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, t!("输入已结束")));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+// 逐行读取多行的值，直到空行；敏感的值不回显
+fn read_multiline(prompt: &str, secret: bool) -> Result<String, io::Error> {
+    let mut lines = Vec::new();
+    loop {
+        let prompt = if lines.is_empty() { prompt } else { "" };
+        let line = if secret { read_password(prompt)? } else { read_input(prompt)? };
+        if line.trim().is_empty() {
+            return Ok(lines.join("\n"));
+        }
+        lines.push(line);
+    }
+}
+
+// 在终端中依次提示输入类型的专用字段（已经指定的字段除外），直接回车跳过
+fn prompt_kind_fields(account: &mut Account) -> Result<(), Box<dyn std::error::Error>> {
+    if !io::stdin().is_terminal() {
+        return Ok(());
+    }
     
-    // 保存到文件
-    let json = serde_json::to_string(&store)?;
-    fs::write(DATA_FILE, json)?;
+    for field in account.kind.fields() {
+        if account.field(field.name).is_some() {
+            continue;
+        }
+        loop {
+            let prompt = format!("{}: ", i18n::tr(field.label));
+            let value = if field.multiline {
+                read_multiline(&t!("{}（逐行粘贴，以空行结束）: ", i18n::tr(field.label)), field.secret)?
+            } else if field.secret {
+                read_password(&prompt)?
+            } else {
+                read_input(&prompt)?
+            };
+            if value.is_empty() {
+                break;
+            }
+            match account.kind.check_field(field.name, &value) {
+                Ok(()) => {
+                    account.set_field(field.name, &value, field.secret)?;
+                    break;
+                }
+                Err(e) => eprintln!("{}", i18n::error_message(&e)),
+            }
+        }
+    }
     
     Ok(())
 }
 
-// 添加账号
-fn add_account(key: &[u8; 32], username: &str, password: &str, notes: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let mut accounts = load_accounts(key)?;
-    
-    if accounts.contains_key(username) {
-        return Err(Box::new(AppError::from("账号已存在")));
+// 按命令行参数设置过期时间：有效期、日期或 never
+fn set_expiry(account: &mut Account, expires: &str) -> Result<(), AppError> {
+    if matches!(expires.trim(), "" | "never") {
+        account.clear_expiry();
+    } else if let Some(period) = timestamp::parse_duration(expires) {
+        account.expire_after(period);
+    } else if let Some(date) = timestamp::parse_date(expires) {
+        account.expire_at(date);
+    } else {
+        return Err(AppError::from(t!("无法识别的过期时间: {}（例如 90d、6m、2025-12-31）", expires)));
+    }
+    Ok(())
+}
+
+// 用于显示的账号名称：用户名有重复时附加短 ID
+fn display_name(vault: &Vault, account: &Account) -> String {
+    if vault.find(&account.username).nth(1).is_some() {
+        format!("{} ({})", account.username, account.short_id())
+    } else {
+        account.username.clone()
+    }
+}
+
+// list 命令的筛选条件
+struct AccountFilter<'a> {
+    tags: &'a [String],
+    folder: Option<&'a str>,
+    kind: Option<EntryKind>,
+    // --match，匹配用户名或网址
+    query: Option<&'a search::Query>,
+}
+
+impl AccountFilter<'_> {
+    fn matches(&self, account: &Account) -> bool {
+        self.tags.iter().all(|tag| account.tags.iter().any(|t| t == tag.trim()))
+            && self.folder.is_none_or(|folder| account.in_folder(folder))
+            && self.kind.is_none_or(|kind| account.kind == kind)
+            && self.query.is_none_or(|query| query.matches(account, &[search::Field::Username, search::Field::Url]))
+    }
+}
+
+// 列出账号，可以按标签、文件夹和类型筛选，按标签或文件夹分组
+fn list_accounts(vault: &Vault, filter: &AccountFilter, options: &ListOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let ListOptions { columns, group_by, sort, reverse, show_passwords, json, pager } = *options;
+    if vault.list().is_empty() && !json {
+        println!("{}", t!("无存储的账号"));
+        return Ok(());
     }
     
-    accounts.insert(username.to_string(), Account {
-        password: password.to_string(),
-        notes: notes.to_string(),
+    let mut accounts: Vec<&Account> = vault
+        .list()
+        .iter()
+        .filter(|account| filter.matches(account))
+        .collect();
+    accounts.sort_by(|a, b| {
+        let order = match sort {
+            SortBy::Username => a.username.cmp(&b.username),
+            SortBy::Created => b.created_at.cmp(&a.created_at),
+            SortBy::Updated => b.updated_at.cmp(&a.updated_at),
+            SortBy::LastUsed => b.last_used_at.cmp(&a.last_used_at),
+        };
+        order.then_with(|| a.username.cmp(&b.username))
     });
+    if reverse {
+        accounts.reverse();
+    }
     
-    save_accounts(key, &accounts)?;
+    // JSON 中包含标签和文件夹，不再分组
+    if json {
+        let entries: Vec<EntryJson> = accounts.iter().map(|account| EntryJson::summary(account, show_passwords)).collect();
+        output::print(&entries)?;
+        return Ok(());
+    }
+    if accounts.is_empty() {
+        println!("{}", t!("没有符合条件的账号"));
+        return Ok(());
+    }
     
-    Ok(())
-}
-
-// 删除账号
-fn delete_account(key: &[u8; 32], username: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let mut accounts = load_accounts(key)?;
+    let Some(group_by) = group_by else {
+        pager::show(&accounts_table(&accounts, columns, show_passwords), pager)?;
+        return Ok(());
+    };
     
-    if !accounts.contains_key(username) {
-        return Err(Box::new(AppError::from("账号不存在")));
+    // 分组名称及其中的账号，未分组的账号放在最后
+    let mut groups: BTreeMap<&str, Vec<&Account>> = BTreeMap::new();
+    let mut ungrouped = Vec::new();
+    for &account in &accounts {
+        let names: Vec<&str> = match group_by {
+            GroupBy::Tag => account.tags.iter().map(String::as_str).collect(),
+            GroupBy::Folder => account.folder.as_deref().into_iter().collect(),
+        };
+        if names.is_empty() {
+            ungrouped.push(account);
+        }
+        for name in names {
+            groups.entry(name).or_default().push(account);
+        }
     }
     
-    accounts.remove(username);
-    save_accounts(key, &accounts)?;
+    let (label, none) = match group_by {
+        GroupBy::Tag => (t!("标签"), t!("(无标签)")),
+        GroupBy::Folder => (t!("文件夹"), t!("(无文件夹)")),
+    };
+    let mut text = String::new();
+    for (name, accounts) in &groups {
+        text += &format!("{}: {} ({})\n", label, name, accounts.len());
+        text += &accounts_table(accounts, columns, show_passwords);
+    }
+    if !ungrouped.is_empty() {
+        text += &format!("{} ({})\n", none, ungrouped.len());
+        text += &accounts_table(&ungrouped, columns, show_passwords);
+    }
+    pager::show(&text, pager)?;
     
     Ok(())
 }
 
-// 更新账号
-fn update_account(
-    key: &[u8; 32],
-    username: &str,
-    password: &Option<String>,
-    notes: &Option<String>
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut accounts = load_accounts(key)?;
+// 账号表格
+fn accounts_table(accounts: &[&Account], columns: &[String], show_passwords: bool) -> String {
+    let mut table = Table::new();
+    table.add_row(Row::new(columns.iter().map(|column| match column.as_str() {
+        "id" => Cell::new("ID"),
+        "type" => Cell::new(t!("类型")),
+        "username" => Cell::new(t!("用户名")),
+        "password" => Cell::new(t!("密码")),
+        "url" => Cell::new(t!("网址")),
+        "folder" => Cell::new(t!("文件夹")),
+        "tags" => Cell::new(t!("标签")),
+        "created" => Cell::new(t!("创建时间")),
+        "updated" => Cell::new(t!("修改时间")),
+        "last-used" => Cell::new(t!("最后使用")),
+        "expires" => Cell::new(t!("过期时间")),
+        _ => Cell::new(t!("备注")),
+    }).collect()));
+    
+    for account in accounts {
+        table.add_row(Row::new(columns.iter().map(|column| match column.as_str() {
+            "id" => Cell::new(account.short_id()),
+            "type" => Cell::new(i18n::tr(account.kind.label())),
+            "username" => color::cell(Role::Name, &account.username),
+            "password" => Cell::new(masked_password(account, show_passwords)),
+            "url" => Cell::new(account.url.as_deref().unwrap_or("")),
+            "folder" => Cell::new(account.folder.as_deref().unwrap_or("")),
+            "tags" => Cell::new(&account.tags.join(", ")),
+            "created" => Cell::new(&format_time(account.created_at)),
+            "updated" => Cell::new(&format_time(account.updated_at)),
+            "last-used" => Cell::new(&account.last_used_at.map(format_time).unwrap_or_default()),
+            "expires" => account.expires_at.map(expiry_cell).unwrap_or_else(|| Cell::new("")),
+            // 安全笔记的内容只在 get 和 note get 中显示
+            _ if account.kind == EntryKind::Note => Cell::new(SECRET_MASK),
+            _ => Cell::new(&account.notes),
+        }).collect()));
+    }
     
-    if !accounts.contains_key(username) {
-        return Err(Box::new(AppError::from("账号不存在")));
+    color::render(&table)
+}
+
+// 未指定 --show-passwords 时密码显示为掩码（没有密码时为空）
+fn masked_password(account: &Account, show_passwords: bool) -> &str {
+    if show_passwords || account.password.is_empty() { &account.password } else { SECRET_MASK }
+}
+
+// 获取特定账号
+fn get_account(vault: &Vault, selector: &Selector, reveal: bool, show_passwords: bool, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let account = vault.select(selector)?;
+    if json {
+        output::print(&EntryJson::detail(account, reveal, show_passwords))?;
+        return Ok(());
     }
     
-    let account = accounts.get_mut(username).unwrap();
+    let mut header = vec![
+        Cell::new("ID"),
+        Cell::new(t!("用户名")),
+        Cell::new(t!("密码")),
+        Cell::new(t!("备注")),
+    ];
+    let mut row = vec![
+        Cell::new(&account.id),
+        color::cell(Role::Name, &account.username),
+        Cell::new(masked_password(account, show_passwords)),
+        Cell::new(&account.notes),
+    ];
     
-    if let Some(password) = password {
-        account.password = password.clone();
+    // 类型、网址、文件夹和标签只在设置后显示
+    if !account.kind.is_login() {
+        header.push(Cell::new(t!("类型")));
+        row.push(Cell::new(i18n::tr(account.kind.label())));
+    }
+    if let Some(url) = &account.url {
+        header.push(Cell::new(t!("网址")));
+        row.push(Cell::new(url));
+    }
+    if let Some(folder) = &account.folder {
+        header.push(Cell::new(t!("文件夹")));
+        row.push(Cell::new(folder));
     }
+    if !account.tags.is_empty() {
+        header.push(Cell::new(t!("标签")));
+        row.push(Cell::new(&account.tags.join(", ")));
+    }
+    header.push(Cell::new(t!("创建时间")));
+    row.push(Cell::new(&format_time(account.created_at)));
+    header.push(Cell::new(t!("修改时间")));
+    row.push(Cell::new(&format_time(account.updated_at)));
+    if let Some(expires_at) = account.expires_at {
+        header.push(Cell::new(t!("过期时间")));
+        row.push(color::cell(Role::Expiring, &format!("{}\n{}", format_time(expires_at), expiry_status(expires_at))));
+    }
+    
+    let mut table = Table::new();
+    table.add_row(Row::new(header));
+    table.add_row(Row::new(row));
+    color::print_table(&table);
     
-    if let Some(notes) = notes {
-        account.notes = notes.clone();
+    // 自定义字段单独列出，敏感字段默认隐藏
+    if !account.fields.is_empty() {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new(t!("字段")),
+            Cell::new(t!("值")),
+        ]));
+        for field in &account.fields {
+            let value = if field.secret && !reveal { SECRET_MASK } else { field.value.as_str() };
+            let label = account.kind.field(&field.name).map_or(field.name.as_str(), |f| i18n::tr(f.label));
+            table.add_row(Row::new(vec![
+                Cell::new(label),
+                Cell::new(value),
+            ]));
+        }
+        color::print_table(&table);
     }
     
-    save_accounts(key, &accounts)?;
+    if !account.attachments.is_empty() {
+        let names: Vec<&str> = account.attachments.iter().map(|attachment| attachment.name.as_str()).collect();
+        println!("{}", t!("附件: {}", names.join(", ")));
+    }
     
     Ok(())
 }
 
-// 列出所有账号
-fn list_accounts(key: &[u8; 32]) -> Result<(), Box<dyn std::error::Error>> {
-    let accounts = load_accounts(key)?;
-    
+// 列出在 `within` 秒内过期（包括已经过期）的账号，最早过期的在前
+fn list_expiring(vault: &Vault, within: u64) {
+    let deadline = timestamp::now().saturating_add(within);
+    let mut accounts: Vec<(&Account, u64)> = vault
+        .list()
+        .iter()
+        .filter_map(|account| account.expires_at.map(|expires_at| (account, expires_at)))
+        .filter(|&(_, expires_at)| expires_at <= deadline)
+        .collect();
     if accounts.is_empty() {
-        println!("无存储的账号");
-        return Ok(());
+        println!("{}", t!("没有需要更换的密码"));
+        return;
     }
+    accounts.sort_by_key(|&(account, expires_at)| (expires_at, account.username.clone()));
+    
+    let expired = accounts.iter().filter(|&&(_, expires_at)| expires_at <= timestamp::now()).count();
+    println!("{}", color::paint(Role::Expiring, &t!("{} 个密码已过期，{} 个即将过期", expired, accounts.len() - expired)));
     
     let mut table = Table::new();
     table.add_row(Row::new(vec![
-        Cell::new("用户名"),
-        Cell::new("密码"),
-        Cell::new("备注"),
+        Cell::new(t!("用户名")),
+        Cell::new(t!("备注")),
+        Cell::new(t!("过期时间")),
+        Cell::new(t!("状态")),
     ]));
     
-    for (username, account) in accounts {
+    for (account, expires_at) in accounts {
         table.add_row(Row::new(vec![
-            Cell::new(&username),
-            Cell::new(&account.password),
-            Cell::new(&account.notes),
+            color::cell(Role::Name, &display_name(vault, account)),
+            Cell::new(account.notes.lines().next().unwrap_or("")),
+            Cell::new(&format_time(expires_at)),
+            color::cell(Role::Expiring, &expiry_status(expires_at)),
         ]));
     }
     
-    table.printstd();
-    
-    Ok(())
+    color::print_table(&table);
 }
 
-// 获取特定账号
-fn get_account(key: &[u8; 32], username: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let accounts = load_accounts(key)?;
-    
-    if !accounts.contains_key(username) {
-        return Err(Box::new(AppError::from("账号不存在")));
+// 列表中的过期时间，已经过期时着色
+fn expiry_cell(expires_at: u64) -> Cell {
+    let text = format_time(expires_at);
+    if expires_at <= timestamp::now() { color::cell(Role::Expiring, &text) } else { Cell::new(&text) }
+}
+
+// 距离过期的天数描述
+fn expiry_status(expires_at: u64) -> String {
+    let now = timestamp::now();
+    if expires_at <= now {
+        t!("已过期 {} 天", (now - expires_at) / timestamp::DAY)
+    } else {
+        match (expires_at - now).div_ceil(timestamp::DAY) {
+            1 => t!("1 天内过期").to_string(),
+            days => t!("{} 天后过期", days),
+        }
+    }
+}
+
+// 显示历史密码
+fn show_history(account: &Account) {
+    if account.history.is_empty() {
+        println!("{}", t!("账号 {} 没有历史密码", account.username));
+        return;
     }
-    
-    let account = accounts.get(username).unwrap();
     
     let mut table = Table::new();
     table.add_row(Row::new(vec![
-        Cell::new("用户名"),
-        Cell::new("密码"),
-        Cell::new("备注"),
+        Cell::new(t!("序号")),
+        Cell::new(t!("密码")),
+        Cell::new(t!("更换时间")),
     ]));
     
-    table.add_row(Row::new(vec![
-        Cell::new(username),
-        Cell::new(&account.password),
-        Cell::new(&account.notes),
-    ]));
+    for (index, entry) in account.history.iter().enumerate() {
+        table.add_row(Row::new(vec![
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(&entry.password),
+            Cell::new(&format_time(entry.replaced_at)),
+        ]));
+    }
+    
+    color::print_table(&table);
+}
+
+// 一次性密码操作
+fn otp_command(mut vault: Vault, action: &OtpAction, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        OtpAction::Set { entry, secret, hotp, steam, counter, digits, period, algorithm } => {
+            let kind = if *hotp {
+                OtpKind::Hotp { counter: *counter }
+            } else if *steam {
+                OtpKind::Steam
+            } else {
+                OtpKind::Totp { period: *period }
+            };
+            let otp = Otp::with_algorithm(secret, *digits, kind, (*algorithm).into())?;
+            
+            let id = vault.select(&entry.selector())?.id.clone();
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            account.otp = Some(otp);
+            account.touch();
+            let username = account.username.clone();
+            save_vault(&mut vault)?;
+            println!("{}", success(t!("一次性密码设置成功"), &username));
+        }
+        
+        OtpAction::Code { entry, copy, watch } => {
+            let id = vault.select(&entry.selector())?.id.clone();
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            let otp = account.otp.as_mut().ok_or_else(|| AppError::from(t!("该账号未设置一次性密码")))?;
+            if *watch {
+                let Some(period) = otp.kind.period() else {
+                    return Err(AppError::from(t!("--watch 不支持基于计数器的 HOTP")).into());
+                };
+                // 持续显示期间不需要密码库，先关闭它并释放锁，其他 passman 命令可以照常使用
+                let otp = otp.clone();
+                record_use(&mut vault, &id);
+                drop(vault);
+                return Ok(watch_otp(otp, period)?);
+            }
+            let code = otp.generate()?;
+            
+            // HOTP 计数器已递增，需要立即保存
+            if let OtpKind::Hotp { counter } = otp.kind {
+                save_vault(&mut vault)?;
+                println!("{}", t!("计数器: {}", counter - 1));
+            }
+            
+            output_secret(&code, *copy, config)?;
+            record_use(&mut vault, &id);
+        }
+        
+        OtpAction::Uri { entry, qr } => {
+            let account = vault.select(&entry.selector())?;
+            let otp = account.otp.as_ref().ok_or_else(|| AppError::from(t!("该账号未设置一次性密码")))?;
+            let uri = otp.to_uri(&account.username);
+            if *qr {
+                print_qr(&uri)?;
+                println!("{}", t!("用身份验证器应用扫描二维码即可添加 {}", account.username));
+            } else {
+                println!("{}", uri);
+            }
+            let id = account.id.clone();
+            record_use(&mut vault, &id);
+        }
+        
+        OtpAction::Remove { entry } => {
+            let id = vault.select(&entry.selector())?.id.clone();
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            if account.otp.take().is_none() {
+                return Err(Box::new(AppError::from(t!("该账号未设置一次性密码"))));
+            }
+            account.touch();
+            let username = account.username.clone();
+            save_vault(&mut vault)?;
+            println!("{}", t!("一次性密码已移除: {}", username));
+        }
+    }
+    
+    Ok(())
+}
+
+// 安全笔记
+fn note_command(vault: &mut Vault, action: &NoteAction, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        NoteAction::Add { title, content, tags, folder } => {
+            let content = read_note(content.as_deref())?;
+            let mut account = Account::new(title, "", &content);
+            account.kind = EntryKind::Note;
+            account.add_tags(tags);
+            account.set_folder(folder.as_deref().unwrap_or(""));
+            let id = add_account(vault, account)?;
+            println!("{} (ID: {})", success(t!("笔记添加成功"), title), id);
+        }
+        
+        NoteAction::Get { note, copy } => {
+            let account = vault.select(&note.selector())?;
+            output_secret(&account.notes, *copy, config)?;
+            let id = account.id.clone();
+            record_use(vault, &id);
+        }
+        
+        NoteAction::Edit { note, content, append } => {
+            let account = vault.select(&note.selector())?;
+            let (id, title) = (account.id.clone(), account.username.clone());
+            let mut content = read_note(content.as_deref())?;
+            if *append {
+                content = format!("{}\n{}", account.notes, content);
+            }
+            vault.update(&id, None, Some(&content))?;
+            save_vault(vault)?;
+            println!("{}", success(t!("笔记修改成功"), &title));
+        }
+    }
+    
+    Ok(())
+}
+
+// 笔记内容：命令行参数，或者从标准输入读取直到结束
+fn read_note(content: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let content = match content {
+        Some(content) => content.to_string(),
+        None => {
+            if io::stdin().is_terminal() {
+                eprintln!("{}", t!("请输入笔记内容，输入完成后按 Ctrl-D 结束："));
+            }
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            content
+        }
+    };
+    
+    let content = content.trim_end_matches(['\r', '\n']);
+    if content.trim().is_empty() {
+        return Err(AppError::from(t!("笔记内容不能为空")).into());
+    }
+    Ok(content.to_string())
+}
+
+// 附件操作
+fn attach_command(vault_config: &VaultConfig, action: &AttachAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        AttachAction::Add { entry, file, name } => {
+            // 先读取文件，避免输入主密钥后才发现文件有问题
+            let data = fs::read(file)?;
+            let name = match name {
+                Some(name) => name.clone(),
+                None => file
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .ok_or_else(|| AppError::from(t!("无法确定文件名: {}", file.display())))?,
+            };
+            let size = data.len();
+            
+            let mut vault = open_vault(vault_config)?;
+            let id = vault.select(&entry.selector())?.id.clone();
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            account.attach(&name, data)?;
+            account.touch();
+            let username = account.username.clone();
+            save_vault(&mut vault)?;
+            println!("{} ({}) -> {}", success(t!("附件添加成功"), &name), format_size(size), color::paint(Role::Name, &username));
+        }
+        
+        AttachAction::Get { entry, name, output, force } => {
+            let vault = open_vault(vault_config)?;
+            let account = vault.select(&entry.selector())?;
+            let attachment = account
+                .attachment(name)
+                .ok_or_else(|| AppError::from(t!("附件不存在: {}", name)))?;
+                
+            if output.as_deref() == Some(Path::new("-")) {
+                io::stdout().write_all(&attachment.data)?;
+                return Ok(());
+            }
+            // 附件名称可能来自导入的文件，只取最后一段，避免写到当前目录之外
+            let path = match output {
+                Some(path) => path.clone(),
+                None => PathBuf::from(Path::new(&attachment.name).file_name().unwrap_or_default()),
+            };
+            write_private_file(&path, &attachment.data, *force)?;
+            println!("{}", t!("附件已保存到 {}", path.display()));
+        }
+        
+        AttachAction::List { entry } => {
+            let vault = open_vault(vault_config)?;
+            let account = vault.select(&entry.selector())?;
+            if account.attachments.is_empty() {
+                println!("{}", t!("账号 {} 没有附件", account.username));
+                return Ok(());
+            }
+            
+            let mut table = Table::new();
+            table.add_row(Row::new(vec![
+                Cell::new(t!("名称")),
+                Cell::new(t!("大小")),
+                Cell::new(t!("添加时间")),
+            ]));
+            for attachment in &account.attachments {
+                table.add_row(Row::new(vec![
+                    Cell::new(&attachment.name),
+                    Cell::new(&format_size(attachment.data.len())),
+                    Cell::new(&format_time(attachment.added_at)),
+                ]));
+            }
+            color::print_table(&table);
+        }
+        
+        AttachAction::Remove { entry, name } => {
+            let mut vault = open_vault(vault_config)?;
+            let id = vault.select(&entry.selector())?.id.clone();
+            let account = vault.get_mut(&id).ok_or_else(|| AppError::from(t!("账号不存在")))?;
+            if !account.remove_attachment(name) {
+                return Err(AppError::from(t!("附件不存在: {}", name)).into());
+            }
+            account.touch();
+            let username = account.username.clone();
+            save_vault(&mut vault)?;
+            println!("{}", t!("附件已删除: {} ({})", name, username));
+        }
+    }
     
-    table.printstd();
+    Ok(())
+}
+
+// 写入只有当前用户可以读写的文件，默认不覆盖已存在的文件
+fn write_private_file(path: &Path, data: &[u8], overwrite: bool) -> Result<(), AppError> {
+    if !overwrite && fs::symlink_metadata(path).is_ok() {
+        return Err(AppError::from(t!("文件已存在: {}（使用 --force 覆盖）", path.display())));
+    }
+    atomic::write_private(path, data)?;
+    Ok(())
+}
+
+// 以 B、KB、MB 显示文件大小
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1048576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1048576.0),
+    }
+}
+
+// 密码审计
+fn audit_command(vault_config: &VaultConfig, action: &AuditAction, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        AuditAction::Strength { all } => {
+            let vault = open_vault(vault_config)?;
+            let mut results: Vec<_> = vault
+                .list()
+                .iter()
+                .map(|account| (account, strength::estimate(&account.password)))
+                .collect();
+            results.sort_by(|a, b| a.1.guesses.total_cmp(&b.1.guesses));
+            
+            let checked = results.len();
+            let weak = results.iter().filter(|(_, estimate)| estimate.score < 3).count();
+            if !*all {
+                results.retain(|(_, estimate)| estimate.score < 3);
+            }
+            if json {
+                let accounts = results.iter().map(|(account, estimate)| output::StrengthJson::new(account, estimate)).collect();
+                output::print(&output::StrengthReport { checked, weak, accounts })?;
+                return Ok(());
+            }
+            
+            let summary = t!("共检查 {} 个账号，其中 {} 个密码强度较弱", checked, weak);
+            println!("{}", if weak > 0 { color::paint(Role::Warning, &summary) } else { summary });
+            if results.is_empty() {
+                return Ok(());
+            }
+            
+            let mut table = Table::new();
+            table.add_row(Row::new(vec![
+                Cell::new(t!("用户名")),
+                Cell::new(t!("评分")),
+                Cell::new(t!("离线破解时间")),
+                Cell::new(t!("问题")),
+                Cell::new(t!("建议")),
+            ]));
+            
+            for (account, estimate) in &results {
+                // 较弱的密码着色为警告
+                let warn = |text: &str| if estimate.score < 3 { color::cell(Role::Warning, text) } else { Cell::new(text) };
+                table.add_row(Row::new(vec![
+                    color::cell(Role::Name, &display_name(&vault, account)),
+                    warn(&format!("{}/4", estimate.score)),
+                    Cell::new(&i18n::translate(&strength::display_time(estimate.offline_seconds))),
+                    warn(i18n::tr(estimate.warning.as_deref().unwrap_or(""))),
+                    Cell::new(&estimate.suggestions.iter().map(|suggestion| i18n::tr(suggestion)).collect::<Vec<_>>().join("\n")),
+                ]));
+            }
+            
+            color::print_table(&table);
+        }
+        
+        AuditAction::Reuse { exact } => {
+            let vault = open_vault(vault_config)?;
+            let groups = audit::find_reused(vault.list(), !*exact);
+            if json {
+                let groups: Vec<output::ReuseJson> = groups
+                    .iter()
+                    .map(|group| output::ReuseJson {
+                        identical: group.identical,
+                        accounts: group.ids.iter().filter_map(|id| vault.get(id)).map(output::AccountRef::new).collect(),
+                    })
+                    .collect();
+                output::print(&groups)?;
+                return Ok(());
+            }
+            if groups.is_empty() {
+                println!("{}", t!("未发现重复使用的密码"));
+                return Ok(());
+            }
+            
+            let total: usize = groups.iter().map(|group| group.ids.len()).sum();
+            println!("{}", t!("发现 {} 组共用密码的账号，涉及 {} 个账号", groups.len(), total));
+            
+            let mut table = Table::new();
+            table.add_row(Row::new(vec![
+                Cell::new(t!("组")),
+                Cell::new(t!("类型")),
+                Cell::new(t!("用户名")),
+            ]));
+            
+            for (index, group) in groups.iter().enumerate() {
+                let names: Vec<String> = group
+                    .ids
+                    .iter()
+                    .filter_map(|id| vault.get(id))
+                    .map(|account| display_name(&vault, account))
+                    .collect();
+                table.add_row(Row::new(vec![
+                    Cell::new(&(index + 1).to_string()),
+                    Cell::new(if group.identical { t!("相同") } else { t!("近似") }),
+                    Cell::new(&names.join("\n")),
+                ]));
+            }
+            
+            color::print_table(&table);
+        }
+        
+        AuditAction::Breach { offline, db } => {
+            let bloom = db.as_deref().map(BloomFilter::open).transpose()?;
+            let vault = open_vault(vault_config)?;
+            let mut checker = BreachChecker::new(config::cache_dir().map(|dir| dir.join("hibp")), *offline);
+            let mut accounts: Vec<&Account> = vault.list().iter().collect();
+            accounts.sort_by(|a, b| a.username.cmp(&b.username));
+            
+            let mut breached = Vec::new();
+            let mut unchecked = 0;
+            for (index, &account) in accounts.iter().enumerate() {
+                eprint!("\r{}", t!("正在检查: {}/{}", index + 1, accounts.len()));
+                let _ = io::stderr().flush();
+                let result = match &bloom {
+                    Some(bloom) if bloom.contains(&account.password) => Ok(Breach::Listed),
+                    Some(_) => Ok(Breach::NotFound),
+                    None => checker.check(&account.password),
+                };
+                match result {
+                    Ok(Breach::Found(count)) => breached.push((account, Some(count))),
+                    Ok(Breach::Listed) => breached.push((account, None)),
+                    Ok(Breach::NotFound) => {}
+                    Ok(Breach::Unchecked) => unchecked += 1,
+                    Err(e) => {
+                        eprintln!();
+                        eprintln!("{}", t!("无法联网时可以使用 --offline 只检查本地缓存"));
+                        return Err(e.into());
+                    }
+                }
+            }
+            if !accounts.is_empty() {
+                eprintln!();
+            }
+            
+            breached.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            if json {
+                let breached = breached
+                    .iter()
+                    .map(|&(account, count)| output::BreachJson { account: output::AccountRef::new(account), count })
+                    .collect();
+                output::print(&output::BreachReport { checked: accounts.len() - unchecked, unchecked, breached })?;
+                return Ok(());
+            }
+            
+            println!("{}", t!("共检查 {} 个账号，其中 {} 个密码出现在已知泄露中", accounts.len() - unchecked, breached.len()));
+            if unchecked > 0 {
+                println!("{}", t!("{} 个账号没有本地缓存，未能检查", unchecked));
+            }
+            if let Some(bloom) = &bloom {
+                let rate = format!("{:.3}", bloom.false_positive_rate() * 100.0);
+                println!("{}", t!("本地泄露数据库收录 {} 个密码，误报率约 {}%", bloom.count(), rate));
+            }
+            if breached.is_empty() {
+                return Ok(());
+            }
+            
+            let mut table = Table::new();
+            table.add_row(Row::new(vec![
+                Cell::new(t!("用户名")),
+                Cell::new(t!("泄露次数")),
+            ]));
+            
+            for (account, count) in &breached {
+                table.add_row(Row::new(vec![
+                    color::cell(Role::Name, &display_name(&vault, account)),
+                    color::cell(Role::Warning, &count.map(|count| count.to_string()).unwrap_or_else(|| "-".to_string())),
+                ]));
+            }
+            
+            color::print_table(&table);
+        }
+        
+        AuditAction::BuildDb { false_positive_rate, input, output } => {
+            eprint!("{}", t!("正在生成泄露数据库..."));
+            let input = fs::File::open(input)?;
+            // 生成失败时不会留下不完整的数据库
+            let count = atomic::write_with(output, false, |file| audit::build_bloom(&input, io::BufWriter::new(file), *false_positive_rate))?;
+            eprintln!("{}", t!(" 完成"));
+            println!("{}", t!("已收录 {} 个密码: {}", count, output.display()));
+        }
+    }
     
     Ok(())
 }
+
+// 打印导入结果
+fn print_import_report(report: &ImportReport) {
+    println!("{}", t!("成功导入 {} 个账号，跳过 {} 个", report.imported.len(), report.skipped.len()));
+    if !report.replaced.is_empty() {
+        println!("{}", t!("覆盖了 {} 个已有的账号: {}", report.replaced.len(), report.replaced.join(", ")));
+    }
+    
+    if !report.imported.is_empty() {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![Cell::new(t!("已导入"))]));
+        
+        for username in &report.imported {
+            table.add_row(Row::new(vec![Cell::new(username)]));
+        }
+        
+        color::print_table(&table);
+    }
+    
+    if !report.skipped.is_empty() {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new(t!("名称")),
+            Cell::new(t!("跳过原因")),
+        ]));
+        
+        for (name, reason) in &report.skipped {
+            table.add_row(Row::new(vec![
+                Cell::new(name),
+                Cell::new(&i18n::translate(reason)),
+            ]));
+        }
+        
+        color::print_table(&table);
+    }
+}
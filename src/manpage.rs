@@ -0,0 +1,150 @@
+// 根据 clap 的命令定义生成 roff 格式的 man 手册
+//
+// 主命令的手册为 passman(1)，每个子命令各有一页，例如 passman-note-add(1)。
+
+use clap::{Arg, Command};
+
+use crate::i18n::t;
+
+/// 一页手册：文件名（例如 `passman-note.1`）和内容
+pub struct Page {
+    pub file_name: String,
+    pub content: String,
+}
+
+/// 生成主命令和所有子命令的手册
+pub fn pages(mut command: Command) -> Vec<Page> {
+    // build 之后才能看到全局选项和自动添加的 --help
+    command.build();
+    let mut pages = Vec::new();
+    collect(&command, &[command.get_name()], &mut pages);
+    pages
+}
+
+fn collect(command: &Command, path: &[&str], pages: &mut Vec<Page>) {
+    let name = path.join("-");
+    pages.push(Page { file_name: format!("{}.1", name), content: render(command, path) });
+
+    for sub in visible_subcommands(command) {
+        let mut sub_path = path.to_vec();
+        sub_path.push(sub.get_name());
+        collect(sub, &sub_path, pages);
+    }
+}
+
+// 隐藏的子命令和 clap 自动添加的 help 子命令不生成手册
+fn visible_subcommands(command: &Command) -> impl Iterator<Item = &Command> {
+    command.get_subcommands().filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+}
+
+fn render(command: &Command, path: &[&str]) -> String {
+    let name = path.join("-");
+    let about = command.get_about().map(ToString::to_string).unwrap_or_default();
+    let mut page = format!(".TH {} 1 \"\" \"passman {}\"\n", escape(&name.to_uppercase()), env!("CARGO_PKG_VERSION"));
+
+    page += &format!(".SH NAME\n{} \\- {}\n", escape(&name), escape(&about));
+
+    page += ".SH SYNOPSIS\n";
+    page += &format!("\\fB{}\\fR", escape(&path.join(" ")));
+    let options: Vec<&Arg> = command.get_arguments().filter(|arg| !arg.is_positional() && !arg.is_hide_set()).collect();
+    if !options.is_empty() {
+        page += " [\\fIOPTIONS\\fR]";
+    }
+    for arg in command.get_positionals().filter(|arg| !arg.is_hide_set()) {
+        let value = format!("\\fI{}\\fR", escape(&value_name(arg)));
+        page += &if arg.is_required_set() { format!(" {}", value) } else { format!(" [{}]", value) };
+    }
+    if command.has_subcommands() {
+        page += " \\fICOMMAND\\fR";
+    }
+    page += "\n";
+
+    if let Some(long_about) = command.get_long_about() {
+        page += &format!(".SH DESCRIPTION\n{}\n", paragraphs(&long_about.to_string()));
+    }
+
+    let positionals: Vec<&Arg> = command.get_positionals().filter(|arg| !arg.is_hide_set()).collect();
+    if !positionals.is_empty() {
+        page += ".SH ARGUMENTS\n";
+        for arg in positionals {
+            page += &format!(".TP\n\\fI{}\\fR\n{}", escape(&value_name(arg)), describe(arg));
+        }
+    }
+
+    if !options.is_empty() {
+        page += ".SH OPTIONS\n";
+        for arg in options {
+            let mut flags = Vec::new();
+            if let Some(short) = arg.get_short() {
+                flags.push(format!("\\fB\\-{}\\fR", short));
+            }
+            if let Some(long) = arg.get_long() {
+                flags.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+            }
+            let mut line = flags.join(", ");
+            if arg.get_action().takes_values() {
+                line += &format!(" \\fI{}\\fR", escape(&value_name(arg)));
+            }
+            page += &format!(".TP\n{}\n{}", line, describe(arg));
+        }
+    }
+
+    let subcommands: Vec<&Command> = visible_subcommands(command).collect();
+    if !subcommands.is_empty() {
+        page += ".SH COMMANDS\n";
+        for sub in &subcommands {
+            let sub_name = format!("{}-{}", name, sub.get_name());
+            let about = sub.get_about().map(ToString::to_string).unwrap_or_default();
+            page += &format!(".TP\n\\fB{}\\fR\n{}\n.br\n{}(1)\n", escape(sub.get_name()), escape(&about), escape(&sub_name));
+        }
+    }
+
+    // 子命令的手册指向上一级命令
+    if path.len() > 1 {
+        page += &format!(".SH SEE ALSO\n{}(1)\n", escape(&path[..path.len() - 1].join("-")));
+    }
+    page
+}
+
+fn value_name(arg: &Arg) -> String {
+    match arg.get_value_names() {
+        Some(names) if !names.is_empty() => names.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "),
+        _ => arg.get_id().as_str().to_uppercase(),
+    }
+}
+
+// 参数说明，以及可选值和默认值
+fn describe(arg: &Arg) -> String {
+    let help = arg.get_long_help().or(arg.get_help()).map(ToString::to_string).unwrap_or_default();
+    let mut text = paragraphs(&help);
+    let values: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    if !values.is_empty() && arg.get_action().takes_values() {
+        text += &format!("\n.br\n{}", t!("可选值: {}", escape(&values.join(", "))));
+    }
+    let defaults: Vec<String> = arg.get_default_values().iter().map(|value| value.to_string_lossy().into_owned()).collect();
+    if !defaults.is_empty() && arg.get_action().takes_values() {
+        text += &format!("\n.br\n{}", t!("默认值: {}", escape(&defaults.join(", "))));
+    }
+    text + "\n"
+}
+
+// 空行分隔的段落
+fn paragraphs(text: &str) -> String {
+    text.split("\n\n").map(|paragraph| escape(paragraph.trim())).collect::<Vec<_>>().join("\n.PP\n")
+}
+
+// 转义反斜杠和连字符，以 . 或 ' 开头的行前加 \&，以免被当作 roff 请求
+fn escape(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let line = line.replace('\\', "\\e").replace('-', "\\-");
+            if line.starts_with('.') || line.starts_with('\'') { format!("\\&{}", line) } else { line }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
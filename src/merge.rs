@@ -0,0 +1,154 @@
+//! 同步时的三方合并
+//!
+//! 以上次同步时的账号为共同祖先，按账号 ID 比较本地和远程的版本：只有一边修改（添加、
+//! 更新或删除）的账号采用修改后的版本，两边改成相同内容的视为一致，两边都修改且内容不同
+//! 的账号作为冲突（[`Conflict`]）交给调用者选择。只有使用时间不同的账号不算修改，合并后
+//! 取较晚的使用时间。没有共同祖先时（第一次同步两个已有的密码库）只有一边有的账号都保留。
+//!
+//! ```
+//! use passman::merge::{self, Resolution};
+//! use passman::Account;
+//!
+//! let mut base = Account::new("alice", "old", "github");
+//! base.id = "1".to_string();
+//! let mut local = base.clone();
+//! local.set_password("local");
+//! let mut remote = base.clone();
+//! remote.notes = "github.com".to_string();
+//!
+//! let merged = merge::merge(&[base], &[local], &[remote]);
+//! assert_eq!(merged.conflicts().len(), 1);
+//! let accounts = merged.resolve(|_| Ok::<_, ()>(Resolution::Local)).unwrap();
+//! assert_eq!(accounts[0].password, "local");
+//! ```
+
+use std::collections::HashMap;
+
+use crate::vault::{new_id, Account, AccountStore};
+
+/// 两边都修改了同一个账号
+pub struct Conflict {
+    /// 上次同步时的版本，没有共同祖先或两边都是新添加的账号时为 None
+    pub base: Option<Account>,
+    /// 本地的版本，本地已删除时为 None
+    pub local: Option<Account>,
+    /// 远程的版本，远程已删除时为 None
+    pub remote: Option<Account>,
+}
+
+/// 如何解决一个冲突
+#[derive(Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// 保留本地的版本（本地已删除时删除）
+    Local,
+    /// 保留远程的版本（远程已删除时删除）
+    Remote,
+    /// 两个版本都保留，远程的版本以新的 ID 另存为一个账号
+    Both,
+}
+
+/// 合并的结果
+pub struct Merge {
+    slots: Vec<Slot>,
+    conflicts: Vec<Conflict>,
+    /// 从远程采用的新账号数量
+    pub added: usize,
+    /// 从远程采用的修改数量
+    pub updated: usize,
+    /// 从远程采用的删除数量
+    pub deleted: usize,
+}
+
+// 合并后的一个位置：确定的账号，或者第几个冲突
+enum Slot {
+    Account(Box<Account>),
+    Conflict(usize),
+}
+
+/// 以 `base` 为共同祖先合并 `local` 和 `remote`
+///
+/// 合并后的账号按本地的顺序排列，远程新添加的账号排在最后。
+pub fn merge(base: &[Account], local: &[Account], remote: &[Account]) -> Merge {
+    let index = |accounts: &[Account]| -> HashMap<String, Account> {
+        accounts.iter().map(|account| (account.id.clone(), account.clone())).collect()
+    };
+    let (base_map, local_map, remote_map) = (index(base), index(local), index(remote));
+    let ids = local.iter().chain(remote.iter().filter(|account| !local_map.contains_key(&account.id))).map(|account| &account.id);
+
+    let mut merge = Merge { slots: Vec::new(), conflicts: Vec::new(), added: 0, updated: 0, deleted: 0 };
+    for id in ids {
+        let (base, local, remote) = (base_map.get(id), local_map.get(id), remote_map.get(id));
+        let chosen = if same(local, remote) {
+            local
+        } else if same(base, local) {
+            match (local, remote) {
+                (_, None) => merge.deleted += 1,
+                (None, Some(_)) => merge.added += 1,
+                (Some(_), Some(_)) => merge.updated += 1,
+            }
+            remote
+        } else if same(base, remote) {
+            local
+        } else {
+            merge.slots.push(Slot::Conflict(merge.conflicts.len()));
+            merge.conflicts.push(Conflict { base: base.cloned(), local: local.cloned(), remote: remote.cloned() });
+            continue;
+        };
+        if let Some(account) = chosen {
+            merge.slots.push(Slot::Account(Box::new(latest_use(account, [local, remote]))));
+        }
+    }
+    merge
+}
+
+impl Merge {
+    /// 两边都修改且内容不同的账号
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    /// 由 `choose` 逐个选择如何解决冲突，返回合并后的账号
+    pub fn resolve<E>(self, mut choose: impl FnMut(&Conflict) -> Result<Resolution, E>) -> Result<AccountStore, E> {
+        let resolutions = self.conflicts.iter().map(&mut choose).collect::<Result<Vec<_>, E>>()?;
+        let mut accounts = AccountStore::new();
+        for slot in self.slots {
+            let index = match slot {
+                Slot::Account(account) => {
+                    accounts.push(*account);
+                    continue;
+                }
+                Slot::Conflict(index) => index,
+            };
+            let conflict = &self.conflicts[index];
+            match resolutions[index] {
+                Resolution::Local => accounts.extend(conflict.local.clone()),
+                Resolution::Remote => accounts.extend(conflict.remote.clone()),
+                Resolution::Both => {
+                    accounts.extend(conflict.local.clone());
+                    accounts.extend(conflict.remote.clone().map(|mut account| {
+                        if conflict.local.is_some() {
+                            account.id = new_id();
+                        }
+                        account
+                    }));
+                }
+            }
+        }
+        Ok(accounts)
+    }
+}
+
+// 除使用时间以外是否相同（都不存在也算相同）
+fn same(a: Option<&Account>, b: Option<&Account>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.same_content(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+// 采用 `account`，使用时间取各个版本中最晚的
+fn latest_use(account: &Account, versions: [Option<&Account>; 2]) -> Account {
+    let last_used_at = versions.into_iter().flatten().filter_map(|version| version.last_used_at).chain(account.last_used_at).max();
+    Account { last_used_at, ..account.clone() }
+}
@@ -0,0 +1,110 @@
+// 密码库格式迁移
+//
+// 格式版本记录在密码库文件头部。打开版本较旧的密码库时，解密后的数据按顺序经过各版本
+// 的迁移升级到当前版本，再立即以当前格式保存；保存前先把原文件复制为
+// `<文件名>.v<版本>.bak`，升级后出现问题时可以用它恢复。以后修改账号数据的结构（新增
+// 字段的含义、条目类型等）时，提升 VERSION 并在 MIGRATIONS 中加入对应的迁移，而不是
+// 依靠 serde 的默认值猜测旧数据的含义。
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::atomic;
+use crate::error::AppError;
+use crate::vault;
+
+/// 当前密码库格式版本，没有头部的旧文件视为版本 0
+pub(crate) const VERSION: u32 = 3;
+
+// 一次迁移：把版本 n 的账号数据升级为版本 n + 1
+struct Migration {
+    // 显示给用户的说明
+    description: &'static str,
+    run: fn(&mut Value) -> Result<(), AppError>,
+}
+
+// 第 n 项把版本 n 升级为版本 n + 1
+const MIGRATIONS: [Migration; VERSION as usize] = [
+    Migration {
+        description: "账号数据改为带 ID 的列表，文件头部加入格式版本和加密算法",
+        run: to_v1,
+    },
+    Migration {
+        description: "文件头部参与加密校验，修改文件的任何部分都会被发现",
+        run: to_v2,
+    },
+    Migration {
+        description: "文件头部加入主密钥校验值，可以区分主密钥错误和文件损坏",
+        run: to_v3,
+    },
+];
+
+/// 把版本 `from` 的账号数据（解密后的 JSON）依次升级到当前版本，返回各次迁移的说明
+pub(crate) fn migrate(data: &mut Value, from: u32) -> Result<Vec<&'static str>, AppError> {
+    let mut changes = Vec::new();
+    for migration in MIGRATIONS.iter().skip(from as usize) {
+        (migration.run)(data)?;
+        changes.push(migration.description);
+    }
+    Ok(changes)
+}
+
+/// 升级前备份原文件，返回备份文件的路径
+pub(crate) fn backup(path: &Path, version: u32) -> Result<PathBuf, AppError> {
+    let mut name = OsString::from(path.file_name().ok_or("无效的密码库路径")?);
+    name.push(format!(".v{}.bak", version));
+    let backup = path.with_file_name(name);
+    atomic::write_private(&backup, fs::read(path)?)?;
+    Ok(backup)
+}
+
+// 版本 0 的账号数据有三种形式：账号列表加修改记录、账号列表，以及更早的以用户名为键
+// 的映射。统一为账号列表加修改记录，并为缺少 ID 的账号分配 ID。
+pub(crate) fn to_v1(data: &mut Value) -> Result<(), AppError> {
+    let mut payload = Map::new();
+    let mut accounts = match data.take() {
+        Value::Object(mut object) if object.get("accounts").is_some_and(Value::is_array) => {
+            if let Some(journal) = object.remove("journal") {
+                payload.insert("journal".to_string(), journal);
+            }
+            match object.remove("accounts") {
+                Some(Value::Array(accounts)) => accounts,
+                _ => Vec::new(),
+            }
+        }
+        Value::Array(accounts) => accounts,
+        Value::Object(map) => {
+            // serde_json 的映射按键排序，账号因此按用户名排列
+            map.into_iter()
+                .map(|(username, mut account)| {
+                    if let Some(account) = account.as_object_mut() {
+                        account.insert("username".to_string(), Value::String(username));
+                    }
+                    account
+                })
+                .collect()
+        }
+        _ => return Err(AppError::from("无法识别的账号数据")),
+    };
+    for account in accounts.iter_mut().filter_map(Value::as_object_mut) {
+        if account.get("id").and_then(Value::as_str).is_none_or(str::is_empty) {
+            account.insert("id".to_string(), Value::String(vault::new_id()));
+        }
+    }
+    payload.insert("accounts".to_string(), Value::Array(accounts));
+    *data = Value::Object(payload);
+    Ok(())
+}
+
+// 版本 2 只改变加密方式（头部作为附加认证数据，保存时生效），账号数据不变
+fn to_v2(_data: &mut Value) -> Result<(), AppError> {
+    Ok(())
+}
+
+// 版本 3 在头部加入主密钥校验值（保存时生成），账号数据不变
+fn to_v3(_data: &mut Value) -> Result<(), AppError> {
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// shell 补全使用的用户名索引
+//
+// 补全时无法请求主密钥，因此解锁或保存密码库后把其中的用户名写入缓存目录中的索引
+// 文件（每个密码库一个），`passman _complete usernames` 只读取这个文件。索引只包含
+// 用户名，不包含密码、备注或其他内容；配置 completion.usernames = false 时不保存。
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use passman::{atomic, Vault};
+
+use crate::config;
+
+/// 索引文件路径，以密码库绝对路径的哈希命名
+pub fn index_path(vault_path: &Path) -> Option<PathBuf> {
+    config::cache_dir().map(|dir| dir.join("names").join(config::vault_id(vault_path)))
+}
+
+/// 写入密码库的用户名索引，失败时忽略（只影响补全）
+pub fn write(vault: &Vault) {
+    let Some(path) = index_path(vault.path()) else {
+        return;
+    };
+    let names: BTreeSet<&str> = vault.list().iter().map(|account| account.username.as_str()).collect();
+    let mut content = String::new();
+    for name in names.into_iter().filter(|name| !name.contains('\n')) {
+        content += name;
+        content.push('\n');
+    }
+
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = atomic::write_private(&path, content);
+}
+
+/// 索引已存在时更新（密码库保存后调用，未启用索引时不会创建）
+pub fn refresh(vault: &Vault) {
+    if index_path(vault.path()).is_some_and(|path| path.exists()) {
+        write(vault);
+    }
+}
+
+/// 删除密码库的用户名索引
+pub fn remove(vault_path: &Path) {
+    if let Some(path) = index_path(vault_path) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// 读取密码库的用户名索引，索引不存在时为空
+pub fn read(vault_path: &Path) -> Vec<String> {
+    index_path(vault_path)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| content.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
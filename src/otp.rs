@@ -0,0 +1,380 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::AppError;
+use crate::hkdf;
+use crate::sha1;
+
+/// 默认验证码位数
+pub const DEFAULT_DIGITS: u32 = 6;
+/// 默认 TOTP 时间步长（秒）
+pub const DEFAULT_PERIOD: u64 = 30;
+/// Steam 令牌验证码的字符数
+pub const STEAM_DIGITS: u32 = 5;
+/// Steam 令牌的时间步长（秒）
+pub const STEAM_PERIOD: u64 = 30;
+
+// Steam 令牌验证码使用的字符，去掉了容易混淆的字母和数字
+const STEAM_ALPHABET: &[u8] = b"23456789BCDFGHJKMNPQRTVWXY";
+
+/// 一次性密码的类型
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OtpKind {
+    /// 基于时间 (RFC 6238)
+    Totp { period: u64 },
+    /// 基于计数器 (RFC 4226)，每生成一次验证码计数器加一
+    Hotp { counter: u64 },
+    /// Steam 令牌 (Steam Guard)：与 TOTP 相同的算法，但验证码是 5 个字母和数字
+    Steam,
+}
+
+impl OtpKind {
+    /// 基于时间的类型的时间步长（秒），HOTP 为 None
+    pub fn period(&self) -> Option<u64> {
+        match self {
+            OtpKind::Totp { period } => Some(*period),
+            OtpKind::Hotp { .. } => None,
+            OtpKind::Steam => Some(STEAM_PERIOD),
+        }
+    }
+}
+
+/// 计算验证码时 HMAC 使用的哈希算法；绝大多数服务使用 SHA1
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum OtpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    /// otpauth URI 中 `algorithm` 参数的写法
+    pub fn name(self) -> &'static str {
+        match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn is_sha1(&self) -> bool {
+        *self == OtpAlgorithm::Sha1
+    }
+
+    fn hmac(self, key: &[u8], message: &[u8]) -> Vec<u8> {
+        match self {
+            OtpAlgorithm::Sha1 => sha1::hmac_sha1(key, message).to_vec(),
+            OtpAlgorithm::Sha256 => hkdf::hmac_sha256(key, &[message]).to_vec(),
+            OtpAlgorithm::Sha512 => hmac_sha512(key, message).to_vec(),
+        }
+    }
+}
+
+/// 账号的一次性密码配置
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Otp {
+    /// Base32 编码的共享密钥
+    pub secret: String,
+    pub digits: u32,
+    #[serde(flatten)]
+    pub kind: OtpKind,
+    /// 旧的密码库没有这个字段，按 SHA1 处理
+    #[serde(default, skip_serializing_if = "OtpAlgorithm::is_sha1")]
+    pub algorithm: OtpAlgorithm,
+}
+
+impl Otp {
+    /// 创建配置并校验密钥；Steam 令牌的验证码总是 5 个字符，忽略 `digits`
+    pub fn new(secret: &str, digits: u32, kind: OtpKind) -> Result<Self, AppError> {
+        Otp::with_algorithm(secret, digits, kind, OtpAlgorithm::Sha1)
+    }
+
+    /// 与 [`Otp::new`] 相同，但 HMAC 使用 `algorithm`；Steam 令牌只支持 SHA1
+    pub fn with_algorithm(secret: &str, digits: u32, kind: OtpKind, algorithm: OtpAlgorithm) -> Result<Self, AppError> {
+        let digits = if kind == OtpKind::Steam { STEAM_DIGITS } else { digits };
+        if kind != OtpKind::Steam && !(6..=8).contains(&digits) {
+            return Err(AppError::from("验证码位数必须在 6 到 8 之间"));
+        }
+        if let OtpKind::Totp { period: 0 } = kind {
+            return Err(AppError::from("时间步长必须大于 0"));
+        }
+        if kind == OtpKind::Steam && algorithm != OtpAlgorithm::Sha1 {
+            return Err(AppError::from("Steam 令牌只支持 SHA1 算法"));
+        }
+
+        let otp = Otp {
+            secret: secret.split_whitespace().collect::<String>().to_uppercase(),
+            digits,
+            kind,
+            algorithm,
+        };
+        otp.secret_bytes()?;
+        Ok(otp)
+    }
+
+    /// 解析 otpauth:// URI（Google Authenticator 密钥 URI 格式）
+    ///
+    /// Steam 令牌可以写为 `otpauth://steam/...`、带 `encoder=steam` 参数的 `otpauth://totp/...`
+    /// （KeePassXC）或 `steam://<密钥>`（Bitwarden）。
+    pub fn from_uri(uri: &str) -> Result<Self, AppError> {
+        if let Some(secret) = uri.strip_prefix("steam://") {
+            return Otp::new(secret, STEAM_DIGITS, OtpKind::Steam);
+        }
+        let rest = uri
+            .strip_prefix("otpauth://")
+            .ok_or_else(|| AppError::from("不是 otpauth:// URI"))?;
+        let (kind, rest) = rest.split_once('/').ok_or_else(|| AppError::from("无效的 otpauth URI"))?;
+        let query = rest.split_once('?').map(|(_, q)| q).unwrap_or("");
+
+        let mut secret = None;
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+        let mut counter = 0;
+        let mut steam = false;
+        let mut algorithm = OtpAlgorithm::Sha1;
+
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value);
+            let invalid = || AppError::from(format!("无效的参数: {}", key));
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => secret = Some(value),
+                "digits" => digits = value.parse().map_err(|_| invalid())?,
+                "period" => period = value.parse().map_err(|_| invalid())?,
+                "counter" => counter = value.parse().map_err(|_| invalid())?,
+                "encoder" => steam = value.eq_ignore_ascii_case("steam"),
+                "algorithm" => {
+                    algorithm = match value.to_ascii_uppercase().replace('-', "").as_str() {
+                        "SHA1" => OtpAlgorithm::Sha1,
+                        "SHA256" => OtpAlgorithm::Sha256,
+                        "SHA512" => OtpAlgorithm::Sha512,
+                        _ => return Err(AppError::from(format!("不支持的哈希算法: {}", value))),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let secret = secret.ok_or_else(|| AppError::from("otpauth URI 缺少 secret 参数"))?;
+        let kind = match kind.to_ascii_lowercase().as_str() {
+            "totp" if steam => OtpKind::Steam,
+            "steam" => OtpKind::Steam,
+            "totp" => OtpKind::Totp { period },
+            "hotp" => OtpKind::Hotp { counter },
+            other => return Err(AppError::from(format!("不支持的一次性密码类型: {}", other))),
+        };
+
+        Otp::with_algorithm(&secret, digits, kind, algorithm)
+    }
+
+    /// 生成 otpauth:// URI，`label` 通常为账号名；Steam 令牌使用 KeePassXC 的 `encoder=steam` 参数
+    pub fn to_uri(&self, label: &str) -> String {
+        let (kind, param) = match self.kind {
+            OtpKind::Totp { period } => ("totp", format!("period={}", period)),
+            OtpKind::Hotp { counter } => ("hotp", format!("counter={}", counter)),
+            OtpKind::Steam => ("totp", format!("period={}&encoder=steam", STEAM_PERIOD)),
+        };
+        let mut uri = format!(
+            "otpauth://{}/{}?secret={}&digits={}&{}",
+            kind,
+            percent_encode(label),
+            self.secret,
+            self.digits,
+            param
+        );
+        // 省略时身份验证器按 SHA1 处理，只有其他算法才写出来
+        if !self.algorithm.is_sha1() {
+            uri.push_str(&format!("&algorithm={}", self.algorithm.name()));
+        }
+        uri
+    }
+
+    fn secret_bytes(&self) -> Result<Vec<u8>, AppError> {
+        base32_decode(&self.secret).ok_or_else(|| AppError::from("无效的 Base32 密钥"))
+    }
+
+    /// 生成当前验证码；HOTP 会递增计数器，调用方需要保存密码库
+    pub fn generate(&mut self) -> Result<String, AppError> {
+        let secret = self.secret_bytes()?;
+
+        match &mut self.kind {
+            OtpKind::Totp { period } => Ok(hotp_with(self.algorithm, &secret, time_step(*period)?, self.digits)),
+            OtpKind::Hotp { counter } => {
+                let code = hotp_with(self.algorithm, &secret, *counter, self.digits);
+                *counter += 1;
+                Ok(code)
+            }
+            OtpKind::Steam => Ok(steam(&secret, time_step(STEAM_PERIOD)?)),
+        }
+    }
+}
+
+/// 计算 HOTP 验证码 (RFC 4226)
+pub fn hotp(secret: &[u8], counter: u64, digits: u32) -> String {
+    hotp_with(OtpAlgorithm::Sha1, secret, counter, digits)
+}
+
+/// 以指定的 HMAC 算法计算 HOTP 验证码 (RFC 6238 允许 SHA256、SHA512)
+pub fn hotp_with(algorithm: OtpAlgorithm, secret: &[u8], counter: u64, digits: u32) -> String {
+    let code = truncate(algorithm, secret, counter) % 10u32.pow(digits);
+    format!("{:0width$}", code, width = digits as usize)
+}
+
+/// 计算 Steam 令牌验证码：截断后的值依次取除以 26 的余数，对应到 Steam 的字符表
+pub fn steam(secret: &[u8], counter: u64) -> String {
+    let mut binary = truncate(OtpAlgorithm::Sha1, secret, counter);
+    let mut code = String::with_capacity(STEAM_DIGITS as usize);
+    for _ in 0..STEAM_DIGITS {
+        code.push(STEAM_ALPHABET[binary as usize % STEAM_ALPHABET.len()] as char);
+        binary /= STEAM_ALPHABET.len() as u32;
+    }
+    code
+}
+
+// 当前时间所在的时间步
+fn time_step(period: u64) -> Result<u64, AppError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| AppError::from(e.to_string()))?
+        .as_secs();
+    Ok(now / period)
+}
+
+// HMAC 后的动态截断，得到 31 位的整数
+fn truncate(algorithm: OtpAlgorithm, secret: &[u8], counter: u64) -> u32 {
+    let hash = algorithm.hmac(secret, &counter.to_be_bytes());
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff
+}
+
+// HMAC-SHA512 (RFC 2104)，分组长度为 128 字节
+fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+    const BLOCK_SIZE: usize = 128;
+    let mut block_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..64].copy_from_slice(&Sha512::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha512::new();
+    inner.update(block_key.map(|b| b ^ 0x36));
+    inner.update(message);
+    let inner = inner.finalize();
+
+    let mut outer = Sha512::new();
+    outer.update(block_key.map(|b| b ^ 0x5c));
+    outer.update(inner);
+    outer.finalize().into()
+}
+
+/// 解码 Base32 (RFC 4648)，忽略大小写、空白和末尾的填充
+pub fn base32_decode(input: &str) -> Option<Vec<u8>> {
+    let mut bits: u64 = 0;
+    let mut bit_count = 0;
+    let mut out = Vec::new();
+
+    for c in input.chars().filter(|c| !c.is_whitespace()) {
+        if c == '=' {
+            break;
+        }
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return None,
+        };
+        bits = (bits << 5) | value;
+        bit_count += 5;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+
+    if out.is_empty() {
+        return None;
+    }
+    Some(out)
+}
+
+// 对 URI 中的保留字符与非 ASCII 字节进行百分号转义
+fn percent_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for &b in input.as_bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~@".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+// 解码 URI 中的百分号转义
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 附录 B 的测试向量：8 位验证码，时间步长 30 秒，各算法的密钥长度与哈希输出相同
+    const VECTORS: &[(u64, &str, &str, &str)] = &[
+        (59, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    #[test]
+    fn rfc6238_vectors() {
+        let sha1 = b"12345678901234567890";
+        let sha256 = b"12345678901234567890123456789012";
+        let sha512 = b"1234567890123456789012345678901234567890123456789012345678901234";
+        for &(time, code_sha1, code_sha256, code_sha512) in VECTORS {
+            let step = time / DEFAULT_PERIOD;
+            assert_eq!(hotp_with(OtpAlgorithm::Sha1, sha1, step, 8), code_sha1, "SHA1 T={}", time);
+            assert_eq!(hotp_with(OtpAlgorithm::Sha256, sha256, step, 8), code_sha256, "SHA256 T={}", time);
+            assert_eq!(hotp_with(OtpAlgorithm::Sha512, sha512, step, 8), code_sha512, "SHA512 T={}", time);
+        }
+    }
+
+    #[test]
+    fn uri_algorithm() {
+        let otp = Otp::from_uri("otpauth://totp/alice?secret=GEZDGNBVGY3TQOJQ&algorithm=SHA256").unwrap();
+        assert!(otp.algorithm == OtpAlgorithm::Sha256);
+        assert!(otp.to_uri("alice").ends_with("&algorithm=SHA256"));
+        assert!(Otp::from_uri("otpauth://totp/alice?secret=GEZDGNBVGY3TQOJQ&algorithm=MD5").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+// --output json 使用的 JSON 结构
+//
+// 字段名称保持稳定，以后只会增加字段。时间为 Unix 时间戳（秒），未知时为 null；
+// 隐藏的值（未指定 --show-passwords 时的密码、敏感字段、列表中的安全笔记内容）为 null。
+
+use serde::Serialize;
+
+use passman::strength::Estimate;
+use passman::{Account, AppError, EntryKind};
+
+// 账号
+#[derive(Serialize)]
+pub struct EntryJson<'a> {
+    id: &'a str,
+    username: &'a str,
+    #[serde(rename = "type")]
+    kind: EntryKind,
+    password: Option<&'a str>,
+    notes: Option<&'a str>,
+    url: Option<&'a str>,
+    folder: Option<&'a str>,
+    tags: &'a [String],
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+    last_used_at: Option<u64>,
+    expires_at: Option<u64>,
+    otp: bool,
+    fields: Vec<FieldJson<'a>>,
+    attachments: Vec<AttachmentJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct FieldJson<'a> {
+    name: &'a str,
+    value: Option<&'a str>,
+    secret: bool,
+}
+
+#[derive(Serialize)]
+struct AttachmentJson<'a> {
+    name: &'a str,
+    size: usize,
+    added_at: u64,
+}
+
+impl<'a> EntryJson<'a> {
+    // list 和 search 中的账号：与表格一样隐藏敏感字段和安全笔记的内容
+    pub fn summary(account: &'a Account, show_password: bool) -> Self {
+        let mut entry = Self::detail(account, false, show_password);
+        if account.kind == EntryKind::Note {
+            entry.notes = None;
+        }
+        entry
+    }
+
+    // get 中的账号，`reveal` 为真时包括敏感字段的值
+    pub fn detail(account: &'a Account, reveal: bool, show_password: bool) -> Self {
+        let known = |time: u64| (time != 0).then_some(time);
+        EntryJson {
+            id: &account.id,
+            username: &account.username,
+            kind: account.kind,
+            password: show_password.then_some(account.password.as_str()),
+            notes: Some(&account.notes),
+            url: account.url.as_deref(),
+            folder: account.folder.as_deref(),
+            tags: &account.tags,
+            created_at: known(account.created_at),
+            updated_at: known(account.updated_at),
+            last_used_at: account.last_used_at,
+            expires_at: account.expires_at,
+            otp: account.otp.is_some(),
+            fields: account
+                .fields
+                .iter()
+                .map(|field| FieldJson {
+                    name: &field.name,
+                    value: (!field.secret || reveal).then_some(field.value.as_str()),
+                    secret: field.secret,
+                })
+                .collect(),
+            attachments: account
+                .attachments
+                .iter()
+                .map(|attachment| AttachmentJson {
+                    name: &attachment.name,
+                    size: attachment.data.len(),
+                    added_at: attachment.added_at,
+                })
+                .collect(),
+        }
+    }
+}
+
+// 审计中提到的账号
+#[derive(Serialize)]
+pub struct AccountRef<'a> {
+    id: &'a str,
+    username: &'a str,
+}
+
+impl<'a> AccountRef<'a> {
+    pub fn new(account: &'a Account) -> Self {
+        AccountRef { id: &account.id, username: &account.username }
+    }
+}
+
+// audit strength
+#[derive(Serialize)]
+pub struct StrengthReport<'a> {
+    pub checked: usize,
+    pub weak: usize,
+    pub accounts: Vec<StrengthJson<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct StrengthJson<'a> {
+    #[serde(flatten)]
+    account: AccountRef<'a>,
+    score: u8,
+    guesses: f64,
+    offline_seconds: f64,
+    online_seconds: f64,
+    warning: Option<&'a str>,
+    suggestions: &'a [String],
+}
+
+impl<'a> StrengthJson<'a> {
+    pub fn new(account: &'a Account, estimate: &'a Estimate) -> Self {
+        StrengthJson {
+            account: AccountRef::new(account),
+            score: estimate.score,
+            guesses: estimate.guesses,
+            offline_seconds: estimate.offline_seconds,
+            online_seconds: estimate.online_seconds,
+            warning: estimate.warning.as_deref(),
+            suggestions: &estimate.suggestions,
+        }
+    }
+}
+
+// audit reuse 中的一组账号
+#[derive(Serialize)]
+pub struct ReuseJson<'a> {
+    pub identical: bool,
+    pub accounts: Vec<AccountRef<'a>>,
+}
+
+// audit breach
+#[derive(Serialize)]
+pub struct BreachReport<'a> {
+    pub checked: usize,
+    pub unchecked: usize,
+    pub breached: Vec<BreachJson<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct BreachJson<'a> {
+    #[serde(flatten)]
+    pub account: AccountRef<'a>,
+    // 泄露次数，使用本地泄露数据库时为 null
+    pub count: Option<u64>,
+}
+
+// 以缩进格式输出 JSON
+pub fn print(value: &impl Serialize) -> Result<(), AppError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
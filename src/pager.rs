@@ -0,0 +1,46 @@
+// 长输出分页
+//
+// 与 git 类似：标准输出是终端且内容超过终端高度时，通过 $PAGER（默认为 less）显示；
+// 未设置 LESS 时使用 `FRX`，内容不足一屏时 less 直接退出，退出后内容保留在终端中。
+
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::tui;
+
+/// 显示文本，`enabled` 为假（--no-pager）时直接输出
+pub fn show(text: &str, enabled: bool) -> io::Result<()> {
+    let (rows, _) = tui::terminal_size();
+    if !enabled || !io::stdout().is_terminal() || text.lines().count() < rows {
+        return print(text);
+    }
+
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut words = pager.split_whitespace();
+    let Some(program) = words.next().filter(|program| *program != "cat") else {
+        return print(text);
+    };
+    let mut command = Command::new(program);
+    command.args(words).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    // 无法启动分页程序时直接输出
+    let Ok(mut child) = command.spawn() else {
+        return print(text);
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // 用户提前退出分页程序时写入会失败，忽略即可
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+fn print(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
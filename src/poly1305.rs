@@ -0,0 +1,141 @@
+// Poly1305 消息认证码 (RFC 8439)
+//
+// 按 26 位分组进行 130 位运算（与 poly1305-donna 的 32 位版本相同），只用于
+// XChaCha20-Poly1305。
+
+const MASK: u32 = 0x3ffffff;
+
+pub struct Poly1305 {
+    r: [u32; 5],
+    s: [u32; 4],
+    h: [u32; 5],
+    buffer: [u8; 16],
+    offset: usize,
+}
+
+fn le32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+impl Poly1305 {
+    /// 使用一次性密钥创建，同一个密钥只能认证一条消息
+    pub fn new(key: &[u8; 32]) -> Self {
+        // r 的部分位按规定清零
+        let r = [
+            le32(&key[0..]) & 0x3ffffff,
+            (le32(&key[3..]) >> 2) & 0x3ffff03,
+            (le32(&key[6..]) >> 4) & 0x3ffc0ff,
+            (le32(&key[9..]) >> 6) & 0x3f03fff,
+            (le32(&key[12..]) >> 8) & 0x00fffff,
+        ];
+        let s = [le32(&key[16..]), le32(&key[20..]), le32(&key[24..]), le32(&key[28..])];
+        Poly1305 { r, s, h: [0; 5], buffer: [0; 16], offset: 0 }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        if self.offset > 0 {
+            let take = data.len().min(16 - self.offset);
+            self.buffer[self.offset..self.offset + take].copy_from_slice(&data[..take]);
+            self.offset += take;
+            data = &data[take..];
+            if self.offset < 16 {
+                return;
+            }
+            let block = self.buffer;
+            self.block(&block, 1 << 24);
+            self.offset = 0;
+        }
+        let mut chunks = data.chunks_exact(16);
+        for chunk in &mut chunks {
+            self.block(chunk.try_into().unwrap(), 1 << 24);
+        }
+        let rest = chunks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.offset = rest.len();
+    }
+
+    /// 补零到 16 字节的整数倍（AEAD 构造中使用）
+    pub fn pad(&mut self) {
+        if self.offset > 0 {
+            self.update(&[0; 16][self.offset..]);
+        }
+    }
+
+    // h = (h + 块) * r mod 2^130 - 5，hibit 是块末尾补上的 1
+    fn block(&mut self, m: &[u8; 16], hibit: u32) {
+        let [r0, r1, r2, r3, r4] = self.r.map(u64::from);
+        let (s1, s2, s3, s4) = (r1 * 5, r2 * 5, r3 * 5, r4 * 5);
+
+        let h0 = u64::from(self.h[0] + (le32(&m[0..]) & MASK));
+        let h1 = u64::from(self.h[1] + ((le32(&m[3..]) >> 2) & MASK));
+        let h2 = u64::from(self.h[2] + ((le32(&m[6..]) >> 4) & MASK));
+        let h3 = u64::from(self.h[3] + ((le32(&m[9..]) >> 6) & MASK));
+        let h4 = u64::from(self.h[4] + ((le32(&m[12..]) >> 8) | hibit));
+
+        let d0 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+
+        d1 += d0 >> 26;
+        d2 += d1 >> 26;
+        d3 += d2 >> 26;
+        d4 += d3 >> 26;
+        let mut h0 = (d0 as u32 & MASK) + (d4 >> 26) as u32 * 5;
+        let h1 = (d1 as u32 & MASK) + (h0 >> 26);
+        h0 &= MASK;
+        self.h = [h0, h1, d2 as u32 & MASK, d3 as u32 & MASK, d4 as u32 & MASK];
+    }
+
+    /// 计算 16 字节的认证标签
+    pub fn finalize(mut self) -> [u8; 16] {
+        if self.offset > 0 {
+            let mut block = [0u8; 16];
+            block[..self.offset].copy_from_slice(&self.buffer[..self.offset]);
+            block[self.offset] = 1;
+            self.block(&block, 0);
+        }
+
+        // 完全进位
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+        h2 += h1 >> 26;
+        h1 &= MASK;
+        h3 += h2 >> 26;
+        h2 &= MASK;
+        h4 += h3 >> 26;
+        h3 &= MASK;
+        h0 += (h4 >> 26) * 5;
+        h4 &= MASK;
+        h1 += h0 >> 26;
+        h0 &= MASK;
+
+        // g = h - p，h >= p 时取 g（不使用分支）
+        let mut g0 = h0 + 5;
+        let mut g1 = h1 + (g0 >> 26);
+        g0 &= MASK;
+        let mut g2 = h2 + (g1 >> 26);
+        g1 &= MASK;
+        let mut g3 = h3 + (g2 >> 26);
+        g2 &= MASK;
+        let g4 = (h4 + (g3 >> 26)).wrapping_sub(1 << 26);
+        g3 &= MASK;
+        let select = (g4 >> 31).wrapping_sub(1);
+        h0 = (h0 & !select) | (g0 & select);
+        h1 = (h1 & !select) | (g1 & select);
+        h2 = (h2 & !select) | (g2 & select);
+        h3 = (h3 & !select) | (g3 & select);
+        h4 = (h4 & !select) | (g4 & select);
+
+        // 标签 = (h + s) mod 2^128
+        let words = [h0 | (h1 << 26), (h1 >> 6) | (h2 << 20), (h2 >> 12) | (h3 << 14), (h3 >> 18) | (h4 << 8)];
+        let mut tag = [0u8; 16];
+        let mut carry = 0u64;
+        for i in 0..4 {
+            carry += u64::from(words[i]) + u64::from(self.s[i]);
+            tag[i * 4..i * 4 + 4].copy_from_slice(&(carry as u32).to_le_bytes());
+            carry >>= 32;
+        }
+        tag
+    }
+}
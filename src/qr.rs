@@ -0,0 +1,446 @@
+//! QR 码编码（ISO/IEC 18004）
+//!
+//! 只实现在终端中显示二维码所需的部分：字节模式、纠错等级 M，自动选择能容纳数据的最小版本和
+//! 惩罚分最低的掩码。终端中每个字符显示上下两个模块，可以用 ANSI 颜色固定为白底黑码，深色背景的
+//! 终端也能扫描。
+
+use crate::error::AppError;
+
+// 纠错等级 M 下各版本每块的纠错码字数和块数，下标为版本号
+const ECC_PER_BLOCK: [usize; 41] = [
+    0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    28, 28, 28, 28, 28,
+];
+const BLOCKS: [usize; 41] = [
+    0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47,
+    49,
+];
+
+// 惩罚规则的分数
+const PENALTY_RUN: i32 = 3;
+const PENALTY_BLOCK: i32 = 3;
+const PENALTY_FINDER: i32 = 40;
+const PENALTY_BALANCE: i32 = 10;
+
+// 二维码四周空白区的宽度（模块数），标准要求至少 4
+const QUIET_ZONE: usize = 4;
+
+/// 编码后的 QR 码，由深色和浅色的模块组成的正方形，不含四周的空白区
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    // 定位、时序、格式等功能图形所在的模块，不放数据也不加掩码
+    function: Vec<bool>,
+}
+
+impl QrCode {
+    /// 以字节模式编码数据，数据超过版本 40 的容量时返回错误
+    pub fn encode(data: &[u8]) -> Result<QrCode, AppError> {
+        let version = (1..=40)
+            .find(|&version| 4 + count_bits(version) + data.len() * 8 <= data_codewords(version) * 8)
+            .ok_or_else(|| AppError::from(format!("内容太长（{} 字节），无法编码为二维码", data.len())))?;
+
+        // 模式指示符、字符数和数据，之后是终止符和填充
+        let capacity = data_codewords(version) * 8;
+        let mut bits = Bits::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, count_bits(version));
+        for &byte in data {
+            bits.push(byte.into(), 8);
+        }
+        bits.push(0, (capacity - bits.len).min(4));
+        bits.push(0, (8 - bits.len % 8) % 8);
+        for pad in [0xEC, 0x11].into_iter().cycle().take(capacity / 8 - bits.bytes.len()) {
+            bits.push(pad, 8);
+        }
+
+        let size = version * 4 + 17;
+        let mut qr = QrCode { size, modules: vec![false; size * size], function: vec![false; size * size] };
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&add_error_correction(version, &bits.bytes));
+
+        // 依次尝试 8 种掩码，选择惩罚分最低的（再次应用同一掩码即可还原）
+        let mut best = (i32::MAX, 0);
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            best = best.min((qr.penalty(), mask));
+            qr.apply_mask(mask);
+        }
+        qr.apply_mask(best.1);
+        qr.draw_format_bits(best.1);
+        Ok(qr)
+    }
+
+    /// 边长（模块数）
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// 第 `y` 行第 `x` 列的模块是否为深色，超出范围时为浅色
+    pub fn dark(&self, x: usize, y: usize) -> bool {
+        x < self.size && y < self.size && self.modules[y * self.size + x]
+    }
+
+    /// 以半高的方块字符画出二维码（含空白区），`ansi` 时固定为白底黑码
+    pub fn render(&self, ansi: bool) -> String {
+        let size = self.size + QUIET_ZONE * 2;
+        let dark = |x: usize, y: usize| self.dark(x.wrapping_sub(QUIET_ZONE), y.wrapping_sub(QUIET_ZONE));
+        let mut output = String::new();
+        for y in (0..size).step_by(2) {
+            if ansi {
+                output.push_str("\x1b[30;47m");
+            }
+            for x in 0..size {
+                output.push(match (dark(x, y), dark(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            if ansi {
+                output.push_str("\x1b[0m");
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        for i in 0..self.size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (x, y) in [(3, 3), (self.size - 4, 3), (3, self.size - 4)] {
+            self.draw_finder(x, y);
+        }
+        let positions = alignment_positions(version);
+        let last = positions.len().saturating_sub(1);
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                // 与定位图形重叠的三个角不放校正图形
+                let corner = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+                if !corner {
+                    self.draw_alignment(x, y);
+                }
+            }
+        }
+        // 先占住格式信息的位置，选定掩码后再写入
+        self.draw_format_bits(0);
+        self.draw_version(version);
+    }
+
+    // 定位图形：7x7 的同心方框和四周一圈浅色的分隔符
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4..=4_isize {
+            for dx in -4..=4_isize {
+                let (xx, yy) = (x as isize + dx, y as isize + dy);
+                if (0..self.size as isize).contains(&xx) && (0..self.size as isize).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in -2..=2_isize {
+            for dx in -2..=2_isize {
+                self.set_function(x.wrapping_add_signed(dx), y.wrapping_add_signed(dy), dx.abs().max(dy.abs()) != 1);
+            }
+        }
+    }
+
+    // 格式信息：纠错等级 M（00）和掩码编号，加上 BCH 校验位，在两个位置各写一份
+    fn draw_format_bits(&mut self, mask: u32) {
+        let data = mask; // 纠错等级 M 的两位为 0
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(self.size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, self.size - 15 + i, bit(i));
+        }
+        // 总是深色的模块
+        self.set_function(8, self.size - 8, true);
+    }
+
+    // 版本 7 起在两个角写入版本信息
+    fn draw_version(&mut self, version: usize) {
+        if version < 7 {
+            return;
+        }
+        let mut remainder = version as u32;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = ((version as u32) << 12) | remainder;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    // 从右下角开始，每两列为一组上下蛇形放置数据位，跳过功能图形和第 6 列的时序图形
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let mut i = 0;
+        let mut right = self.size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vertical in 0..self.size {
+                for x in [right, right - 1] {
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { self.size - 1 - vertical } else { vertical };
+                    if !self.function[y * self.size + x] && i < codewords.len() * 8 {
+                        self.modules[y * self.size + x] = (codewords[i / 8] >> (7 - i % 8)) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let index = y * self.size + x;
+                self.modules[index] ^= invert && !self.function[index];
+            }
+        }
+    }
+
+    // 标准中的四条惩罚规则：同色的长串、2x2 同色块、类似定位图形的 1:1:3:1:1 图案和深浅不均
+    fn penalty(&self) -> i32 {
+        let mut result = 0;
+        for transposed in [false, true] {
+            for a in 0..self.size {
+                let mut color = false;
+                let mut run = 0;
+                let mut history = [0; 7];
+                for b in 0..self.size {
+                    let dark = if transposed { self.dark(a, b) } else { self.dark(b, a) };
+                    if dark == color {
+                        run += 1;
+                        result += match run {
+                            5 => PENALTY_RUN,
+                            6.. => 1,
+                            _ => 0,
+                        };
+                    } else {
+                        self.add_history(run, &mut history);
+                        if !color {
+                            result += finder_patterns(&history) * PENALTY_FINDER;
+                        }
+                        color = dark;
+                        run = 1;
+                    }
+                }
+                if color {
+                    self.add_history(run, &mut history);
+                    run = 0;
+                }
+                self.add_history(run + self.size as i32, &mut history);
+                result += finder_patterns(&history) * PENALTY_FINDER;
+            }
+        }
+        for y in 0..self.size - 1 {
+            for x in 0..self.size - 1 {
+                let color = self.dark(x, y);
+                if color == self.dark(x + 1, y) && color == self.dark(x, y + 1) && color == self.dark(x + 1, y + 1) {
+                    result += PENALTY_BLOCK;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|&&dark| dark).count() as i32;
+        let total = (self.size * self.size) as i32;
+        result + ((dark * 20 - total * 10).abs() + total - 1) / total * PENALTY_BALANCE - PENALTY_BALANCE
+    }
+
+    // 记录一段同色的长度，行首的浅色段算上四周的空白区
+    fn add_history(&self, mut run: i32, history: &mut [i32; 7]) {
+        if history[0] == 0 {
+            run += self.size as i32;
+        }
+        history.copy_within(0..6, 1);
+        history[0] = run;
+    }
+}
+
+// 深-浅-深深深-浅-深 的图案，两侧至少一边有 4 倍宽的浅色
+fn finder_patterns(history: &[i32; 7]) -> i32 {
+    let n = history[1];
+    let core = n > 0 && history[2] == n && history[3] == n * 3 && history[4] == n && history[5] == n;
+    i32::from(core && history[0] >= n * 4 && history[6] >= n) + i32::from(core && history[6] >= n * 4 && history[0] >= n)
+}
+
+// 字节模式中字符数所占的位数
+fn count_bits(version: usize) -> usize {
+    if version <= 9 { 8 } else { 16 }
+}
+
+// 除功能图形外可以放置码字的模块数
+fn raw_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        result -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_modules(version) / 8 - ECC_PER_BLOCK[version] * BLOCKS[version]
+}
+
+// 校正图形中心所在的行列，从 6 开始到距离末尾 7 的位置大致等距
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let mut positions = vec![6];
+    let mut position = version * 4 + 10;
+    for _ in 0..count - 1 {
+        positions.insert(1, position);
+        position -= step;
+    }
+    positions
+}
+
+// 把数据分块并加上 Reed-Solomon 纠错码字，再把各块交错排列
+fn add_error_correction(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = BLOCKS[version];
+    let ecc_len = ECC_PER_BLOCK[version];
+    let raw_codewords = raw_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_len = raw_codewords / blocks;
+    let divisor = rs_divisor(ecc_len);
+
+    let mut encoded: Vec<Vec<u8>> = Vec::with_capacity(blocks);
+    let mut offset = 0;
+    for i in 0..blocks {
+        let len = short_len - ecc_len + usize::from(i >= short_blocks);
+        let mut block = data[offset..offset + len].to_vec();
+        offset += len;
+        let ecc = rs_remainder(&block, &divisor);
+        // 短块补一个占位字节，交错时跳过
+        if i < short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        encoded.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..encoded[0].len() {
+        for (j, block) in encoded.iter().enumerate() {
+            if i != short_len - ecc_len || j >= short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+// 生成多项式 (x - 2^0)(x - 2^1)...(x - 2^(degree-1)) 的系数，最高次项的系数 1 省略
+fn rs_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn rs_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (value, &coefficient) in result.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(coefficient, factor);
+        }
+    }
+    result
+}
+
+// GF(2^8) 中的乘法，模 x^8 + x^4 + x^3 + x^2 + 1
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((u32::from(y) >> i) & 1) * u32::from(x);
+    }
+    z as u8
+}
+
+// 按位追加的缓冲区
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.bytes.last_mut().expect("刚刚追加过") |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! 忘记主密钥时使用的恢复代码
+//!
+//! 随机生成的恢复代码（160 位，Base32 编码）经 HKDF-SHA256 派生出包装密钥，以 AES-256-GCM
+//! 加密由主密钥派生出的密码库密钥，结果（[`RecoveryKey`]）保存为密码库同一目录下的
+//! `<文件名>.recovery`，随密码库一起同步和备份。恢复代码本身有足够的熵，不需要慢速的密钥
+//! 派生；只有恢复文件或只有恢复代码都无法解锁。恢复代码只能使用一次：重新设置主密钥后
+//! 密钥改变，恢复文件随之删除，更换主密钥或重新加密后也同样失效。
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::crypto::{Cipher, KeyBackend, KeyHeader};
+use crate::error::AppError;
+use crate::hkdf::hkdf_sha256;
+use crate::otp;
+use crate::secmem::{self, SecretBytes};
+
+// 恢复代码的字节数，以及显示时每组的字符数
+const CODE_LENGTH: usize = 20;
+const GROUP_LENGTH: usize = 4;
+// 派生包装密钥时的 HKDF info
+const INFO: &[u8] = b"passman recovery key";
+// Base32 字母表 (RFC 4648)
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// 以恢复代码加密的密码库密钥
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecoveryKey {
+    /// Base64 编码的 HKDF 盐
+    pub salt: String,
+    /// Base64 编码的 AES-256-GCM 初始化向量
+    pub nonce: String,
+    /// Base64 编码的加密后的密钥（附带认证标签）
+    pub wrapped: String,
+    /// 生成时密码库的密钥校验值，更换主密钥或重新加密后不再一致
+    pub key_check: String,
+}
+
+impl RecoveryKey {
+    /// 加密的密钥是否仍然是头部为 `header` 的密码库的密钥
+    pub fn matches(&self, header: &KeyHeader) -> bool {
+        header.key_check.as_deref() == Some(self.key_check.as_str())
+    }
+}
+
+/// 生成新的恢复代码，并用它加密头部为 `header` 的密码库的密钥 `key`
+///
+/// 返回的恢复代码以连字符分组，例如 `ABCD-EFGH-…`，只在这时出现一次。
+pub fn create(key: &[u8], header: &KeyHeader) -> Result<(String, RecoveryKey), AppError> {
+    let key_check = header.key_check.clone().ok_or("无法计算密钥校验值")?;
+    let mut code = rand::random::<[u8; CODE_LENGTH]>();
+    let salt = rand::random::<[u8; 16]>();
+    let nonce = rand::random::<[u8; 12]>();
+
+    let mut wrapping_key = wrapping_key(&code, &salt);
+    let mut wrapped = key.to_vec();
+    let result = Cipher::Aes256Gcm.encrypt(&wrapping_key, &nonce, key_check.as_bytes(), &mut wrapped);
+    secmem::zero(&mut wrapping_key);
+    result?;
+
+    let text = format_code(&code);
+    secmem::zero(&mut code);
+    let recovery = RecoveryKey {
+        salt: general_purpose::STANDARD.encode(salt),
+        nonce: general_purpose::STANDARD.encode(nonce),
+        wrapped: general_purpose::STANDARD.encode(wrapped),
+        key_check,
+    };
+    Ok((text, recovery))
+}
+
+/// 用恢复代码 `code` 解开 `recovery`，返回打开头部为 `header` 的密码库的后端
+///
+/// 恢复代码忽略大小写、空白和连字符。
+pub fn unlock(recovery: &RecoveryKey, code: &str, header: &KeyHeader) -> Result<KeyBackend, AppError> {
+    if !recovery.matches(header) {
+        return Err(AppError::from("恢复代码已失效（主密钥已更换或密码库已重新加密）"));
+    }
+    let normalized: String = code.chars().filter(|c| *c != '-').collect();
+    let code = otp::base32_decode(&normalized).filter(|code| code.len() == CODE_LENGTH).ok_or("恢复代码格式不正确")?;
+    let code = SecretBytes::from_slice(&code);
+    let decode = |text: &str| general_purpose::STANDARD.decode(text).map_err(|_| AppError::from("恢复文件已损坏"));
+    let salt = decode(&recovery.salt)?;
+    let nonce = decode(&recovery.nonce)?;
+    if nonce.len() != Cipher::Aes256Gcm.nonce_length() {
+        return Err(AppError::from("恢复文件已损坏"));
+    }
+
+    let mut wrapping_key = wrapping_key(&code, &salt);
+    let mut key = SecretBytes::from_slice(&decode(&recovery.wrapped)?);
+    let result = Cipher::Aes256Gcm.decrypt(&wrapping_key, &nonce, recovery.key_check.as_bytes(), &mut key);
+    secmem::zero(&mut wrapping_key);
+    result.map_err(|_| AppError::from("恢复代码错误"))?;
+    KeyBackend::new(&key, header).map_err(|_| AppError::from("恢复代码解开的密钥与密码库不符"))
+}
+
+/// 密码库 `vault_path` 的恢复文件路径：同一目录下的 `<文件名>.recovery`
+pub fn path(vault_path: &Path) -> PathBuf {
+    let mut name = OsString::from(vault_path.file_name().unwrap_or_default());
+    name.push(".recovery");
+    vault_path.with_file_name(name)
+}
+
+/// 读取密码库的恢复文件，没有或无法读取时为 None
+pub fn read(vault_path: &Path) -> Option<RecoveryKey> {
+    let content = fs::read_to_string(path(vault_path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 保存密码库的恢复文件（Unix 上只有当前用户可以读写）
+pub fn write(vault_path: &Path, recovery: &RecoveryKey) -> Result<(), AppError> {
+    atomic::write_private(path(vault_path), serde_json::to_string_pretty(recovery)?)?;
+    Ok(())
+}
+
+/// 删除密码库的恢复文件，返回是否存在
+pub fn remove(vault_path: &Path) -> bool {
+    fs::remove_file(path(vault_path)).is_ok()
+}
+
+fn wrapping_key(code: &[u8], salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    hkdf_sha256(code, salt, INFO, &mut key);
+    key
+}
+
+// Base32 编码并按 GROUP_LENGTH 个字符分组
+fn format_code(code: &[u8]) -> String {
+    let mut chars = Vec::new();
+    let (mut bits, mut bit_count) = (0u32, 0);
+    for &byte in code {
+        bits = (bits << 8) | byte as u32;
+        bit_count += 8;
+        while bit_count >= 5 {
+            bit_count -= 5;
+            chars.push(ALPHABET[(bits >> bit_count) as usize & 31] as char);
+        }
+        bits &= (1 << bit_count) - 1;
+    }
+    let groups: Vec<String> = chars.chunks(GROUP_LENGTH).map(|group| group.iter().collect()).collect();
+    groups.join("-")
+}
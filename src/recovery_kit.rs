@@ -0,0 +1,39 @@
+// 紧急恢复包：打印出来离线保管的 Markdown 文档
+//
+// 包含恢复代码、密码库和恢复文件的位置、重新设置主密钥的步骤，以及恢复文件的内容，
+// 恢复文件丢失时可以照着重新创建。
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use passman::recovery::{self, RecoveryKey};
+use passman::timestamp;
+
+use crate::i18n::t;
+
+/// 生成密码库 `vault_path` 的恢复包
+pub fn render(vault_path: &Path, code: &str, recovery_key: &RecoveryKey) -> String {
+    let vault_path = fs::canonicalize(vault_path).unwrap_or_else(|_| vault_path.to_path_buf());
+    let recovery_path = recovery::path(&vault_path);
+    let file_name = recovery_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let content = serde_json::to_string_pretty(recovery_key).unwrap_or_default();
+
+    let mut sheet = String::new();
+    let _ = writeln!(sheet, "# {}\n", t!("passman 紧急恢复包"));
+    let _ = writeln!(sheet, "- {}", t!("生成时间：{}", timestamp::format(timestamp::now())));
+    let _ = writeln!(sheet, "- {}", t!("密码库：`{}`", vault_path.display()));
+    let _ = writeln!(sheet, "- {}\n", t!("恢复文件：`{}`", recovery_path.display()));
+    let _ = writeln!(sheet, "## {}\n", t!("恢复代码"));
+    let _ = writeln!(sheet, "```\n{}\n```\n", code);
+    let _ = writeln!(sheet, "{}\n", t!("恢复代码相当于主密钥，请把这份文档打印出来，与存放密码库的设备分开妥善保管，然后删除电子版。"));
+    let _ = writeln!(sheet, "## {}\n", t!("忘记主密钥时"));
+    let _ = writeln!(sheet, "1. {}", t!("确认密码库文件和恢复文件都在上面列出的位置（或同一目录中）。"));
+    let _ = writeln!(sheet, "2. {}", t!("运行 `passman --vault {} recovery-kit restore`，输入上面的恢复代码，再设置新的主密钥。", vault_path.display()));
+    let _ = writeln!(sheet, "3. {}\n", t!("恢复代码只能使用一次，重新设置主密钥后请运行 `passman recovery-kit create` 生成新的恢复包。"));
+    let _ = writeln!(sheet, "{}\n", t!("更换主密钥（change-master）或重新加密（rekey）后这份恢复包失效，也需要重新生成。"));
+    let _ = writeln!(sheet, "## {}\n", t!("恢复文件丢失时"));
+    let _ = writeln!(sheet, "{}\n", t!("把下面的内容保存为密码库同一目录下的 `{}`：", file_name));
+    let _ = writeln!(sheet, "```json\n{}\n```", content);
+    sheet
+}
@@ -0,0 +1,75 @@
+// 通过 curl 发送 HTTP 请求，供 WebDAV、S3 远程存储和 HashiCorp Vault 使用
+//
+// 响应头由 curl 写入临时文件，响应内容和状态码从标准输出读取。上传的内容也先写入临时文件，
+// 这样请求带有 Content-Length（S3 不接受分块上传）。访问密钥等不应出现在命令行参数中的
+// 选项写入只有当前用户可以读取的临时配置文件，以 --config 传给 curl。
+
+use std::ffi::OsStr;
+use std::fs;
+use std::process::{Command, Stdio};
+
+use passman::AppError;
+
+use super::TempFile;
+use crate::i18n::t;
+
+/// 一次请求的状态码、响应头和响应内容
+pub struct Response {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// 名为 `name`（小写）的响应头
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone())
+    }
+
+    pub fn etag(&self) -> Option<String> {
+        self.header("etag")
+    }
+}
+
+/// 以 curl 请求 `url`，`args` 为额外的参数，`secret` 为通过配置文件传入的 curl 选项，
+/// `input` 为上传的内容
+pub fn request(url: &str, args: &[&str], secret: &[String], input: Option<&[u8]>) -> Result<Response, AppError> {
+    let headers_file = TempFile::create(b"")?;
+    let config_file = TempFile::create(secret.join("\n").as_bytes())?;
+    let upload_file = input.map(TempFile::create).transpose()?;
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--netrc-optional", "--max-time", "60", "--user-agent", "passman"])
+        .arg("--config")
+        .arg(config_file.path())
+        .arg("--dump-header")
+        .arg(headers_file.path())
+        .args(["--write-out", "\n%{http_code}"])
+        .args(args)
+        .args(upload_file.iter().flat_map(|file| [OsStr::new("--upload-file"), file.path().as_os_str()]))
+        .arg(url)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| AppError::from(t!("无法运行 {}: {}", "curl", e)))?;
+    if !output.status.success() {
+        return Err(AppError::from(t!("连接远程存储失败: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+
+    let mut body = output.stdout;
+    let newline = body.iter().rposition(|&b| b == b'\n').ok_or_else(|| AppError::from(t!("curl 的输出不完整")))?;
+    let status = String::from_utf8_lossy(&body[newline + 1..]).trim().parse().map_err(|_| AppError::from(t!("curl 的输出不完整")))?;
+    body.truncate(newline);
+    // 跟随 100 Continue 等中间响应时有多组响应头，只取最后一组
+    let text = fs::read_to_string(headers_file.path()).unwrap_or_default();
+    let block = text.trim_end().rsplit("\r\n\r\n").next().unwrap_or_default();
+    let headers = block
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    Ok(Response { status, headers, body })
+}
+
+/// curl 配置文件中的一个选项，例如 `option("user", "key:secret")`
+pub fn option(name: &str, value: &str) -> String {
+    format!("{} = \"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,413 @@
+// 与远程存储同步加密后的密码库文件
+//
+// 远程存储只保存加密后的密码库文件。每次同步后把远程文件的版本（ETag）和本地文件的
+// SHA-256 摘要记在数据目录中，下次同步时据此判断哪一边有新的修改：只有一边修改时复制到
+// 另一边，两边都修改时返回 [`Outcome::Diverged`]，连同上次同步时的密码库文件交给调用者
+// 逐个账号合并（见 [`passman::merge`]）。上传时带上上次看到的版本，期间远程文件又被修改时
+// 服务器会拒绝，不会覆盖其他设备刚上传的修改。推送和拉取（[`Direction`]）只向一个方向
+// 复制，需要另一个方向时报告而不复制。
+// 每种存储是一个 [`SyncBackend`]，只需实现下载和条件上传；rclone 后端把其余几十种存储交给
+// rclone 访问。
+//
+// 密码库文件虽然加密，文件名和 JSON 头部（密钥派生参数、接收者等）仍能看出是 passman
+// 的密码库。使用同步密钥（[`SyncKey`]）时远程文件名换成由密钥派生的随机名称，内容再以
+// AES-256-GCM 加密一层，远程存储只能看到一个不透明的文件。
+
+pub mod http;
+pub mod rclone;
+pub mod s3;
+pub mod sftp;
+pub mod webdav;
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::{Engine as _, engine::general_purpose};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use passman::crypto::Cipher;
+use passman::hkdf::{hkdf_sha256, hmac_sha256};
+use passman::secmem::SecretBytes;
+use passman::{atomic, AppError};
+
+use crate::config;
+use crate::i18n::t;
+
+// 派生同步密钥的子密钥时的 HKDF info
+const NAME_INFO: &[u8] = b"passman sync name";
+const CONTENT_INFO: &[u8] = b"passman sync content";
+// 加密一层后的远程文件格式的版本
+const SEALED_VERSION: u8 = 1;
+
+/// 冲突时保留哪一边
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Keep {
+    /// 保留本地的版本
+    Local,
+    /// 保留远程的版本
+    Remote,
+}
+
+/// 只推送或只拉取
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    /// 只上传本地的修改
+    Push,
+    /// 只下载远程的修改
+    Pull,
+}
+
+/// 同步的方式
+#[derive(Clone, Copy, Default)]
+pub struct Options {
+    /// 为 None 时双向同步
+    pub direction: Option<Direction>,
+    /// 两边都有修改时不合并，整个保留一边的密码库
+    pub keep: Option<Keep>,
+    /// 只判断会怎样同步，不复制文件也不记录状态
+    pub dry_run: bool,
+}
+
+/// 同步的结果（`dry_run` 时为将会怎样同步）
+pub enum Outcome {
+    /// 两边相同，没有复制
+    UpToDate,
+    /// 上传了本地的密码库；覆盖了远程的修改且存储保留旧版本时，`replaced` 为旧版本的 ID
+    Uploaded { replaced: Option<String> },
+    /// 下载了远程的密码库
+    Downloaded,
+    /// 本地和远程都有新的修改，需要合并
+    Diverged(Diverged),
+    /// 只拉取时远程没有新的修改，只有本地有
+    Ahead,
+    /// 只推送时远程有本地没有的修改，需要先拉取
+    Behind,
+}
+
+/// 两边都有修改时远程的密码库文件，以及上次同步时的密码库文件（共同祖先）
+pub struct Diverged {
+    pub remote: Vec<u8>,
+    /// 没有同步过或没有保存共同祖先时为 None
+    pub base: Option<Vec<u8>>,
+    etag: String,
+}
+
+/// 远程存储的后端
+///
+/// 后端只负责传输文件；比较版本、加密一层和记录同步状态由 [`sync`] 统一处理。
+pub trait SyncBackend {
+    /// 下载远程文件，文件不存在时为 None
+    fn download(&self) -> Result<Option<Object>, AppError>;
+
+    /// 上传远程文件，`etag` 为下载时的 [`Object::etag`]（为 None 时要求文件还不存在）；
+    /// 返回新的 ETag，远程文件在此期间被修改时返回 None
+    fn upload(&self, data: &[u8], etag: Option<&str>) -> Result<Option<String>, AppError>;
+}
+
+/// 远程存储中的文件
+pub struct Object {
+    pub data: Vec<u8>,
+    /// 内容改变时随之改变的版本标识，没有 ETag 的存储以内容的摘要代替
+    pub etag: String,
+    /// 存储保留历史版本时（例如启用了版本控制的 S3 存储桶）的版本 ID
+    pub version: Option<String>,
+}
+
+/// 隐藏远程文件名和内容格式的同步密钥（256 位），需要在每台设备上使用同一个
+pub struct SyncKey(SecretBytes);
+
+// 上次同步时的状态
+#[derive(Serialize, Deserialize)]
+struct State {
+    remote: String,
+    etag: String,
+    // 本地文件的 SHA-256 摘要（十六进制）
+    hash: String,
+    // Base64 编码的同步密钥
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+}
+
+impl SyncKey {
+    /// 随机生成新的同步密钥
+    pub fn generate() -> Self {
+        SyncKey(SecretBytes::from_slice(&rand::random::<[u8; 32]>()))
+    }
+
+    /// 解析 [`SyncKey::encode`] 的结果，忽略首尾的空白
+    pub fn parse(text: &str) -> Option<Self> {
+        let key = general_purpose::URL_SAFE_NO_PAD.decode(text.trim()).ok().filter(|key| key.len() == 32)?;
+        Some(SyncKey(SecretBytes::from_slice(&key)))
+    }
+
+    /// 以 Base64 编码，用于在其他设备上输入
+    pub fn encode(&self) -> String {
+        general_purpose::URL_SAFE_NO_PAD.encode(&self.0)
+    }
+
+    fn subkey(&self, info: &[u8]) -> SecretBytes {
+        let mut key = SecretBytes::from_slice(&[0u8; 32]);
+        hkdf_sha256(&self.0, &[], info, &mut key);
+        key
+    }
+
+    // 远程文件名：由密钥和原来的文件名派生，每台设备上相同
+    fn conceal(&self, remote: &str) -> String {
+        // 文件名在最后一个 `/` 之后（`rclone://remote:vault.json` 中在 `:` 之后）
+        let start = remote.find("://").map_or(0, |i| i + 3);
+        let split = remote[start..].rfind(['/', ':']).map_or(start, |i| start + i + 1);
+        let (dir, name) = remote.split_at(split);
+        let digest = hmac_sha256(&self.subkey(NAME_INFO), &[name.as_bytes()]);
+        let name: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}{}", dir, name)
+    }
+
+    // 以远程文件名为附加数据加密，防止把其他文件换到这个名称下
+    fn seal(&self, name: &str, data: &[u8]) -> Result<Vec<u8>, AppError> {
+        let nonce = rand::random::<[u8; 12]>();
+        let mut sealed = data.to_vec();
+        Cipher::Aes256Gcm
+            .encrypt(&self.subkey(CONTENT_INFO), &nonce, name.as_bytes(), &mut sealed)
+            .map_err(|_| AppError::from(t!("加密远程文件失败")))?;
+        Ok([&[SEALED_VERSION][..], &nonce, &sealed].concat())
+    }
+
+    fn open(&self, name: &str, data: &[u8]) -> Result<Vec<u8>, AppError> {
+        let invalid = || AppError::from(t!("无法解密远程文件，请检查同步密钥是否与其他设备上的相同"));
+        let (&version, rest) = data.split_first().ok_or_else(invalid)?;
+        if version != SEALED_VERSION || rest.len() < 12 {
+            return Err(invalid());
+        }
+        let (nonce, sealed) = rest.split_at(12);
+        let mut opened = sealed.to_vec();
+        Cipher::Aes256Gcm.decrypt(&self.subkey(CONTENT_INFO), nonce, name.as_bytes(), &mut opened).map_err(|_| invalid())?;
+        Ok(opened)
+    }
+}
+
+// 按地址的协议选择后端
+fn backend(remote: &str) -> Option<Box<dyn SyncBackend>> {
+    if let Some(backend) = webdav::WebDav::parse(remote) {
+        return Some(Box::new(backend));
+    }
+    if let Some(backend) = s3::S3::parse(remote) {
+        return Some(Box::new(backend));
+    }
+    if let Some(backend) = sftp::Sftp::parse(remote) {
+        return Some(Box::new(backend));
+    }
+    rclone::Rclone::parse(remote).map(|backend| Box::new(backend) as Box<dyn SyncBackend>)
+}
+
+/// 同步密码库 `vault_path` 与远程存储 `remote`（例如 `webdav://host/path/vault.json`、
+/// `s3://bucket/path/vault.json`、`ssh://host/path/vault.json`、`rclone://remote:vault.json`），
+/// `key` 为同步密钥
+///
+/// 调用前应持有密码库的锁，下载时会替换密码库文件。
+pub fn sync(vault_path: &Path, remote: &str, key: Option<&SyncKey>, options: Options) -> Result<Outcome, AppError> {
+    let Options { direction, keep, dry_run } = options;
+    let location = key.map_or_else(|| remote.to_string(), |key| key.conceal(remote));
+    let storage = backend(&location).ok_or_else(|| AppError::from(t!("不支持的远程存储: {}", display(remote))))?;
+    let name = location.rsplit('/').next().unwrap_or_default().to_string();
+    let encoded_key = key.map(SyncKey::encode);
+    let state = load_state(vault_path).filter(|state| state.remote == remote && state.key == encoded_key);
+    // 记下同步后两边相同的密码库文件，作为下次合并时的共同祖先
+    let save = |etag: &str, data: &[u8]| {
+        let state = State { remote: remote.to_string(), etag: etag.to_string(), hash: digest(data), key: encoded_key.clone() };
+        save_state(vault_path, &state)?;
+        save_base(vault_path, data)
+    };
+    let upload = |data: &[u8], etag: Option<&str>| match key {
+        Some(key) => storage.upload(&key.seal(&name, data)?, etag),
+        None => storage.upload(data, etag),
+    };
+    let open = |data: Vec<u8>| match key {
+        Some(key) => key.open(&name, &data),
+        None => Ok(data),
+    };
+    let missing = || match key {
+        Some(_) => AppError::from(t!("远程存储中没有这个同步密钥对应的密码库: {}", display(remote))),
+        None => AppError::from(t!("远程存储中还没有密码库: {}", display(remote))),
+    };
+    // 新设备上还没有密码库时直接下载
+    if !vault_path.exists() {
+        if direction == Some(Direction::Push) {
+            return Err(AppError::from(t!("本地还没有密码库，没有可以推送的内容")));
+        }
+        let object = storage.download()?.ok_or_else(missing)?;
+        let data = open(object.data)?;
+        if !dry_run {
+            download(vault_path, &data)?;
+            save(&object.etag, &data)?;
+        }
+        return Ok(Outcome::Downloaded);
+    }
+    let local = fs::read(vault_path)?;
+    let hash = digest(&local);
+
+    let Some(object) = storage.download()? else {
+        if direction == Some(Direction::Pull) {
+            return Err(missing());
+        }
+        if !dry_run {
+            let etag = upload(&local, None)?.ok_or_else(|| AppError::from(t!("上传期间远程文件被其他设备创建，请重新同步")))?;
+            save(&etag, &local)?;
+        }
+        return Ok(Outcome::Uploaded { replaced: None });
+    };
+    let etag = object.etag;
+    let data = open(object.data)?;
+    if data == local {
+        if !dry_run {
+            save(&etag, &local)?;
+        }
+        return Ok(Outcome::UpToDate);
+    }
+
+    let local_changed = state.as_ref().is_none_or(|state| state.hash != hash);
+    let remote_changed = state.as_ref().is_none_or(|state| state.etag != etag);
+    let to_remote = match keep {
+        Some(keep) => keep == Keep::Local,
+        None if local_changed && remote_changed && direction == Some(Direction::Push) => return Ok(Outcome::Behind),
+        None if local_changed && remote_changed => {
+            let base = state.and_then(|_| fs::read(base_path(vault_path)?).ok());
+            return Ok(Outcome::Diverged(Diverged { remote: data, base, etag }));
+        }
+        None => local_changed,
+    };
+
+    match (to_remote, direction) {
+        (true, Some(Direction::Pull)) => Ok(Outcome::Ahead),
+        (false, Some(Direction::Push)) => Ok(Outcome::Behind),
+        (true, _) => {
+            if !dry_run {
+                let new_etag = upload(&local, Some(&etag))?.ok_or_else(|| AppError::from(t!("上传期间远程文件被其他设备修改，请重新同步")))?;
+                save(&new_etag, &local)?;
+            }
+            Ok(Outcome::Uploaded { replaced: object.version.filter(|_| remote_changed) })
+        }
+        (false, _) => {
+            if !dry_run {
+                download(vault_path, &data)?;
+                save(&etag, &data)?;
+            }
+            Ok(Outcome::Downloaded)
+        }
+    }
+}
+
+/// 记下本地的密码库已经合并了 `diverged` 中远程的修改，之后再次 [`sync`] 时上传
+///
+/// 远程的密码库成为新的共同祖先；在此期间远程文件又被修改时再次返回 [`Outcome::Diverged`]。
+pub fn merged(vault_path: &Path, remote: &str, key: Option<&SyncKey>, diverged: &Diverged) -> Result<(), AppError> {
+    let state = State { remote: remote.to_string(), etag: diverged.etag.clone(), hash: String::new(), key: key.map(SyncKey::encode) };
+    save_state(vault_path, &state)?;
+    save_base(vault_path, &diverged.remote)
+}
+
+// 以下载的内容替换本地的密码库
+fn download(vault_path: &Path, data: &[u8]) -> Result<(), AppError> {
+    if !looks_like_vault(data) {
+        return Err(AppError::from(t!("远程文件不是 passman 密码库，没有覆盖本地的密码库")));
+    }
+    if let Some(dir) = vault_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    atomic::write_private(vault_path, data)?;
+    Ok(())
+}
+
+/// 上次同步使用的远程存储
+pub fn saved_remote(vault_path: &Path) -> Option<String> {
+    load_state(vault_path).map(|state| state.remote)
+}
+
+/// 上次与 `remote` 同步时使用的同步密钥
+pub fn saved_key(vault_path: &Path, remote: &str) -> Option<SyncKey> {
+    load_state(vault_path).filter(|state| state.remote == remote).and_then(|state| SyncKey::parse(&state.key?))
+}
+
+/// 显示用的地址，去掉其中的用户名和密码
+pub fn display(remote: &str) -> String {
+    match remote.split_once("://") {
+        Some((scheme, rest)) => {
+            let (authority, path) = rest.split_once('/').map_or((rest, ""), |(authority, path)| (authority, path));
+            let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            match path.is_empty() {
+                true => format!("{}://{}", scheme, host),
+                false => format!("{}://{}/{}", scheme, host, path),
+            }
+        }
+        None => remote.to_string(),
+    }
+}
+
+// 同步状态的文件路径，以密码库绝对路径的哈希命名
+fn state_path(vault_path: &Path) -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("sync").join(format!("{}.json", config::vault_id(vault_path))))
+}
+
+// 共同祖先的文件路径，与同步状态放在一起
+fn base_path(vault_path: &Path) -> Option<PathBuf> {
+    state_path(vault_path).map(|path| path.with_extension("base"))
+}
+
+fn load_state(vault_path: &Path) -> Option<State> {
+    let content = fs::read_to_string(state_path(vault_path)?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// 地址中可能带有密码，还有同步密钥，只有当前用户可以读写
+fn save_state(vault_path: &Path, state: &State) -> Result<(), AppError> {
+    let path = state_path(vault_path).ok_or_else(|| AppError::from(t!("无法确定数据目录")))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic::write_private(&path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+// 共同祖先是加密的密码库文件，与密码库一样只有当前用户可以读写
+fn save_base(vault_path: &Path, data: &[u8]) -> Result<(), AppError> {
+    let path = base_path(vault_path).ok_or_else(|| AppError::from(t!("无法确定数据目录")))?;
+    atomic::write_private(&path, data)?;
+    Ok(())
+}
+
+// 只有当前用户可以读写的临时文件，结束时删除
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(content: &[u8]) -> Result<Self, AppError> {
+        let path = env::temp_dir().join(format!("passman-sync-{:016x}", rand::random::<u64>()));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&path)?.write_all(content)?;
+        Ok(TempFile(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn digest(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 下载的内容至少应是带有加密数据的 JSON，以免把登录页面等错误响应当作密码库
+fn looks_like_vault(data: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(data).is_ok_and(|value| value.get("encrypted_data").is_some_and(|data| data.is_string()))
+}
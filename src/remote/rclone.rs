@@ -0,0 +1,81 @@
+// rclone 远程存储：把文件交给 rclone 传输，支持 rclone 的任何存储（Google Drive、Dropbox、
+// OneDrive、Box 等）
+//
+// `rclone://remote:path/vault.json` 中 `rclone://` 之后的部分原样交给 rclone，`remote` 是
+// `rclone config` 中配置的远程存储名称，认证和传输选项都沿用 rclone 的配置。rclone 没有
+// 通用的条件写入，与 SFTP 一样以文件内容的 SHA-256 摘要代替 ETag，上传前重新下载比较。
+
+use std::fs;
+use std::process::{Command, Output, Stdio};
+
+use passman::AppError;
+
+use super::{digest, Object, SyncBackend, TempFile};
+use crate::i18n::t;
+
+// rclone 表示目录或文件不存在的退出码
+const EXIT_DIRECTORY_NOT_FOUND: i32 = 3;
+const EXIT_FILE_NOT_FOUND: i32 = 4;
+
+/// rclone 远程存储中的一个文件
+pub struct Rclone {
+    target: String,
+}
+
+impl Rclone {
+    /// 解析 `rclone://` 地址，其他地址返回 None
+    pub fn parse(remote: &str) -> Option<Self> {
+        let target = remote.strip_prefix("rclone://")?;
+        (!target.is_empty() && !target.ends_with('/') && !target.ends_with(':')).then(|| Rclone { target: target.to_string() })
+    }
+
+    // 运行 rclone，返回它的输出
+    fn rclone(&self, args: &[&str]) -> Result<Output, AppError> {
+        Command::new("rclone")
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| AppError::from(t!("无法运行 {}: {}", "rclone", e)))
+    }
+}
+
+impl SyncBackend for Rclone {
+    fn download(&self) -> Result<Option<Object>, AppError> {
+        // 先查询文件，从退出码区分文件不存在和其他错误
+        let stat = self.rclone(&["lsjson", "--stat", "--", &self.target])?;
+        match stat.status.code() {
+            // 有的版本对不存在的文件输出 null
+            Some(0) if matches!(String::from_utf8_lossy(&stat.stdout).trim(), "" | "null") => return Ok(None),
+            Some(0) => {}
+            Some(EXIT_DIRECTORY_NOT_FOUND | EXIT_FILE_NOT_FOUND) => return Ok(None),
+            _ => return Err(error(&stat)),
+        }
+        let local = TempFile::create(b"")?;
+        let copy = self.rclone(&["copyto", "--ignore-times", "--", &self.target, &local.path().to_string_lossy()])?;
+        if !copy.status.success() {
+            return Err(error(&copy));
+        }
+        let data = fs::read(local.path())?;
+        Ok(Some(Object { etag: digest(&data), data, version: None }))
+    }
+
+    fn upload(&self, data: &[u8], etag: Option<&str>) -> Result<Option<String>, AppError> {
+        let current = self.download()?;
+        if current.as_ref().map(|object| object.etag.as_str()) != etag {
+            return Ok(None);
+        }
+        let local = TempFile::create(data)?;
+        // 大小和修改时间相同时 rclone 默认跳过传输
+        let copy = self.rclone(&["copyto", "--ignore-times", "--", &local.path().to_string_lossy(), &self.target])?;
+        if !copy.status.success() {
+            return Err(error(&copy));
+        }
+        Ok(Some(digest(data)))
+    }
+}
+
+fn error(output: &Output) -> AppError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or_default();
+    AppError::from(t!("rclone 失败: {}", message))
+}
@@ -0,0 +1,163 @@
+// S3 兼容的对象存储（Amazon S3、MinIO、Backblaze B2 等），通过 curl 的 --aws-sigv4 签名请求
+//
+// `s3://bucket/path/vault.json` 访问 Amazon S3；`s3+https://host/bucket/path/vault.json` 以
+// 路径形式访问其他兼容 S3 的服务（`s3+http://` 只应用于本机或局域网中的服务器）。区域取自
+// AWS_REGION（或 AWS_DEFAULT_REGION），没有设置时从 `s3.<区域>.` 形式的主机名中取，
+// 否则为 us-east-1。访问密钥取自 AWS_ACCESS_KEY_ID、AWS_SECRET_ACCESS_KEY（以及
+// AWS_SESSION_TOKEN），没有设置时读取 ~/.aws/credentials 中 AWS_PROFILE（默认 default）
+// 的配置。上传时以 If-Match / If-None-Match 带上上次看到的 ETag；存储桶启用了版本控制时
+// 记下对象的版本 ID，覆盖远程的修改后告诉用户旧版本仍可找回。
+
+use std::env;
+use std::fs;
+
+use passman::AppError;
+
+use super::http::{self, Response};
+use super::{digest, Object, SyncBackend};
+use crate::i18n::t;
+
+// 没有设置区域时使用的区域
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// 存储桶中的一个对象
+pub struct S3 {
+    url: String,
+    region: String,
+}
+
+// 签名请求使用的访问密钥
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl S3 {
+    /// 解析 `s3://`、`s3+https://` 或 `s3+http://` 地址，其他地址返回 None
+    pub fn parse(remote: &str) -> Option<Self> {
+        let (scheme, rest) = remote.split_once("://")?;
+        let (authority, key) = rest.split_once('/')?;
+        let configured = env::var("AWS_REGION").or_else(|_| env::var("AWS_DEFAULT_REGION")).ok().filter(|region| !region.is_empty());
+        let endpoint_region = || host_region(authority.split(':').next().unwrap_or_default());
+        let (url, region) = match scheme {
+            "s3" => {
+                let region = configured.unwrap_or_else(|| DEFAULT_REGION.to_string());
+                (format!("https://{}.s3.{}.amazonaws.com/{}", authority, region, key), region)
+            }
+            "s3+https" | "s3+http" => {
+                let region = configured.or_else(endpoint_region).unwrap_or_else(|| DEFAULT_REGION.to_string());
+                (format!("{}://{}", &scheme[3..], rest), region)
+            }
+            _ => return None,
+        };
+        Some(S3 { url, region })
+    }
+
+    fn request(&self, args: &[&str], input: Option<&[u8]>) -> Result<Response, AppError> {
+        let credentials = Credentials::load()?;
+        let mut secret = vec![
+            http::option("aws-sigv4", &format!("aws:amz:{}:s3", self.region)),
+            http::option("user", &format!("{}:{}", credentials.access_key, credentials.secret_key)),
+        ];
+        if let Some(token) = &credentials.session_token {
+            secret.push(http::option("header", &format!("x-amz-security-token: {}", token)));
+        }
+        http::request(&self.url, args, &secret, input)
+    }
+}
+
+impl SyncBackend for S3 {
+    fn download(&self) -> Result<Option<Object>, AppError> {
+        let response = self.request(&[], None)?;
+        match response.status {
+            200 => {
+                let etag = response.etag().ok_or_else(|| AppError::from(t!("S3 服务没有返回 ETag，无法检测冲突")))?;
+                Ok(Some(Object { etag, version: version(&response), data: response.body }))
+            }
+            404 => Ok(None),
+            status => Err(error(status, &response)),
+        }
+    }
+
+    fn upload(&self, data: &[u8], etag: Option<&str>) -> Result<Option<String>, AppError> {
+        let condition = match etag {
+            Some(etag) => format!("If-Match: {}", etag),
+            None => "If-None-Match: *".to_string(),
+        };
+        // curl 不计算上传内容的摘要，需要由请求头给出
+        let content_hash = format!("x-amz-content-sha256: {}", digest(data));
+        let args = ["--header", &condition, "--header", &content_hash, "--header", "Content-Type: application/octet-stream"];
+        let response = self.request(&args, Some(data))?;
+        match response.status {
+            200 => response.etag().map(Some).ok_or_else(|| AppError::from(t!("S3 服务没有返回 ETag，无法检测冲突"))),
+            // 409 是同时有另一个条件上传的请求
+            412 | 409 => Ok(None),
+            status => Err(error(status, &response)),
+        }
+    }
+}
+
+impl Credentials {
+    fn load() -> Result<Self, AppError> {
+        let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        if let (Some(access_key), Some(secret_key)) = (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+            return Ok(Credentials { access_key, secret_key, session_token: var("AWS_SESSION_TOKEN") });
+        }
+        let profile = var("AWS_PROFILE").unwrap_or_else(|| "default".to_string());
+        let path = var("AWS_SHARED_CREDENTIALS_FILE")
+            .map(Into::into)
+            .or_else(|| dirs_next::home_dir().map(|home| home.join(".aws").join("credentials")));
+        let content = path.and_then(|path| fs::read_to_string(path).ok()).unwrap_or_default();
+        let value = |key: &str| profile_value(&content, &profile, key);
+        match (value("aws_access_key_id"), value("aws_secret_access_key")) {
+            (Some(access_key), Some(secret_key)) => {
+                Ok(Credentials { access_key, secret_key, session_token: value("aws_session_token") })
+            }
+            _ => Err(AppError::from(t!("没有找到 S3 的访问密钥，请设置 AWS_ACCESS_KEY_ID 和 AWS_SECRET_ACCESS_KEY，或在 ~/.aws/credentials 中配置 {}", profile))),
+        }
+    }
+}
+
+// ~/.aws/credentials 中 `[profile]` 一节的 `key = value`
+fn profile_value(content: &str, profile: &str, key: &str) -> Option<String> {
+    let mut in_profile = false;
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            in_profile = name.trim() == profile;
+        } else if in_profile
+            && let Some((name, value)) = line.split_once('=')
+            && name.trim() == key
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+// `s3.us-west-004.backblazeb2.com` 形式的主机名中的区域
+fn host_region(host: &str) -> Option<String> {
+    let mut labels = host.split('.');
+    while let Some(label) = labels.next() {
+        if label == "s3" {
+            return labels.next().filter(|region| region.contains('-')).map(str::to_string);
+        }
+    }
+    None
+}
+
+// 存储桶启用了版本控制时对象的版本 ID
+fn version(response: &Response) -> Option<String> {
+    response.header("x-amz-version-id").filter(|version| version != "null")
+}
+
+fn error(status: u16, response: &Response) -> AppError {
+    // S3 的错误响应是 XML，取其中的 <Code>
+    let body = String::from_utf8_lossy(&response.body);
+    let code = body.split_once("<Code>").and_then(|(_, rest)| rest.split_once("</Code>")).map(|(code, _)| code.to_string());
+    match (status, code) {
+        (401 | 403, _) => AppError::from(t!("S3 服务拒绝访问（HTTP {}），请检查访问密钥、区域和存储桶的权限", status)),
+        (status, Some(code)) => AppError::from(t!("S3 请求失败: HTTP {}（{}）", status, code)),
+        (status, None) => AppError::from(t!("S3 请求失败: HTTP {}", status)),
+    }
+}
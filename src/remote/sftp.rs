@@ -0,0 +1,107 @@
+// SFTP 远程存储：通过 OpenSSH 的 sftp 命令访问只提供 SSH 的服务器
+//
+// `ssh://[user@]host[:port]/path/vault.json`（或 `sftp://…`）中的路径是绝对路径，
+// `ssh://host/~/vault.json` 相对于登录后的主目录。认证交给 ssh：沿用 ssh-agent 中的密钥和
+// ~/.ssh/config 的配置（主机别名、端口、跳板机等），以 BatchMode 运行，不会提示输入密码。
+// SFTP 没有 ETag，也没有条件写入，以文件内容的 SHA-256 摘要代替 ETag：上传前重新下载并比较
+// 摘要，再写入同一目录下的临时文件并改名替换，不会留下写了一半的文件。比较和替换之间的
+// 短暂间隔内另一台设备的上传仍可能被覆盖。
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use passman::AppError;
+
+use super::{digest, Object, SyncBackend, TempFile};
+use crate::i18n::t;
+
+/// SSH 服务器上的一个文件
+pub struct Sftp {
+    // [user@]host
+    destination: String,
+    port: Option<String>,
+    path: String,
+}
+
+impl Sftp {
+    /// 解析 `ssh://` 或 `sftp://` 地址，其他地址返回 None
+    pub fn parse(remote: &str) -> Option<Self> {
+        let (scheme, rest) = remote.split_once("://")?;
+        if scheme != "ssh" && scheme != "sftp" {
+            return None;
+        }
+        let (authority, path) = rest.split_once('/')?;
+        let (destination, port) = match authority.rsplit_once(':') {
+            Some((destination, port)) if port.chars().all(|c| c.is_ascii_digit()) => (destination, Some(port.to_string())),
+            _ => (authority, None),
+        };
+        let path = match path.strip_prefix("~/") {
+            Some(path) => path.to_string(),
+            None => format!("/{}", path),
+        };
+        (!destination.is_empty() && !path.ends_with('/')).then(|| Sftp { destination: destination.to_string(), port, path })
+    }
+
+    // 以批处理模式运行 sftp，失败时返回 sftp 的错误信息
+    fn batch(&self, commands: &[String]) -> Result<(), String> {
+        let mut child = Command::new("sftp")
+            .args(["-q", "-b", "-", "-o", "BatchMode=yes"])
+            .args(self.port.iter().flat_map(|port| ["-P", port.as_str()]))
+            .arg("--")
+            .arg(&self.destination)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| t!("无法运行 {}: {}", "sftp", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(format!("{}\n", commands.join("\n")).as_bytes()).map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join("; "))
+    }
+}
+
+impl SyncBackend for Sftp {
+    fn download(&self) -> Result<Option<Object>, AppError> {
+        let local = TempFile::create(b"")?;
+        if let Err(message) = self.batch(&[format!("get {} {}", quote(&self.path), quote(&local.path().to_string_lossy()))]) {
+            if message.contains("not found") || message.contains("No such file") {
+                return Ok(None);
+            }
+            return Err(AppError::from(t!("SFTP 下载失败: {}", message)));
+        }
+        let data = fs::read(local.path())?;
+        Ok(Some(Object { etag: digest(&data), data, version: None }))
+    }
+
+    fn upload(&self, data: &[u8], etag: Option<&str>) -> Result<Option<String>, AppError> {
+        let current = self.download()?;
+        if current.as_ref().map(|object| object.etag.as_str()) != etag {
+            return Ok(None);
+        }
+        let local = TempFile::create(data)?;
+        let (dir, name) = self.path.rsplit_once('/').map_or(("", self.path.as_str()), |(dir, name)| (dir, name));
+        let temp = format!("{}{}.{}.passman-{:08x}", dir, if dir.is_empty() { "" } else { "/" }, name, rand::random::<u32>());
+        // OpenSSH 的 sftp 有 posix-rename 扩展时以它替换已存在的文件
+        let result = self.batch(&[
+            format!("put {} {}", quote(&local.path().to_string_lossy()), quote(&temp)),
+            format!("rename {} {}", quote(&temp), quote(&self.path)),
+        ]);
+        if let Err(message) = result {
+            let _ = self.batch(&[format!("-rm {}", quote(&temp))]);
+            return Err(AppError::from(t!("SFTP 上传失败: {}", message)));
+        }
+        Ok(Some(digest(data)))
+    }
+}
+
+// sftp 批处理命令中的参数：加上双引号，引号中的通配符不会展开
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
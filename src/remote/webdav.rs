@@ -0,0 +1,79 @@
+// WebDAV 远程存储（Nextcloud、ownCloud 等），通过 curl 访问
+//
+// `webdav://host/path/vault.json` 以 HTTPS 访问，`webdav+http://` 以 HTTP 访问（只应用于
+// 本机或局域网中的服务器）。用户名和密码可以写在地址中，更好的做法是写在 ~/.netrc 里，
+// 以免出现在命令行参数中。上传时以 If-Match（首次上传时以 If-None-Match: *）带上上次看到
+// 的 ETag，远程文件在此期间被修改时服务器返回 412。`passman serve-sync` 的同步服务器使用
+// 同样的协议，地址为 `passman://`（`passman+http://` 以 HTTP 访问）。
+
+use passman::AppError;
+
+use super::http::{self, Response};
+use super::{Object, SyncBackend};
+use crate::i18n::t;
+
+/// WebDAV 服务器上的一个文件
+pub struct WebDav {
+    url: String,
+}
+
+impl WebDav {
+    /// 解析 `webdav://`、`passman://` 或以 `+http` 结尾的这两种地址，其他地址返回 None
+    pub fn parse(remote: &str) -> Option<Self> {
+        let (scheme, rest) = remote.split_once("://")?;
+        let scheme = match scheme {
+            "webdav" | "passman" => "https",
+            "webdav+http" | "passman+http" => "http",
+            _ => return None,
+        };
+        Some(WebDav { url: format!("{}://{}", scheme, rest) })
+    }
+
+    fn request(&self, args: &[&str], input: Option<&[u8]>) -> Result<Response, AppError> {
+        http::request(&self.url, args, &[], input)
+    }
+}
+
+impl SyncBackend for WebDav {
+    fn download(&self) -> Result<Option<Object>, AppError> {
+        let response = self.request(&[], None)?;
+        match response.status {
+            200 => {
+                let etag = require_etag(&response)?;
+                Ok(Some(Object { data: response.body, etag, version: None }))
+            }
+            404 => Ok(None),
+            status => Err(error(status)),
+        }
+    }
+
+    fn upload(&self, data: &[u8], etag: Option<&str>) -> Result<Option<String>, AppError> {
+        let condition = match etag {
+            Some(etag) => format!("If-Match: {}", etag),
+            None => "If-None-Match: *".to_string(),
+        };
+        let response = self.request(&["--header", &condition], Some(data))?;
+        match response.status {
+            200 | 201 | 204 => {}
+            412 => return Ok(None),
+            409 => return Err(AppError::from(t!("WebDAV 服务器上的目录不存在"))),
+            status => return Err(error(status)),
+        }
+        // 有的服务器不在上传的响应中返回 ETag，需要再查询一次
+        match response.etag() {
+            Some(etag) => Ok(Some(etag)),
+            None => require_etag(&self.request(&["--head"], None)?).map(Some),
+        }
+    }
+}
+
+fn require_etag(response: &Response) -> Result<String, AppError> {
+    response.etag().ok_or_else(|| AppError::from(t!("WebDAV 服务器没有返回 ETag，无法检测冲突")))
+}
+
+fn error(status: u16) -> AppError {
+    match status {
+        401 | 403 => AppError::from(t!("WebDAV 服务器拒绝访问（HTTP {}），请检查 ~/.netrc 或地址中的用户名和密码", status)),
+        status => AppError::from(t!("WebDAV 请求失败: HTTP {}", status)),
+    }
+}
@@ -0,0 +1,183 @@
+//! 在用户名、备注、网址、标签和文件夹中搜索账号
+//!
+//! 支持四种匹配方式：忽略大小写的子串匹配、按顺序包含查询字符的模糊匹配、通配符以及正则表达式。
+
+mod regex;
+
+use crate::error::AppError;
+use crate::vault::{Account, AccountStore};
+
+pub use regex::Regex;
+
+/// 匹配方式
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// 忽略大小写的子串匹配
+    Substring,
+    /// 查询中的字符按顺序出现即可（例如 `gthb` 匹配 `github`），结果按匹配程度排序
+    Fuzzy,
+    /// 正则表达式
+    Regex,
+    /// 忽略大小写的通配符，`*` 匹配任意多个字符，`?` 匹配一个字符，需要匹配整个字段；
+    /// 不含通配符时与子串匹配相同
+    Glob,
+}
+
+/// 参与搜索的字段
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Username,
+    Notes,
+    /// 备注中的网址
+    Url,
+    Tags,
+    Folder,
+}
+
+/// 所有字段
+pub const ALL_FIELDS: [Field; 5] = [Field::Username, Field::Notes, Field::Url, Field::Tags, Field::Folder];
+
+/// 编译后的查询
+pub struct Query {
+    matcher: Matcher,
+}
+
+enum Matcher {
+    Substring(String),
+    Fuzzy(Vec<char>),
+    Regex(Regex),
+    Glob(Vec<char>),
+}
+
+impl Query {
+    pub fn new(pattern: &str, mode: Mode) -> Result<Self, AppError> {
+        let matcher = match mode {
+            Mode::Substring => Matcher::Substring(pattern.to_lowercase()),
+            Mode::Fuzzy => Matcher::Fuzzy(pattern.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect()),
+            Mode::Regex => Matcher::Regex(Regex::new(pattern, false)?),
+            Mode::Glob if !pattern.contains(['*', '?']) => Matcher::Substring(pattern.to_lowercase()),
+            Mode::Glob => Matcher::Glob(pattern.chars().flat_map(char::to_lowercase).collect()),
+        };
+        Ok(Query { matcher })
+    }
+
+    /// 文本与查询的匹配得分，不匹配时返回 `None`（模糊匹配以外的得分恒为 0）
+    pub fn score(&self, text: &str) -> Option<i64> {
+        match &self.matcher {
+            Matcher::Substring(pattern) => text.to_lowercase().contains(pattern.as_str()).then_some(0),
+            Matcher::Fuzzy(pattern) => fuzzy_score(pattern, text),
+            Matcher::Regex(regex) => regex.is_match(text).then_some(0),
+            Matcher::Glob(pattern) => {
+                let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+                glob_match(pattern, &text).then_some(0)
+            }
+        }
+    }
+
+    /// 账号的指定字段中是否有匹配的值
+    pub fn matches(&self, account: &Account, fields: &[Field]) -> bool {
+        fields.iter().flat_map(|field| field_values(*field, account)).any(|value| self.score(value).is_some())
+    }
+}
+
+/// 在指定字段中搜索，返回匹配的账号（模糊匹配按得分从高到低，其余按用户名排序）
+pub fn search<'a>(accounts: &'a AccountStore, query: &Query, fields: &[Field]) -> Vec<&'a Account> {
+    let mut results: Vec<(i64, &Account)> = accounts
+        .iter()
+        .filter_map(|account| {
+            let score = fields
+                .iter()
+                .flat_map(|field| field_values(*field, account))
+                .filter_map(|value| query.score(value))
+                .max()?;
+            Some((score, account))
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.username.cmp(&b.1.username)));
+    results.into_iter().map(|(_, account)| account).collect()
+}
+
+// 字段的所有取值
+fn field_values(field: Field, account: &Account) -> Vec<&str> {
+    match field {
+        Field::Username => vec![account.username.as_str()],
+        Field::Notes => vec![account.notes.as_str()],
+        Field::Url => account
+            .url
+            .as_deref()
+            .into_iter()
+            .chain(
+                account
+                    .notes
+                    .split_whitespace()
+                    .filter(|word| word.starts_with("http://") || word.starts_with("https://")),
+            )
+            .collect(),
+        Field::Tags => account.tags.iter().map(String::as_str).collect(),
+        Field::Folder => account.folder.as_deref().into_iter().collect(),
+    }
+}
+
+// 通配符匹配：记录最近一个 * 的位置，失配时让它多匹配一个字符后重试
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// 模糊匹配得分：每个匹配的字符得分，连续匹配、位于单词开头的匹配额外加分，跳过的字符扣分
+fn fuzzy_score(pattern: &[char], text: &str) -> Option<i64> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut index = 0;
+    let mut previous: Option<usize> = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if index == pattern.len() {
+            break;
+        }
+        if c.to_lowercase().next() != Some(pattern[index]) {
+            continue;
+        }
+
+        score += 10;
+        match previous {
+            Some(p) if p + 1 == i => score += 15,
+            Some(p) => score -= (i - p - 1).min(10) as i64,
+            None => score -= i.min(10) as i64,
+        }
+        let at_word_start = i == 0 || !chars[i - 1].is_alphanumeric() || (chars[i - 1].is_lowercase() && c.is_uppercase());
+        if at_word_start {
+            score += 10;
+        }
+
+        previous = Some(i);
+        index += 1;
+    }
+
+    (index == pattern.len()).then_some(score)
+}
@@ -0,0 +1,461 @@
+// 搜索使用的小型正则表达式引擎
+//
+// 支持字面字符、`.`、字符类（`[a-z]`、`[^0-9]`、`\d \w \s` 及其大写形式）、
+// 锚点 `^ $`、分组与选择 `( | )`、`(?: )`、量词 `* + ? {n} {n,} {n,m}`（及其惰性形式）。
+// 只判断是否匹配，不提供捕获组。
+// 表达式编译为 Thompson NFA 后逐字符模拟，匹配时间与文本长度成线性关系，不会因回溯而变慢。
+
+// 计数量词允许的最大次数，避免编译出过大的程序
+const MAX_REPEAT: u32 = 1000;
+// 编译后程序的最大指令数
+const MAX_PROGRAM: usize = 100_000;
+
+/// 编译后的正则表达式
+pub struct Regex {
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+impl Regex {
+    /// 编译正则表达式，`ignore_case` 为真时忽略大小写（也可以在表达式开头写 `(?i)`）
+    pub fn new(pattern: &str, ignore_case: bool) -> Result<Self, String> {
+        let (pattern, ignore_case) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (rest, true),
+            None => (pattern, ignore_case),
+        };
+
+        let mut parser = Parser { chars: pattern.chars().collect(), pos: 0 };
+        let node = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("正则表达式第 {} 个字符处有多余的 )", parser.pos + 1));
+        }
+
+        let mut compiler = Compiler { program: Vec::new() };
+        compiler.compile(&node)?;
+        compiler.push(Inst::Match)?;
+        Ok(Regex { program: compiler.program, ignore_case })
+    }
+
+    /// 文本中是否有任意位置与表达式匹配
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+
+        for pos in 0..=chars.len() {
+            // 未锚定搜索：每个位置都开始一个新线程
+            self.add_thread(&mut current, 0, pos, &chars);
+            if current.list.iter().any(|&pc| matches!(self.program[pc], Inst::Match)) {
+                return true;
+            }
+            let Some(&c) = chars.get(pos) else { break };
+
+            for &pc in &current.list {
+                let matched = match &self.program[pc] {
+                    Inst::Char(expected) => self.char_eq(*expected, c),
+                    Inst::Any => true,
+                    Inst::Class(class) => self.class_matches(class, c),
+                    _ => false,
+                };
+                if matched {
+                    self.add_thread(&mut next, pc + 1, pos + 1, &chars);
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        current.list.iter().any(|&pc| matches!(self.program[pc], Inst::Match))
+    }
+
+    // 沿空转移加入线程，遇到需要消耗字符的指令为止
+    fn add_thread(&self, threads: &mut Threads, pc: usize, pos: usize, chars: &[char]) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            match self.program[pc] {
+                Inst::Jump(target) => stack.push(target),
+                Inst::Split(first, second) => {
+                    // 后入栈的先处理，保持优先顺序
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == chars.len() => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => threads.list.push(pc),
+            }
+        }
+    }
+
+    fn char_eq(&self, expected: char, c: char) -> bool {
+        expected == c || (self.ignore_case && fold(expected) == fold(c))
+    }
+
+    fn class_matches(&self, class: &Class, c: char) -> bool {
+        let contains = |c: char| class.items.iter().any(|item| item.contains(c));
+        let found = contains(c)
+            || (self.ignore_case && (contains(fold(c)) || c.to_uppercase().any(contains)));
+        found != class.negated
+    }
+}
+
+// 大小写折叠（只取第一个小写字符）
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+// 线程列表，`seen` 保证同一指令在一步中只出现一次
+struct Threads {
+    list: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Threads { list: Vec::new(), seen: vec![false; size] }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.seen.fill(false);
+    }
+}
+
+// NFA 指令
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    Jump(usize),
+    // 依次尝试两个分支
+    Split(usize, usize),
+    Match,
+}
+
+#[derive(Clone)]
+struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+#[derive(Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn contains(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(low, high) => low <= c && c <= high,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => (c.is_alphanumeric() || c == '_') != negated,
+            ClassItem::Space(negated) => c.is_whitespace() != negated,
+        }
+    }
+}
+
+// 语法树
+#[derive(Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat { node: Box<Node>, min: u32, max: Option<u32> },
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("正则表达式第 {} 个字符处{}", self.pos + 1, message)
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alternate(branches) })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantifier(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or_else(|| self.error("不完整"))?;
+        self.pos += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let node = self.alternation()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("缺少 )"));
+                }
+                self.pos += 1;
+                node
+            }
+            '[' => Node::Class(self.class()?),
+            '\\' => self.escape(false)?,
+            '*' | '+' | '?' => return Err(self.error("的量词前面没有内容")),
+            c => Node::Char(c),
+        })
+    }
+
+    // 转义序列，`in_class` 为真时只允许出现在字符类中的形式
+    fn escape(&mut self, in_class: bool) -> Result<Node, String> {
+        let c = self.peek().ok_or_else(|| self.error("的 \\ 后面缺少字符"))?;
+        self.pos += 1;
+        let class = |item| Node::Class(Class { negated: false, items: vec![item] });
+        Ok(match c {
+            'd' => class(ClassItem::Digit(false)),
+            'D' => class(ClassItem::Digit(true)),
+            'w' => class(ClassItem::Word(false)),
+            'W' => class(ClassItem::Word(true)),
+            's' => class(ClassItem::Space(false)),
+            'S' => class(ClassItem::Space(true)),
+            'n' => Node::Char('\n'),
+            't' => Node::Char('\t'),
+            'r' => Node::Char('\r'),
+            c if c.is_ascii_punctuation() || (in_class && c == ' ') => Node::Char(c),
+            c => return Err(format!("正则表达式中不支持的转义: \\{}", c)),
+        })
+    }
+
+    fn class(&mut self) -> Result<Class, String> {
+        let mut class = Class { negated: false, items: Vec::new() };
+        if self.peek() == Some('^') {
+            class.negated = true;
+            self.pos += 1;
+        }
+
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("缺少 ]"))?;
+            if c == ']' && !first {
+                self.pos += 1;
+                return Ok(class);
+            }
+            first = false;
+            self.pos += 1;
+
+            let low = match c {
+                '\\' => match self.escape(true)? {
+                    Node::Char(c) => c,
+                    Node::Class(inner) => {
+                        class.items.extend(inner.items);
+                        continue;
+                    }
+                    _ => unreachable!(),
+                },
+                c => c,
+            };
+
+            // 范围 a-z（末尾的 - 按字面处理）
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let mut high = self.chars[self.pos];
+                self.pos += 1;
+                if high == '\\' {
+                    high = match self.escape(true)? {
+                        Node::Char(c) => c,
+                        _ => return Err(self.error("的范围无效")),
+                    };
+                }
+                if high < low {
+                    return Err(self.error("的范围顺序颠倒"));
+                }
+                class.items.push(ClassItem::Range(low, high));
+            } else {
+                class.items.push(ClassItem::Range(low, low));
+            }
+        }
+    }
+
+    fn quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.counted()? {
+                Some(range) => range,
+                // 不是合法的计数量词，按字面字符处理
+                None => return Ok(atom),
+            },
+            Some(c @ ('*' | '+' | '?')) => {
+                self.pos += 1;
+                match c {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End | Node::Empty) {
+            return Err(self.error("的量词前面没有内容"));
+        }
+
+        // 只判断是否匹配，惰性量词与贪婪量词的结果相同
+        if self.peek() == Some('?') {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('*' | '+' | '?')) {
+            return Err(self.error("有重复的量词"));
+        }
+        Ok(Node::Repeat { node: Box::new(atom), min, max })
+    }
+
+    // 解析 {n}、{n,}、{n,m}，成功时越过右括号
+    fn counted(&mut self) -> Result<Option<(u32, Option<u32>)>, String> {
+        let rest: String = self.chars[self.pos..].iter().collect();
+        let Some(end) = rest.find('}') else { return Ok(None) };
+        let body = &rest[1..end];
+        let number = |s: &str| s.parse::<u32>().ok();
+
+        let (min, max) = match body.split_once(',') {
+            None => match number(body) {
+                Some(n) => (n, Some(n)),
+                None => return Ok(None),
+            },
+            Some((low, "")) => match number(low) {
+                Some(n) => (n, None),
+                None => return Ok(None),
+            },
+            Some((low, high)) => match (number(low), number(high)) {
+                (Some(low), Some(high)) => (low, Some(high)),
+                _ => return Ok(None),
+            },
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("的重复次数范围无效"));
+        }
+        if min.max(max.unwrap_or(0)) > MAX_REPEAT {
+            return Err(format!("正则表达式的重复次数不能超过 {}", MAX_REPEAT));
+        }
+        self.pos += body.chars().count() + 2;
+        Ok(Some((min, max)))
+    }
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> Result<usize, String> {
+        if self.program.len() >= MAX_PROGRAM {
+            return Err("正则表达式过于复杂".to_string());
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn compile(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Empty => {}
+            Node::Char(c) => {
+                self.push(Inst::Char(*c))?;
+            }
+            Node::Any => {
+                self.push(Inst::Any)?;
+            }
+            Node::Class(class) => {
+                self.push(Inst::Class(class.clone()))?;
+            }
+            Node::Start => {
+                self.push(Inst::Start)?;
+            }
+            Node::End => {
+                self.push(Inst::End)?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            }
+            Node::Alternate(branches) => {
+                // split L1, next; L1: 分支; jump end; ...
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 < branches.len() {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile(branch)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        let next = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.compile(branch)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat { node, min, max } => {
+                for _ in 0..*min {
+                    self.compile(node)?;
+                }
+                match max {
+                    // 剩余部分：x*
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile(node)?;
+                        self.push(Inst::Jump(split))?;
+                        let end = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, end);
+                    }
+                    // 剩余部分：(x(x(x)?)?)?
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.compile(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! 锁定保存敏感数据的内存
+//!
+//! 派生出的密钥和解密后的密码库数据保存在按页分配、用 mlock 锁定的内存中，不会被
+//! 交换到磁盘，释放前用零覆盖。操作系统拒绝锁定时（例如超过 `ulimit -l` 的限制）照常
+//! 使用，只记录下来，由调用者决定是否提示，见 [`lock_denied`]。Windows 上暂不锁定。
+
+use std::alloc::{self, Layout};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{self, AtomicBool, Ordering};
+
+// 是否有内存被操作系统拒绝锁定
+static LOCK_DENIED: AtomicBool = AtomicBool::new(false);
+
+/// 是否有敏感数据因为操作系统拒绝而没能锁定在内存中
+pub fn lock_denied() -> bool {
+    LOCK_DENIED.load(Ordering::Relaxed)
+}
+
+/// 锁定在内存中的字节缓冲区，释放前用零覆盖
+// 按整页分配，以免解锁时连带解锁其他缓冲区所在的页
+pub struct SecretBytes {
+    ptr: NonNull<u8>,
+    len: usize,
+    capacity: usize,
+    locked: bool,
+}
+
+// 与 Vec<u8> 一样独占所指向的内存
+unsafe impl Send for SecretBytes {}
+unsafe impl Sync for SecretBytes {}
+
+impl SecretBytes {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let page = page_size();
+        let capacity = capacity.max(1).div_ceil(page) * page;
+        let layout = layout(capacity);
+        let ptr = NonNull::new(unsafe { alloc::alloc_zeroed(layout) }).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        let locked = lock(ptr, capacity);
+        SecretBytes { ptr, len: 0, capacity, locked }
+    }
+
+    /// 复制 `data` 到新的缓冲区
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut bytes = SecretBytes::with_capacity(data.len());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    pub(crate) fn extend_from_slice(&mut self, data: &[u8]) {
+        if self.len + data.len() > self.capacity {
+            // 换到更大的缓冲区，原来的缓冲区在替换后被清零并释放
+            let mut larger = SecretBytes::with_capacity((self.len + data.len()).max(self.capacity * 2));
+            larger.extend_from_slice(self);
+            *self = larger;
+        }
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), self.ptr.as_ptr().add(self.len), data.len()) };
+        self.len += data.len();
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            zero(&mut self[len..]);
+            self.len = len;
+        }
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for SecretBytes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for SecretBytes {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+// 用于 serde_json::to_writer 等直接写入
+impl io::Write for SecretBytes {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// 用于原地加密和解密
+impl aes_gcm::aead::Buffer for SecretBytes {
+    fn extend_from_slice(&mut self, other: &[u8]) -> aes_gcm::aead::Result<()> {
+        SecretBytes::extend_from_slice(self, other);
+        Ok(())
+    }
+
+    fn truncate(&mut self, len: usize) {
+        SecretBytes::truncate(self, len);
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        let all = unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.capacity) };
+        zero(all);
+        if self.locked {
+            unlock(self.ptr, self.capacity);
+        }
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), layout(self.capacity)) };
+    }
+}
+
+/// 用零覆盖内存，不会被编译器优化掉
+pub fn zero(data: &mut [u8]) {
+    for byte in data.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+fn layout(capacity: usize) -> Layout {
+    Layout::from_size_align(capacity, page_size()).expect("页大小是 2 的幂")
+}
+
+fn page_size() -> usize {
+    #[cfg(unix)]
+    {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            return size as usize;
+        }
+    }
+    4096
+}
+
+// 锁定内存，返回是否成功；失败时记录下来
+fn lock(ptr: NonNull<u8>, len: usize) -> bool {
+    #[cfg(unix)]
+    {
+        let locked = unsafe { libc::mlock(ptr.as_ptr().cast(), len) } == 0;
+        if !locked {
+            LOCK_DENIED.store(true, Ordering::Relaxed);
+        }
+        locked
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (ptr, len);
+        false
+    }
+}
+
+fn unlock(ptr: NonNull<u8>, len: usize) {
+    #[cfg(unix)]
+    unsafe {
+        libc::munlock(ptr.as_ptr().cast(), len);
+    }
+    #[cfg(not(unix))]
+    let _ = (ptr, len);
+}
@@ -0,0 +1,560 @@
+// freedesktop Secret Service 的提供者（`passman secret-service`）
+//
+// 在会话总线上占用 org.freedesktop.secrets，让 NetworkManager、Skype、git-credential-libsecret
+// 等使用 libsecret 的程序把密码保存到密码库中，代替 gnome-keyring 或 KWallet。启动时解锁一次
+// 密码库，与 `serve` 一样只在内存中保留密钥，按自动锁定的设置在空闲过久或系统休眠时退出；
+// 每个调用重新读取密码库文件并在处理期间锁定它。
+//
+// 只有一个集合（别名 default），其中的条目是指定文件夹中的账号：标签为用户名，密码为秘密，
+// 非敏感的自定义字段为属性。服务运行期间集合始终处于解锁状态，Unlock 不需要提示；只支持
+// 明文（plain）会话，秘密只在本机的会话总线上传递。秘密必须是文本。
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::net::Shutdown;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+
+use passman::crypto::CryptoBackend;
+use passman::{timestamp, Account, AppError, Vault};
+
+use crate::api_server::SharedBackend;
+use crate::autolock::{self, Reason};
+use crate::dbus::{self, Connection, Message, Value};
+use crate::i18n::{self, t};
+
+/// 在会话总线上占用的名称
+pub const BUS_NAME: &str = "org.freedesktop.secrets";
+
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/passman";
+const ALIAS_PATH: &str = "/org/freedesktop/secrets/aliases/default";
+const SESSION_PREFIX: &str = "/org/freedesktop/secrets/session/";
+// 不需要提示时返回的提示对象
+const NO_PROMPT: &str = "/";
+
+const SERVICE: &str = "org.freedesktop.Secret.Service";
+const COLLECTION: &str = "org.freedesktop.Secret.Collection";
+const ITEM: &str = "org.freedesktop.Secret.Item";
+const SESSION: &str = "org.freedesktop.Secret.Session";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+const INTROSPECTABLE: &str = "org.freedesktop.DBus.Introspectable";
+const PEER: &str = "org.freedesktop.DBus.Peer";
+
+// 错误名称
+const UNKNOWN_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+const UNKNOWN_OBJECT: &str = "org.freedesktop.DBus.Error.UnknownObject";
+const UNKNOWN_PROPERTY: &str = "org.freedesktop.DBus.Error.UnknownProperty";
+const READ_ONLY: &str = "org.freedesktop.DBus.Error.PropertyReadOnly";
+const INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const NOT_SUPPORTED: &str = "org.freedesktop.DBus.Error.NotSupported";
+const FAILED: &str = "org.freedesktop.DBus.Error.Failed";
+const NO_SUCH_OBJECT: &str = "org.freedesktop.Secret.Error.NoSuchObject";
+const NO_SESSION: &str = "org.freedesktop.Secret.Error.NoSession";
+
+// 各接口的方法（名称、参数类型、返回值类型）和属性（名称、类型、是否可写），用于内省
+type Methods = &'static [(&'static str, &'static str, &'static str)];
+type Properties = &'static [(&'static str, &'static str, bool)];
+
+const SERVICE_METHODS: Methods = &[
+    ("OpenSession", "sv", "vo"),
+    ("CreateCollection", "a{sv}s", "oo"),
+    ("SearchItems", "a{ss}", "aoao"),
+    ("Unlock", "ao", "aoo"),
+    ("Lock", "ao", "aoo"),
+    ("GetSecrets", "aoo", "a{o(oayays)}"),
+    ("ReadAlias", "s", "o"),
+    ("SetAlias", "so", ""),
+];
+const SERVICE_PROPERTIES: Properties = &[("Collections", "ao", false)];
+const COLLECTION_METHODS: Methods = &[("Delete", "", "o"), ("SearchItems", "a{ss}", "ao"), ("CreateItem", "a{sv}(oayays)b", "oo")];
+const COLLECTION_PROPERTIES: Properties = &[("Items", "ao", false), ("Label", "s", false), ("Locked", "b", false), ("Created", "t", false), ("Modified", "t", false)];
+const ITEM_METHODS: Methods = &[("Delete", "", "o"), ("GetSecret", "o", "(oayays)"), ("SetSecret", "(oayays)", "")];
+const ITEM_PROPERTIES: Properties = &[("Locked", "b", false), ("Attributes", "a{ss}", true), ("Label", "s", true), ("Created", "t", false), ("Modified", "t", false)];
+const SESSION_METHODS: Methods = &[("Close", "", "")];
+
+// 服务的状态
+struct Service<'a> {
+    vault_path: &'a Path,
+    backend: Rc<dyn CryptoBackend>,
+    folder: &'a str,
+    sessions: HashSet<String>,
+    next_session: u64,
+    // 处理调用时产生的信号，回复之后发出
+    signals: Vec<Message>,
+}
+
+// 调用路径指向的对象
+enum Object {
+    Service,
+    Collection,
+    Item(String),
+    Session(String),
+}
+
+// 出错时的错误名称和说明
+struct Failure(&'static str, String);
+
+/// 以 `backend` 中的密钥提供 Secret Service，新的条目保存在文件夹 `folder` 中，直到按自动
+/// 锁定的设置退出（返回原因）、其他程序取代了它或进程被终止
+pub fn run(vault_path: &Path, backend: Box<dyn CryptoBackend>, folder: &str, autolock: autolock::Settings) -> Result<Option<Reason>, Box<dyn Error>> {
+    let mut bus = Connection::session()?;
+    // 1 表示已成为名称的所有者，4 表示原本就是
+    if !matches!(bus.request_name(BUS_NAME)?, 1 | 4) {
+        return Err(AppError::from(t!("会话总线上已有其他程序（例如 gnome-keyring）提供 Secret Service，请先停止它")).into());
+    }
+    println!("{}", t!("正在会话总线上提供 Secret Service，条目保存在文件夹 {} 中，按 Ctrl-C 停止", folder));
+
+    // 应当锁定时关闭连接，让等待中的读取返回
+    let autolock = Arc::new(autolock.start());
+    {
+        let (autolock, stream) = (Arc::clone(&autolock), bus.try_clone_stream()?);
+        thread::spawn(move || {
+            autolock.wait(None);
+            let _ = stream.shutdown(Shutdown::Both);
+        });
+    }
+    let mut service = Service { vault_path, backend: Rc::from(backend), folder, sessions: HashSet::new(), next_session: 1, signals: Vec::new() };
+    loop {
+        let message = match bus.receive() {
+            Ok(message) => message,
+            Err(_) if autolock.reason().is_some() => break,
+            Err(e) => return Err(AppError::from(t!("与 D-Bus 会话总线的连接已断开: {}", e)).into()),
+        };
+        match message.kind {
+            dbus::METHOD_CALL => {
+                autolock.touch();
+                let (reply, result) = match service.handle(&message) {
+                    Ok(body) => (Message::method_return(&message, body), "OK"),
+                    Err(Failure(name, text)) => (Message::error(&message, name, &text), name),
+                };
+                let (sender, member, path) = (message.sender.as_deref(), message.member.as_deref(), message.path.as_deref());
+                println!("{} {} {} {} {}", timestamp::format(timestamp::now()), sender.unwrap_or("-"), member.unwrap_or_default(), path.unwrap_or_default(), result);
+                if message.flags & dbus::NO_REPLY_EXPECTED == 0 {
+                    bus.send(reply)?;
+                }
+                for signal in service.signals.drain(..) {
+                    bus.send(signal)?;
+                }
+            }
+            dbus::SIGNAL if message.member.as_deref() == Some("NameLost") && message.body.first().and_then(Value::as_str) == Some(BUS_NAME) => {
+                println!("{}", t!("其他程序取代了 Secret Service，已停止"));
+                break;
+            }
+            _ => {}
+        }
+    }
+    let reason = autolock.reason();
+    autolock.stop();
+    Ok(reason)
+}
+
+impl Service<'_> {
+    fn handle(&mut self, call: &Message) -> Result<Vec<Value>, Failure> {
+        let path = call.path.as_deref().unwrap_or_default();
+        let member = call.member.as_deref().unwrap_or_default();
+        let Some(object) = self.object(path) else {
+            return Err(Failure(UNKNOWN_OBJECT, t!("没有对象 {}", path)));
+        };
+        let (interface, methods, _) = object.interface();
+        match (call.interface.as_deref(), member) {
+            (None | Some(PEER), "Ping") => return Ok(Vec::new()),
+            (None | Some(INTROSPECTABLE), "Introspect") => return Ok(vec![Value::str(object.introspect())]),
+            (Some(PROPERTIES), "Get" | "GetAll" | "Set") => return self.properties(&object, call, member),
+            (Some(name), _) if name != interface => return Err(unknown_method(call)),
+            _ => {}
+        }
+        let Some((_, signature, _)) = methods.iter().find(|(name, _, _)| *name == member) else {
+            return Err(unknown_method(call));
+        };
+        if call.signature() != *signature {
+            return Err(Failure(INVALID_ARGS, t!("{} 的参数类型应为 ({})", member, signature)));
+        }
+        let args = &call.body;
+        match (&object, member) {
+            (Object::Service, "OpenSession") => self.open_session(args[0].as_str().unwrap_or_default()),
+            (Object::Service, "CreateCollection") => Ok(vec![Value::path(COLLECTION_PATH), Value::path(NO_PROMPT)]),
+            (Object::Service, "SearchItems") => Ok(vec![self.search(&args[0])?, Value::paths([])]),
+            // 集合始终是解锁的
+            (Object::Service, "Unlock") => Ok(vec![args[0].clone(), Value::path(NO_PROMPT)]),
+            (Object::Service, "Lock") => Ok(vec![Value::paths([]), Value::path(NO_PROMPT)]),
+            (Object::Service, "GetSecrets") => self.get_secrets(&args[0], args[1].as_str().unwrap_or_default()),
+            (Object::Service, "ReadAlias") => {
+                let path = if args[0].as_str() == Some("default") { COLLECTION_PATH } else { NO_PROMPT };
+                Ok(vec![Value::path(path)])
+            }
+            (Object::Collection, "SearchItems") => Ok(vec![self.search(&args[0])?]),
+            (Object::Collection, "CreateItem") => self.create_item(&args[0], &args[1], args[2].as_bool().unwrap_or_default()),
+            (Object::Item(id), "Delete") => self.delete_item(id),
+            (Object::Item(id), "GetSecret") => self.get_secret(id, args[0].as_str().unwrap_or_default()),
+            (Object::Item(id), "SetSecret") => self.set_secret(id, &args[0]),
+            (Object::Session(path), "Close") => {
+                self.sessions.remove(path);
+                Ok(Vec::new())
+            }
+            _ => Err(Failure(NOT_SUPPORTED, t!("passman 不支持 {}", member))),
+        }
+    }
+
+    // 路径对应的对象；条目要在打开密码库后才能确定是否存在
+    fn object(&self, path: &str) -> Option<Object> {
+        match path {
+            SERVICE_PATH => Some(Object::Service),
+            COLLECTION_PATH | ALIAS_PATH => Some(Object::Collection),
+            _ if self.sessions.contains(path) => Some(Object::Session(path.to_string())),
+            _ => {
+                let element = path.strip_prefix(COLLECTION_PATH).or_else(|| path.strip_prefix(ALIAS_PATH))?.strip_prefix('/')?;
+                item_id(element).map(Object::Item)
+            }
+        }
+    }
+
+    fn open_session(&mut self, algorithm: &str) -> Result<Vec<Value>, Failure> {
+        if algorithm != "plain" {
+            return Err(Failure(NOT_SUPPORTED, t!("只支持明文（plain）会话").to_string()));
+        }
+        let path = format!("{}{}", SESSION_PREFIX, self.next_session);
+        self.next_session += 1;
+        self.sessions.insert(path.clone());
+        Ok(vec![Value::variant(Value::str("")), Value::path(path)])
+    }
+
+    fn search(&self, attributes: &Value) -> Result<Value, Failure> {
+        let wanted = string_dict(attributes)?;
+        let vault = self.open()?;
+        let paths = self
+            .items(&vault)
+            .filter(|account| wanted.iter().all(|(name, value)| account.field(name).is_some_and(|field| !field.secret && field.value == *value)))
+            .map(|account| item_path(&account.id));
+        Ok(Value::paths(paths))
+    }
+
+    fn get_secrets(&self, items: &Value, session: &str) -> Result<Vec<Value>, Failure> {
+        self.check_session(session)?;
+        let mut vault = self.open()?;
+        let mut secrets = Vec::new();
+        let mut used = Vec::new();
+        for path in items.as_array().unwrap_or_default().iter().filter_map(Value::as_str) {
+            // 不存在的条目不出现在结果中
+            let Some(account) = path.rsplit_once('/').and_then(|(_, element)| item_id(element)).and_then(|id| self.item(&vault, &id)) else {
+                continue;
+            };
+            secrets.push((Value::path(path), secret(session, account)));
+            used.push(account.id.clone());
+        }
+        for id in used {
+            crate::record_use(&mut vault, &id);
+        }
+        Ok(vec![Value::dict("o(oayays)", secrets)])
+    }
+
+    fn create_item(&mut self, properties: &Value, secret: &Value, replace: bool) -> Result<Vec<Value>, Failure> {
+        let password = self.secret_text(secret)?;
+        let mut label = None;
+        let mut attributes = Vec::new();
+        for (name, value) in properties.entries().unwrap_or_default() {
+            match name.as_str() {
+                Some("org.freedesktop.Secret.Item.Label") => label = value.unwrap_variant().as_str().map(str::to_string),
+                Some("org.freedesktop.Secret.Item.Attributes") => attributes = string_dict(value.unwrap_variant())?,
+                _ => {}
+            }
+        }
+        let label = label.filter(|label| !label.trim().is_empty());
+
+        let mut vault = self.open()?;
+        let existing = match replace {
+            true => self.items(&vault).find(|account| same_attributes(account, &attributes)).map(|account| account.id.clone()),
+            false => None,
+        };
+        let (id, signal) = match existing {
+            Some(id) => {
+                let account = vault.get_mut(&id).ok_or_else(|| no_such_item(&id))?;
+                if let Some(label) = label {
+                    account.username = label;
+                }
+                account.set_password(&password);
+                account.touch();
+                (id, "ItemChanged")
+            }
+            None => {
+                // 条目必须有用户名，没有标签时以属性中的 schema 代替
+                let label = label
+                    .or_else(|| attributes.iter().find(|(name, _)| name == "xdg:schema").map(|(_, value)| value.clone()))
+                    .unwrap_or_else(|| "secret".to_string());
+                let mut account = Account::new(&label, &password, "");
+                account.set_folder(self.folder);
+                set_attributes(&mut account, &attributes)?;
+                (vault.add(account)?, "ItemCreated")
+            }
+        };
+        crate::save_vault(&mut vault)?;
+        self.signals.push(Message::signal(COLLECTION_PATH, COLLECTION, signal, vec![Value::path(item_path(&id))]));
+        Ok(vec![Value::path(item_path(&id)), Value::path(NO_PROMPT)])
+    }
+
+    fn delete_item(&mut self, id: &str) -> Result<Vec<Value>, Failure> {
+        let mut vault = self.open()?;
+        self.item(&vault, id).ok_or_else(|| no_such_item(id))?;
+        vault.remove(id)?;
+        crate::save_vault(&mut vault)?;
+        self.signals.push(Message::signal(COLLECTION_PATH, COLLECTION, "ItemDeleted", vec![Value::path(item_path(id))]));
+        Ok(vec![Value::path(NO_PROMPT)])
+    }
+
+    fn get_secret(&self, id: &str, session: &str) -> Result<Vec<Value>, Failure> {
+        self.check_session(session)?;
+        let mut vault = self.open()?;
+        let secret = secret(session, self.item(&vault, id).ok_or_else(|| no_such_item(id))?);
+        crate::record_use(&mut vault, id);
+        Ok(vec![secret])
+    }
+
+    fn set_secret(&mut self, id: &str, secret: &Value) -> Result<Vec<Value>, Failure> {
+        let password = self.secret_text(secret)?;
+        self.modify(id, |account| {
+            account.set_password(&password);
+            Ok(())
+        })?;
+        Ok(Vec::new())
+    }
+
+    fn properties(&mut self, object: &Object, call: &Message, member: &str) -> Result<Vec<Value>, Failure> {
+        let expected = match member {
+            "Get" => "ss",
+            "GetAll" => "s",
+            _ => "ssv",
+        };
+        if call.signature() != expected {
+            return Err(Failure(INVALID_ARGS, t!("{} 的参数类型应为 ({})", member, expected)));
+        }
+        let (interface, _, properties) = object.interface();
+        let requested = call.body[0].as_str().unwrap_or_default();
+        if !requested.is_empty() && requested != interface {
+            // 其他接口没有属性
+            if member == "GetAll" {
+                return Ok(vec![Value::dict("sv", [])]);
+            }
+            return Err(Failure(UNKNOWN_PROPERTY, t!("对象没有接口 {}", requested)));
+        }
+        if member == "Set" {
+            let name = call.body[1].as_str().unwrap_or_default();
+            if !properties.iter().any(|(property, _, writable)| *property == name && *writable) {
+                return Err(Failure(READ_ONLY, t!("属性 {} 不能修改", name)));
+            }
+            let Object::Item(id) = object else { return Err(Failure(READ_ONLY, t!("属性 {} 不能修改", name))) };
+            let value = call.body[2].unwrap_variant();
+            match name {
+                "Label" => {
+                    let label = value.as_str().filter(|label| !label.trim().is_empty()).ok_or_else(|| Failure(INVALID_ARGS, t!("用户名不能为空").to_string()))?.to_string();
+                    self.modify(id, |account| {
+                        account.username = label;
+                        Ok(())
+                    })?;
+                }
+                _ => {
+                    let attributes = string_dict(value)?;
+                    self.modify(id, |account| set_attributes(account, &attributes))?;
+                }
+            }
+            return Ok(Vec::new());
+        }
+
+        let values = self.property_values(object)?;
+        if member == "GetAll" {
+            return Ok(vec![Value::dict("sv", values.into_iter().map(|(name, value)| (Value::str(name), Value::variant(value))))]);
+        }
+        let name = call.body[1].as_str().unwrap_or_default();
+        let (_, value) = values.into_iter().find(|(property, _)| *property == name).ok_or_else(|| Failure(UNKNOWN_PROPERTY, t!("没有属性 {}", name)))?;
+        Ok(vec![Value::variant(value)])
+    }
+
+    fn property_values(&self, object: &Object) -> Result<Vec<(&'static str, Value)>, Failure> {
+        Ok(match object {
+            Object::Service => vec![("Collections", Value::paths([COLLECTION_PATH.to_string()]))],
+            Object::Collection => {
+                let vault = self.open()?;
+                let items: Vec<&Account> = self.items(&vault).collect();
+                vec![
+                    ("Items", Value::paths(items.iter().map(|account| item_path(&account.id)))),
+                    ("Label", Value::str("passman")),
+                    ("Locked", Value::Bool(false)),
+                    ("Created", Value::U64(items.iter().map(|account| account.created_at).min().unwrap_or_default())),
+                    ("Modified", Value::U64(items.iter().map(|account| account.updated_at).max().unwrap_or_default())),
+                ]
+            }
+            Object::Item(id) => {
+                let vault = self.open()?;
+                let account = self.item(&vault, id).ok_or_else(|| no_such_item(id))?;
+                let attributes = account.fields.iter().filter(|field| !field.secret).map(|field| (Value::str(&field.name), Value::str(&field.value)));
+                vec![
+                    ("Locked", Value::Bool(false)),
+                    ("Attributes", Value::dict("ss", attributes)),
+                    ("Label", Value::str(&account.username)),
+                    ("Created", Value::U64(account.created_at)),
+                    ("Modified", Value::U64(account.updated_at)),
+                ]
+            }
+            Object::Session(_) => Vec::new(),
+        })
+    }
+
+    // 修改条目并保存，发出 ItemChanged 信号
+    fn modify(&mut self, id: &str, change: impl FnOnce(&mut Account) -> Result<(), AppError>) -> Result<(), Failure> {
+        let mut vault = self.open()?;
+        self.item(&vault, id).ok_or_else(|| no_such_item(id))?;
+        let account = vault.get_mut(id).ok_or_else(|| no_such_item(id))?;
+        change(account)?;
+        account.touch();
+        crate::save_vault(&mut vault)?;
+        self.signals.push(Message::signal(COLLECTION_PATH, COLLECTION, "ItemChanged", vec![Value::path(item_path(id))]));
+        Ok(())
+    }
+
+    // 用保存的密钥打开密码库，直到调用处理完才释放锁
+    fn open(&self) -> Result<Vault, Failure> {
+        let backend = Rc::clone(&self.backend);
+        Ok(Vault::open_with_backend(self.vault_path, |_| Ok(Box::new(SharedBackend(backend))))?)
+    }
+
+    // 集合中的条目：文件夹中的账号
+    fn items<'v>(&self, vault: &'v Vault) -> impl Iterator<Item = &'v Account> {
+        let folder = self.folder.to_string();
+        vault.list().iter().filter(move |account| account.in_folder(&folder))
+    }
+
+    fn item<'v>(&self, vault: &'v Vault, id: &str) -> Option<&'v Account> {
+        vault.get(id).filter(|account| account.in_folder(self.folder))
+    }
+
+    fn check_session(&self, session: &str) -> Result<(), Failure> {
+        match self.sessions.contains(session) {
+            true => Ok(()),
+            false => Err(Failure(NO_SESSION, t!("会话 {} 不存在", session))),
+        }
+    }
+
+    // 秘密结构 (oayays) 中的文本
+    fn secret_text(&self, secret: &Value) -> Result<String, Failure> {
+        let [session, _, value, _] = secret.as_struct().unwrap_or_default() else {
+            return Err(Failure(INVALID_ARGS, t!("无效的秘密").to_string()));
+        };
+        self.check_session(session.as_str().unwrap_or_default())?;
+        String::from_utf8(value.as_bytes().unwrap_or_default()).map_err(|_| Failure(NOT_SUPPORTED, t!("passman 只能保存文本形式的秘密").to_string()))
+    }
+}
+
+impl Object {
+    fn interface(&self) -> (&'static str, Methods, Properties) {
+        match self {
+            Object::Service => (SERVICE, SERVICE_METHODS, SERVICE_PROPERTIES),
+            Object::Collection => (COLLECTION, COLLECTION_METHODS, COLLECTION_PROPERTIES),
+            Object::Item(_) => (ITEM, ITEM_METHODS, ITEM_PROPERTIES),
+            Object::Session(_) => (SESSION, SESSION_METHODS, &[]),
+        }
+    }
+
+    // 内省数据（XML）
+    fn introspect(&self) -> String {
+        let (interface, methods, properties) = self.interface();
+        let args = |signature: &str, direction: &str| -> String {
+            let mut xml = String::new();
+            let mut rest = signature;
+            while let Ok((first, remaining)) = dbus::split_type(rest) {
+                xml.push_str(&format!("<arg type=\"{}\" direction=\"{}\"/>", first, direction));
+                rest = remaining;
+            }
+            xml
+        };
+        let mut xml = String::from("<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\" \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">\n<node>\n");
+        xml.push_str(&format!("  <interface name=\"{}\">\n", interface));
+        for (name, input, output) in methods {
+            xml.push_str(&format!("    <method name=\"{}\">{}{}</method>\n", name, args(input, "in"), args(output, "out")));
+        }
+        for (name, signature, writable) in properties {
+            xml.push_str(&format!("    <property name=\"{}\" type=\"{}\" access=\"{}\"/>\n", name, signature, if *writable { "readwrite" } else { "read" }));
+        }
+        xml.push_str("  </interface>\n");
+        xml.push_str(&format!("  <interface name=\"{}\"><method name=\"Introspect\"><arg type=\"s\" direction=\"out\"/></method></interface>\n", INTROSPECTABLE));
+        xml.push_str(&format!("  <interface name=\"{}\"><method name=\"Ping\"/></interface>\n", PEER));
+        xml.push_str(&format!(
+            "  <interface name=\"{}\"><method name=\"Get\"><arg type=\"s\" direction=\"in\"/><arg type=\"s\" direction=\"in\"/><arg type=\"v\" direction=\"out\"/></method>\
+             <method name=\"GetAll\"><arg type=\"s\" direction=\"in\"/><arg type=\"a{{sv}}\" direction=\"out\"/></method>\
+             <method name=\"Set\"><arg type=\"s\" direction=\"in\"/><arg type=\"s\" direction=\"in\"/><arg type=\"v\" direction=\"in\"/></method></interface>\n",
+            PROPERTIES
+        ));
+        xml.push_str("</node>\n");
+        xml
+    }
+}
+
+impl From<AppError> for Failure {
+    fn from(e: AppError) -> Self {
+        Failure(FAILED, i18n::error_message(&e))
+    }
+}
+
+fn unknown_method(call: &Message) -> Failure {
+    let member = call.member.as_deref().unwrap_or_default();
+    Failure(UNKNOWN_METHOD, t!("没有方法 {}.{}", call.interface.as_deref().unwrap_or_default(), member))
+}
+
+fn no_such_item(id: &str) -> Failure {
+    Failure(NO_SUCH_OBJECT, t!("没有条目 {}", item_path(id)))
+}
+
+// 条目的对象路径：账号 ID 中字母和数字以外的字符写作 `_` 加两位十六进制数
+fn item_path(id: &str) -> String {
+    let element: String = id.bytes().map(|b| if b.is_ascii_alphanumeric() { char::from(b).to_string() } else { format!("_{:02x}", b) }).collect();
+    format!("{}/{}", COLLECTION_PATH, element)
+}
+
+fn item_id(element: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = element.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'_' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok().filter(|id| !id.is_empty())
+}
+
+// 秘密结构：会话、参数（明文会话为空）、内容和内容类型
+fn secret(session: &str, account: &Account) -> Value {
+    Value::Struct(vec![Value::path(session), Value::bytes(&[]), Value::bytes(account.password.as_bytes()), Value::str("text/plain")])
+}
+
+// `a{ss}` 字典的各项
+fn string_dict(value: &Value) -> Result<Vec<(String, String)>, Failure> {
+    let invalid = || Failure(INVALID_ARGS, t!("属性应为字符串到字符串的字典").to_string());
+    value
+        .entries()
+        .ok_or_else(invalid)?
+        .into_iter()
+        .map(|(name, value)| Some((name.as_str()?.to_string(), value.as_str()?.to_string())))
+        .collect::<Option<_>>()
+        .ok_or_else(invalid)
+}
+
+// 账号的非敏感字段恰好是这些属性
+fn same_attributes(account: &Account, attributes: &[(String, String)]) -> bool {
+    let own: HashSet<(&str, &str)> = account.fields.iter().filter(|field| !field.secret).map(|field| (field.name.as_str(), field.value.as_str())).collect();
+    let wanted: HashSet<(&str, &str)> = attributes.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+    own == wanted
+}
+
+// 以 `attributes` 替换账号的非敏感字段
+fn set_attributes(account: &mut Account, attributes: &[(String, String)]) -> Result<(), AppError> {
+    account.fields.retain(|field| field.secret);
+    for (name, value) in attributes {
+        account.set_field(name, value, false)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+// 缓存在系统钥匙串中的解锁会话
+//
+// 用主密钥解锁后，把派生出的密钥连同过期时间保存到系统钥匙串（Linux 上为 Secret
+// Service，macOS 上为钥匙串，Windows 上为凭据管理器），之后的 passman 在过期前直接使用
+// 它而不再请求主密钥；`passman lock` 清除会话。调用平台自带的命令，避免链接 D-Bus 或
+// 系统框架。过期时间由 passman 自己检查，钥匙串中过期的会话在下次读取时删除。
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use base64::{Engine as _, engine::general_purpose};
+
+use passman::secmem::{self, SecretBytes};
+use passman::timestamp;
+
+use crate::config;
+use crate::i18n::t;
+
+// 钥匙串中条目的服务名
+const SERVICE: &str = "passman";
+
+/// 保存密码库 `vault_path` 的会话，`minutes` 分钟后过期
+pub fn store(vault_path: &Path, key: &[u8], minutes: u64) -> Result<(), String> {
+    let expires = timestamp::now() + minutes * 60;
+    let secret = SecretBytes::from_slice(format!("{}:{}", expires, general_purpose::STANDARD.encode(key)).as_bytes());
+    keyring::store(&config::vault_id(vault_path), &secret)
+}
+
+/// 读取密码库 `vault_path` 未过期的会话密钥
+pub fn load(vault_path: &Path) -> Option<SecretBytes> {
+    let account = config::vault_id(vault_path);
+    let mut secret = keyring::lookup(&account)?;
+    let session = std::str::from_utf8(&secret).ok().and_then(|text| {
+        let (expires, key) = text.trim().split_once(':')?;
+        let expires: u64 = expires.parse().ok()?;
+        let mut key = general_purpose::STANDARD.decode(key).ok()?;
+        let locked = SecretBytes::from_slice(&key);
+        secmem::zero(&mut key);
+        Some((expires, locked))
+    });
+    secmem::zero(&mut secret);
+    match session {
+        Some((expires, key)) if expires > timestamp::now() => Some(key),
+        _ => {
+            keyring::clear(&account);
+            None
+        }
+    }
+}
+
+/// 清除密码库 `vault_path` 的会话，返回是否存在
+pub fn clear(vault_path: &Path) -> bool {
+    keyring::clear(&config::vault_id(vault_path))
+}
+
+/// 清除所有密码库的会话
+pub fn clear_all() {
+    keyring::clear_all();
+}
+
+// 运行命令，把 `input` 写入标准输入，成功时返回标准输出
+fn run(program: &str, args: &[&str], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| t!("无法运行 {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let mut stdout = output.stdout;
+        secmem::zero(&mut stdout);
+        return Err(t!("{} 异常退出", program));
+    }
+    Ok(output.stdout)
+}
+
+// Secret Service，通过 libsecret 的 secret-tool
+#[cfg(not(any(target_os = "macos", windows)))]
+mod keyring {
+    use passman::secmem::SecretBytes;
+
+    use super::{run, SERVICE};
+
+    pub fn store(account: &str, secret: &[u8]) -> Result<(), String> {
+        run("secret-tool", &["store", "--label=passman", "service", SERVICE, "vault", account], secret).map(|_| ())
+    }
+
+    pub fn lookup(account: &str) -> Option<SecretBytes> {
+        let mut output = run("secret-tool", &["lookup", "service", SERVICE, "vault", account], &[]).ok()?;
+        let secret = SecretBytes::from_slice(&output);
+        passman::secmem::zero(&mut output);
+        Some(secret)
+    }
+
+    pub fn clear(account: &str) -> bool {
+        lookup(account).is_some() && run("secret-tool", &["clear", "service", SERVICE, "vault", account], &[]).is_ok()
+    }
+
+    pub fn clear_all() {
+        let _ = run("secret-tool", &["clear", "service", SERVICE], &[]);
+    }
+}
+
+// macOS 钥匙串，通过 security；写入时以交互模式从标准输入读取命令，密钥不出现在命令行参数中
+#[cfg(target_os = "macos")]
+mod keyring {
+    use passman::secmem::SecretBytes;
+
+    use super::{run, SERVICE};
+
+    pub fn store(account: &str, secret: &[u8]) -> Result<(), String> {
+        let secret = std::str::from_utf8(secret).map_err(|e| e.to_string())?;
+        let command = format!("add-generic-password -U -s {} -a {} -w {}\n", SERVICE, account, secret);
+        let command = SecretBytes::from_slice(command.as_bytes());
+        run("security", &["-i"], &command).map(|_| ())
+    }
+
+    pub fn lookup(account: &str) -> Option<SecretBytes> {
+        let mut output = run("security", &["find-generic-password", "-s", SERVICE, "-a", account, "-w"], &[]).ok()?;
+        let secret = SecretBytes::from_slice(&output);
+        passman::secmem::zero(&mut output);
+        Some(secret)
+    }
+
+    pub fn clear(account: &str) -> bool {
+        run("security", &["delete-generic-password", "-s", SERVICE, "-a", account], &[]).is_ok()
+    }
+
+    pub fn clear_all() {
+        while run("security", &["delete-generic-password", "-s", SERVICE], &[]).is_ok() {}
+    }
+}
+
+// Windows 凭据管理器，通过 PowerShell 使用 Windows.Security.Credentials.PasswordVault；
+// 密钥从标准输入读取
+#[cfg(windows)]
+mod keyring {
+    use passman::secmem::SecretBytes;
+
+    use super::{run, SERVICE};
+
+    const VAULT: &str = "[void][Windows.Security.Credentials.PasswordVault,Windows.Security.Credentials,ContentType=WindowsRuntime]; \
+                         $vault = New-Object Windows.Security.Credentials.PasswordVault";
+
+    fn powershell(script: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+        run("powershell", &["-NoProfile", "-NonInteractive", "-Command", &format!("{}; {}", VAULT, script)], input)
+    }
+
+    pub fn store(account: &str, secret: &[u8]) -> Result<(), String> {
+        let script = format!(
+            "$vault.Add((New-Object Windows.Security.Credentials.PasswordCredential('{}', '{}', [Console]::In.ReadLine())))",
+            SERVICE, account
+        );
+        powershell(&script, secret).map(|_| ())
+    }
+
+    pub fn lookup(account: &str) -> Option<SecretBytes> {
+        let script = format!("$c = $vault.Retrieve('{}', '{}'); $c.RetrievePassword(); [Console]::Out.Write($c.Password)", SERVICE, account);
+        let mut output = powershell(&script, &[]).ok()?;
+        let secret = SecretBytes::from_slice(&output);
+        passman::secmem::zero(&mut output);
+        Some(secret)
+    }
+
+    pub fn clear(account: &str) -> bool {
+        powershell(&format!("$vault.Remove($vault.Retrieve('{}', '{}'))", SERVICE, account), &[]).is_ok()
+    }
+
+    pub fn clear_all() {
+        let _ = powershell(&format!("$vault.FindAllByResource('{}') | ForEach-Object {{ $vault.Remove($_) }}", SERVICE), &[]);
+    }
+}
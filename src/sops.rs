@@ -0,0 +1,252 @@
+// 运行时解密配置文件中的机密（`passman sops-exec`），用法与 sops 的 exec-file 和 exec-env 相同
+//
+// 提交到仓库的文件中只写 `{{passman:账号.字段}}` 占位符（与 `passman env --template` 相同），运行
+// 命令时才替换为解密后的值：默认写到只属于当前用户的临时文件，命令中的 `{}` 替换为它的路径，
+// 命令结束后删除文件；`--env` 时把文件顶层的键值作为环境变量传给命令。替换时按文件的格式和
+// 占位符所在的引号转义，值中的引号和换行不会破坏 YAML、JSON 或 .env 的结构。
+
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde_json::Value;
+
+use passman::AppError;
+
+use crate::dotenv;
+use crate::i18n::t;
+
+/// 文件的格式，决定值的转义方式和 `--env` 时如何读取键值
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    /// YAML
+    Yaml,
+    /// JSON
+    Json,
+    /// .env 文件（NAME=值）
+    Dotenv,
+    /// 其他文件，值原样替换
+    Binary,
+}
+
+impl Format {
+    /// 由文件名判断格式：.yaml、.yml、.json、.env 和 .env.*，其余按原样替换
+    pub fn detect(path: &Path) -> Format {
+        let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).as_deref() {
+            Some("yaml" | "yml") => Format::Yaml,
+            Some("json") => Format::Json,
+            Some("env") => Format::Dotenv,
+            _ if name == ".env" || name.starts_with(".env.") => Format::Dotenv,
+            _ => Format::Binary,
+        }
+    }
+
+    /// 临时文件的扩展名（原文件没有扩展名时使用）
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Yaml => "yaml",
+            Format::Json => "json",
+            Format::Dotenv => "env",
+            Format::Binary => "txt",
+        }
+    }
+}
+
+/// 转义要替换占位符的值，`line` 是同一行中占位符之前的内容，用来判断占位符是否在引号中；
+/// 值不能放在占位符所在的单引号中时返回 None
+pub fn escape(format: Format, line: &str, value: &str) -> Option<String> {
+    let json = || serde_json::to_string(value).expect("字符串总能序列化为 JSON");
+    Some(match (format, quote_state(format, line)) {
+        (Format::Binary, _) => value.to_string(),
+        // YAML 的双引号字符串支持 JSON 的全部转义
+        (Format::Json | Format::Yaml, Some('"')) => {
+            let json = json();
+            json[1..json.len() - 1].to_string()
+        }
+        (Format::Json, _) => json(),
+        (Format::Yaml, Some(_)) if value.contains(['\n', '\r']) => return None,
+        (Format::Yaml, Some(_)) => value.replace('\'', "''"),
+        (Format::Yaml, None) if yaml_plain(value) => value.to_string(),
+        (Format::Yaml, None) => json(),
+        (Format::Dotenv, Some('"')) => dotenv::escape(value),
+        (Format::Dotenv, Some(_)) if value.contains(['\'', '\n', '\r']) => return None,
+        (Format::Dotenv, Some(_)) => value.to_string(),
+        (Format::Dotenv, None) => dotenv::quote(value),
+    })
+}
+
+/// `--env` 时传给命令的环境变量：文件顶层的键和值
+pub fn variables(format: Format, text: &str) -> Result<Vec<(String, String)>, AppError> {
+    let variables = match format {
+        Format::Json => json_variables(text)?,
+        Format::Yaml => yaml_variables(text)?,
+        Format::Dotenv => dotenv_variables(text)?,
+        Format::Binary => return Err(AppError::from(t!("--env 需要 YAML、JSON 或 .env 格式的文件，可以用 --input-type 指定格式"))),
+    };
+    if let Some((name, _)) = variables.iter().find(|(name, _)| name.is_empty() || name.contains(['=', '\0'])) {
+        return Err(AppError::from(t!("{} 不能作为环境变量的名称", name)));
+    }
+    Ok(variables)
+}
+
+// 行中最后一个没有结束的引号；YAML 中引号只在值的开头才有意义，JSON 只有双引号
+fn quote_state(format: Format, line: &str) -> Option<char> {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut previous = None;
+    // YAML 单引号字符串中的 '' 表示一个单引号
+    let mut reopen = false;
+    for c in line.chars() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => {
+                quote = None;
+                reopen = q == '\'';
+                previous = Some(c);
+            }
+            Some(_) => {}
+            None => {
+                let start = match format {
+                    Format::Yaml => reopen || matches!(previous, None | Some(':' | '-' | '[' | '{' | ',' | '?')),
+                    _ => true,
+                };
+                if start && (c == '"' || (c == '\'' && format != Format::Json)) {
+                    quote = Some(c);
+                }
+                reopen = false;
+                if !c.is_whitespace() {
+                    previous = Some(c);
+                }
+            }
+        }
+    }
+    quote
+}
+
+// 不加引号也不会被 YAML 当作其他类型或语法的值
+fn yaml_plain(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_alphabetic())
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./@+%~".contains(c))
+        && !["true", "false", "yes", "no", "on", "off", "y", "n", "null"].contains(&value.to_ascii_lowercase().as_str())
+}
+
+fn json_variables(text: &str) -> Result<Vec<(String, String)>, AppError> {
+    let value: Value = serde_json::from_str(text).map_err(|e| AppError::from(t!("不是有效的 JSON: {}", e)))?;
+    let Value::Object(object) = value else {
+        return Err(AppError::from(t!("JSON 文件的顶层需要是对象")));
+    };
+    object
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => Ok((key, value)),
+            Value::Null => Ok((key, String::new())),
+            Value::Bool(_) | Value::Number(_) => Ok((key, value.to_string())),
+            Value::Array(_) | Value::Object(_) => Err(AppError::from(t!("{} 的值是对象或数组，--env 只支持顶层的简单键值", key))),
+        })
+        .collect()
+}
+
+// 只支持顶层的 `键: 值`，值可以带引号；嵌套、多行和流式的值报告错误
+fn yaml_variables(text: &str) -> Result<Vec<(String, String)>, AppError> {
+    let mut variables = Vec::new();
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim_end();
+        if line.trim_start().is_empty() || line.trim_start().starts_with('#') || line == "---" {
+            continue;
+        }
+        if line == "..." {
+            break;
+        }
+        let unsupported = || AppError::from(t!("第 {} 行: --env 只支持顶层的简单键值（键: 值）", i + 1));
+        if line.starts_with([' ', '\t']) {
+            return Err(unsupported());
+        }
+        let (key, value) = yaml_split(line).ok_or_else(unsupported)?;
+        variables.push((yaml_scalar(key).ok_or_else(unsupported)?, yaml_scalar(value.trim()).ok_or_else(unsupported)?));
+    }
+    Ok(variables)
+}
+
+fn yaml_split(line: &str) -> Option<(&str, &str)> {
+    let start = match line.chars().next()? {
+        quote @ ('"' | '\'') => quoted_end(line, quote)?,
+        _ => 0,
+    };
+    let colon = line[start..].find(": ").map(|i| start + i).or_else(|| line.ends_with(':').then(|| line.len() - 1))?;
+    Some((line[..colon].trim(), &line[colon + 1..]))
+}
+
+fn yaml_scalar(value: &str) -> Option<String> {
+    let rest_is_comment = |end: usize| value[end..].trim_start().is_empty() || value[end..].trim_start().starts_with('#');
+    match value.chars().next() {
+        None => Some(String::new()),
+        Some('"') => {
+            let end = quoted_end(value, '"').filter(|&end| rest_is_comment(end))?;
+            serde_json::from_str(&value[..end]).ok()
+        }
+        Some('\'') => {
+            let end = quoted_end(value, '\'').filter(|&end| rest_is_comment(end))?;
+            Some(value[1..end - 1].replace("''", "'"))
+        }
+        Some('|' | '>' | '[' | '{' | '&' | '*' | '!') => None,
+        _ => {
+            let value = value.split(" #").next().unwrap_or_default().trim();
+            Some(if value == "~" || value == "null" { String::new() } else { value.to_string() })
+        }
+    }
+}
+
+// 引号字符串结尾的位置（结束引号之后），`value` 以引号开头
+fn quoted_end(value: &str, quote: char) -> Option<usize> {
+    let mut chars = value.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            if quote == '\'' && chars.peek().is_some_and(|&(_, next)| next == '\'') {
+                chars.next();
+            } else {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+fn dotenv_variables(text: &str) -> Result<Vec<(String, String)>, AppError> {
+    let mut variables = Vec::new();
+    for (line, item) in dotenv::mapping_lines(text) {
+        let item = item.strip_prefix("export ").unwrap_or(item);
+        let (name, value) = item.split_once('=').ok_or_else(|| AppError::from(t!("第 {} 行不是 NAME=值", line)))?;
+        let value = value.trim();
+        let value = if let Some(inner) = value.strip_prefix('\'').and_then(|value| value.strip_suffix('\'')) {
+            inner.to_string()
+        } else if let Some(inner) = value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+            unescape(inner)
+        } else {
+            value.split(" #").next().unwrap_or_default().trim_end().to_string()
+        };
+        variables.push((name.trim().to_string(), value));
+    }
+    Ok(variables)
+}
+
+// 还原 `dotenv::escape` 的转义
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(next) => unescaped.push(next),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
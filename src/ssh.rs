@@ -0,0 +1,88 @@
+// 保存在密码库中的 SSH 私钥（`passman ssh import|add`）
+//
+// 私钥是 ssh-key 类型条目的 private_key 字段，口令（如果有）保存在条目的密码中。`ssh add` 把私钥
+// 从标准输入交给 `ssh-add -`，私钥不会写到磁盘；有口令时以 passman 自己作为 ssh-add 的
+// SSH_ASKPASS 程序回答口令，口令只出现在 ssh-add 及其子进程的环境中。
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{self, Command, ExitStatus, Stdio};
+
+use base64::{Engine as _, engine::general_purpose};
+
+use passman::AppError;
+
+use crate::i18n::t;
+
+/// 作为 SSH_ASKPASS 运行时从中读取口令的环境变量
+const ASKPASS_ENV: &str = "PASSMAN_SSH_ASKPASS";
+
+/// ssh-add 以 SSH_ASKPASS 运行 passman 时回答口令并退出，其他时候什么也不做
+///
+/// 口令错误时 ssh-add 会再次询问，这时回答空口令让它放弃，而不是一直重试。
+pub fn answer_askpass() {
+    let Some(passphrase) = env::var_os(ASKPASS_ENV) else {
+        return;
+    };
+    let retry = env::args().nth(1).is_some_and(|prompt| prompt.starts_with("Bad passphrase"));
+    if !retry {
+        println!("{}", passphrase.to_string_lossy());
+    }
+    process::exit(0)
+}
+
+/// 读取私钥文件和旁边同名的 .pub 公钥文件（没有时为 None）
+pub fn read_key_file(path: &Path) -> Result<(String, Option<String>), AppError> {
+    let private_key = fs::read_to_string(path).map_err(|e| AppError::from(t!("无法读取文件 {}: {}", path.display(), e)))?;
+    let mut public_path = path.as_os_str().to_owned();
+    public_path.push(".pub");
+    let public_key = fs::read_to_string(public_path).ok().map(|key| key.trim().to_string()).filter(|key| !key.is_empty());
+    Ok((private_key.trim().replace('\r', ""), public_key))
+}
+
+/// 私钥是否受口令保护：PEM 格式带有 ENCRYPTED 标记，OpenSSH 格式的加密算法不是 none
+pub fn encrypted(key: &str) -> bool {
+    if key.contains("ENCRYPTED") {
+        return true;
+    }
+    let body: String = key.lines().filter(|line| !line.starts_with("-----")).collect();
+    let Ok(data) = general_purpose::STANDARD.decode(body.trim()) else {
+        return false;
+    };
+    // openssh-key-v1 之后是 SSH 字符串形式的加密算法名称
+    let Some(rest) = data.strip_prefix(b"openssh-key-v1\0") else {
+        return false;
+    };
+    let cipher = rest.get(..4).map(|len| u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize).and_then(|len| rest.get(4..4 + len));
+    cipher.is_some_and(|cipher| cipher != b"none")
+}
+
+/// 用 ssh-add 把私钥加载到运行中的 ssh-agent；`lifetime` 是 ssh-add -t 的时长（例如 3600、30m、8h），
+/// `confirm` 时每次使用私钥都要在 ssh-agent 的对话框中确认
+pub fn add(key: &str, passphrase: &str, lifetime: Option<&str>, confirm: bool) -> Result<ExitStatus, AppError> {
+    if env::var_os("SSH_AUTH_SOCK").is_none_or(|socket| socket.is_empty()) {
+        return Err(AppError::from(t!("没有运行中的 ssh-agent（没有设置 SSH_AUTH_SOCK）")));
+    }
+    let mut command = Command::new("ssh-add");
+    if let Some(lifetime) = lifetime {
+        command.args(["-t", lifetime]);
+    }
+    if confirm {
+        command.arg("-c");
+    }
+    command.arg("-").stdin(Stdio::piped());
+    if !passphrase.is_empty() {
+        command.env("SSH_ASKPASS", env::current_exe()?).env("SSH_ASKPASS_REQUIRE", "force").env(ASKPASS_ENV, passphrase);
+    }
+    let mut child = command.spawn().map_err(|e| AppError::from(t!("无法运行 {}: {}", "ssh-add", e)))?;
+    // ssh-add 要求私钥以换行结尾
+    let key = format!("{}\n", key.trim_end());
+    let written = child.stdin.take().expect("已设置为管道").write_all(key.as_bytes());
+    let status = child.wait()?;
+    if status.success() {
+        written?;
+    }
+    Ok(status)
+}
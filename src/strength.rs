@@ -0,0 +1,673 @@
+//! 密码强度估计
+//!
+//! 思路与 zxcvbn 相同：先找出密码中可以被猜到的模式（常用密码和单词、键盘
+//! 模式、重复、序列、年份和日期），再求出猜测次数最少的模式组合，以此估计
+//! 破解所需的时间并给出改进建议。
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::generator;
+
+// 常用密码列表（按常用程度排序）
+const COMMON_PASSWORDS: &str = include_str!("../assets/common_passwords.txt");
+
+// 离线破解速度（慢哈希，每秒猜测次数）
+const OFFLINE_GUESSES_PER_SECOND: f64 = 1e4;
+// 在线破解速度（有速率限制，每秒猜测次数）
+const ONLINE_GUESSES_PER_SECOND: f64 = 100.0 / 3600.0;
+
+// 每增加一个模式的额外猜测次数
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10000.0;
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_YEAR_SPACE: f64 = 20.0;
+
+// 字典匹配的最大长度
+const MAX_WORD_LENGTH: usize = 32;
+
+// 倾斜排列的 QWERTY 键盘，每行前面的空格数决定按键的横向位置
+const QWERTY: [&str; 4] = [
+    "`~ 1! 2@ 3# 4$ 5% 6^ 7& 8* 9( 0) -_ =+",
+    "    qQ wW eE rR tT yY uU iI oO pP [{ ]} \\|",
+    "     aA sS dD fF gG hH jJ kK lL ;: '\"",
+    "      zZ xX cC vV bB nN mM ,< .> /?",
+];
+
+// 常见的 l33t 替换
+const L33T: [(char, &[char]); 12] = [
+    ('4', &['a']),
+    ('@', &['a']),
+    ('8', &['b']),
+    ('(', &['c']),
+    ('3', &['e']),
+    ('6', &['g']),
+    ('9', &['g']),
+    ('1', &['i', 'l']),
+    ('!', &['i']),
+    ('0', &['o']),
+    ('$', &['s']),
+    ('5', &['s']),
+];
+
+/// 强度估计结果
+pub struct Estimate {
+    /// 估计的猜测次数
+    pub guesses: f64,
+    /// 评分：0（极弱）到 4（很强）
+    pub score: u8,
+    /// 离线破解（慢哈希，每秒一万次）所需秒数
+    pub offline_seconds: f64,
+    /// 在线破解（每小时一百次）所需秒数
+    pub online_seconds: f64,
+    /// 主要问题
+    pub warning: Option<String>,
+    /// 改进建议
+    pub suggestions: Vec<String>,
+}
+
+// 匹配到的模式
+#[derive(Clone)]
+enum Pattern {
+    Dictionary { rank: usize, common: bool, reversed: bool, l33t: bool },
+    Spatial { turns: usize },
+    Repeat { base_len: usize },
+    Sequence,
+    Year,
+    Date,
+    Bruteforce,
+}
+
+#[derive(Clone)]
+struct Match {
+    // 在字符数组中的起止位置（包含两端）
+    i: usize,
+    j: usize,
+    pattern: Pattern,
+    guesses: f64,
+}
+
+/// 估计密码强度
+pub fn estimate(password: &str) -> Estimate {
+    let chars: Vec<char> = password.chars().collect();
+    let sequence = most_guessable(&chars);
+    let guesses = if sequence.is_empty() { 1.0 } else { sequence_guesses(&sequence) };
+
+    let score = match guesses {
+        g if g < 1e3 + 5.0 => 0,
+        g if g < 1e6 + 5.0 => 1,
+        g if g < 1e8 + 5.0 => 2,
+        g if g < 1e10 + 5.0 => 3,
+        _ => 4,
+    };
+    let (warning, suggestions) = feedback(&chars, score, &sequence);
+
+    Estimate {
+        guesses,
+        score,
+        offline_seconds: guesses / OFFLINE_GUESSES_PER_SECOND,
+        online_seconds: guesses / ONLINE_GUESSES_PER_SECOND,
+        warning,
+        suggestions,
+    }
+}
+
+/// 将秒数显示为易读的时间
+pub fn display_time(seconds: f64) -> String {
+    const MINUTE: f64 = 60.0;
+    const HOUR: f64 = MINUTE * 60.0;
+    const DAY: f64 = HOUR * 24.0;
+    const MONTH: f64 = DAY * 31.0;
+    const YEAR: f64 = MONTH * 12.0;
+    const CENTURY: f64 = YEAR * 100.0;
+
+    let (value, unit) = match seconds {
+        s if s < 1.0 => return "不到 1 秒".to_string(),
+        s if s < MINUTE => (s, "秒"),
+        s if s < HOUR => (s / MINUTE, "分钟"),
+        s if s < DAY => (s / HOUR, "小时"),
+        s if s < MONTH => (s / DAY, "天"),
+        s if s < YEAR => (s / MONTH, "个月"),
+        s if s < CENTURY => (s / YEAR, "年"),
+        _ => return "数百年以上".to_string(),
+    };
+    format!("{} {}", value.round(), unit)
+}
+
+// 字典：单词到排名
+struct Dictionaries {
+    common: HashMap<String, usize>,
+    words: HashMap<String, usize>,
+}
+
+fn dictionaries() -> &'static Dictionaries {
+    static DICTIONARIES: OnceLock<Dictionaries> = OnceLock::new();
+    DICTIONARIES.get_or_init(|| {
+        let ranked = |words: Vec<&str>| {
+            let mut map = HashMap::new();
+            for (rank, word) in words.into_iter().enumerate() {
+                map.entry(word.to_lowercase()).or_insert(rank + 1);
+            }
+            map
+        };
+        Dictionaries {
+            common: ranked(COMMON_PASSWORDS.lines().map(str::trim).filter(|l| !l.is_empty()).collect()),
+            words: ranked(generator::wordlist()),
+        }
+    })
+}
+
+// 键盘邻接表：按键到六个方向上的相邻按键（不按 Shift 与按住 Shift 时的字符）
+type Keyboard = HashMap<char, [Option<(char, char)>; 6]>;
+
+fn keyboard() -> &'static Keyboard {
+    static KEYBOARD: OnceLock<Keyboard> = OnceLock::new();
+    KEYBOARD.get_or_init(|| {
+        let mut positions = HashMap::new();
+        for (y, row) in QWERTY.iter().enumerate() {
+            for (index, _) in row.char_indices().filter(|(_, c)| !c.is_whitespace()).step_by(2) {
+                let mut keys = row[index..].chars();
+                let (lower, upper) = (keys.next().unwrap(), keys.next().unwrap());
+                let x = (index - y) / 3;
+                positions.insert((x as i32, y as i32), (lower, upper));
+            }
+        }
+
+        let mut graph = HashMap::new();
+        for (&(x, y), &(lower, upper)) in &positions {
+            let directions = [(x - 1, y), (x, y - 1), (x + 1, y - 1), (x + 1, y), (x, y + 1), (x - 1, y + 1)];
+            let neighbors = directions.map(|position| positions.get(&position).copied());
+            graph.insert(lower, neighbors);
+            graph.insert(upper, neighbors);
+        }
+        graph
+    })
+}
+
+// 找出所有模式后选出猜测次数最少的组合
+fn most_guessable(chars: &[char]) -> Vec<Match> {
+    let n = chars.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    dictionary_matches(chars, &mut matches);
+    spatial_matches(chars, &mut matches);
+    repeat_matches(chars, &mut matches);
+    sequence_matches(chars, &mut matches);
+    date_matches(chars, &mut matches);
+
+    let mut by_end: Vec<Vec<Match>> = vec![Vec::new(); n];
+    for m in matches {
+        by_end[m.j].push(m);
+    }
+
+    // optimal[k]：以位置 k 结尾、由 l 个模式组成时的（累计乘积，总猜测次数，最后一个模式）
+    let mut optimal: Vec<HashMap<usize, (f64, f64, Match)>> = vec![HashMap::new(); n];
+
+    let update = |optimal: &mut Vec<HashMap<usize, (f64, f64, Match)>>, m: Match, l: usize| {
+        let k = m.j;
+        let mut product = m.guesses;
+        if l > 1 {
+            product *= optimal[m.i - 1][&(l - 1)].0;
+        }
+        let total = factorial(l) * product + MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(l as i32 - 1);
+        if optimal[k].iter().any(|(&other_l, &(_, other_total, _))| other_l <= l && other_total <= total) {
+            return;
+        }
+        optimal[k].insert(l, (product, total, m));
+    };
+
+    for (k, ending) in by_end.iter().enumerate() {
+        for m in ending.clone() {
+            if m.i == 0 {
+                update(&mut optimal, m, 1);
+            } else {
+                let lengths: Vec<usize> = optimal[m.i - 1].keys().copied().collect();
+                for l in lengths {
+                    update(&mut optimal, m.clone(), l + 1);
+                }
+            }
+        }
+
+        // 暴力破解的片段，不与另一个暴力破解片段相邻
+        update(&mut optimal, bruteforce(0, k), 1);
+        for i in 1..=k {
+            let previous: Vec<usize> = optimal[i - 1]
+                .iter()
+                .filter(|(_, (_, _, last))| !matches!(last.pattern, Pattern::Bruteforce))
+                .map(|(&l, _)| l)
+                .collect();
+            for l in previous {
+                update(&mut optimal, bruteforce(i, k), l + 1);
+            }
+        }
+    }
+
+    // 回溯出最优组合
+    let mut sequence = Vec::new();
+    let mut k = n - 1;
+    let (mut l, _) = optimal[k]
+        .iter()
+        .map(|(&l, &(_, total, _))| (l, total))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap();
+    loop {
+        let m = optimal[k][&l].2.clone();
+        let start = m.i;
+        sequence.push(m);
+        if start == 0 {
+            break;
+        }
+        k = start - 1;
+        l -= 1;
+    }
+    sequence.reverse();
+    sequence
+}
+
+// 一组模式的总猜测次数：模式数的阶乘乘以各模式猜测次数之积，再加上模式数带来的额外次数
+fn sequence_guesses(sequence: &[Match]) -> f64 {
+    let product: f64 = sequence.iter().map(|m| m.guesses).product();
+    let l = sequence.len();
+    factorial(l) * product + MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(l as i32 - 1)
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+fn n_choose_k(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (1..=k).fold(1.0, |acc, i| acc * (n - k + i) as f64 / i as f64)
+}
+
+fn new_match(i: usize, j: usize, pattern: Pattern, guesses: f64) -> Match {
+    let min = if j == i { MIN_SUBMATCH_GUESSES_SINGLE_CHAR } else { MIN_SUBMATCH_GUESSES_MULTI_CHAR };
+    Match { i, j, pattern, guesses: guesses.max(min) }
+}
+
+fn bruteforce(i: usize, j: usize) -> Match {
+    let len = j - i + 1;
+    let guesses = BRUTEFORCE_CARDINALITY.powi(len as i32).max(if len == 1 { 11.0 } else { 51.0 });
+    Match { i, j, pattern: Pattern::Bruteforce, guesses }
+}
+
+// 大小写变化带来的额外猜测次数
+fn uppercase_variations(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_upper = token.first().is_some_and(|c| c.is_uppercase()) && upper == 1;
+    let last_upper = token.last().is_some_and(|c| c.is_uppercase()) && upper == 1;
+    if first_upper || last_upper || lower == 0 {
+        return 2.0;
+    }
+    (1..=upper.min(lower)).map(|i| n_choose_k(upper + lower, i)).sum()
+}
+
+// 字典匹配：常用密码与单词，包括倒序和 l33t 替换
+fn dictionary_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let dictionaries = dictionaries();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    if lower.len() != chars.len() {
+        return;
+    }
+    let n = chars.len();
+
+    for i in 0..n {
+        for j in i..n.min(i + MAX_WORD_LENGTH) {
+            let token = &chars[i..=j];
+            let word: String = lower[i..=j].iter().collect();
+            let reversed: String = lower[i..=j].iter().rev().collect();
+            let upper = uppercase_variations(token);
+
+            for (candidate, is_reversed) in [(word.clone(), false), (reversed, true)] {
+                if is_reversed && j - i < 2 {
+                    continue;
+                }
+                for (dictionary, common) in [(&dictionaries.common, true), (&dictionaries.words, false)] {
+                    if let Some(&rank) = dictionary.get(&candidate) {
+                        let guesses = rank as f64 * upper * if is_reversed { 2.0 } else { 1.0 };
+                        let pattern = Pattern::Dictionary { rank, common, reversed: is_reversed, l33t: false };
+                        matches.push(new_match(i, j, pattern, guesses));
+                    }
+                }
+            }
+
+            // l33t：只在包含可替换字符时尝试
+            if !token.iter().any(|c| L33T.iter().any(|(l, _)| l == c)) {
+                continue;
+            }
+            for (candidate, variations) in unl33t(&lower[i..=j]) {
+                for (dictionary, common) in [(&dictionaries.common, true), (&dictionaries.words, false)] {
+                    if let Some(&rank) = dictionary.get(&candidate) {
+                        let guesses = rank as f64 * upper * variations;
+                        let pattern = Pattern::Dictionary { rank, common, reversed: false, l33t: true };
+                        matches.push(new_match(i, j, pattern, guesses));
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 还原 l33t 替换，返回候选单词及替换带来的额外猜测次数
+fn unl33t(token: &[char]) -> Vec<(String, f64)> {
+    // 候选单词及其中的替换（l33t 字符，原字母）
+    type Candidate = (Vec<char>, Vec<(char, char)>);
+    let mut candidates: Vec<Candidate> = vec![(Vec::new(), Vec::new())];
+    for &c in token {
+        let options = L33T.iter().find(|(l, _)| *l == c).map(|(_, letters)| *letters);
+        candidates = match options {
+            Some(letters) if candidates.len() < 16 => candidates
+                .into_iter()
+                .flat_map(|(word, subs)| {
+                    letters.iter().map(move |&letter| {
+                        let mut word = word.clone();
+                        let mut subs = subs.clone();
+                        word.push(letter);
+                        subs.push((c, letter));
+                        (word, subs)
+                    })
+                })
+                .collect(),
+            _ => candidates
+                .into_iter()
+                .map(|(mut word, subs)| {
+                    word.push(c);
+                    (word, subs)
+                })
+                .collect(),
+        };
+    }
+
+    candidates
+        .into_iter()
+        .map(|(word, subs)| {
+            let mut variations = 1.0;
+            let mut seen = Vec::new();
+            for &(l33t, letter) in &subs {
+                if seen.contains(&(l33t, letter)) {
+                    continue;
+                }
+                seen.push((l33t, letter));
+                let substituted = token.iter().filter(|&&c| c == l33t).count();
+                let unsubstituted = token.iter().filter(|&&c| c == letter).count();
+                if unsubstituted == 0 {
+                    variations *= 2.0;
+                } else {
+                    let possibilities: f64 = (1..=substituted.min(unsubstituted))
+                        .map(|i| n_choose_k(substituted + unsubstituted, i))
+                        .sum();
+                    variations *= possibilities.max(2.0);
+                }
+            }
+            (word.into_iter().collect(), variations)
+        })
+        .collect()
+}
+
+// 键盘模式：至少三个相邻按键
+fn spatial_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let graph = keyboard();
+    let starting_positions = (graph.len() / 2) as f64;
+    let average_degree = graph
+        .values()
+        .map(|neighbors| neighbors.iter().filter(|n| n.is_some()).count() as f64)
+        .sum::<f64>()
+        / graph.len() as f64;
+
+    let n = chars.len();
+    let mut i = 0;
+    while i + 2 < n {
+        let mut j = i;
+        let mut last_direction = None;
+        let mut turns = 0;
+        let mut shifted = usize::from(is_shifted(chars[i]));
+
+        while j + 1 < n {
+            let Some(neighbors) = graph.get(&chars[j]) else { break };
+            let next = chars[j + 1];
+            let Some(direction) = neighbors
+                .iter()
+                .position(|n| n.is_some_and(|(lower, upper)| lower == next || upper == next))
+            else {
+                break;
+            };
+            if last_direction != Some(direction) {
+                turns += 1;
+                last_direction = Some(direction);
+            }
+            if is_shifted(next) {
+                shifted += 1;
+            }
+            j += 1;
+        }
+
+        if j - i >= 2 {
+            let length = j - i + 1;
+            let mut guesses = 0.0;
+            for l in 2..=length {
+                for t in 1..=turns.min(l - 1) {
+                    guesses += n_choose_k(l - 1, t - 1) * starting_positions * average_degree.powi(t as i32);
+                }
+            }
+            let unshifted = length - shifted;
+            if shifted > 0 {
+                guesses *= if unshifted == 0 {
+                    2.0
+                } else {
+                    (1..=shifted.min(unshifted)).map(|k| n_choose_k(length, k)).sum()
+                };
+            }
+            matches.push(new_match(i, j, Pattern::Spatial { turns }, guesses));
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+// 按键是否需要按住 Shift
+fn is_shifted(c: char) -> bool {
+    c.is_ascii_uppercase() || "~!@#$%^&*()_+{}|:\"<>?".contains(c)
+}
+
+// 重复：由同一片段重复两次以上组成
+fn repeat_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let n = chars.len();
+    let mut i = 0;
+    while i < n {
+        let mut best: Option<(usize, usize)> = None;
+        for base_len in 1..=(n - i) / 2 {
+            let base = &chars[i..i + base_len];
+            let mut count = 1;
+            while i + (count + 1) * base_len <= n && chars[i + count * base_len..i + (count + 1) * base_len] == *base {
+                count += 1;
+            }
+            if count >= 2 && best.is_none_or(|(b, c)| base_len * count > b * c) {
+                best = Some((base_len, count));
+            }
+        }
+
+        match best {
+            Some((base_len, count)) => {
+                let base = &chars[i..i + base_len];
+                let base_guesses = sequence_guesses(&most_guessable(base));
+                let j = i + base_len * count - 1;
+                matches.push(new_match(i, j, Pattern::Repeat { base_len }, base_guesses * count as f64));
+                i = j + 1;
+            }
+            None => i += 1,
+        }
+    }
+}
+
+// 序列：相邻字符的编码差值相同（例如 abc、7531）
+fn sequence_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let n = chars.len();
+    if n < 3 {
+        return;
+    }
+
+    let mut i = 0;
+    while i + 2 < n {
+        let delta = chars[i + 1] as i64 - chars[i] as i64;
+        let mut j = i + 1;
+        while j + 1 < n && chars[j + 1] as i64 - chars[j] as i64 == delta {
+            j += 1;
+        }
+
+        if (1..=5).contains(&delta.abs()) && j - i >= 2 {
+            let first = chars[i];
+            let base = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let ascending = delta > 0;
+            let guesses = base * (j - i + 1) as f64 * if ascending { 1.0 } else { 2.0 };
+            matches.push(new_match(i, j, Pattern::Sequence, guesses));
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+// 年份 (1900-2099) 与不带分隔符的日期 (YYYYMMDD、DDMMYYYY、MMDDYYYY、YYMMDD、DDMMYY)
+fn date_matches(chars: &[char], matches: &mut Vec<Match>) {
+    let reference_year = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| 1970 + d.as_secs() / 31_556_952)
+        .unwrap_or(2024) as f64;
+    let year_space = |year: f64| (year - reference_year).abs().max(MIN_YEAR_SPACE);
+    let number = |slice: &[char]| slice.iter().collect::<String>().parse::<u32>().ok();
+
+    let n = chars.len();
+    for i in 0..n {
+        if i + 4 <= n
+            && chars[i..i + 4].iter().all(char::is_ascii_digit)
+            && let Some(year) = number(&chars[i..i + 4])
+            && (1900..2100).contains(&year)
+        {
+            matches.push(new_match(i, i + 3, Pattern::Year, year_space(year as f64)));
+        }
+
+        for len in [6, 8] {
+            if i + len > n || !chars[i..i + len].iter().all(char::is_ascii_digit) {
+                continue;
+            }
+            let token = &chars[i..i + len];
+            let layouts: &[(usize, usize, usize)] = if len == 8 {
+                // （年、月、日）的起始位置
+                &[(0, 4, 6), (4, 2, 0), (4, 0, 2)]
+            } else {
+                &[(0, 2, 4), (4, 2, 0)]
+            };
+            let year_len = len - 4;
+
+            let valid = layouts.iter().find_map(|&(y, m, d)| {
+                let year = number(&token[y..y + year_len])?;
+                let month = number(&token[m..m + 2])?;
+                let day = number(&token[d..d + 2])?;
+                let year = match year_len {
+                    2 if year > 50 => 1900 + year,
+                    2 => 2000 + year,
+                    _ => year,
+                };
+                ((1..=12).contains(&month) && (1..=31).contains(&day) && (1900..2100).contains(&year)).then_some(year)
+            });
+            if let Some(year) = valid {
+                matches.push(new_match(i, i + len - 1, Pattern::Date, year_space(year as f64) * 365.0));
+            }
+        }
+    }
+}
+
+// 根据评分与主要模式给出问题和建议
+fn feedback(chars: &[char], score: u8, sequence: &[Match]) -> (Option<String>, Vec<String>) {
+    if chars.is_empty() {
+        return (
+            None,
+            vec!["使用由几个单词组成的口令，避免常见的短语".to_string(), "不需要符号、数字或大写字母".to_string()],
+        );
+    }
+    if score > 2 {
+        return (None, Vec::new());
+    }
+
+    let mut suggestions = vec!["增加一两个单词，不常见的单词更好".to_string()];
+    let Some(longest) = sequence.iter().max_by_key(|m| m.j - m.i) else {
+        return (None, suggestions);
+    };
+    let token = &chars[longest.i..=longest.j];
+
+    let warning = match &longest.pattern {
+        Pattern::Dictionary { rank, common, reversed, l33t } => {
+            let upper = token.iter().filter(|c| c.is_uppercase()).count();
+            if token.first().is_some_and(|c| c.is_uppercase()) && upper == 1 {
+                suggestions.push("首字母大写帮助不大".to_string());
+            } else if upper == token.len() && token.len() > 1 {
+                suggestions.push("全部大写和全部小写一样容易被猜到".to_string());
+            }
+            if *reversed && token.len() >= 4 {
+                suggestions.push("倒序拼写的单词并不难猜".to_string());
+            }
+            if *l33t {
+                suggestions.push("用 @ 代替 a 这类可预见的替换帮助不大".to_string());
+            }
+
+            match (common, sequence.len() == 1) {
+                (true, true) if *rank <= 10 => "这是最常用的十个密码之一",
+                (true, true) if *rank <= 100 => "这是最常用的一百个密码之一",
+                (true, true) => "这是一个很常用的密码",
+                (true, false) => "这与一个常用密码相似",
+                (false, true) => "单独一个单词很容易被猜到",
+                (false, false) => "单词很容易被猜到",
+            }
+        }
+        Pattern::Spatial { turns } => {
+            suggestions.push("使用更长且多次转向的键盘模式".to_string());
+            if *turns == 1 { "同一排相邻的按键很容易被猜到" } else { "短的键盘模式很容易被猜到" }
+        }
+        Pattern::Repeat { base_len } => {
+            suggestions.push("避免重复的单词和字符".to_string());
+            if *base_len == 1 {
+                "\"aaa\" 这样的重复很容易被猜到"
+            } else {
+                "\"abcabcabc\" 这样的重复只比 \"abc\" 略难猜"
+            }
+        }
+        Pattern::Sequence => {
+            suggestions.push("避免使用序列".to_string());
+            "\"abc\" 或 \"6543\" 这样的序列很容易被猜到"
+        }
+        Pattern::Year => {
+            suggestions.push("避免使用近年的年份和与你相关的年份".to_string());
+            "年份很容易被猜到"
+        }
+        Pattern::Date => {
+            suggestions.push("避免使用与你相关的日期和年份".to_string());
+            "日期往往很容易被猜到"
+        }
+        Pattern::Bruteforce => {
+            if chars.len() < 12 {
+                suggestions.push("使用更长的密码".to_string());
+            }
+            return (None, suggestions);
+        }
+    };
+
+    (Some(warning.to_string()), suggestions)
+}
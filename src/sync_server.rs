@@ -0,0 +1,260 @@
+// 自托管的同步服务器（`passman serve-sync`），让家庭中的几台设备不借助第三方存储同步
+//
+// 协议是 WebDAV 远程存储用到的 HTTP 子集：GET、HEAD 和带 If-Match / If-None-Match 的 PUT，
+// 客户端以 `passman://host/vault.json`（`passman+http://` 以 HTTP 访问）同步。服务器只保存和
+// 返回收到的字节，不解析也不解密，不需要密码库的密钥。每个文件有一个递增的版本号作为 ETag，
+// 上传时带上的版本号不是最新的返回 412，由客户端合并后重新上传。每个版本记下上传的设备，
+// 保留最近的若干个版本。
+//
+// 每台设备有自己的访问令牌（`--add-device` 生成），以 HTTP Basic 认证：用户名为设备名，
+// 密码为令牌。服务器只保存令牌的 SHA-256 摘要，移除设备后它的令牌立即失效。服务器本身只
+// 提供 HTTP，在局域网以外提供服务时应放在提供 HTTPS 的反向代理之后。
+
+use std::fs;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use passman::{atomic, timestamp, AppError};
+
+use crate::http_server::{Connection, Request, Response};
+use crate::i18n::t;
+
+// 上传的文件的最大长度
+const MAX_BODY: u64 = 64 * 1024 * 1024;
+// 每个文件保留的版本数
+const KEEP_REVISIONS: usize = 10;
+
+/// 一台可以访问服务器的设备
+#[derive(Serialize, Deserialize)]
+pub struct Device {
+    pub name: String,
+    // 访问令牌的 SHA-256 摘要（十六进制）
+    token_sha256: String,
+    pub added_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen_at: Option<u64>,
+}
+
+// devices.json
+#[derive(Default, Serialize, Deserialize)]
+struct Devices {
+    devices: Vec<Device>,
+}
+
+// 一个文件的版本记录（files/<name>/meta.json），最新的版本在最后
+#[derive(Default, Serialize, Deserialize)]
+struct Meta {
+    revisions: Vec<Revision>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Revision {
+    revision: u64,
+    device: String,
+    size: u64,
+    uploaded_at: u64,
+}
+
+// 服务器的数据目录，所有修改都在锁内进行
+struct Server {
+    dir: PathBuf,
+    lock: Mutex<()>,
+}
+
+/// 添加设备 `name`，返回它的访问令牌（只显示这一次）
+pub fn add_device(dir: &Path, name: &str) -> Result<String, AppError> {
+    if !valid_name(name) {
+        return Err(AppError::from(t!("设备名称只能包含字母、数字、点、下划线和连字符，且不能以点开头")));
+    }
+    create_dir(dir)?;
+    let mut devices = load_devices(dir)?;
+    if devices.devices.iter().any(|device| device.name == name) {
+        return Err(AppError::from(t!("设备 {} 已存在", name)));
+    }
+    let token = general_purpose::URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>());
+    devices.devices.push(Device { name: name.to_string(), token_sha256: digest(token.as_bytes()), added_at: timestamp::now(), last_seen_at: None });
+    save_devices(dir, &devices)?;
+    Ok(token)
+}
+
+/// 移除设备 `name`，设备不存在时返回 false
+pub fn remove_device(dir: &Path, name: &str) -> Result<bool, AppError> {
+    let mut devices = load_devices(dir)?;
+    let count = devices.devices.len();
+    devices.devices.retain(|device| device.name != name);
+    if devices.devices.len() == count {
+        return Ok(false);
+    }
+    save_devices(dir, &devices)?;
+    Ok(true)
+}
+
+/// 可以访问服务器的设备
+pub fn devices(dir: &Path) -> Result<Vec<Device>, AppError> {
+    Ok(load_devices(dir)?.devices)
+}
+
+/// 在 `listen` 上运行服务器，直到进程被终止
+pub fn run(dir: &Path, listen: &str) -> Result<(), AppError> {
+    if load_devices(dir)?.devices.is_empty() {
+        return Err(AppError::from(t!("还没有添加设备，请先运行 passman serve-sync --add-device <名称>")));
+    }
+    create_dir(&dir.join("files"))?;
+    let listener = TcpListener::bind(listen).map_err(|e| AppError::from(t!("无法监听 {}: {}", listen, e)))?;
+    println!("{}", t!("同步服务器正在监听 {}，数据目录为 {}", listen, dir.display()));
+    let server = Arc::new(Server { dir: dir.to_path_buf(), lock: Mutex::new(()) });
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            let _ = server.serve(stream);
+        });
+    }
+    Ok(())
+}
+
+impl Server {
+    // 处理一个连接上的一个请求
+    fn serve(&self, stream: TcpStream) -> io::Result<()> {
+        let mut connection = Connection::new(stream)?;
+        let Some(request) = connection.read_request()? else {
+            return connection.respond(Response::status(400));
+        };
+        let (device, response) = self.handle(&request, &mut connection);
+        println!("{} {} {} {} {}", timestamp::format(timestamp::now()), device.as_deref().unwrap_or("-"), request.method, request.path, response.status);
+        connection.respond(response)
+    }
+
+    fn handle(&self, request: &Request, connection: &mut Connection) -> (Option<String>, Response) {
+        let Some(device) = self.authenticate(request) else {
+            return (None, Response::status(401).header("WWW-Authenticate", "Basic realm=\"passman\""));
+        };
+        let Some(name) = request.path.strip_prefix('/').filter(|name| valid_name(name)) else {
+            return (Some(device), Response::status(404));
+        };
+        let response = match request.method.as_str() {
+            "GET" | "HEAD" => self.get(name, request.method == "HEAD"),
+            "PUT" => match connection.read_body(request, MAX_BODY) {
+                Ok(body) => self.put(name, &device, request, &body),
+                Err(response) => Ok(response),
+            },
+            _ => Ok(Response::status(405).header("Allow", "GET, HEAD, PUT")),
+        };
+        (Some(device), response.unwrap_or_else(|_| Response::status(500)))
+    }
+
+    // 检查 Basic 认证，返回设备名称
+    fn authenticate(&self, request: &Request) -> Option<String> {
+        let decoded = String::from_utf8(general_purpose::STANDARD.decode(request.credentials("Basic")?).ok()?).ok()?;
+        let (name, token) = decoded.split_once(':')?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut devices = load_devices(&self.dir).ok()?;
+        let device = devices.devices.iter_mut().find(|device| device.name == name && device.token_sha256 == digest(token.as_bytes()))?;
+        // 最近使用的时间精确到分钟即可，不必每个请求都写入
+        let now = timestamp::now();
+        if device.last_seen_at.is_none_or(|seen| now >= seen + 60) {
+            device.last_seen_at = Some(now);
+            let _ = save_devices(&self.dir, &devices);
+        }
+        Some(name.to_string())
+    }
+
+    fn get(&self, name: &str, head: bool) -> Result<Response, AppError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(latest) = load_meta(&self.dir, name)?.revisions.pop() else {
+            return Ok(Response::status(404));
+        };
+        let data = fs::read(self.file_dir(name).join(latest.revision.to_string()))?;
+        let response = match head {
+            true => Response::new(200, "application/octet-stream", Vec::new()).header("Content-Length", data.len().to_string()),
+            false => Response::new(200, "application/octet-stream", data),
+        };
+        Ok(response.header("ETag", etag(latest.revision)))
+    }
+
+    fn put(&self, name: &str, device: &str, request: &Request, body: &[u8]) -> Result<Response, AppError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut meta = load_meta(&self.dir, name)?;
+        let current = meta.revisions.last().map(|latest| latest.revision);
+        let matches = |condition: &str, current: u64| condition.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag(current));
+        let allowed = match (request.header("if-match"), request.header("if-none-match"), current) {
+            (Some(condition), _, Some(current)) => matches(&condition, current),
+            (Some(_), _, None) => false,
+            (None, Some(condition), Some(current)) => !matches(&condition, current),
+            (None, _, _) => true,
+        };
+        if !allowed {
+            return Ok(Response::status(412));
+        }
+
+        let revision = current.map_or(1, |current| current + 1);
+        let dir = self.file_dir(name);
+        create_dir(&dir)?;
+        atomic::write_private(dir.join(revision.to_string()), body)?;
+        meta.revisions.push(Revision { revision, device: device.to_string(), size: body.len() as u64, uploaded_at: timestamp::now() });
+        let expired = meta.revisions.len().saturating_sub(KEEP_REVISIONS);
+        for old in meta.revisions.drain(..expired) {
+            let _ = fs::remove_file(dir.join(old.revision.to_string()));
+        }
+        atomic::write_private(dir.join("meta.json"), serde_json::to_string_pretty(&meta)?)?;
+        let status = if current.is_some() { 204 } else { 201 };
+        Ok(Response::new(status, "text/plain", Vec::new()).header("ETag", etag(revision)))
+    }
+
+    fn file_dir(&self, name: &str) -> PathBuf {
+        self.dir.join("files").join(name)
+    }
+}
+
+fn etag(revision: u64) -> String {
+    format!("\"{}\"", revision)
+}
+
+// 文件和设备的名称：字母、数字、点、下划线和连字符，不以点开头，不会跳出数据目录
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn digest(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 数据目录只有当前用户可以访问
+fn create_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+fn load_devices(dir: &Path) -> Result<Devices, AppError> {
+    match fs::read_to_string(dir.join("devices.json")) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Devices::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save_devices(dir: &Path, devices: &Devices) -> Result<(), AppError> {
+    atomic::write_private(dir.join("devices.json"), serde_json::to_string_pretty(devices)?)?;
+    Ok(())
+}
+
+fn load_meta(dir: &Path, name: &str) -> Result<Meta, AppError> {
+    match fs::read_to_string(dir.join("files").join(name).join("meta.json")) {
+        Ok(content) => Ok(serde_json::from_str(&content)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Meta::default()),
+        Err(e) => Err(e.into()),
+    }
+}
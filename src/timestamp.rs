@@ -0,0 +1,68 @@
+//! 时间戳（Unix 时间，秒）的获取与显示
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 当前时间
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// 以本地时间显示，例如 `2024-05-01 13:45`；0 表示时间未知
+pub fn format(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "未知".to_string();
+    }
+
+    let time = timestamp as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return timestamp.to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min
+    )
+}
+
+/// 一天的秒数
+pub const DAY: u64 = 24 * 60 * 60;
+
+/// 解析时长，例如 `90d`、`12w`、`6m`（按 30 天计）、`1y`（按 365 天计），不带单位时按天计
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (number, days) = match text.char_indices().last()? {
+        (i, 'd') => (&text[..i], 1),
+        (i, 'w') => (&text[..i], 7),
+        (i, 'm') => (&text[..i], 30),
+        (i, 'y') => (&text[..i], 365),
+        _ => (text, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(days * DAY)
+}
+
+/// 解析本地日期 `YYYY-MM-DD`，返回当天零点的时间戳
+pub fn parse_date(text: &str) -> Option<u64> {
+    let mut parts = text.trim().split('-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: i32 = parts.next()?.parse().ok()?;
+    let day: i32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = year - 1900;
+    tm.tm_mon = month - 1;
+    tm.tm_mday = day;
+    tm.tm_isdst = -1;
+    let time = unsafe { libc::mktime(&mut tm) };
+    // mktime 会把 2 月 30 日之类的日期顺延，这里视为无效
+    if time < 0 || tm.tm_mday != day {
+        return None;
+    }
+    Some(time as u64)
+}
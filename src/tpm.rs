@@ -0,0 +1,170 @@
+//! 把密码库的密钥封存到 TPM
+//!
+//! 由主密钥派生出的密钥可以另外封存到本机的 TPM 中：封存对象的策略要求 PCR（默认为
+//! 固件和安全启动状态所在的 0 和 7）与封存时一致，并要求输入 PIN；PIN 输错多次后 TPM
+//! 的防字典攻击机制会暂时锁定，因此短 PIN 也足够。封存的数据只能在这台机器的 TPM 中解开，
+//! 拿到密码库文件（以及封存的数据）的人仍然只能暴力破解主密钥。密码库本身不变，随时可以
+//! 继续用主密钥解锁。
+//!
+//! 通过 tpm2-tools 与 TPM 通信；Windows 上需要使用以 TBS 支持编译的 tpm2-tools，并设置
+//! `TPM2TOOLS_TCTI=tbs`。
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+
+use crate::crypto::{CryptoBackend, KeyBackend, KeyHeader, PasswordBackend};
+use crate::error::AppError;
+use crate::secmem;
+
+/// 默认绑定的 PCR
+pub const DEFAULT_PCRS: &str = "0,7";
+
+/// 封存在 TPM 中的密钥，只能在封存它的机器上解开
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SealedKey {
+    /// 策略绑定的 PCR，例如 `sha256:0,7`
+    pub pcrs: String,
+    /// Base64 编码的封存对象公开部分
+    pub public: String,
+    /// Base64 编码的封存对象私有部分（以 TPM 的存储主密钥加密）
+    pub private: String,
+    /// 封存时密码库的密钥校验值，更换主密钥或重新加密后不再一致
+    pub key_check: String,
+}
+
+impl SealedKey {
+    /// 封存的密钥是否仍然是头部为 `header` 的密码库的密钥
+    pub fn matches(&self, header: &KeyHeader) -> bool {
+        header.key_check.as_deref() == Some(self.key_check.as_str())
+    }
+}
+
+/// 以 `pin` 和 PCR 列表 `pcrs`（例如 `0,7`）把 `backend` 的密钥封存到 TPM
+pub fn seal(backend: &PasswordBackend, pin: &str, pcrs: &str) -> Result<SealedKey, AppError> {
+    let pcrs = pcr_selection(pcrs)?;
+    let key_check = backend.key_header().key_check.ok_or("无法计算密钥校验值")?;
+    let work = WorkDir::create(pin)?;
+    work.tpm2(&["tpm2_startauthsession", "-S", "session.ctx"], None)?;
+    work.tpm2(&["tpm2_policypcr", "-S", "session.ctx", "-l", &pcrs, "-L", "pcr.policy"], None)?;
+    work.tpm2(&["tpm2_policypassword", "-S", "session.ctx", "-L", "policy.dat"], None)?;
+    work.tpm2(&["tpm2_flushcontext", "session.ctx"], None)?;
+    work.tpm2(&["tpm2_createprimary", "-C", "o", "-c", "primary.ctx"], None)?;
+    work.tpm2(
+        &["tpm2_create", "-C", "primary.ctx", "-L", "policy.dat", "-p", "file:pin", "-i", "-", "-u", "key.pub", "-r", "key.priv"],
+        Some(backend.key()),
+    )?;
+    Ok(SealedKey { pcrs, public: work.read("key.pub")?, private: work.read("key.priv")?, key_check })
+}
+
+/// 用 `pin` 解开 `sealed`，返回打开头部为 `header` 的密码库的后端
+pub fn unseal(sealed: &SealedKey, pin: &str, header: &KeyHeader) -> Result<KeyBackend, AppError> {
+    if !sealed.matches(header) {
+        return Err(AppError::from("TPM 中封存的密钥已过期（主密钥已更换或密码库已重新加密）"));
+    }
+    let work = WorkDir::create(pin)?;
+    work.write("key.pub", &sealed.public)?;
+    work.write("key.priv", &sealed.private)?;
+    work.tpm2(&["tpm2_createprimary", "-C", "o", "-c", "primary.ctx"], None)?;
+    work.tpm2(&["tpm2_load", "-C", "primary.ctx", "-u", "key.pub", "-r", "key.priv", "-c", "key.ctx"], None)?;
+    work.tpm2(&["tpm2_startauthsession", "--policy-session", "-S", "session.ctx"], None)?;
+    work.tpm2(&["tpm2_policypcr", "-S", "session.ctx", "-l", &sealed.pcrs], None)?;
+    work.tpm2(&["tpm2_policypassword", "-S", "session.ctx"], None)?;
+    let unsealed = work.tpm2(&["tpm2_unseal", "-c", "key.ctx", "-p", "session:session.ctx+file:pin"], None);
+    let _ = work.tpm2(&["tpm2_flushcontext", "session.ctx"], None);
+
+    let mut unsealed = unsealed?;
+    let backend = KeyBackend::new(&unsealed, header).map_err(|_| AppError::from("TPM 解开的密钥与密码库不符"));
+    secmem::zero(&mut unsealed);
+    backend
+}
+
+// 把逗号分隔的 PCR 编号（例如 `0,7`）转为 tpm2-tools 的 PCR 选择
+pub fn pcr_selection(pcrs: &str) -> Result<String, AppError> {
+    let valid = pcrs.split(',').all(|pcr| pcr.parse::<u8>().is_ok_and(|pcr| pcr < 24));
+    if !valid {
+        return Err(AppError::from(format!("无效的 PCR 列表: {}", pcrs)));
+    }
+    Ok(format!("sha256:{}", pcrs))
+}
+
+// tpm2-tools 的上下文文件和 PIN 使用的临时目录，只有当前用户可以访问，结束时删除
+struct WorkDir {
+    path: PathBuf,
+}
+
+impl WorkDir {
+    fn create(pin: &str) -> Result<Self, AppError> {
+        let dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|dir| dir.is_dir()).unwrap_or_else(env::temp_dir);
+        let path = dir.join(format!("passman-tpm-{:016x}", rand::random::<u64>()));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder.create(&path)?;
+        let work = WorkDir { path };
+        fs::write(work.path.join("pin"), pin)?;
+        Ok(work)
+    }
+
+    fn read(&self, name: &str) -> Result<String, AppError> {
+        Ok(general_purpose::STANDARD.encode(fs::read(self.path.join(name))?))
+    }
+
+    fn write(&self, name: &str, data: &str) -> Result<(), AppError> {
+        let data = general_purpose::STANDARD.decode(data).map_err(|_| "TPM 封存的数据已损坏")?;
+        Ok(fs::write(self.path.join(name), data)?)
+    }
+
+    // 在临时目录中运行 tpm2-tools 的命令，返回标准输出；失败时附上命令的错误信息
+    fn tpm2(&self, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>, AppError> {
+        let mut child = Command::new(args[0])
+            .args(&args[1..])
+            .current_dir(&self.path)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| AppError::from(format!("无法运行 {}（需要安装 tpm2-tools）: {}", args[0], e)))?;
+        if let Some(input) = input {
+            child.stdin.take().ok_or("无法写入 tpm2-tools")?.write_all(input)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            // tpm2-tools 先输出 tss 库的诊断信息，最后是“ERROR: Unable to run ...”，
+            // 中间以“ERROR: ”开头的一行说明了原因（例如 PIN 错误或 PCR 不符）
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let lines: Vec<&str> = stderr.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+            let message = lines
+                .iter()
+                .rev()
+                .find(|line| line.starts_with("ERROR: ") && !line.starts_with("ERROR: Unable to run"))
+                .or(lines.last())
+                .map_or("", |line| line.trim_start_matches("ERROR: "));
+            return Err(AppError::from(format!("{} 失败: {}", args[0], message)));
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        overwrite(&self.path.join("pin"));
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+// 删除前用零覆盖 PIN 文件
+fn overwrite(path: &Path) {
+    if let Ok(length) = fs::metadata(path).map(|metadata| metadata.len()) {
+        let _ = fs::write(path, vec![0; length as usize]);
+    }
+}
@@ -0,0 +1,39 @@
+// 封存在 TPM 中的密码库密钥
+//
+// 封存的数据只能在这台机器上使用，因此不写入（可能同步到其他设备的）密码库文件，而是
+// 保存在数据目录中，每个密码库一个文件，以密码库绝对路径的哈希命名。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use passman::tpm::SealedKey;
+use passman::{atomic, AppError};
+
+use crate::config;
+use crate::i18n::t;
+
+/// 封存数据的文件路径
+pub fn sealed_path(vault_path: &Path) -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("tpm").join(format!("{}.json", config::vault_id(vault_path))))
+}
+
+/// 读取密码库封存的密钥，没有封存或无法读取时为 None
+pub fn read(vault_path: &Path) -> Option<SealedKey> {
+    let content = fs::read_to_string(sealed_path(vault_path)?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 保存密码库封存的密钥
+pub fn write(vault_path: &Path, sealed: &SealedKey) -> Result<(), AppError> {
+    let path = sealed_path(vault_path).ok_or_else(|| AppError::from(t!("无法确定数据目录")))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    atomic::write_private(&path, serde_json::to_string_pretty(sealed)?)?;
+    Ok(())
+}
+
+/// 删除密码库封存的密钥，返回是否存在
+pub fn remove(vault_path: &Path) -> bool {
+    sealed_path(vault_path).is_some_and(|path| fs::remove_file(path).is_ok())
+}
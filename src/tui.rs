@@ -0,0 +1,487 @@
+// 交互式终端界面
+//
+// 直接使用 termios 原始模式与 ANSI 转义序列绘制，解锁一次后即可连续浏览、
+// 搜索、添加、编辑和删除账号。
+
+use std::io::{self, Read, Write};
+
+use unicode_width::UnicodeWidthStr;
+
+use passman::{Account, Vault};
+
+use crate::autolock::{AutoLock, Reason};
+use crate::i18n::{self, t};
+
+// 密码遮盖显示
+const MASK: &str = "••••••";
+// 等待按键时检查自动锁定的间隔（毫秒）
+const POLL_INTERVAL: i32 = 1000;
+
+// 界面结束的原因
+pub enum Exit {
+    // 用户退出
+    Quit,
+    // 空闲或系统休眠，需要重新解锁
+    Locked(Reason),
+}
+
+// 按键
+enum Key {
+    Up,
+    Down,
+    Enter,
+    Esc,
+    Backspace,
+    Tab,
+    Char(char),
+    Other,
+}
+
+// 界面模式
+enum Mode {
+    // 浏览列表
+    Browse,
+    // 增量搜索
+    Search,
+    // 添加或编辑表单
+    Form(Form),
+    // 删除确认（账号 ID）
+    ConfirmDelete(String),
+}
+
+// 添加/编辑表单
+struct Form {
+    // 正在编辑的账号 ID，None 表示添加
+    original: Option<String>,
+    // 用户名、密码、备注
+    fields: [String; 3],
+    // 当前输入的字段
+    focus: usize,
+}
+
+const FORM_LABELS: [&str; 3] = ["用户名", "密码", "备注"];
+
+struct App<'a> {
+    vault: &'a mut Vault,
+    filter: String,
+    selected: usize,
+    reveal: bool,
+    mode: Mode,
+    status: String,
+}
+
+// 启动交互式界面，直到用户退出或按 `autolock` 自动锁定
+pub fn run(vault: &mut Vault, autolock: &AutoLock) -> Result<Exit, Box<dyn std::error::Error>> {
+    let _raw = RawMode::enable()?;
+
+    let mut app = App {
+        vault,
+        filter: String::new(),
+        selected: 0,
+        reveal: false,
+        mode: Mode::Browse,
+        status: String::new(),
+    };
+
+    loop {
+        app.render()?;
+        loop {
+            if let Some(reason) = autolock.reason() {
+                return Ok(Exit::Locked(reason));
+            }
+            if input_ready(POLL_INTERVAL)? {
+                break;
+            }
+        }
+        autolock.touch();
+        for key in read_keys()? {
+            if !app.handle(key)? {
+                return Ok(Exit::Quit);
+            }
+        }
+    }
+}
+
+impl App<'_> {
+    // 当前过滤后的账号 ID 列表（按用户名排序）
+    fn visible(&self) -> Vec<String> {
+        let filter = self.filter.to_lowercase();
+        let mut accounts: Vec<&Account> = self
+            .vault
+            .list()
+            .iter()
+            .filter(|account| {
+                filter.is_empty()
+                    || account.username.to_lowercase().contains(&filter)
+                    || account.notes.to_lowercase().contains(&filter)
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.username.cmp(&b.username));
+        accounts.into_iter().map(|account| account.id.clone()).collect()
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.visible().get(self.selected).cloned()
+    }
+
+    // 列表中显示的名称：用户名有重复时附加短 ID
+    fn label(&self, id: &str) -> String {
+        let Some(account) = self.vault.get(id) else {
+            return String::new();
+        };
+        if self.vault.find(&account.username).nth(1).is_some() {
+            format!("{} ({})", account.username, account.short_id())
+        } else {
+            account.username.clone()
+        }
+    }
+
+    // 处理按键，返回 false 表示退出
+    fn handle(&mut self, key: Key) -> Result<bool, Box<dyn std::error::Error>> {
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.handle_browse(key),
+            Mode::Search => self.handle_search(key),
+            Mode::Form(form) => self.handle_form(form, key)?,
+            Mode::ConfirmDelete(id) => self.handle_confirm(id, key)?,
+        }
+        Ok(true)
+    }
+
+    fn handle_browse(&mut self, key: Key) -> Result<bool, Box<dyn std::error::Error>> {
+        let count = self.visible().len();
+        self.status.clear();
+
+        match key {
+            Key::Char('q') => return Ok(false),
+            Key::Esc if self.filter.is_empty() => return Ok(false),
+            Key::Esc => {
+                self.filter.clear();
+                self.selected = 0;
+            }
+            Key::Up | Key::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                self.reveal = false;
+            }
+            Key::Down | Key::Char('j') => {
+                if self.selected + 1 < count {
+                    self.selected += 1;
+                }
+                self.reveal = false;
+            }
+            Key::Char('/') => self.mode = Mode::Search,
+            Key::Enter | Key::Char(' ') => self.reveal = !self.reveal,
+            Key::Char('a') => {
+                self.mode = Mode::Form(Form {
+                    original: None,
+                    fields: Default::default(),
+                    focus: 0,
+                });
+            }
+            Key::Char('e') => {
+                if let Some(id) = self.selected_id() {
+                    let account = self.vault.get(&id).unwrap();
+                    self.mode = Mode::Form(Form {
+                        fields: [account.username.clone(), account.password.clone(), account.notes.clone()],
+                        original: Some(id),
+                        focus: 1,
+                    });
+                }
+            }
+            Key::Char('d') => {
+                if let Some(id) = self.selected_id() {
+                    self.mode = Mode::ConfirmDelete(id);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    fn handle_search(&mut self, key: Key) {
+        match key {
+            Key::Enter | Key::Esc => return,
+            Key::Backspace => {
+                self.filter.pop();
+            }
+            Key::Char(c) => self.filter.push(c),
+            _ => {}
+        }
+        self.selected = 0;
+        self.mode = Mode::Search;
+    }
+
+    fn handle_form(&mut self, mut form: Form, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        match key {
+            Key::Esc => {
+                self.status = t!("已取消").to_string();
+                return Ok(());
+            }
+            Key::Tab | Key::Down => form.focus = (form.focus + 1) % FORM_LABELS.len(),
+            Key::Up => form.focus = (form.focus + FORM_LABELS.len() - 1) % FORM_LABELS.len(),
+            Key::Backspace => {
+                form.fields[form.focus].pop();
+            }
+            Key::Char(c) => form.fields[form.focus].push(c),
+            Key::Enter if form.focus + 1 < FORM_LABELS.len() => form.focus += 1,
+            Key::Enter => {
+                match self.submit(&form) {
+                    Ok(message) => self.status = message,
+                    Err(e) => {
+                        self.status = t!("错误: {}", i18n::error_message(e.as_ref()));
+                        self.mode = Mode::Form(form);
+                    }
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        self.mode = Mode::Form(form);
+        Ok(())
+    }
+
+    // 保存表单内容
+    fn submit(&mut self, form: &Form) -> Result<String, Box<dyn std::error::Error>> {
+        let [username, password, notes] = &form.fields;
+        if username.is_empty() {
+            return Err(t!("用户名不能为空").into());
+        }
+
+        match &form.original {
+            None => {
+                let id = self.vault.add(Account::new(username, password, notes))?;
+                crate::save_vault(self.vault)?;
+                self.select(&id);
+                Ok(t!("账号添加成功: {}", username))
+            }
+            Some(id) => {
+                self.vault.update(id, Some(password), Some(notes))?;
+                if let Some(account) = self.vault.get_mut(id) {
+                    account.username = username.clone();
+                }
+                crate::save_vault(self.vault)?;
+                self.select(id);
+                Ok(t!("账号更新成功: {}", username))
+            }
+        }
+    }
+
+    fn handle_confirm(&mut self, id: String, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        if let Key::Char('y') | Key::Char('Y') = key {
+            let account = self.vault.remove(&id)?;
+            crate::save_vault(self.vault)?;
+            let count = self.visible().len();
+            self.selected = self.selected.min(count.saturating_sub(1));
+            self.status = t!("账号删除成功: {}", account.username);
+        } else {
+            self.status = t!("已取消").to_string();
+        }
+        Ok(())
+    }
+
+    // 选中指定账号（清除过滤条件后定位）
+    fn select(&mut self, id: &str) {
+        self.filter.clear();
+        self.selected = self.visible().iter().position(|n| n == id).unwrap_or(0);
+        self.reveal = false;
+    }
+
+    // 绘制界面
+    fn render(&self) -> io::Result<()> {
+        let (rows, _) = terminal_size();
+        let ids = self.visible();
+        let mut out = String::new();
+
+        out.push_str("\x1b[H\x1b[2J");
+        out.push_str(&format!("\x1b[1mpassman\x1b[0m  {}\r\n", t!("↑/↓ 移动  / 搜索  Enter 显示密码  a 添加  e 编辑  d 删除  q 退出")));
+
+        let search_line = match self.mode {
+            Mode::Search => format!("{}█", t!("搜索: {}", self.filter)),
+            _ if !self.filter.is_empty() => t!("搜索: {}", self.filter),
+            _ => String::new(),
+        };
+        out.push_str(&search_line);
+        out.push_str("\r\n\r\n");
+
+        // 列表区域高度：总行数减去标题、详情和状态栏
+        let list_height = rows.saturating_sub(12).max(3);
+        let start = self.selected.saturating_sub(list_height - 1);
+
+        if ids.is_empty() {
+            out.push_str(&format!("  {}\r\n", t!("无匹配的账号")));
+        }
+        for (i, id) in ids.iter().enumerate().skip(start).take(list_height) {
+            let name = self.label(id);
+            if i == self.selected {
+                out.push_str(&format!("\x1b[7m> {}\x1b[0m\r\n", name));
+            } else {
+                out.push_str(&format!("  {}\r\n", name));
+            }
+        }
+
+        out.push_str("\r\n");
+
+        match &self.mode {
+            Mode::Form(form) => {
+                let title = if form.original.is_some() { t!("编辑账号") } else { t!("添加账号") };
+                out.push_str(&format!("\x1b[1m{}\x1b[0m  {}\r\n", title, t!("Tab 切换字段  Enter 确认  Esc 取消")));
+                for (i, label) in FORM_LABELS.iter().enumerate() {
+                    let marker = if i == form.focus { ">" } else { " " };
+                    let value = if i == 1 && i != form.focus {
+                        MASK.to_string()
+                    } else {
+                        form.fields[i].clone()
+                    };
+                    let cursor = if i == form.focus { "█" } else { "" };
+                    out.push_str(&format!("{} {}: {}{}\r\n", marker, i18n::tr(label), value, cursor));
+                }
+            }
+            _ => {
+                if let Some(id) = ids.get(self.selected) {
+                    let account = self.vault.get(id).unwrap();
+                    let password = if self.reveal { account.password.as_str() } else { MASK };
+                    // 字段名按最宽的一个对齐
+                    let labels = [t!("用户名"), t!("密码"), t!("网址"), t!("备注")];
+                    let width = labels.iter().map(|label| label.width()).max().unwrap_or(0);
+                    let line = |label: &str, value: &str| {
+                        format!("\x1b[1m{}\x1b[0m:{} {}\r\n", label, " ".repeat(width - label.width()), value)
+                    };
+                    out.push_str(&line(labels[0], &account.username));
+                    out.push_str(&line(labels[1], password));
+                    if let Some(url) = &account.url {
+                        out.push_str(&line(labels[2], url));
+                    }
+                    out.push_str(&line(labels[3], &account.notes));
+                }
+            }
+        }
+
+        out.push_str("\r\n");
+        if let Mode::ConfirmDelete(id) = &self.mode {
+            out.push_str(&t!("确认删除账号 {}? (y/N)", self.label(id)));
+        } else {
+            out.push_str(&self.status);
+        }
+
+        let mut stdout = io::stdout();
+        stdout.write_all(out.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+// 等待最多 `timeout` 毫秒，返回是否有输入可以读取
+#[cfg(unix)]
+fn input_ready(timeout: i32) -> io::Result<bool> {
+    let mut fd = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+    match unsafe { libc::poll(&mut fd, 1, timeout) } {
+        -1 => {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::Interrupted { Ok(false) } else { Err(e) }
+        }
+        n => Ok(n > 0),
+    }
+}
+
+#[cfg(not(unix))]
+fn input_ready(_timeout: i32) -> io::Result<bool> {
+    Ok(true)
+}
+
+// 读取按键（一次读取可能包含多个按键，例如粘贴的文本）
+fn read_keys() -> io::Result<Vec<Key>> {
+    let mut buf = [0u8; 256];
+    let n = io::stdin().read(&mut buf)?;
+    if n == 0 {
+        return Ok(vec![Key::Esc]);
+    }
+
+    let text = String::from_utf8_lossy(&buf[..n]);
+    let mut chars = text.chars().peekable();
+    let mut keys = Vec::new();
+
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => match chars.peek() {
+                Some('[') | Some('O') => {
+                    chars.next();
+                    match chars.next() {
+                        Some('A') => Key::Up,
+                        Some('B') => Key::Down,
+                        _ => Key::Other,
+                    }
+                }
+                _ => Key::Esc,
+            },
+            '\r' | '\n' => Key::Enter,
+            '\t' => Key::Tab,
+            '\x7f' | '\x08' => Key::Backspace,
+            c if c.is_control() => Key::Other,
+            c => Key::Char(c),
+        };
+        keys.push(key);
+    }
+
+    Ok(keys)
+}
+
+// 终端大小（行，列）
+#[cfg(unix)]
+pub fn terminal_size() -> (usize, usize) {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    if ok && size.ws_row > 0 {
+        (size.ws_row as usize, size.ws_col as usize)
+    } else {
+        (24, 80)
+    }
+}
+
+#[cfg(not(unix))]
+pub fn terminal_size() -> (usize, usize) {
+    (24, 80)
+}
+
+// 终端原始模式，离开作用域时恢复
+struct RawMode {
+    #[cfg(unix)]
+    original: libc::termios,
+}
+
+impl RawMode {
+    #[cfg(unix)]
+    fn enable() -> io::Result<Self> {
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // 切换到备用屏幕并隐藏光标
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+
+        Ok(RawMode { original })
+    }
+
+    #[cfg(not(unix))]
+    fn enable() -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, t!("交互式界面仅支持类 Unix 系统")))
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        #[cfg(unix)]
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
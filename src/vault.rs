@@ -0,0 +1,888 @@
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use base64::{Engine as _, engine::general_purpose};
+use rand::RngCore;
+
+use crate::atomic;
+use crate::crypto::{Cipher, CryptoBackend, KeyHeader, PasswordBackend, Unlock};
+use crate::error::AppError;
+use crate::journal::{self, Changed, Journal, Undone};
+use crate::kdf;
+use crate::kind::EntryKind;
+use crate::lock::VaultLock;
+use crate::migrate;
+use crate::otp::Otp;
+use crate::secmem::SecretBytes;
+use crate::timestamp;
+
+// 密码库文件格式标识
+const FORMAT: &str = "passman-vault";
+// 显示用的短 ID 长度
+const SHORT_ID_LENGTH: usize = 8;
+// 每个账号最多保留的历史密码数量
+const MAX_HISTORY: usize = 20;
+
+/// 被替换的旧密码
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct PasswordHistory {
+    pub password: String,
+    /// 被替换的时间（Unix 时间戳，秒）
+    pub replaced_at: u64,
+}
+
+/// 自定义字段，例如密保问题、账户号码
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    /// 敏感字段，默认不显示其值
+    #[serde(default)]
+    pub secret: bool,
+}
+
+/// 附件，与账号一起加密保存在密码库中
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Attachment {
+    /// 文件名
+    pub name: String,
+    /// 文件内容（在密码库中以 Base64 保存）
+    #[serde(with = "base64_data")]
+    pub data: Vec<u8>,
+    /// 添加时间（Unix 时间戳，秒）
+    pub added_at: u64,
+}
+
+/// 单个附件的大小上限（字节）
+pub const MAX_ATTACHMENT_SIZE: usize = 1024 * 1024;
+
+/// 账号信息
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Account {
+    /// 唯一标识（UUID），添加到密码库时自动生成
+    #[serde(default)]
+    pub id: String,
+    /// 用户名，不同账号的用户名可以相同（银行卡等类型为条目名称）
+    #[serde(default)]
+    pub username: String,
+    /// 条目类型
+    #[serde(default, skip_serializing_if = "EntryKind::is_login")]
+    pub kind: EntryKind,
+    pub password: String,
+    pub notes: String,
+    /// 网址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 标签
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 文件夹路径，以 `/` 分隔各级，例如 `工作/服务器`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// 一次性密码配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp: Option<Otp>,
+    /// 创建时间（Unix 时间戳，秒），旧版本创建的账号为 0
+    #[serde(default)]
+    pub created_at: u64,
+    /// 最后修改时间（Unix 时间戳，秒），旧版本创建的账号为 0
+    #[serde(default)]
+    pub updated_at: u64,
+    /// 最后使用（查看密码、打开网址、生成一次性密码）的时间（Unix 时间戳，秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+    /// 历史密码，最近替换的在前
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<PasswordHistory>,
+    /// 密码过期时间（Unix 时间戳，秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// 密码轮换周期（秒），设置后每次更换密码都会重新计算过期时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<u64>,
+    /// 自定义字段（按添加顺序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<CustomField>,
+    /// 附件（按添加顺序）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+impl Account {
+    pub fn new(username: &str, password: &str, notes: &str) -> Self {
+        let now = timestamp::now();
+        Account {
+            id: String::new(),
+            username: username.to_string(),
+            kind: EntryKind::Login,
+            password: password.to_string(),
+            notes: notes.to_string(),
+            url: None,
+            tags: Vec::new(),
+            folder: None,
+            otp: None,
+            created_at: now,
+            updated_at: now,
+            last_used_at: None,
+            history: Vec::new(),
+            expires_at: None,
+            rotation: None,
+            fields: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// 以此账号为模板创建新账号：复制类型、密码、备注、网址、标签、文件夹、自定义字段和
+    /// 密码轮换周期，不复制 ID、历史密码、一次性密码和附件
+    pub fn duplicate(&self, username: &str) -> Account {
+        let mut account = Account::new(username, &self.password, &self.notes);
+        account.kind = self.kind;
+        account.url = self.url.clone();
+        account.tags = self.tags.clone();
+        account.folder = self.folder.clone();
+        account.fields = self.fields.clone();
+        if let Some(period) = self.rotation {
+            account.expire_after(period);
+        }
+        account
+    }
+
+    /// 设置网址，空字符串表示清除
+    pub fn set_url(&mut self, url: &str) {
+        let url = url.trim();
+        self.url = (!url.is_empty()).then(|| url.to_string());
+    }
+
+    /// 按名称获取自定义字段
+    pub fn field(&self, name: &str) -> Option<&CustomField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// 设置自定义字段，同名字段已存在时替换其值
+    pub fn set_field(&mut self, name: &str, value: &str, secret: bool) -> Result<(), AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::from("字段名称不能为空"));
+        }
+
+        match self.fields.iter_mut().find(|field| field.name == name) {
+            Some(field) => {
+                field.value = value.to_string();
+                field.secret = secret;
+            }
+            None => self.fields.push(CustomField { name: name.to_string(), value: value.to_string(), secret }),
+        }
+        Ok(())
+    }
+
+    /// 移除自定义字段，返回字段是否存在
+    pub fn remove_field(&mut self, name: &str) -> bool {
+        let count = self.fields.len();
+        self.fields.retain(|field| field.name != name.trim());
+        self.fields.len() != count
+    }
+
+    /// 按名称获取附件
+    pub fn attachment(&self, name: &str) -> Option<&Attachment> {
+        self.attachments.iter().find(|attachment| attachment.name == name)
+    }
+
+    /// 添加附件，同名附件已存在时替换其内容
+    pub fn attach(&mut self, name: &str, data: Vec<u8>) -> Result<(), AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::from("附件名称不能为空"));
+        }
+        if data.len() > MAX_ATTACHMENT_SIZE {
+            return Err(AppError::from(format!("附件过大（{} 字节），最大为 {} 字节", data.len(), MAX_ATTACHMENT_SIZE)));
+        }
+
+        let attachment = Attachment { name: name.to_string(), data, added_at: timestamp::now() };
+        match self.attachments.iter_mut().find(|attachment| attachment.name == name) {
+            Some(existing) => *existing = attachment,
+            None => self.attachments.push(attachment),
+        }
+        Ok(())
+    }
+
+    /// 移除附件，返回附件是否存在
+    pub fn remove_attachment(&mut self, name: &str) -> bool {
+        let count = self.attachments.len();
+        self.attachments.retain(|attachment| attachment.name != name);
+        self.attachments.len() != count
+    }
+
+    /// 设置密码在 `period` 秒后过期，之后每次更换密码时重新计算
+    pub fn expire_after(&mut self, period: u64) {
+        self.rotation = Some(period);
+        self.expires_at = Some(timestamp::now().saturating_add(period));
+    }
+
+    /// 设置密码在指定时间过期（不随更换密码改变）
+    pub fn expire_at(&mut self, time: u64) {
+        self.rotation = None;
+        self.expires_at = Some(time);
+    }
+
+    /// 取消过期时间
+    pub fn clear_expiry(&mut self) {
+        self.rotation = None;
+        self.expires_at = None;
+    }
+
+    /// 更换密码，旧密码记入历史（最多保留 20 个）
+    pub fn set_password(&mut self, password: &str) {
+        if self.password == password {
+            return;
+        }
+
+        let old = std::mem::replace(&mut self.password, password.to_string());
+        if !old.is_empty() {
+            self.history.insert(0, PasswordHistory { password: old, replaced_at: timestamp::now() });
+            self.history.truncate(MAX_HISTORY);
+        }
+        if let Some(period) = self.rotation {
+            self.expires_at = Some(timestamp::now().saturating_add(period));
+        }
+        self.touch();
+    }
+
+    /// 恢复第 `index` 个历史密码（从 0 开始），当前密码记入历史
+    pub fn restore_password(&mut self, index: usize) -> Result<(), AppError> {
+        if index >= self.history.len() {
+            return Err(AppError::from(format!("没有第 {} 个历史密码", index + 1)));
+        }
+
+        let entry = self.history.remove(index);
+        self.set_password(&entry.password);
+        Ok(())
+    }
+
+    /// 记录账号被修改
+    pub fn touch(&mut self) {
+        self.updated_at = timestamp::now();
+    }
+
+    /// 记录账号被使用，不算作修改（不更新修改时间，也不记入修改记录）
+    pub fn mark_used(&mut self) {
+        self.last_used_at = Some(timestamp::now());
+    }
+
+    /// 除使用时间以外内容是否相同
+    pub fn same_content(&self, other: &Account) -> bool {
+        Account { last_used_at: other.last_used_at, ..self.clone() } == *other
+    }
+
+    /// 用于显示的短 ID（UUID 的前 8 位）
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(SHORT_ID_LENGTH)]
+    }
+
+    /// 添加标签（去除首尾空白，忽略空标签和已有的标签）
+    pub fn add_tags<S: AsRef<str>>(&mut self, tags: impl IntoIterator<Item = S>) {
+        for tag in tags {
+            let tag = tag.as_ref().trim();
+            if !tag.is_empty() && !self.tags.iter().any(|t| t == tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    /// 设置文件夹，空路径表示不在任何文件夹中
+    pub fn set_folder(&mut self, folder: &str) {
+        self.folder = normalize_folder(folder);
+    }
+
+    /// 账号位于指定文件夹或其子文件夹中
+    pub fn in_folder(&self, folder: &str) -> bool {
+        match (normalize_folder(folder), &self.folder) {
+            (None, _) => true,
+            (Some(folder), Some(own)) => own == &folder || own.starts_with(&format!("{}/", folder)),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+// 去除多余的 `/` 和各级名称首尾的空白
+fn normalize_folder(folder: &str) -> Option<String> {
+    let parts: Vec<&str> = folder.split('/').map(str::trim).filter(|p| !p.is_empty()).collect();
+    if parts.is_empty() { None } else { Some(parts.join("/")) }
+}
+
+/// 账号集合（按添加顺序）
+pub type AccountStore = Vec<Account>;
+
+/// 定位账号的条件，各条件同时满足
+#[derive(Default)]
+pub struct Selector<'a> {
+    /// 账号 ID，也可以只写开头几位
+    pub id: Option<&'a str>,
+    pub username: Option<&'a str>,
+    /// 网址、备注或文件夹中包含的文字，忽略大小写
+    pub site: Option<&'a str>,
+    /// 条目类型
+    pub kind: Option<EntryKind>,
+}
+
+impl Selector<'_> {
+    fn matches(&self, account: &Account) -> bool {
+        let id = self.id.is_none_or(|id| !id.is_empty() && account.id.starts_with(&id.to_lowercase()));
+        let username = self.username.is_none_or(|username| account.username == username);
+        let site = self.site.is_none_or(|site| {
+            let site = site.to_lowercase();
+            account.notes.to_lowercase().contains(&site)
+                || account.url.as_ref().is_some_and(|u| u.to_lowercase().contains(&site))
+                || account.folder.as_ref().is_some_and(|f| f.to_lowercase().contains(&site))
+        });
+        let kind = self.kind.is_none_or(|kind| account.kind == kind);
+        id && username && site && kind
+    }
+}
+
+// 附件内容在 JSON 中以 Base64 字符串保存
+mod base64_data {
+    use base64::{Engine as _, engine::general_purpose};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&general_purpose::STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        general_purpose::STANDARD.decode(text).map_err(D::Error::custom)
+    }
+}
+
+// 生成随机的 UUID (v4)
+pub(crate) fn new_id() -> String {
+    let mut bytes = rand::random::<[u8; 16]>();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// 反序列化账号数据（兼容旧版本以用户名为键的格式，缺少 ID 的账号会被分配 ID）
+pub(crate) fn deserialize_accounts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AccountStore, D::Error> {
+    let mut data = serde_json::Value::deserialize(deserializer)?;
+    migrate::to_v1(&mut data).map_err(D::Error::custom)?;
+    let payload: StoredPayload = serde_json::from_value(data).map_err(D::Error::custom)?;
+    Ok(payload.accounts)
+}
+
+// 加密前的数据
+#[derive(Serialize)]
+struct Payload<'a> {
+    accounts: &'a AccountStore,
+    #[serde(skip_serializing_if = "Journal::is_empty")]
+    journal: &'a Journal,
+}
+
+// 解密后的数据（当前格式版本）
+#[derive(Deserialize)]
+struct StoredPayload {
+    accounts: AccountStore,
+    #[serde(default)]
+    journal: Journal,
+}
+
+/// 打开密码库时对旧版本格式做的升级
+pub struct Migration {
+    /// 原来的格式版本
+    pub from: u32,
+    /// 升级后的格式版本
+    pub to: u32,
+    /// 升级前的密码库文件备份
+    pub backup: PathBuf,
+    /// 各项改动的说明
+    pub changes: Vec<&'static str>,
+}
+
+// 密码库文件头部：格式标识、版本、加密算法和密钥派生参数
+//
+// 版本 1 之前的密码库只有 kdf 字段，更早的版本连 kdf 也没有，缺少的字段读取为空。
+// 从版本 2 开始，头部的序列化结果作为附加认证数据参与加密，修改头部中的任何字段
+// （例如降低密钥派生参数）都会导致解密失败；版本 3 加入了主密钥校验值，用来区分
+// 主密钥错误和文件损坏。以 age 接收者或 GPG 公钥加密的密码库没有 kdf 和 key_check，
+// 而是记录封装后的密钥。
+#[derive(Serialize, Deserialize)]
+struct Header {
+    #[serde(default)]
+    format: String,
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    cipher: String,
+    // 密钥派生参数、age 接收者和密钥校验值（见 CryptoBackend::key_header）
+    #[serde(flatten)]
+    key: KeyHeader,
+}
+
+impl Header {
+    fn new(backend: &dyn CryptoBackend, cipher: Cipher) -> Self {
+        Header {
+            format: FORMAT.to_string(),
+            version: migrate::VERSION,
+            cipher: cipher.name().to_string(),
+            key: backend.key_header(),
+        }
+    }
+
+    // 拒绝无法识别的文件，以免用错误的方式解密或覆盖新版本的密码库
+    fn check(&self) -> Result<(), AppError> {
+        if !self.format.is_empty() && self.format != FORMAT {
+            return Err(AppError::from("不是有效的 passman 密码库文件"));
+        }
+        if self.version > migrate::VERSION {
+            return Err(AppError::from(format!("密码库格式版本 {} 过新，请升级 passman", self.version)));
+        }
+        if !self.cipher.is_empty() && Cipher::from_name(&self.cipher).is_none() {
+            return Err(AppError::from(format!("不支持的加密算法: {}", self.cipher)));
+        }
+        Ok(())
+    }
+
+    // 加密算法，版本 1 之前的密码库没有此字段，使用的是 AES-256-GCM
+    fn cipher(&self) -> Cipher {
+        Cipher::from_name(&self.cipher).unwrap_or_default()
+    }
+
+    // 用校验值确认解锁得到的密钥，没有校验值的旧文件只能通过解密来确认
+    fn check_key(&self, backend: &dyn CryptoBackend) -> Result<(), AppError> {
+        match (&self.key.key_check, backend.key_header().key_check) {
+            (Some(expected), Some(actual)) if *expected != actual => Err(AppError::WrongMasterKey),
+            _ => Ok(()),
+        }
+    }
+}
+
+// 密码库文件结构
+#[derive(Serialize, Deserialize)]
+struct PasswordStore {
+    #[serde(flatten)]
+    header: Header,
+    // 使用随机生成的初始化向量(IV)
+    iv: String,
+    // 加密后的数据
+    encrypted_data: String,
+}
+
+/// 已解锁的密码库
+///
+/// 所有修改只作用于内存中的数据，调用 [`Vault::save`] 后才会写入文件。每次保存的修改
+/// 都会记入加密的修改记录，可以用 [`Vault::undo`] 撤销。
+///
+/// 打开期间持有密码库的锁，其他进程要等它被释放后才能打开同一个密码库。
+pub struct Vault {
+    path: PathBuf,
+    _lock: Option<VaultLock>,
+    backend: Box<dyn CryptoBackend>,
+    cipher: Cipher,
+    accounts: AccountStore,
+    // 上次读取或保存时的账号，用于在保存时找出修改
+    saved: AccountStore,
+    journal: Journal,
+    migration: Option<Migration>,
+}
+
+impl Vault {
+    /// 使用主密钥打开密码库，文件不存在时创建一个空密码库
+    ///
+    /// 密码库正被其他进程使用时最多等待 10 秒，仍未释放则返回错误。旧版本（SHA-256 派生密钥）的密码库会被自动升级为 Argon2id 并立即保存。
+    pub fn open(path: impl AsRef<Path>, master_key: &str) -> Result<Self, AppError> {
+        Self::open_with_kdf(path, master_key, kdf::KdfParams::generate())
+    }
+
+    /// 与 [`Vault::open`] 相同，但新建或升级密码库时使用给定的密钥派生参数
+    pub fn open_with_kdf(path: impl AsRef<Path>, master_key: &str, new_kdf: kdf::KdfParams) -> Result<Self, AppError> {
+        Self::open_with_backend(path, |unlock| {
+            let backend = match unlock {
+                Unlock::Create => PasswordBackend::new(master_key, new_kdf)?,
+                Unlock::Existing { header } => PasswordBackend::unlock(master_key, header, new_kdf)?,
+            };
+            Ok(Box::new(backend))
+        })
+    }
+
+    /// 使用 `unlock` 创建的加密后端打开密码库，文件不存在时创建一个空密码库
+    ///
+    /// `unlock` 根据文件头部的信息（见 [`Unlock`]）返回后端，例如请求口令或连接硬件密钥；
+    /// 后端提供了密钥校验值时先校验再解密。需要升级格式或后端要求更换时（见
+    /// [`CryptoBackend::upgrade`]）先备份原文件，再以当前格式立即保存。
+    pub fn open_with_backend(
+        path: impl AsRef<Path>,
+        unlock: impl FnOnce(Unlock<'_>) -> Result<Box<dyn CryptoBackend>, AppError>,
+    ) -> Result<Self, AppError> {
+        let path = path.as_ref().to_path_buf();
+        // 先获取锁再读取，以免读到其他进程即将覆盖的内容
+        let lock = VaultLock::acquire(&path)?;
+
+        if !path.exists() {
+            let backend = unlock(Unlock::Create)?;
+            return Ok(Vault::new(path, lock, backend, Cipher::default(), AccountStore::new(), Journal::default()));
+        }
+
+        let store = read_store(&path)?;
+        let version = store.header.version;
+        let backend = unlock(Unlock::Existing { header: &store.header.key })?;
+        store.header.check_key(&*backend)?;
+        let data = decrypt_store(&*backend, &store)?;
+        let mut changes = Vec::new();
+        let backend = match backend.upgrade()? {
+            Some(upgrade) => {
+                changes.push(upgrade.change);
+                upgrade.backend
+            }
+            None => backend,
+        };
+
+        if version == migrate::VERSION && changes.is_empty() {
+            let payload: StoredPayload = serde_json::from_slice(&data)?;
+            return Ok(Vault::new(path, lock, backend, store.header.cipher(), payload.accounts, payload.journal));
+        }
+
+        // 升级到当前格式，先备份原文件再保存
+        let mut data = serde_json::from_slice(&data)?;
+        changes.extend(migrate::migrate(&mut data, version)?);
+        let payload: StoredPayload = serde_json::from_value(data)?;
+        let backup = migrate::backup(&path, version)?;
+        let mut vault = Vault::new(path, lock, backend, store.header.cipher(), payload.accounts, payload.journal);
+        vault.save()?;
+        vault.migration = Some(Migration { from: version, to: migrate::VERSION, backup, changes });
+        Ok(vault)
+    }
+
+    fn new(
+        path: PathBuf,
+        lock: Option<VaultLock>,
+        backend: Box<dyn CryptoBackend>,
+        cipher: Cipher,
+        accounts: AccountStore,
+        journal: Journal,
+    ) -> Self {
+        Vault { path, _lock: lock, backend, cipher, saved: accounts.clone(), accounts, journal, migration: None }
+    }
+
+    /// 密码库文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 密码库使用的加密算法
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    /// 文件头部中的密钥信息，例如以 age 接收者加密时的接收者
+    pub fn key_header(&self) -> KeyHeader {
+        self.backend.key_header()
+    }
+
+    /// 更换加密算法，下次保存或更换主密钥时生效
+    ///
+    /// 新建的密码库默认使用 AES-256-GCM，在第一次保存前调用即可选择其他算法。
+    pub fn set_cipher(&mut self, cipher: Cipher) {
+        self.cipher = cipher;
+    }
+
+    /// 关闭密码库并取出它的加密后端，例如交给长期运行的进程以便之后再次打开；密码库的锁
+    /// 随之释放
+    pub fn into_backend(self) -> Box<dyn CryptoBackend> {
+        self.backend
+    }
+
+    /// 本次打开时对旧版本格式做的升级，没有升级时为 `None`
+    pub fn migration(&self) -> Option<&Migration> {
+        self.migration.as_ref()
+    }
+
+    /// 添加账号并返回其 ID
+    ///
+    /// 账号没有 ID 时自动生成；用户名可以与已有账号相同，ID 已存在时返回错误。
+    pub fn add(&mut self, mut account: Account) -> Result<String, AppError> {
+        if account.username.is_empty() {
+            return Err(AppError::from("用户名不能为空"));
+        }
+        if account.id.is_empty() {
+            account.id = new_id();
+        } else if self.get(&account.id).is_some() {
+            return Err(AppError::from("账号 ID 已存在"));
+        }
+
+        let id = account.id.clone();
+        self.accounts.push(account);
+        Ok(id)
+    }
+
+    /// 按 ID 获取账号
+    pub fn get(&self, id: &str) -> Option<&Account> {
+        self.accounts.iter().find(|account| account.id == id)
+    }
+
+    /// 按 ID 获取账号的可变引用
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Account> {
+        self.accounts.iter_mut().find(|account| account.id == id)
+    }
+
+    /// 用户名为 `username` 的所有账号
+    pub fn find(&self, username: &str) -> impl Iterator<Item = &Account> {
+        self.accounts.iter().filter(move |account| account.username == username)
+    }
+
+    /// 定位唯一满足条件的账号
+    ///
+    /// 没有满足条件的账号，或者有多个时（例如同一用户名用于多个网站）返回错误，
+    /// 后者会列出候选账号的短 ID 以便进一步区分。
+    pub fn select(&self, selector: &Selector) -> Result<&Account, AppError> {
+        let matches: Vec<&Account> = self.accounts.iter().filter(|account| selector.matches(account)).collect();
+        match matches.as_slice() {
+            [] => Err(AppError::from("账号不存在")),
+            [account] => Ok(account),
+            _ => {
+                let candidates: Vec<String> = matches
+                    .iter()
+                    .map(|account| {
+                        let site = account.notes.lines().next().unwrap_or("");
+                        format!("{} {} {}", account.short_id(), account.username, site).trim_end().to_string()
+                    })
+                    .collect();
+                Err(AppError::from(format!(
+                    "有 {} 个账号符合条件（{}），请使用 --id 或 --site 进一步指定",
+                    matches.len(),
+                    candidates.join("; ")
+                )))
+            }
+        }
+    }
+
+    /// 所有账号
+    pub fn list(&self) -> &AccountStore {
+        &self.accounts
+    }
+
+    /// 更新账号的密码和/或备注
+    pub fn update(
+        &mut self,
+        id: &str,
+        password: Option<&str>,
+        notes: Option<&str>,
+    ) -> Result<(), AppError> {
+        let account = self.get_mut(id).ok_or("账号不存在")?;
+
+        if let Some(password) = password {
+            account.set_password(password);
+        }
+
+        if let Some(notes) = notes {
+            account.notes = notes.to_string();
+        }
+
+        account.touch();
+        Ok(())
+    }
+
+    /// 按 ID 删除账号并返回被删除的账号
+    pub fn remove(&mut self, id: &str) -> Result<Account, AppError> {
+        let index = self.accounts.iter().position(|account| account.id == id).ok_or("账号不存在")?;
+        Ok(self.accounts.remove(index))
+    }
+
+    /// 自上次读取或保存以来修改过的账号，只更新了使用时间的账号不算修改
+    pub fn changes(&self) -> Vec<Changed<'_>> {
+        journal::compare(&self.saved, &self.accounts)
+    }
+
+    /// 加密并写入密码库文件（Unix 上只有当前用户可以读写），同时记录自上次保存以来的修改
+    pub fn save(&mut self) -> Result<(), AppError> {
+        let mut journal = self.journal.clone();
+        journal.record(&self.saved, &self.accounts);
+        self.write(&journal)?;
+
+        self.journal = journal;
+        self.saved = self.accounts.clone();
+        Ok(())
+    }
+
+    /// 最近 `n` 次保存之前的账号（由修改记录还原），以及其中最早一次保存的时间
+    ///
+    /// 修改记录只保留最近 20 次保存，不足 `n` 次时返回 `None`。
+    pub fn revision(&self, n: usize) -> Option<(u64, AccountStore)> {
+        self.journal.before(&self.saved, n)
+    }
+
+    /// 撤销最近一次保存的修改并写入文件，返回修改的时间和撤销的内容
+    ///
+    /// 没有修改记录时返回 `None`；有未保存的修改时返回错误。
+    pub fn undo(&mut self) -> Result<Option<(u64, Vec<Undone>)>, AppError> {
+        if self.accounts != self.saved {
+            return Err(AppError::from("有未保存的修改，请先保存"));
+        }
+
+        let mut journal = self.journal.clone();
+        let mut accounts = self.accounts.clone();
+        let Some(undone) = journal.undo(&mut accounts) else {
+            return Ok(None);
+        };
+        self.accounts = accounts;
+        if let Err(e) = self.write(&journal) {
+            self.accounts = self.saved.clone();
+            return Err(e);
+        }
+
+        self.journal = journal;
+        self.saved = self.accounts.clone();
+        Ok(Some(undone))
+    }
+
+    // 加密并写入文件
+    fn write(&self, journal: &Journal) -> Result<(), AppError> {
+        let json = self.encrypt(journal)?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic::write_private(&self.path, json)?;
+
+        Ok(())
+    }
+
+    /// 更换主密钥：使用新的主密钥和密钥派生参数重新加密整个密码库
+    ///
+    /// 与保存一样先写入临时文件再替换原文件，中途失败时原密码库保持不变。
+    pub fn change_master_key(&mut self, new_master_key: &str, kdf: kdf::KdfParams) -> Result<(), AppError> {
+        self.change_backend(Box::new(PasswordBackend::new(new_master_key, kdf)?))
+    }
+
+    /// 更换加密后端并重新加密整个密码库，例如改用其他密钥派生算法或硬件密钥
+    ///
+    /// 写入方式与 [`Vault::change_master_key`] 相同，失败时继续使用原来的后端。
+    pub fn change_backend(&mut self, backend: Box<dyn CryptoBackend>) -> Result<(), AppError> {
+        let old_backend = std::mem::replace(&mut self.backend, backend);
+
+        let result = self.encrypt(&self.journal).and_then(|json| Ok(atomic::write_private(&self.path, json)?));
+        if result.is_err() {
+            self.backend = old_backend;
+        }
+        result
+    }
+
+    /// 用这个密码库的密钥解密另一份密码库文件的内容（例如同步时其他设备上传的副本），
+    /// 返回其中的账号
+    ///
+    /// 两份文件的主密钥或加密后端不同时返回错误。
+    pub fn read_accounts(&self, content: &[u8]) -> Result<AccountStore, AppError> {
+        let store = parse_store(content)?;
+        store.header.check_key(&*self.backend)?;
+        let data = decrypt_store(&*self.backend, &store)?;
+        if store.header.version == migrate::VERSION {
+            return Ok(serde_json::from_slice::<StoredPayload>(&data)?.accounts);
+        }
+        let mut data = serde_json::from_slice(&data)?;
+        migrate::migrate(&mut data, store.header.version)?;
+        Ok(serde_json::from_value::<StoredPayload>(data)?.accounts)
+    }
+
+    /// 以 `accounts` 替换全部账号，例如同步时合并的结果；与其他修改一样在保存时记入修改记录
+    pub fn replace_accounts(&mut self, accounts: AccountStore) {
+        self.accounts = accounts;
+    }
+
+    /// 重新读取并解密密码库文件，逐个确认账号与内存中的数据一致
+    ///
+    /// `progress` 在每个账号校验后以（已完成数量，总数）调用。
+    pub fn verify(&self, mut progress: impl FnMut(usize, usize)) -> Result<(), AppError> {
+        let store = read_store(&self.path)?;
+        let payload: StoredPayload = serde_json::from_slice(&decrypt_store(&*self.backend, &store)?)?;
+        let accounts = payload.accounts;
+        if accounts.len() != self.accounts.len() {
+            return Err(AppError::from("校验失败：账号数量不一致"));
+        }
+
+        for (done, (account, stored)) in self.accounts.iter().zip(&accounts).enumerate() {
+            if account != stored {
+                return Err(AppError::from(format!("校验失败：账号 {} 不一致", account.username)));
+            }
+            progress(done + 1, self.accounts.len());
+        }
+        Ok(())
+    }
+
+    // 序列化并加密为密码库文件内容
+    fn encrypt(&self, journal: &Journal) -> Result<String, AppError> {
+        // 序列化账号数据和修改记录，明文只保存在锁定的内存中
+        let mut data = SecretBytes::with_capacity(0);
+        serde_json::to_writer(&mut data, &Payload { accounts: &self.accounts, journal })?;
+
+        // 生成随机IV
+        let mut iv = vec![0u8; self.cipher.nonce_length()];
+        rand::thread_rng().fill_bytes(&mut iv);
+
+        // 加密，头部作为附加认证数据
+        let header = Header::new(&*self.backend, self.cipher);
+        let aad = serde_json::to_vec(&header)?;
+        self.backend.encrypt(self.cipher, &iv, &aad, &mut data)?;
+        let encrypted_data = &*data;
+
+        // 创建密码库结构
+        let store = PasswordStore {
+            header,
+            iv: general_purpose::STANDARD.encode(iv),
+            encrypted_data: general_purpose::STANDARD.encode(encrypted_data),
+        };
+
+        Ok(serde_json::to_string(&store)?)
+    }
+}
+
+// 读取密码库文件
+fn read_store(path: &Path) -> Result<PasswordStore, AppError> {
+    parse_store(&fs::read(path)?)
+}
+
+// 解析密码库文件的内容
+fn parse_store(file_content: &[u8]) -> Result<PasswordStore, AppError> {
+    let store: PasswordStore = serde_json::from_slice(file_content).map_err(|_| {
+        // 看起来是密码库却无法解析，多半是文件被截断或损坏
+        let marker = |text: &[u8]| file_content.windows(text.len()).any(|window| window == text);
+        if marker(FORMAT.as_bytes()) || marker(b"encrypted_data") {
+            AppError::from("密码库文件已损坏（不完整或格式错误）")
+        } else {
+            AppError::from("不是有效的 passman 密码库文件")
+        }
+    })?;
+    store.header.check()?;
+    Ok(store)
+}
+
+// 解密密码库，返回账号数据和修改记录的 JSON（保存在锁定的内存中）
+//
+// 认证标签同时覆盖初始化向量、加密数据和（版本 2 起的）头部，文件任何部分被修改、
+// 截断或调换都会导致解密失败。
+fn decrypt_store(backend: &dyn CryptoBackend, store: &PasswordStore) -> Result<SecretBytes, AppError> {
+    let damaged = || AppError::from("密码库文件已损坏（不完整或格式错误）");
+
+    // 解码IV
+    let iv = general_purpose::STANDARD.decode(&store.iv).map_err(|_| damaged())?;
+    let cipher = store.header.cipher();
+    if iv.len() != cipher.nonce_length() {
+        return Err(damaged());
+    }
+
+    // 解码加密数据，在锁定的内存中原地解密
+    let encrypted_data = general_purpose::STANDARD.decode(&store.encrypted_data).map_err(|_| damaged())?;
+    let mut data = SecretBytes::from_slice(&encrypted_data);
+
+    // 解密，版本 2 之前的密码库没有附加认证数据
+    let aad = if store.header.version >= 2 { serde_json::to_vec(&store.header)? } else { Vec::new() };
+    // 有校验值时主密钥已经确认无误，age 接收者和 GPG 加密的密钥在解开时已经过认证，
+    // FIDO2 凭据只有注册它的安全密钥才能使用
+    let key = &store.header.key;
+    let key_confirmed = key.key_check.is_some() || key.recipients.is_some() || key.gpg.is_some() || key.fido2.is_some();
+    backend.decrypt(cipher, &iv, &aad, &mut data).map_err(|e| match e {
+        AppError::Aes(_) if key_confirmed => AppError::from("密码库文件已被篡改或损坏"),
+        AppError::Aes(_) => AppError::from("主密钥错误，或密码库文件已被篡改或损坏"),
+        e => e,
+    })?;
+
+    Ok(data)
+}
@@ -0,0 +1,44 @@
+// WiFi 网络条目的连接二维码（`passman wifi qr`）
+//
+// 二维码的内容是手机相机通用的 `WIFI:T:<加密方式>;S:<SSID>;P:<密码>;H:true;;`，值中的 `\`、`;`、
+// `,`、`:` 和 `"` 前加上反斜杠。
+
+use passman::Account;
+
+/// 网络名称：ssid 字段，没有时为条目的用户名
+pub fn ssid(account: &Account) -> &str {
+    account.field("ssid").map(|field| field.value.trim()).filter(|ssid| !ssid.is_empty()).unwrap_or(&account.username)
+}
+
+/// 二维码的内容；没有指定加密方式时按是否有密码选择 WPA 或不加密
+pub fn payload(account: &Account) -> String {
+    let field = |name: &str| account.field(name).map(|field| field.value.trim().to_lowercase()).unwrap_or_default();
+    let security = match field("security").as_str() {
+        "wep" => "WEP",
+        // WPA3 在规范中写为 SAE
+        "wpa3" | "sae" => "SAE",
+        "nopass" => "nopass",
+        _ if account.password.is_empty() => "nopass",
+        _ => "WPA",
+    };
+    let mut payload = format!("WIFI:T:{};S:{};", security, escape(ssid(account)));
+    if security != "nopass" {
+        payload.push_str(&format!("P:{};", escape(&account.password)));
+    }
+    if matches!(field("hidden").as_str(), "yes" | "true") {
+        payload.push_str("H:true;");
+    }
+    payload.push(';');
+    payload
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,221 @@
+// X25519 密钥交换 (RFC 7748)
+//
+// GF(2^255 - 19) 上的元素按 51 位分为 5 组，标量乘法使用常数时间的 Montgomery 阶梯。
+// 只用于 age 接收者的密钥封装。
+
+const MASK: u64 = (1 << 51) - 1;
+
+// 有限域元素，各组不超过 54 位，乘法前无需完全约简
+#[derive(Clone, Copy)]
+struct Fe([u64; 5]);
+
+impl Fe {
+    const ZERO: Fe = Fe([0; 5]);
+    const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+    // 小端序的 32 字节，忽略最高位
+    fn from_bytes(bytes: &[u8; 32]) -> Fe {
+        let mut limbs = [0u64; 5];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut limb = 0;
+        for &byte in bytes {
+            acc |= u128::from(byte) << bits;
+            bits += 8;
+            if bits >= 51 && limb < 5 {
+                limbs[limb] = acc as u64 & MASK;
+                acc >>= 51;
+                bits -= 51;
+                limb += 1;
+            }
+        }
+        // 剩下的 1 位是忽略的最高位
+        Fe(limbs)
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        // 完全约简到 [0, p)
+        let mut l = Fe::carry(self.0);
+        let mut q = (l[0] + 19) >> 51;
+        q = (l[1] + q) >> 51;
+        q = (l[2] + q) >> 51;
+        q = (l[3] + q) >> 51;
+        q = (l[4] + q) >> 51;
+        l[0] += 19 * q;
+        l[1] += l[0] >> 51;
+        l[0] &= MASK;
+        l[2] += l[1] >> 51;
+        l[1] &= MASK;
+        l[3] += l[2] >> 51;
+        l[2] &= MASK;
+        l[4] += l[3] >> 51;
+        l[3] &= MASK;
+        l[4] &= MASK;
+
+        let mut out = [0u8; 32];
+        let mut acc: u128 = 0;
+        let mut bits = 0;
+        let mut i = 0;
+        for limb in l {
+            acc |= u128::from(limb) << bits;
+            bits += 51;
+            while bits >= 8 {
+                out[i] = acc as u8;
+                acc >>= 8;
+                bits -= 8;
+                i += 1;
+            }
+        }
+        out[i] = acc as u8;
+        out
+    }
+
+    // 进位，使各组不超过 51 位（最高位的进位乘 19 加回最低组）
+    fn carry(mut l: [u64; 5]) -> [u64; 5] {
+        let c0 = l[0] >> 51;
+        let c1 = l[1] >> 51;
+        let c2 = l[2] >> 51;
+        let c3 = l[3] >> 51;
+        let c4 = l[4] >> 51;
+        l[0] &= MASK;
+        l[1] &= MASK;
+        l[2] &= MASK;
+        l[3] &= MASK;
+        l[4] &= MASK;
+        l[0] += c4 * 19;
+        l[1] += c0;
+        l[2] += c1;
+        l[3] += c2;
+        l[4] += c3;
+        l
+    }
+
+    fn add(self, other: Fe) -> Fe {
+        let (a, b) = (self.0, other.0);
+        Fe(Fe::carry([a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3], a[4] + b[4]]))
+    }
+
+    // 先加上 16p，避免出现负数
+    fn sub(self, other: Fe) -> Fe {
+        let (a, b) = (self.0, other.0);
+        const P16_0: u64 = 16 * ((1 << 51) - 19);
+        const P16: u64 = 16 * ((1 << 51) - 1);
+        Fe(Fe::carry([
+            a[0] + P16_0 - b[0],
+            a[1] + P16 - b[1],
+            a[2] + P16 - b[2],
+            a[3] + P16 - b[3],
+            a[4] + P16 - b[4],
+        ]))
+    }
+
+    fn mul(self, other: Fe) -> Fe {
+        let [a0, a1, a2, a3, a4] = self.0.map(u128::from);
+        let [b0, b1, b2, b3, b4] = other.0.map(u128::from);
+        let (b1_19, b2_19, b3_19, b4_19) = (b1 * 19, b2 * 19, b3 * 19, b4 * 19);
+
+        let c0 = a0 * b0 + a4 * b1_19 + a3 * b2_19 + a2 * b3_19 + a1 * b4_19;
+        let mut c1 = a1 * b0 + a0 * b1 + a4 * b2_19 + a3 * b3_19 + a2 * b4_19;
+        let mut c2 = a2 * b0 + a1 * b1 + a0 * b2 + a4 * b3_19 + a3 * b4_19;
+        let mut c3 = a3 * b0 + a2 * b1 + a1 * b2 + a0 * b3 + a4 * b4_19;
+        let mut c4 = a4 * b0 + a3 * b1 + a2 * b2 + a1 * b3 + a0 * b4;
+
+        c1 += c0 >> 51;
+        c2 += c1 >> 51;
+        c3 += c2 >> 51;
+        c4 += c3 >> 51;
+        let mut l0 = (c0 as u64 & MASK) + (c4 >> 51) as u64 * 19;
+        let l1 = (c1 as u64 & MASK) + (l0 >> 51);
+        l0 &= MASK;
+        Fe([l0, l1, c2 as u64 & MASK, c3 as u64 & MASK, c4 as u64 & MASK])
+    }
+
+    fn square(self) -> Fe {
+        self.mul(self)
+    }
+
+    fn mul_small(self, n: u64) -> Fe {
+        let mut l = [0u64; 5];
+        let mut carry: u128 = 0;
+        for (out, &limb) in l.iter_mut().zip(&self.0) {
+            let product = u128::from(limb) * u128::from(n) + carry;
+            *out = product as u64 & MASK;
+            carry = product >> 51;
+        }
+        l[0] += carry as u64 * 19;
+        Fe(Fe::carry(l))
+    }
+
+    // 求逆：a^(p - 2)，p - 2 = 2^255 - 21 除第 2、4 位外全为 1（指数是公开的常数）
+    fn invert(self) -> Fe {
+        let mut result = Fe::ONE;
+        for bit in (0..255).rev() {
+            result = result.square();
+            if bit != 2 && bit != 4 {
+                result = result.mul(self);
+            }
+        }
+        result
+    }
+
+    // swap 为 1 时交换，不使用分支
+    fn swap(a: &mut Fe, b: &mut Fe, swap: u64) {
+        let mask = 0u64.wrapping_sub(swap);
+        for (x, y) in a.0.iter_mut().zip(b.0.iter_mut()) {
+            let t = mask & (*x ^ *y);
+            *x ^= t;
+            *y ^= t;
+        }
+    }
+}
+
+/// 标量乘法：以 `u` 坐标表示的点乘以（按规定处理过的）标量
+pub fn x25519(scalar: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    let x1 = Fe::from_bytes(u);
+    let (mut x2, mut z2, mut x3, mut z3) = (Fe::ONE, Fe::ZERO, x1, Fe::ONE);
+    let mut swap = 0;
+    for t in (0..255).rev() {
+        let bit = u64::from((k[t / 8] >> (t % 8)) & 1);
+        swap ^= bit;
+        Fe::swap(&mut x2, &mut x3, swap);
+        Fe::swap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = x2.add(z2);
+        let aa = a.square();
+        let b = x2.sub(z2);
+        let bb = b.square();
+        let e = aa.sub(bb);
+        let c = x3.add(z3);
+        let d = x3.sub(z3);
+        let da = d.mul(a);
+        let cb = c.mul(b);
+        x3 = da.add(cb).square();
+        z3 = x1.mul(da.sub(cb).square());
+        x2 = aa.mul(bb);
+        z2 = e.mul(aa.add(e.mul_small(121665)));
+    }
+    Fe::swap(&mut x2, &mut x3, swap);
+    Fe::swap(&mut z2, &mut z3, swap);
+    k.fill(0);
+
+    x2.mul(z2.invert()).to_bytes()
+}
+
+/// 由私钥计算公钥（乘以基点 u = 9）
+pub fn public_key(secret: &[u8; 32]) -> [u8; 32] {
+    let mut base = [0u8; 32];
+    base[0] = 9;
+    x25519(secret, &base)
+}
+
+/// 把 Ed25519 公钥转换为对应的 X25519 公钥：u = (1 + y) / (1 - y)
+pub fn from_ed25519(public: &[u8; 32]) -> [u8; 32] {
+    let y = Fe::from_bytes(public);
+    Fe::ONE.add(y).mul(Fe::ONE.sub(y).invert()).to_bytes()
+}
@@ -0,0 +1,162 @@
+// ChaCha20-Poly1305 与 XChaCha20-Poly1305 认证加密 (RFC 8439、draft-irtf-cfrg-xchacha)
+//
+// XChaCha20-Poly1305 先用 HChaCha20 由密钥和 nonce 的前 16 字节派生子密钥，再以子密钥
+// 和 nonce 剩余的 8 字节按 ChaCha20-Poly1305 加密。24 字节的 nonce 足够长，每次随机生成
+// 也无需担心重复。两者都实现了 aead 的 AeadInPlace 接口，用法与 aes-gcm 相同；
+// ChaCha20-Poly1305 只用于 age 接收者的密钥封装。
+
+use aes_gcm::aead::consts::{U0, U12, U16, U24, U32};
+use aes_gcm::aead::{AeadCore, AeadInPlace, Error, Key, KeyInit, KeySizeUser, Nonce, Tag};
+
+use crate::chacha20::{hchacha20, ChaCha20};
+use crate::poly1305::Poly1305;
+use crate::secmem;
+
+pub struct ChaCha20Poly1305 {
+    key: [u8; 32],
+}
+
+pub struct XChaCha20Poly1305 {
+    key: [u8; 32],
+}
+
+impl KeySizeUser for ChaCha20Poly1305 {
+    type KeySize = U32;
+}
+
+impl KeyInit for ChaCha20Poly1305 {
+    fn new(key: &Key<Self>) -> Self {
+        ChaCha20Poly1305 { key: (*key).into() }
+    }
+}
+
+impl AeadCore for ChaCha20Poly1305 {
+    type NonceSize = U12;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadInPlace for ChaCha20Poly1305 {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<Tag<Self>, Error> {
+        Ok(seal(&self.key, nonce.as_ref(), associated_data, buffer).into())
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> Result<(), Error> {
+        open(&self.key, nonce.as_ref(), associated_data, buffer, tag.as_ref())
+    }
+}
+
+impl KeySizeUser for XChaCha20Poly1305 {
+    type KeySize = U32;
+}
+
+impl KeyInit for XChaCha20Poly1305 {
+    fn new(key: &Key<Self>) -> Self {
+        XChaCha20Poly1305 { key: (*key).into() }
+    }
+}
+
+impl AeadCore for XChaCha20Poly1305 {
+    type NonceSize = U24;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl XChaCha20Poly1305 {
+    // 派生子密钥和 ChaCha20 使用的 12 字节 nonce
+    fn subkey(&self, nonce: &Nonce<Self>) -> ([u8; 32], [u8; 12]) {
+        let subkey = hchacha20(&self.key, nonce[..16].try_into().unwrap());
+        let mut chacha_nonce = [0u8; 12];
+        chacha_nonce[4..].copy_from_slice(&nonce[16..]);
+        (subkey, chacha_nonce)
+    }
+}
+
+impl AeadInPlace for XChaCha20Poly1305 {
+    fn encrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<Tag<Self>, Error> {
+        let (mut subkey, chacha_nonce) = self.subkey(nonce);
+        let tag = seal(&subkey, &chacha_nonce, associated_data, buffer);
+        secmem::zero(&mut subkey);
+        Ok(tag.into())
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        associated_data: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> Result<(), Error> {
+        let (mut subkey, chacha_nonce) = self.subkey(nonce);
+        let result = open(&subkey, &chacha_nonce, associated_data, buffer, tag.as_ref());
+        secmem::zero(&mut subkey);
+        result
+    }
+}
+
+impl Drop for ChaCha20Poly1305 {
+    fn drop(&mut self) {
+        secmem::zero(&mut self.key);
+    }
+}
+
+impl Drop for XChaCha20Poly1305 {
+    fn drop(&mut self) {
+        secmem::zero(&mut self.key);
+    }
+}
+
+// 返回从块 1 开始的密钥流和用块 0 生成的 Poly1305 一次性密钥
+fn setup(key: &[u8; 32], nonce: &[u8]) -> (ChaCha20, Poly1305) {
+    let nonce: &[u8; 12] = nonce.try_into().unwrap();
+    let mut poly_key = [0u8; 32];
+    ChaCha20::new(key, nonce, 0).apply(&mut poly_key);
+    let mac = Poly1305::new(&poly_key);
+    secmem::zero(&mut poly_key);
+    (ChaCha20::new(key, nonce, 1), mac)
+}
+
+// 认证标签覆盖附加数据和密文，两者各自补零到 16 字节的整数倍，最后是两者的长度
+fn tag(mut mac: Poly1305, associated_data: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+    mac.update(associated_data);
+    mac.pad();
+    mac.update(ciphertext);
+    mac.pad();
+    mac.update(&(associated_data.len() as u64).to_le_bytes());
+    mac.update(&(ciphertext.len() as u64).to_le_bytes());
+    mac.finalize()
+}
+
+fn seal(key: &[u8; 32], nonce: &[u8], associated_data: &[u8], buffer: &mut [u8]) -> [u8; 16] {
+    let (mut stream, mac) = setup(key, nonce);
+    stream.apply(buffer);
+    tag(mac, associated_data, buffer)
+}
+
+// 先校验再解密，比较时不提前退出，以免泄露不一致的位置
+fn open(key: &[u8; 32], nonce: &[u8], associated_data: &[u8], buffer: &mut [u8], expected: &[u8]) -> Result<(), Error> {
+    let (mut stream, mac) = setup(key, nonce);
+    let actual = tag(mac, associated_data, buffer);
+    let difference = actual.iter().zip(expected).fold(0, |acc, (a, b)| acc | (a ^ b));
+    if difference != 0 {
+        return Err(Error);
+    }
+    stream.apply(buffer);
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! YubiKey 的 HMAC-SHA1 挑战-响应
+//!
+//! 密码库头部保存一个随机挑战和 YubiKey 的 OTP 槽位，派生密钥时把挑战发给 YubiKey，
+//! 它用槽位中的密钥计算 HMAC-SHA1，响应接在主密钥之后作为 Argon2id 的输入（见
+//! [`kdf::derive_key_with`](crate::kdf::derive_key_with)）。没有插入同一个 YubiKey（槽位
+//! 设置为需要触摸时还要触摸它）就无法解锁。每次重新派生密钥时换一个新的挑战。
+//!
+//! 通过 YubiKey Manager 的 `ykman otp calculate` 与 YubiKey 通信，没有安装 ykman 时使用
+//! yubikey-personalization 的 `ykchalresp`。槽位需要事先设置为 HMAC-SHA1 挑战-响应模式，
+//! 例如 `ykman otp chalresp --generate --touch 2`。
+
+use std::io;
+use std::process::{Command, Stdio};
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::secmem::{self, SecretBytes};
+
+// 挑战长度，HMAC-SHA1 挑战-响应最多接受 64 字节
+const CHALLENGE_LENGTH: usize = 32;
+// HMAC-SHA1 响应的长度
+const RESPONSE_LENGTH: usize = 20;
+
+/// 挑战-响应参数，保存在密码库头部的密钥派生参数中
+#[derive(Serialize, Deserialize, Clone)]
+pub struct YubikeyParams {
+    /// OTP 槽位，1 或 2
+    pub slot: u8,
+    /// Base64 编码的随机挑战
+    pub challenge: String,
+}
+
+impl YubikeyParams {
+    /// 为槽位 `slot` 生成新的随机挑战
+    pub fn generate(slot: u8) -> Self {
+        let challenge = rand::random::<[u8; CHALLENGE_LENGTH]>();
+        YubikeyParams { slot, challenge: general_purpose::STANDARD.encode(challenge) }
+    }
+}
+
+// 把挑战发给 YubiKey 并返回响应；槽位设置为需要触摸时会一直等待触摸
+pub(crate) fn challenge_response(params: &YubikeyParams) -> Result<SecretBytes, AppError> {
+    if !matches!(params.slot, 1 | 2) {
+        return Err(AppError::from(format!("无效的 YubiKey 槽位: {}", params.slot)));
+    }
+    let challenge = general_purpose::STANDARD
+        .decode(&params.challenge)
+        .map_err(|e| format!("无效的 YubiKey 挑战: {}", e))?;
+    let challenge: String = challenge.iter().map(|b| format!("{:02x}", b)).collect();
+    let slot = params.slot.to_string();
+
+    let ykman = run("ykman", &["otp", "calculate", &slot, &challenge]);
+    let mut output = match ykman {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            run("ykchalresp", &[&format!("-{}", slot), "-x", &challenge]).map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => AppError::from("找不到 ykman 或 ykchalresp，无法使用 YubiKey"),
+                _ => AppError::from(format!("YubiKey 挑战-响应失败: {}", e)),
+            })?
+        }
+        result => result.map_err(|e| AppError::from(format!("YubiKey 挑战-响应失败: {}", e)))?,
+    };
+
+    let response = parse_response(String::from_utf8_lossy(&output).trim());
+    secmem::zero(&mut output);
+    let mut response = response.ok_or("YubiKey 返回了无效的响应")?;
+    let locked = SecretBytes::from_slice(&response);
+    secmem::zero(&mut response);
+    Ok(locked)
+}
+
+// 运行程序并返回标准输出；标准错误留给终端，以便显示“请触摸 YubiKey”之类的提示
+fn run(program: &str, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new(program).args(args).stdin(Stdio::null()).stderr(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        let mut stdout = output.stdout;
+        secmem::zero(&mut stdout);
+        return Err(io::Error::other(format!("{} 退出码 {}", program, output.status.code().unwrap_or(-1))));
+    }
+    Ok(output.stdout)
+}
+
+// 解析十六进制的响应
+fn parse_response(hex: &str) -> Option<[u8; RESPONSE_LENGTH]> {
+    if hex.len() != RESPONSE_LENGTH * 2 || !hex.is_ascii() {
+        return None;
+    }
+    let mut response = [0u8; RESPONSE_LENGTH];
+    for (i, byte) in response.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(response)
+}